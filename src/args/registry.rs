@@ -47,7 +47,28 @@ pub fn flag_registry() -> Vec<FlagDef> {
             short: Some("-b"),
             arity: FlagArity::RequiresValue,
             behavior: FlagBehavior::WrapperOwned,
-            description: "Override default backend",
+            description: "Override default backend or backend group",
+        },
+        FlagDef {
+            long: "--record",
+            short: None,
+            arity: FlagArity::RequiresValue,
+            behavior: FlagBehavior::WrapperOwned,
+            description: "Record upstream exchanges to a directory",
+        },
+        FlagDef {
+            long: "--replay",
+            short: None,
+            arity: FlagArity::RequiresValue,
+            behavior: FlagBehavior::WrapperOwned,
+            description: "Serve recorded responses instead of contacting backends",
+        },
+        FlagDef {
+            long: "--replay-match",
+            short: None,
+            arity: FlagArity::RequiresValue,
+            behavior: FlagBehavior::WrapperOwned,
+            description: "How --replay matches requests (strict or fuzzy)",
         },
         // === Intercepted flags (session management) ===
         FlagDef {
@@ -0,0 +1,295 @@
+//! Spending budgets (`[budgets]`).
+//!
+//! Fed by the observability stream like the circuit breakers: every
+//! finished request adds its measured cost and tokens to its session, the
+//! UTC day and its backend. The pipeline asks [`Budgets::exceeded`] before
+//! forwarding. Reaching `warn_at` of a limit, and later the limit itself,
+//! is posted once to the error registry shown in the header.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+
+use crate::config::{BudgetLimit, BudgetsConfig};
+use crate::error::{ErrorCategory, ErrorRegistry, ErrorSeverity};
+use crate::metrics::ledger::LedgerEntry;
+use crate::metrics::{ObservabilityPlugin, PostResponseContext, TokenUsage};
+use crate::utc::format_utc;
+
+/// Cost and tokens spent against a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spend {
+    pub usd: f64,
+    /// Input (including cache) plus output tokens.
+    pub tokens: u64,
+}
+
+impl Spend {
+    fn add(&mut self, usd: f64, tokens: u64) {
+        self.usd += usd;
+        self.tokens += tokens;
+    }
+
+    /// Largest share of `limit` used; 0 for a limit without caps.
+    fn share(&self, limit: &BudgetLimit) -> f64 {
+        let share = |spent: f64, cap: f64| if cap > 0.0 { spent / cap } else { f64::INFINITY };
+        let usd = limit.usd.map_or(0.0, |cap| share(self.usd, cap));
+        let tokens = limit.tokens.map_or(0.0, |cap| share(self.tokens as f64, cap as f64));
+        usd.max(tokens)
+    }
+}
+
+/// What a limit applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BudgetScope {
+    Session(String),
+    Day,
+    Backend(String),
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetScope::Session(id) => write!(f, "session {}", id),
+            BudgetScope::Day => write!(f, "daily"),
+            BudgetScope::Backend(name) => write!(f, "backend '{}' daily", name),
+        }
+    }
+}
+
+/// A limit that has been reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub scope: BudgetScope,
+    pub spent: Spend,
+    pub limit: BudgetLimit,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} budget of {} reached", self.scope, describe(&self.limit))?;
+        write!(f, " (spent ${:.2}, {} tokens)", self.spent.usd, self.spent.tokens)
+    }
+}
+
+/// `$5.00 / 2000000 tokens` for the caps set on `limit`.
+fn describe(limit: &BudgetLimit) -> String {
+    let usd = limit.usd.map(|usd| format!("${:.2}", usd));
+    let tokens = limit.tokens.map(|tokens| format!("{} tokens", tokens));
+    usd.into_iter().chain(tokens).collect::<Vec<_>>().join(" / ")
+}
+
+/// Thread-safe spend totals checked against `[budgets]`.
+///
+/// Cheap to clone; all clones share state.
+#[derive(Clone)]
+pub struct Budgets {
+    inner: Arc<Mutex<BudgetsInner>>,
+}
+
+struct BudgetsInner {
+    config: BudgetsConfig,
+    /// Where warnings go; replaced by the pipeline's registry at startup.
+    error_registry: ErrorRegistry,
+    /// UTC day (`YYYY-MM-DD`) the day and backend totals belong to.
+    day: String,
+    day_spend: Spend,
+    backend_spend: HashMap<String, Spend>,
+    session_spend: HashMap<String, Spend>,
+    /// Scopes already warned about at `warn_at`.
+    warned: HashSet<BudgetScope>,
+    /// Scopes already reported as over their limit.
+    reached: HashSet<BudgetScope>,
+}
+
+impl BudgetsInner {
+    /// Reset the day and backend totals once the UTC day changes.
+    fn roll_day(&mut self) {
+        let today = today();
+        if self.day != today {
+            self.day = today;
+            self.day_spend = Spend::default();
+            self.backend_spend.clear();
+            let is_session = |scope: &BudgetScope| matches!(scope, BudgetScope::Session(_));
+            self.warned.retain(is_session);
+            self.reached.retain(is_session);
+        }
+    }
+
+    fn limit(&self, scope: &BudgetScope) -> Option<BudgetLimit> {
+        match scope {
+            BudgetScope::Session(_) => self.config.session,
+            BudgetScope::Day => self.config.day,
+            BudgetScope::Backend(name) => self.config.backends.get(name).copied(),
+        }
+    }
+
+    fn spend(&self, scope: &BudgetScope) -> Spend {
+        match scope {
+            BudgetScope::Session(id) => self.session_spend.get(id),
+            BudgetScope::Day => Some(&self.day_spend),
+            BudgetScope::Backend(name) => self.backend_spend.get(name),
+        }
+        .copied()
+        .unwrap_or_default()
+    }
+
+    fn check(&self, scope: &BudgetScope) -> Option<BudgetExceeded> {
+        let limit = self.limit(scope)?;
+        let spent = self.spend(scope);
+        (spent.share(&limit) >= 1.0).then(|| BudgetExceeded {
+            scope: scope.clone(),
+            spent,
+            limit,
+        })
+    }
+
+    /// Post a warning for each scope newly past `warn_at` or its limit.
+    fn notify(&mut self, scopes: &[BudgetScope]) {
+        for scope in scopes {
+            let Some(limit) = self.limit(scope) else {
+                continue;
+            };
+            let share = self.spend(scope).share(&limit);
+            if let Some(exceeded) = self.check(scope) {
+                if self.reached.insert(scope.clone()) {
+                    self.warned.insert(scope.clone());
+                    let (severity, action) = match &self.config.fallback_backend {
+                        Some(fallback) => {
+                            (ErrorSeverity::Warning, format!("switching to '{}'", fallback))
+                        }
+                        None => (ErrorSeverity::Error, "rejecting requests".to_string()),
+                    };
+                    let message = format!("Budget: {}, {}", exceeded, action);
+                    crate::metrics::app_log("budget", &message);
+                    self.error_registry.record(severity, ErrorCategory::Backend, message);
+                }
+            } else if share >= self.config.warn_at && self.warned.insert(scope.clone()) {
+                let message = format!(
+                    "Budget: {} spend at {:.0}% of {}",
+                    scope,
+                    share * 100.0,
+                    describe(&limit)
+                );
+                crate::metrics::app_log("budget", &message);
+                self.error_registry
+                    .record(ErrorSeverity::Warning, ErrorCategory::Backend, message);
+            }
+        }
+    }
+}
+
+impl Budgets {
+    pub fn new(config: BudgetsConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(BudgetsInner {
+                config,
+                error_registry: ErrorRegistry::default(),
+                day: today(),
+                day_spend: Spend::default(),
+                backend_spend: HashMap::new(),
+                session_spend: HashMap::new(),
+                warned: HashSet::new(),
+                reached: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Replace the limits (e.g. on config reload). Totals are kept; limits
+    /// still crossed under the new settings are reported again.
+    pub fn set_config(&self, config: BudgetsConfig) {
+        let mut inner = self.inner.lock();
+        inner.config = config;
+        inner.warned.clear();
+        inner.reached.clear();
+    }
+
+    /// Post warnings to `error_registry` from now on.
+    pub fn set_error_registry(&self, error_registry: ErrorRegistry) {
+        self.inner.lock().error_registry = error_registry;
+    }
+
+    /// Current spend of a scope.
+    pub fn spend(&self, scope: &BudgetScope) -> Spend {
+        let mut inner = self.inner.lock();
+        inner.roll_day();
+        inner.spend(scope)
+    }
+
+    /// The limit of `scope`, if set and reached.
+    pub fn check(&self, scope: &BudgetScope) -> Option<BudgetExceeded> {
+        let mut inner = self.inner.lock();
+        inner.roll_day();
+        inner.check(scope)
+    }
+
+    /// First reached limit among the session, the day and the backend.
+    pub fn exceeded(&self, session_id: Option<&str>, backend: &str) -> Option<BudgetExceeded> {
+        let mut inner = self.inner.lock();
+        inner.roll_day();
+        scopes(session_id, backend)
+            .iter()
+            .find_map(|scope| inner.check(scope))
+    }
+
+    /// Add a finished request's usage to its scopes.
+    pub fn record(&self, session_id: Option<&str>, backend: &str, usage: &TokenUsage) {
+        let mut inner = self.inner.lock();
+        if !inner.config.is_enabled() {
+            return;
+        }
+        inner.roll_day();
+        let usd = usage.cost_usd.unwrap_or(0.0);
+        let tokens = usage.total_input_tokens() + usage.output_tokens;
+        inner.day_spend.add(usd, tokens);
+        inner.backend_spend.entry(backend.to_string()).or_default().add(usd, tokens);
+        if let Some(id) = session_id {
+            inner.session_spend.entry(id.to_string()).or_default().add(usd, tokens);
+        }
+        inner.notify(&scopes(session_id, backend));
+    }
+
+    /// Restore totals from ledger entries, e.g. after a restart. Day and
+    /// backend totals only take today's entries.
+    pub fn seed(&self, entries: &[LedgerEntry]) {
+        let mut inner = self.inner.lock();
+        inner.roll_day();
+        for entry in entries {
+            let usd = entry.cost_usd.unwrap_or(0.0);
+            let tokens = entry.input_tokens
+                + entry.cache_creation_input_tokens
+                + entry.cache_read_input_tokens
+                + entry.output_tokens;
+            if let Some(id) = &entry.session_id {
+                inner.session_spend.entry(id.clone()).or_default().add(usd, tokens);
+            }
+            if entry.day() == inner.day {
+                inner.day_spend.add(usd, tokens);
+                inner.backend_spend.entry(entry.backend.clone()).or_default().add(usd, tokens);
+            }
+        }
+    }
+}
+
+impl ObservabilityPlugin for Budgets {
+    fn post_response(&self, ctx: &mut PostResponseContext<'_>) {
+        if let Some(usage) = &ctx.record.usage {
+            self.record(ctx.record.session_id.as_deref(), &ctx.record.backend, usage);
+        }
+    }
+}
+
+fn scopes(session_id: Option<&str>, backend: &str) -> Vec<BudgetScope> {
+    session_id
+        .map(|id| BudgetScope::Session(id.to_string()))
+        .into_iter()
+        .chain([BudgetScope::Day, BudgetScope::Backend(backend.to_string())])
+        .collect()
+}
+
+fn today() -> String {
+    format_utc(SystemTime::now())[..10].to_string()
+}
@@ -0,0 +1,252 @@
+//! Per-backend circuit breaker.
+//!
+//! Passive health tracking fed by the observability stream: every finished
+//! request record is classified as success or failure for its backend.
+//!
+//! ```text
+//! [Closed]   --N consecutive failures / error rate over window--> [Open]
+//! [Open]     --open_seconds elapsed-------------------------------> [HalfOpen]
+//! [HalfOpen] --success--------------------------------------------> [Closed]
+//! [HalfOpen] --failure--------------------------------------------> [Open]
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::config::CircuitBreakerConfig;
+use crate::metrics::{ObservabilityPlugin, PostResponseContext, RequestRecord};
+
+/// Circuit state of a single backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Healthy: requests flow normally.
+    #[default]
+    Closed,
+    /// Tripped: routing skips the backend when a failover target exists.
+    Open,
+    /// Cool-down elapsed: a single probe request is let through, and its
+    /// outcome decides whether to close or reopen.
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Short label for UI display.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "Closed",
+            CircuitState::Open => "Tripped",
+            CircuitState::HalfOpen => "Probing",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// Recent outcomes, `true` = failure.
+    window: VecDeque<bool>,
+    opened_at: Option<Instant>,
+    /// When the half-open probe was admitted; cleared by its outcome.
+    probe_started: Option<Instant>,
+}
+
+impl Breaker {
+    /// Move Open → HalfOpen once the cool-down has elapsed.
+    fn refresh(&mut self, open_for: Duration) {
+        if self.state == CircuitState::Open
+            && self.opened_at.is_some_and(|at| at.elapsed() >= open_for)
+        {
+            self.state = CircuitState::HalfOpen;
+        }
+    }
+
+    fn open(&mut self) {
+        self.state = CircuitState::Open;
+        self.opened_at = Some(Instant::now());
+        self.probe_started = None;
+    }
+
+    fn close(&mut self) {
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.window.clear();
+        self.opened_at = None;
+        self.probe_started = None;
+    }
+
+    /// Admit the half-open probe unless one is already in flight. A probe
+    /// with no outcome after another `open_for` (routed elsewhere, or its
+    /// client went away) no longer holds the slot.
+    fn admit_probe(&mut self, open_for: Duration) -> bool {
+        if self.probe_started.is_some_and(|at| at.elapsed() < open_for) {
+            return false;
+        }
+        self.probe_started = Some(Instant::now());
+        true
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.window.is_empty() {
+            return 0.0;
+        }
+        let failures = self.window.iter().filter(|f| **f).count();
+        failures as f64 / self.window.len() as f64
+    }
+}
+
+/// Thread-safe set of circuit breakers, one per backend.
+///
+/// Cheap to clone; all clones share state.
+#[derive(Clone)]
+pub struct CircuitBreakers {
+    inner: Arc<Mutex<CircuitBreakersInner>>,
+}
+
+struct CircuitBreakersInner {
+    config: CircuitBreakerConfig,
+    breakers: HashMap<String, Breaker>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CircuitBreakersInner {
+                config,
+                breakers: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Replace the breaker settings (e.g. on config reload). State is kept.
+    pub fn set_config(&self, config: CircuitBreakerConfig) {
+        self.inner.lock().config = config;
+    }
+
+    /// Current circuit state for a backend. Unknown backends are closed.
+    pub fn state(&self, backend: &str) -> CircuitState {
+        let mut inner = self.inner.lock();
+        if !inner.config.enabled {
+            return CircuitState::Closed;
+        }
+        let open_for = Duration::from_secs(inner.config.open_seconds);
+        match inner.breakers.get_mut(backend) {
+            Some(breaker) => {
+                breaker.refresh(open_for);
+                breaker.state
+            }
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Whether a request should be sent to the backend.
+    ///
+    /// While half-open, only the first caller is admitted as the probe;
+    /// the rest are turned away until [`record`](Self::record) settles it.
+    pub fn allows(&self, backend: &str) -> bool {
+        let mut inner = self.inner.lock();
+        if !inner.config.enabled {
+            return true;
+        }
+        let open_for = Duration::from_secs(inner.config.open_seconds);
+        let Some(breaker) = inner.breakers.get_mut(backend) else {
+            return true;
+        };
+        breaker.refresh(open_for);
+        match breaker.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => breaker.admit_probe(open_for),
+        }
+    }
+
+    /// Record a request outcome for a backend.
+    pub fn record(&self, backend: &str, failure: bool) {
+        let mut inner = self.inner.lock();
+        if !inner.config.enabled {
+            return;
+        }
+        let config = inner.config.clone();
+        let breaker = inner.breakers.entry(backend.to_string()).or_default();
+        breaker.refresh(Duration::from_secs(config.open_seconds));
+
+        match breaker.state {
+            // Outcomes of requests that were in flight when the circuit opened.
+            CircuitState::Open => {}
+            CircuitState::HalfOpen => {
+                if failure {
+                    breaker.open();
+                    crate::metrics::app_log(
+                        "circuit",
+                        &format!("Circuit re-opened for '{}' (probe failed)", backend),
+                    );
+                } else {
+                    breaker.close();
+                    crate::metrics::app_log(
+                        "circuit",
+                        &format!("Circuit closed for '{}' (probe succeeded)", backend),
+                    );
+                }
+            }
+            CircuitState::Closed => {
+                breaker.window.push_back(failure);
+                while breaker.window.len() > config.window_size.max(1) {
+                    breaker.window.pop_front();
+                }
+                if failure {
+                    breaker.consecutive_failures += 1;
+                } else {
+                    breaker.consecutive_failures = 0;
+                }
+
+                let tripped_consecutive =
+                    breaker.consecutive_failures >= config.consecutive_failures.max(1);
+                let tripped_rate = breaker.window.len() >= config.min_requests
+                    && breaker.error_rate() >= config.error_rate;
+                if tripped_consecutive || tripped_rate {
+                    crate::metrics::app_log(
+                        "circuit",
+                        &format!(
+                            "Circuit opened for '{}': consecutive_failures={}, error_rate={:.2}",
+                            backend,
+                            breaker.consecutive_failures,
+                            breaker.error_rate()
+                        ),
+                    );
+                    breaker.open();
+                }
+            }
+        }
+    }
+
+    /// Classify a finished request record as failure (`Some(true)`),
+    /// success (`Some(false)`), or no signal (`None`).
+    ///
+    /// Timeouts, connection errors, 429 and 5xx are failures; other
+    /// statuses (including 4xx client errors) mean the backend is reachable.
+    pub fn classify(record: &RequestRecord) -> Option<bool> {
+        if record.timed_out {
+            return Some(true);
+        }
+        let status = record.status?;
+        Some(Self::is_failure(status, false))
+    }
+
+    /// Whether an outcome with `status` counts against the backend.
+    pub fn is_failure(status: u16, timed_out: bool) -> bool {
+        timed_out || status == 429 || status >= 500
+    }
+}
+
+impl ObservabilityPlugin for CircuitBreakers {
+    fn post_response(&self, ctx: &mut PostResponseContext<'_>) {
+        if let Some(failure) = Self::classify(ctx.record) {
+            self.record(&ctx.record.backend, failure);
+        }
+    }
+}
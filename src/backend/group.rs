@@ -0,0 +1,158 @@
+//! Member selection for backend groups.
+//!
+//! A `[[backend_groups]]` entry spreads requests over its member backends.
+//! The balancer holds the state the strategies need: round-robin cursors,
+//! the requests in flight per backend, and the member each group is pinned
+//! to for main-agent traffic.
+//!
+//! In-flight requests are registered by routing and released when their
+//! record reaches the observability stream, so the balancer must be
+//! installed as an [`ObservabilityPlugin`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::config::GroupStrategy;
+use crate::metrics::{ObservabilityPlugin, PostResponseContext};
+
+/// Shared balancing state for all backend groups.
+///
+/// Cheap to clone; all clones share state.
+#[derive(Clone, Default)]
+pub struct GroupBalancer {
+    inner: Arc<Mutex<BalancerInner>>,
+}
+
+#[derive(Default)]
+struct BalancerInner {
+    /// Round-robin position per group.
+    cursors: HashMap<String, u64>,
+    /// Request ID → backend serving it.
+    in_flight: HashMap<String, String>,
+    /// Group → member serving its main-agent requests.
+    pinned: HashMap<String, String>,
+}
+
+impl GroupBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Choose one of `members` (backend name, weight) for a request to `group`.
+    ///
+    /// With `pin`, the member chosen first keeps being returned while it is
+    /// among `members`; otherwise a new one is chosen and pinned.
+    /// `p50_latency` is only consulted by [`GroupStrategy::LowestLatency`].
+    pub fn select(
+        &self,
+        group: &str,
+        strategy: GroupStrategy,
+        members: &[(&str, u32)],
+        pin: bool,
+        p50_latency: impl Fn(&str) -> Option<u64>,
+    ) -> Option<String> {
+        let mut inner = self.inner.lock();
+        let previous = pin.then(|| inner.pinned.get(group).cloned()).flatten();
+        if let Some(member) = previous.as_deref() {
+            if members.iter().any(|(name, _)| *name == member) {
+                return previous;
+            }
+        }
+
+        let total: u64 = members.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        let chosen = match strategy {
+            GroupStrategy::RoundRobin => {
+                let cursor = inner.cursors.entry(group.to_string()).or_default();
+                let slot = *cursor % total.max(1);
+                *cursor = cursor.wrapping_add(1);
+                by_slot(members, slot)
+            }
+            GroupStrategy::WeightedRandom => {
+                let slot = (uuid::Uuid::new_v4().as_u128() % u128::from(total.max(1))) as u64;
+                by_slot(members, slot)
+            }
+            GroupStrategy::LeastInFlight => {
+                let load = |name: &str| {
+                    inner.in_flight.values().filter(|b| b.as_str() == name).count() as u64
+                };
+                // Compare load per unit of weight without dividing.
+                members
+                    .iter()
+                    .map(|(name, weight)| (*name, load(name), u64::from(*weight).max(1)))
+                    .min_by(|a, b| (a.1 * b.2).cmp(&(b.1 * a.2)))
+                    .map(|(name, _, _)| name)
+            }
+            GroupStrategy::LowestLatency => members
+                .iter()
+                .min_by_key(|(name, _)| p50_latency(name))
+                .map(|(name, _)| *name),
+        }?
+        .to_string();
+
+        if pin {
+            if let Some(previous) = previous {
+                crate::metrics::app_log(
+                    "routing",
+                    &format!(
+                        "Group '{}' member '{}' unavailable, pinning main agent to '{}'",
+                        group, previous, chosen
+                    ),
+                );
+            }
+            inner.pinned.insert(group.to_string(), chosen.clone());
+        }
+        Some(chosen)
+    }
+
+    /// Record that `request_id` is now being served by `backend`.
+    ///
+    /// Calling again for the same request (failover) moves it.
+    pub fn begin(&self, request_id: &str, backend: &str) {
+        self.inner
+            .lock()
+            .in_flight
+            .insert(request_id.to_string(), backend.to_string());
+    }
+
+    /// Number of requests currently in flight to `backend`.
+    pub fn in_flight(&self, backend: &str) -> usize {
+        self.inner
+            .lock()
+            .in_flight
+            .values()
+            .filter(|b| b.as_str() == backend)
+            .count()
+    }
+
+    /// Member the group's main-agent requests are pinned to, if any.
+    pub fn pinned(&self, group: &str) -> Option<String> {
+        self.inner.lock().pinned.get(group).cloned()
+    }
+
+    /// Forget cursors and pins, e.g. after the groups were reconfigured.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock();
+        inner.cursors.clear();
+        inner.pinned.clear();
+    }
+}
+
+/// Member whose cumulative weight range contains `slot`.
+fn by_slot<'a>(members: &[(&'a str, u32)], mut slot: u64) -> Option<&'a str> {
+    for (name, weight) in members {
+        let weight = u64::from(*weight);
+        if slot < weight {
+            return Some(name);
+        }
+        slot -= weight;
+    }
+    members.first().map(|(name, _)| *name)
+}
+
+impl ObservabilityPlugin for GroupBalancer {
+    fn post_response(&self, ctx: &mut PostResponseContext<'_>) {
+        self.inner.lock().in_flight.remove(&ctx.record.id);
+    }
+}
@@ -0,0 +1,61 @@
+//! Active health probe results.
+//!
+//! Written by the background prober in `proxy::prober`, read by the
+//! `/health` endpoint and the IPC layer.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use parking_lot::RwLock;
+
+/// Outcome of the most recent probe of one backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendProbe {
+    /// The backend answered (HTTP response or TCP connect).
+    pub reachable: bool,
+    /// Reachable and the answer does not indicate an outage or bad credentials.
+    pub healthy: bool,
+    /// Round-trip time of the probe.
+    pub latency_ms: Option<u64>,
+    /// HTTP status, if the probe was an HTTP request.
+    pub status: Option<u16>,
+    /// Error description when unreachable or unhealthy.
+    pub error: Option<String>,
+    /// When the probe completed.
+    pub checked_at: SystemTime,
+}
+
+/// Shared store of the latest probe per backend.
+///
+/// Cheap to clone; all clones share state.
+#[derive(Clone, Default)]
+pub struct ProbeResults {
+    inner: Arc<RwLock<HashMap<String, BackendProbe>>>,
+}
+
+impl ProbeResults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the latest probe for a backend.
+    pub fn update(&self, backend: &str, probe: BackendProbe) {
+        self.inner.write().insert(backend.to_string(), probe);
+    }
+
+    /// Latest probe for a backend, if any.
+    pub fn get(&self, backend: &str) -> Option<BackendProbe> {
+        self.inner.read().get(backend).cloned()
+    }
+
+    /// Latest probes for all backends.
+    pub fn all(&self) -> HashMap<String, BackendProbe> {
+        self.inner.read().clone()
+    }
+
+    /// Drop results for backends no longer in config.
+    pub fn retain(&self, backends: &[String]) {
+        self.inner.write().retain(|name, _| backends.contains(name));
+    }
+}
@@ -0,0 +1,244 @@
+//! API key rotation for backends with several keys.
+//!
+//! A backend whose `api_key`/`api_key_env`/`api_key_file`/`api_key_command`
+//! plus `api_keys` yield two or more sources spreads its requests over them
+//! per `key_rotation`. A key that is rate limited cools down until the
+//! backend's reset hint (or [`DEFAULT_KEY_COOLDOWN`]) and is skipped meanwhile.
+//!
+//! Keys are resolved outside the lock, since `api_key_command` may run a
+//! process.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::config::{ApiKeySource, AuthType, Backend, KeyRotation, SecureString};
+
+/// Cooldown of a throttled key when the response carries no reset hint.
+pub const DEFAULT_KEY_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Shared key rotation state for all backends.
+///
+/// Cheap to clone; all clones share state.
+#[derive(Clone, Default)]
+pub struct KeyRotator {
+    inner: Arc<Mutex<HashMap<String, BackendKeys>>>,
+}
+
+#[derive(Default)]
+struct BackendKeys {
+    /// Round-robin position.
+    cursor: usize,
+    keys: Vec<KeyState>,
+}
+
+#[derive(Default, Clone)]
+struct KeyState {
+    requests: u64,
+    throttles: u64,
+    cooling_until: Option<Instant>,
+    last_throttled: Option<Instant>,
+    /// Why the key could not be resolved the last time it was tried.
+    error: Option<String>,
+}
+
+impl KeyState {
+    fn cooling(&self, now: Instant) -> bool {
+        self.cooling_until.is_some_and(|until| until > now)
+    }
+}
+
+/// A key chosen for one request.
+#[derive(Debug, Clone)]
+pub struct ApiKeyChoice {
+    /// Position in [`Backend::api_key_sources`].
+    pub index: usize,
+    pub key: SecureString,
+}
+
+/// Usage of one key, for the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyStatus {
+    /// `#1 …abcd`, `#2 $ENV_VAR`, ...
+    pub label: String,
+    pub requests: u64,
+    pub throttles: u64,
+    /// Seconds until the key is used again, while cooling down.
+    pub cooling_secs: Option<u64>,
+    /// Why the key could not be resolved.
+    pub error: Option<String>,
+}
+
+impl KeyRotator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pick the key for the next request to `backend`.
+    ///
+    /// `None` when the backend does not use `api_key`/`bearer` auth with two
+    /// or more key sources, in which case its usual auth header is used. Cooling keys
+    /// are only chosen when every key is cooling; then the one that recovers
+    /// first wins.
+    pub fn select(&self, backend: &Backend) -> Option<ApiKeyChoice> {
+        let sources = rotated_sources(backend)?;
+        let order = {
+            let mut inner = self.inner.lock();
+            let keys = keys_for(&mut inner, &backend.name, sources.len());
+            let order = preference(keys, backend.key_rotation, Instant::now());
+            keys.cursor = keys.cursor.wrapping_add(1);
+            order
+        };
+        self.resolve_first(backend, &sources, order)
+    }
+
+    /// A key other than `current` that is not cooling down, to retry a
+    /// throttled request with.
+    pub fn next_available(&self, backend: &Backend, current: usize) -> Option<ApiKeyChoice> {
+        let sources = backend.api_key_sources();
+        let order = {
+            let mut inner = self.inner.lock();
+            let keys = keys_for(&mut inner, &backend.name, sources.len());
+            let now = Instant::now();
+            preference(keys, backend.key_rotation, now)
+                .into_iter()
+                .filter(|&index| index != current && !keys.keys[index].cooling(now))
+                .collect()
+        };
+        self.resolve_first(backend, &sources, order)
+    }
+
+    /// Key `index` of `backend` was rate limited; rest it for `cooldown`.
+    pub fn throttled(&self, backend: &str, index: usize, cooldown: Duration) {
+        let mut inner = self.inner.lock();
+        let Some(key) = inner.get_mut(backend).and_then(|keys| keys.keys.get_mut(index)) else {
+            return;
+        };
+        let now = Instant::now();
+        key.throttles += 1;
+        key.last_throttled = Some(now);
+        key.cooling_until = Some(now + cooldown);
+    }
+
+    /// Per-key usage of `backend`; empty when its keys are not rotated.
+    pub fn status(&self, backend: &Backend) -> Vec<ApiKeyStatus> {
+        let Some(sources) = rotated_sources(backend) else {
+            return Vec::new();
+        };
+        let inner = self.inner.lock();
+        let now = Instant::now();
+        sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let key = inner
+                    .get(&backend.name)
+                    .and_then(|keys| keys.keys.get(index))
+                    .cloned()
+                    .unwrap_or_default();
+                ApiKeyStatus {
+                    label: format!("#{} {}", index + 1, describe(source)),
+                    requests: key.requests,
+                    throttles: key.throttles,
+                    cooling_secs: key
+                        .cooling_until
+                        .filter(|until| *until > now)
+                        .map(|until| (until - now).as_secs().max(1)),
+                    error: key.error,
+                }
+            })
+            .collect()
+    }
+
+    /// Forget all usage, e.g. after the backends were reconfigured.
+    pub fn reset(&self) {
+        self.inner.lock().clear();
+    }
+
+    /// Resolve the sources in `order`, counting a request against the first
+    /// that yields a key.
+    fn resolve_first(
+        &self,
+        backend: &Backend,
+        sources: &[ApiKeySource],
+        order: Vec<usize>,
+    ) -> Option<ApiKeyChoice> {
+        for index in order {
+            let resolved = sources[index].resolve();
+            let mut inner = self.inner.lock();
+            let keys = keys_for(&mut inner, &backend.name, sources.len());
+            match resolved {
+                Ok(key) => {
+                    keys.keys[index].requests += 1;
+                    keys.keys[index].error = None;
+                    return Some(ApiKeyChoice { index, key });
+                }
+                Err(reason) => {
+                    crate::metrics::app_log(
+                        "upstream",
+                        &format!("Skipping API key #{} of '{}': {}", index + 1, backend.name, reason),
+                    );
+                    keys.keys[index].error = Some(reason);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Key sources of `backend`, if it has several to rotate over.
+fn rotated_sources(backend: &Backend) -> Option<Vec<ApiKeySource>> {
+    if !matches!(backend.auth_type(), AuthType::ApiKey | AuthType::Bearer) {
+        return None;
+    }
+    let sources = backend.api_key_sources();
+    (sources.len() >= 2).then_some(sources)
+}
+
+/// State of `backend`, sized to its `count` key sources.
+fn keys_for<'a>(
+    inner: &'a mut HashMap<String, BackendKeys>,
+    backend: &str,
+    count: usize,
+) -> &'a mut BackendKeys {
+    let keys = inner.entry(backend.to_string()).or_default();
+    keys.keys.resize(count, KeyState::default());
+    keys
+}
+
+/// All key indices, best first: keys that are not cooling in strategy
+/// order, then cooling keys by when they recover.
+fn preference(keys: &BackendKeys, rotation: KeyRotation, now: Instant) -> Vec<usize> {
+    let count = keys.keys.len();
+    let (mut ready, mut cooling): (Vec<usize>, Vec<usize>) =
+        (0..count).partition(|&index| !keys.keys[index].cooling(now));
+
+    match rotation {
+        KeyRotation::RoundRobin => {
+            let start = keys.cursor % count.max(1);
+            ready.sort_by_key(|&index| (index + count - start) % count);
+        }
+        KeyRotation::LeastRecentlyThrottled => {
+            ready.sort_by_key(|&index| {
+                let key = &keys.keys[index];
+                (key.last_throttled, key.requests)
+            });
+        }
+    }
+    cooling.sort_by_key(|&index| keys.keys[index].cooling_until);
+
+    ready.extend(cooling);
+    ready
+}
+
+/// Short description of a key source that does not reveal the key.
+fn describe(source: &ApiKeySource) -> String {
+    match source {
+        ApiKeySource::Literal(key) => SecureString::new(key.clone()).hint(),
+        ApiKeySource::Env { env } => format!("${}", env),
+        ApiKeySource::File { file } => file.clone(),
+        ApiKeySource::Command { .. } => "command".to_string(),
+    }
+}
@@ -3,9 +3,21 @@
 //! Provides thread-safe backend state management with support for
 //! runtime switching without interrupting in-flight requests.
 
+mod budget;
+mod circuit;
+mod group;
+mod health;
+mod keys;
 mod state;
 
-pub use state::{BackendError, BackendState, AgentBackendState, AgentRegistry, SwitchLogEntry};
+pub use budget::{BudgetExceeded, BudgetScope, Budgets, Spend};
+pub use circuit::{CircuitBreakers, CircuitState};
+pub use group::GroupBalancer;
+pub use health::{BackendProbe, ProbeResults};
+pub use keys::{ApiKeyChoice, ApiKeyStatus, KeyRotator, DEFAULT_KEY_COOLDOWN};
+pub use state::{
+    BackendError, BackendState, AgentBackendState, AgentRegistry, SwitchLogEntry, SWITCH_LOG_CAPACITY,
+};
 
 /// Manager for backend operations (placeholder for future CRUD operations).
 ///
@@ -3,13 +3,22 @@
 //! Provides thread-safe backend state management with support for
 //! runtime switching without interrupting in-flight requests.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::SystemTime;
 
 use parking_lot::RwLock;
 
-use crate::config::{Backend, Config};
+use crate::config::{
+    Backend, BackendGroup, BudgetsConfig, Config, RetryConfig, RoutingRule, TracingConfig, UsageLedgerConfig,
+};
+
+use super::budget::Budgets;
+use super::circuit::CircuitBreakers;
+use super::group::GroupBalancer;
+use super::health::ProbeResults;
+use super::keys::KeyRotator;
 
 /// Errors that can occur during backend operations.
 #[derive(Debug, Clone)]
@@ -40,6 +49,9 @@ impl std::fmt::Display for BackendError {
 
 impl std::error::Error for BackendError {}
 
+/// Entries kept in the switch log; the oldest are dropped beyond this.
+pub const SWITCH_LOG_CAPACITY: usize = 256;
+
 /// Log entry for a backend switch event.
 #[derive(Debug, Clone)]
 pub struct SwitchLogEntry {
@@ -49,6 +61,9 @@ pub struct SwitchLogEntry {
     pub old_backend: Option<String>,
     /// The new active backend.
     pub new_backend: String,
+    /// Whether this entry is a per-request failover hop rather than
+    /// a change of the active backend.
+    pub failover: bool,
 }
 
 /// Runtime state for agent backend routing (subagents and teammates).
@@ -139,15 +154,37 @@ impl AgentRegistry {
 #[derive(Clone)]
 pub struct BackendState {
     inner: Arc<RwLock<BackendStateInner>>,
+    /// Passive health tracking per backend.
+    circuit_breakers: CircuitBreakers,
+    /// Spend totals checked against `[budgets]`.
+    budgets: Budgets,
+    /// Latest active health probe per backend.
+    probe_results: ProbeResults,
+    /// Member selection state for backend groups.
+    group_balancer: GroupBalancer,
+    /// API key selection for backends with several keys.
+    key_rotator: KeyRotator,
+    /// `--replay`: responses come from a recording, so no backend needs
+    /// credentials.
+    replay: Arc<AtomicBool>,
 }
 
 struct BackendStateInner {
-    /// The currently active backend ID.
+    /// The currently active backend or backend group ID.
     active_backend: String,
     /// Full configuration (needed to look up backend details).
     config: Config,
-    /// History of backend switches for debugging/auditing.
-    switch_log: Vec<SwitchLogEntry>,
+    /// Most recent backend switches for debugging/auditing.
+    switch_log: VecDeque<SwitchLogEntry>,
+}
+
+impl BackendStateInner {
+    fn log_switch(&mut self, entry: SwitchLogEntry) {
+        if self.switch_log.len() >= SWITCH_LOG_CAPACITY {
+            self.switch_log.pop_front();
+        }
+        self.switch_log.push_back(entry);
+    }
 }
 
 impl BackendState {
@@ -168,7 +205,7 @@ impl BackendState {
         } else {
             // Validate the default backend exists
             let default = &config.defaults.active;
-            if !config.backends.iter().any(|b| &b.name == default) {
+            if !has_backend_or_group(&config, default) {
                 return Err(BackendError::BackendNotFound {
                     backend: default.clone(),
                 });
@@ -176,22 +213,31 @@ impl BackendState {
             default.clone()
         };
 
+        let circuit_breakers = CircuitBreakers::new(config.circuit_breaker.clone());
+        let budgets = Budgets::new(config.budgets.clone());
         let inner = BackendStateInner {
             active_backend: active_backend.clone(),
             config,
-            switch_log: vec![SwitchLogEntry {
+            switch_log: VecDeque::from([SwitchLogEntry {
                 timestamp: SystemTime::now(),
                 old_backend: None,
                 new_backend: active_backend,
-            }],
+                failover: false,
+            }]),
         };
 
         Ok(Self {
             inner: Arc::new(RwLock::new(inner)),
+            circuit_breakers,
+            budgets,
+            probe_results: ProbeResults::new(),
+            group_balancer: GroupBalancer::new(),
+            key_rotator: KeyRotator::new(),
+            replay: Arc::new(AtomicBool::new(false)),
         })
     }
 
-    /// Get the currently active backend ID.
+    /// Get the currently active backend ID (possibly a backend group).
     ///
     /// This is fast and non-blocking for concurrent readers.
     pub fn get_active_backend(&self) -> String {
@@ -229,21 +275,121 @@ impl BackendState {
             })
     }
 
+    /// Serve every request from a recording (`--replay`).
+    pub fn set_replay(&self, replay: bool) {
+        self.replay.store(replay, Ordering::Relaxed);
+    }
+
+    /// Whether `backend` can take requests: it has credentials, or none
+    /// are needed because requests are replayed.
+    pub fn is_configured(&self, backend: &Backend) -> bool {
+        self.replay.load(Ordering::Relaxed) || backend.is_configured()
+    }
+
+    /// Get a backend group by name.
+    pub fn get_group(&self, name: &str) -> Option<BackendGroup> {
+        let state = self.inner.read();
+        state.config.backend_groups.iter().find(|g| g.name == name).cloned()
+    }
+
+    /// Pick the member of `group` that serves the next request.
+    ///
+    /// Members that are unconfigured or have an open circuit are passed
+    /// over while another member is usable. With `pin`, the member chosen
+    /// first is reused for as long as it stays usable, so the request
+    /// sequence keeps talking to one upstream (see [`GroupBalancer::select`]).
+    pub fn select_group_member(
+        &self,
+        group: &BackendGroup,
+        pin: bool,
+        p50_latency: impl Fn(&str) -> Option<u64>,
+    ) -> Result<Backend, BackendError> {
+        let members: Vec<(Backend, u32)> = group
+            .members
+            .iter()
+            .filter_map(|m| self.get_backend_config(&m.backend).ok().map(|b| (b, m.weight)))
+            .collect();
+        let usable = |b: &Backend| self.is_configured(b) && self.circuit_breakers.allows(&b.name);
+        let tier = |keep: &dyn Fn(&Backend) -> bool| -> Vec<(&str, u32)> {
+            members
+                .iter()
+                .filter(|(b, _)| keep(b))
+                .map(|(b, weight)| (b.name.as_str(), *weight))
+                .collect()
+        };
+        // Fall back to members with an open circuit, then to unconfigured
+        // ones, so the request still fails with the member's own error.
+        let mut pool = tier(&usable);
+        if pool.is_empty() {
+            pool = tier(&|b| self.is_configured(b));
+        }
+        if pool.is_empty() {
+            pool = tier(&|_| true);
+        }
+
+        let chosen = self
+            .group_balancer
+            .select(&group.name, group.strategy, &pool, pin, p50_latency)
+            .ok_or_else(|| BackendError::BackendNotFound {
+                backend: group.name.clone(),
+            })?;
+        members
+            .into_iter()
+            .map(|(b, _)| b)
+            .find(|b| b.name == chosen)
+            .ok_or(BackendError::BackendNotFound { backend: chosen })
+    }
+
+    /// Member selection state for backend groups.
+    pub fn group_balancer(&self) -> GroupBalancer {
+        self.group_balancer.clone()
+    }
+
+    /// API key selection for backends with several keys.
+    pub fn key_rotator(&self) -> KeyRotator {
+        self.key_rotator.clone()
+    }
+
     /// Get the full current configuration.
     pub fn get_config(&self) -> Config {
         self.inner.read().config.clone()
     }
 
+    /// Get the configured routing rules, in evaluation order.
+    pub fn routing_rules(&self) -> Vec<RoutingRule> {
+        self.inner.read().config.routing.rules.clone()
+    }
+
+    /// Get the status-based retry settings.
+    pub fn retry_config(&self) -> RetryConfig {
+        self.inner.read().config.retry.clone()
+    }
+
+    /// Get the trace export settings.
+    pub fn tracing_config(&self) -> TracingConfig {
+        self.inner.read().config.tracing.clone()
+    }
+
+    /// Get the usage ledger settings.
+    pub fn usage_ledger_config(&self) -> UsageLedgerConfig {
+        self.inner.read().config.usage_ledger.clone()
+    }
+
+    /// Get the spending limits.
+    pub fn budgets_config(&self) -> BudgetsConfig {
+        self.inner.read().config.budgets.clone()
+    }
+
     /// Get config and active backend atomically under a single lock.
     pub fn get_config_and_active_backend(&self) -> (Config, String) {
         let state = self.inner.read();
         (state.config.clone(), state.active_backend.clone())
     }
 
-    /// Switch to a different backend.
+    /// Switch to a different backend or backend group.
     ///
     /// # Arguments
-    /// * `backend_id` - The ID of the backend to switch to
+    /// * `backend_id` - The ID of the backend or group to switch to
     ///
     /// # Errors
     /// Returns error if the backend doesn't exist. State is unchanged on error.
@@ -254,7 +400,7 @@ impl BackendState {
         let mut state = self.inner.write();
 
         // Validate the target backend exists
-        if !state.config.backends.iter().any(|b| b.name == backend_id) {
+        if !has_backend_or_group(&state.config, backend_id) {
             return Err(BackendError::BackendNotFound {
                 backend: backend_id.to_string(),
             });
@@ -270,8 +416,9 @@ impl BackendState {
             timestamp: SystemTime::now(),
             old_backend: Some(state.active_backend.clone()),
             new_backend: backend_id.to_string(),
+            failover: false,
         };
-        state.switch_log.push(entry);
+        state.log_switch(entry);
 
         // Perform the atomic switch
         let old_backend = state.active_backend.clone();
@@ -283,15 +430,47 @@ impl BackendState {
         Ok(())
     }
 
-    /// Get the switch log for debugging/auditing.
+    /// Record a failover hop in the switch log.
+    ///
+    /// The active backend is left unchanged: failover only redirects the
+    /// request that failed, not subsequent ones.
+    pub fn record_failover(&self, from: &str, to: &str) {
+        let mut state = self.inner.write();
+        state.log_switch(SwitchLogEntry {
+            timestamp: SystemTime::now(),
+            old_backend: Some(from.to_string()),
+            new_backend: to.to_string(),
+            failover: true,
+        });
+    }
+
+    /// Circuit breakers tracking the health of each backend.
+    pub fn circuit_breakers(&self) -> CircuitBreakers {
+        self.circuit_breakers.clone()
+    }
+
+    /// Spend totals per session, day and backend.
+    pub fn budgets(&self) -> Budgets {
+        self.budgets.clone()
+    }
+
+    /// Latest active health probe results.
+    pub fn probe_results(&self) -> ProbeResults {
+        self.probe_results.clone()
+    }
+
+    /// Get the switch log for debugging/auditing, oldest entry first.
+    ///
+    /// Only the last [`SWITCH_LOG_CAPACITY`] switches are kept.
     pub fn get_switch_log(&self) -> Vec<SwitchLogEntry> {
-        self.inner.read().switch_log.clone()
+        self.inner.read().switch_log.iter().cloned().collect()
     }
 
-    /// Validate that a backend ID exists in the current configuration.
+    /// Validate that a backend or backend group ID exists in the current
+    /// configuration.
     pub fn validate_backend(&self, backend_id: &str) -> bool {
         let state = self.inner.read();
-        state.config.backends.iter().any(|b| b.name == backend_id)
+        has_backend_or_group(&state.config, backend_id)
     }
 
     /// Get list of available backend IDs.
@@ -317,10 +496,7 @@ impl BackendState {
         let mut state = self.inner.write();
 
         // Check if current backend still exists
-        let current_exists = new_config
-            .backends
-            .iter()
-            .any(|b| b.name == state.active_backend);
+        let current_exists = has_backend_or_group(&new_config, &state.active_backend);
 
         if !current_exists {
             // Switch to default or first available
@@ -336,12 +512,22 @@ impl BackendState {
                 timestamp: SystemTime::now(),
                 old_backend: Some(state.active_backend.clone()),
                 new_backend: new_active.clone(),
+                failover: false,
             };
-            state.switch_log.push(entry);
+            state.log_switch(entry);
             state.active_backend = new_active;
         }
 
+        self.circuit_breakers.set_config(new_config.circuit_breaker.clone());
+        self.budgets.set_config(new_config.budgets.clone());
+        self.group_balancer.reset();
+        self.key_rotator.reset();
         state.config = new_config;
         Ok(())
     }
 }
+
+fn has_backend_or_group(config: &Config, name: &str) -> bool {
+    config.backends.iter().any(|b| b.name == name)
+        || config.backend_groups.iter().any(|g| g.name == name)
+}
@@ -3,8 +3,8 @@
 //! Builds the appropriate authentication headers based on
 //! backend configuration and resolved credentials.
 
-use super::credentials::{AuthType, CredentialStatus};
-use super::types::Backend;
+use super::credentials::{AuthType, CredentialStatus, SecureString};
+use super::types::{Backend, BackendProtocol};
 
 /// Header name and value for authentication.
 pub type AuthHeader = (String, String);
@@ -13,20 +13,32 @@ pub type AuthHeader = (String, String);
 ///
 /// Returns `Some((header_name, header_value))` if auth is configured,
 /// or `None` if no auth is needed or credentials are missing.
+/// `api_key` auth on Gemini backends uses `x-goog-api-key`. `bedrock`
+/// requests carry no static header; they are SigV4-signed when sent.
 pub fn build_auth_header(backend: &Backend) -> Option<AuthHeader> {
-    let cred = backend.resolve_credential();
-    let auth_type = backend.auth_type();
+    match backend.resolve_credential() {
+        CredentialStatus::Configured(key) => build_auth_header_with_key(backend, &key),
+        CredentialStatus::Unconfigured { .. }
+        | CredentialStatus::NoAuth
+        | CredentialStatus::Pending => None,
+    }
+}
 
-    match (auth_type, cred) {
-        (AuthType::ApiKey, CredentialStatus::Configured(key)) => {
-            Some(("x-api-key".to_string(), key.expose().to_string()))
+/// Build the authentication header for a backend using `key`, e.g. one
+/// picked from its `api_keys`.
+pub fn build_auth_header_with_key(backend: &Backend, key: &SecureString) -> Option<AuthHeader> {
+    match backend.auth_type() {
+        AuthType::ApiKey => {
+            let name = match backend.protocol {
+                BackendProtocol::Gemini => "x-goog-api-key",
+                _ => "x-api-key",
+            };
+            Some((name.to_string(), key.expose().to_string()))
         }
-        (AuthType::Bearer, CredentialStatus::Configured(key)) => Some((
+        AuthType::Bearer | AuthType::Vertex => Some((
             "Authorization".to_string(),
             format!("Bearer {}", key.expose()),
         )),
-        (AuthType::Passthrough, _) => None,
-        (_, CredentialStatus::Unconfigured { .. }) => None,
-        (_, CredentialStatus::NoAuth) => None,
+        AuthType::Bedrock | AuthType::Passthrough => None,
     }
 }
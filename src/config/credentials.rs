@@ -1,9 +1,65 @@
 //! Credential resolution from configuration.
 //!
 //! This module provides secure handling of API keys and credentials
-//! resolved from the config at runtime.
+//! resolved from the config at runtime. API keys come from the config
+//! itself, an environment variable, a file or a helper command.
 
-use super::types::Backend;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use super::loader::expand_tilde;
+use super::types::{ApiKeySource, Backend};
+
+/// Command used for `vertex` auth when `token_command` is not set.
+const DEFAULT_TOKEN_COMMAND: &str = "gcloud auth print-access-token";
+
+/// How long a `token_command` or `api_key_command` result is reused before
+/// the command runs again, unless the backend sets its own TTL.
+const COMMAND_TTL: Duration = Duration::from_secs(300);
+
+/// How long a credential command may run before it is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait after a credential command fails before it runs again; doubles
+/// with every further failure, up to [`COMMAND_TTL`].
+const FAILURE_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Last outcome of each credential command, keyed by command. The lock is
+/// only held to read or store an outcome, never while a command runs.
+static COMMAND_CACHE: LazyLock<Mutex<HashMap<String, CommandOutcome>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// One lock per command, so concurrent refreshes share one invocation.
+static COMMAND_RUNS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// What a credential command last printed, or why it failed.
+#[derive(Clone)]
+struct CommandOutcome {
+    result: Result<SecureString, String>,
+    /// Failures in a row, for the backoff.
+    failures: u32,
+    at: Instant,
+}
+
+impl CommandOutcome {
+    /// Whether the command should run again.
+    fn expired(&self, ttl: Duration) -> bool {
+        let wait = match self.result {
+            Ok(_) => ttl,
+            Err(_) => FAILURE_BACKOFF
+                .saturating_mul(1u32.checked_shl(self.failures.saturating_sub(1)).unwrap_or(u32::MAX))
+                .min(COMMAND_TTL),
+        };
+        self.at.elapsed() >= wait
+    }
+}
 
 /// Authentication type for API requests.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,6 +68,10 @@ pub enum AuthType {
     ApiKey,
     /// Standard `Authorization: Bearer` header.
     Bearer,
+    /// AWS Bedrock: SigV4-signed requests with AWS access keys.
+    Bedrock,
+    /// Google Vertex AI: `Authorization: Bearer` from `token_command`.
+    Vertex,
     /// Passthrough: forward original client headers unchanged (for OAuth).
     Passthrough,
 }
@@ -23,6 +83,8 @@ impl AuthType {
         match s.to_lowercase().as_str() {
             "api_key" => AuthType::ApiKey,
             "bearer" => AuthType::Bearer,
+            "bedrock" => AuthType::Bedrock,
+            "vertex" => AuthType::Vertex,
             _ => AuthType::Passthrough,
         }
     }
@@ -32,7 +94,7 @@ impl AuthType {
     /// When true, incoming auth headers should be stripped and replaced
     /// with the backend's configured credentials.
     pub fn uses_own_credentials(&self) -> bool {
-        matches!(self, AuthType::ApiKey | AuthType::Bearer)
+        !matches!(self, AuthType::Passthrough)
     }
 }
 
@@ -55,6 +117,17 @@ impl SecureString {
     pub fn expose(&self) -> &str {
         &self.0
     }
+
+    /// Last four characters, for telling keys apart in the UI.
+    /// Short values reveal nothing.
+    pub fn hint(&self) -> String {
+        let chars: Vec<char> = self.0.chars().collect();
+        if chars.len() < 12 {
+            return "…".to_string();
+        }
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("…{}", tail)
+    }
 }
 
 impl std::fmt::Debug for SecureString {
@@ -69,6 +142,14 @@ impl std::fmt::Display for SecureString {
     }
 }
 
+/// AWS credentials for SigV4 signing.
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: SecureString,
+    pub session_token: Option<SecureString>,
+}
+
 /// Status of credential resolution for a backend.
 #[derive(Debug, Clone)]
 pub enum CredentialStatus {
@@ -81,6 +162,10 @@ pub enum CredentialStatus {
     },
     /// No authentication required for this backend.
     NoAuth,
+    /// Credential comes from a command that has not run yet; it runs
+    /// before the backend's next request (see
+    /// [`Backend::refresh_credentials`]).
+    Pending,
 }
 
 impl Backend {
@@ -89,31 +174,136 @@ impl Backend {
         AuthType::parse(&self.auth_type_str)
     }
 
-    /// Resolve the API key from environment variable.
+    /// Resolve the credential for this backend's auth type.
     ///
     /// This is called on-demand and NOT cached, enabling hot-reload
-    /// of credentials when environment variables change.
+    /// of credentials when environment variables or key files change.
+    /// `api_key_command`s and `vertex`'s `token_command` are never run
+    /// here: their last output is used, kept fresh by
+    /// [`Backend::refresh_credentials`].
     pub fn resolve_credential(&self) -> CredentialStatus {
         match self.auth_type() {
             AuthType::Passthrough => CredentialStatus::NoAuth,
+            AuthType::ApiKey | AuthType::Bearer => match self.resolve_api_key() {
+                Ok(key) => CredentialStatus::Configured(key),
+                Err(_) if self.api_key_sources().iter().any(ApiKeySource::is_pending) => {
+                    CredentialStatus::Pending
+                }
+                Err(reason) => CredentialStatus::Unconfigured { reason },
+            },
+            AuthType::Bedrock => match self.aws_credentials() {
+                Some(credentials) => CredentialStatus::Configured(credentials.secret_access_key),
+                None => CredentialStatus::Unconfigured {
+                    reason: "aws_access_key_id / aws_secret_access_key are not set".to_string(),
+                },
+            },
+            AuthType::Vertex => {
+                if self.vertex_project.is_none() {
+                    return CredentialStatus::Unconfigured {
+                        reason: "vertex_project is not set".to_string(),
+                    };
+                }
+                match cached_command_output(self.vertex_token_command()) {
+                    Some(Ok(token)) => CredentialStatus::Configured(token),
+                    Some(Err(reason)) => CredentialStatus::Unconfigured { reason },
+                    None => CredentialStatus::Pending,
+                }
+            }
+        }
+    }
+
+    /// Run this backend's credential commands whose output is missing or
+    /// expired, off the async runtime and bounded by a timeout. Call before
+    /// sending a request; the synchronous accessors only read the output.
+    pub async fn refresh_credentials(&self) {
+        match self.auth_type() {
             AuthType::ApiKey | AuthType::Bearer => {
-                if let Some(ref key) = self.api_key {
-                    if !key.is_empty() {
-                        return CredentialStatus::Configured(SecureString::new(key.clone()));
+                for source in self.api_key_sources() {
+                    if let ApiKeySource::Command { command, ttl_seconds } = source {
+                        let ttl = ttl_seconds.map_or(COMMAND_TTL, Duration::from_secs);
+                        refresh_command("api_key_command", &command, ttl).await;
                     }
                 }
-                CredentialStatus::Unconfigured {
-                    reason: "api_key is not set".to_string(),
+            }
+            AuthType::Vertex if self.vertex_project.is_some() => {
+                refresh_command("token_command", self.vertex_token_command(), COMMAND_TTL).await;
+            }
+            _ => {}
+        }
+    }
+
+    fn vertex_token_command(&self) -> &str {
+        self.token_command.as_deref().unwrap_or(DEFAULT_TOKEN_COMMAND)
+    }
+
+    /// Sources of this backend's API keys: the first of `api_key`,
+    /// `api_key_env`, `api_key_file` and `api_key_command` that is set,
+    /// followed by `api_keys`.
+    pub fn api_key_sources(&self) -> Vec<ApiKeySource> {
+        let primary = if let Some(key) = self.api_key.as_ref().filter(|key| !key.is_empty()) {
+            Some(ApiKeySource::Literal(key.clone()))
+        } else if let Some(env) = &self.api_key_env {
+            Some(ApiKeySource::Env { env: env.clone() })
+        } else if let Some(file) = &self.api_key_file {
+            Some(ApiKeySource::File { file: file.clone() })
+        } else {
+            self.api_key_command.as_ref().map(|command| ApiKeySource::Command {
+                command: command.clone(),
+                ttl_seconds: self.api_key_command_ttl_seconds,
+            })
+        };
+        primary.into_iter().chain(self.api_keys.iter().cloned()).collect()
+    }
+
+    /// The first API key that resolves, or why the first source failed.
+    fn resolve_api_key(&self) -> Result<SecureString, String> {
+        let mut first_error = None;
+        for source in self.api_key_sources() {
+            match source.resolve() {
+                Ok(key) => return Ok(key),
+                Err(reason) => {
+                    first_error.get_or_insert(reason);
                 }
             }
         }
+        Err(first_error.unwrap_or_else(|| {
+            "api_key is not set (or api_key_env, api_key_file, api_key_command)".to_string()
+        }))
+    }
+
+    /// Resolve AWS credentials from config, falling back to the standard
+    /// `AWS_*` environment variables.
+    pub fn aws_credentials(&self) -> Option<AwsCredentials> {
+        let access_key_id = config_or_env(&self.aws_access_key_id, &["AWS_ACCESS_KEY_ID"])?;
+        let secret_access_key = config_or_env(&self.aws_secret_access_key, &["AWS_SECRET_ACCESS_KEY"])?;
+        Some(AwsCredentials {
+            access_key_id,
+            secret_access_key: SecureString::new(secret_access_key),
+            session_token: config_or_env(&self.aws_session_token, &["AWS_SESSION_TOKEN"])
+                .map(SecureString::new),
+        })
+    }
+
+    /// AWS region for SigV4: config, then `AWS_REGION` / `AWS_DEFAULT_REGION`,
+    /// then the `bedrock-runtime.{region}.amazonaws.com` base_url host.
+    pub fn aws_region(&self) -> Option<String> {
+        config_or_env(&self.aws_region, &["AWS_REGION", "AWS_DEFAULT_REGION"]).or_else(|| {
+            let url = reqwest::Url::parse(&self.base_url).ok()?;
+            let (service, rest) = url.host_str()?.split_once('.')?;
+            if !service.starts_with("bedrock-runtime") {
+                return None;
+            }
+            rest.split('.').next().map(String::from)
+        })
     }
 
     /// Check if this backend is configured (has valid credentials or doesn't need them).
+    ///
+    /// A credential command that has not run yet counts as configured.
     pub fn is_configured(&self) -> bool {
         matches!(
             self.resolve_credential(),
-            CredentialStatus::Configured(_) | CredentialStatus::NoAuth
+            CredentialStatus::Configured(_) | CredentialStatus::NoAuth | CredentialStatus::Pending
         )
     }
 
@@ -124,13 +314,17 @@ impl Backend {
         self.thinking_compat.unwrap_or(false)
     }
 
-    /// Resolve model ID via family-based mapping.
+    /// Resolve model ID for this backend.
     ///
-    /// Matches the request model against Anthropic family keywords (opus/sonnet/haiku)
-    /// and returns the backend-specific model name if configured.
-    /// Returns `None` when no mapping applies (passthrough).
-    pub fn resolve_model(&self, original: &str) -> Option<&str> {
-        if original.contains("opus") {
+    /// Tries `model_map` entries in order, then matches the request model
+    /// against Anthropic family keywords (opus/sonnet/haiku), then falls back
+    /// to `default_model`. Returns `None` when no mapping applies
+    /// (passthrough).
+    pub fn resolve_model(&self, original: &str) -> Option<Cow<'_, str>> {
+        if let Some(mapped) = self.model_map.iter().find_map(|entry| entry.apply(original)) {
+            return Some(mapped);
+        }
+        let family = if original.contains("opus") {
             self.model_opus.as_deref()
         } else if original.contains("sonnet") {
             self.model_sonnet.as_deref()
@@ -138,7 +332,169 @@ impl Backend {
             self.model_haiku.as_deref()
         } else {
             None
+        };
+        family.or(self.default_model.as_deref()).map(Cow::Borrowed)
+    }
+
+    /// Whether an upstream status should hand the request to the next
+    /// backend in the failover chain.
+    ///
+    /// Uses `failover_on_status` when set, otherwise the overload set:
+    /// 429, 529 and 500/502/503/504.
+    pub fn should_failover_on_status(&self, status: u16) -> bool {
+        match &self.failover_on_status {
+            Some(statuses) => statuses.contains(&status),
+            None => matches!(status, 429 | 500 | 502 | 503 | 504 | 529),
         }
     }
+}
+
+impl ApiKeySource {
+    /// Read the key. Environment variables and files are read on every
+    /// call; commands are not run, their last output is used (see
+    /// [`Backend::refresh_credentials`]).
+    pub fn resolve(&self) -> Result<SecureString, String> {
+        match self {
+            ApiKeySource::Literal(key) => non_empty(key.clone(), || "api_keys entry is empty".to_string()),
+            ApiKeySource::Env { env } => {
+                let key = std::env::var(env)
+                    .map_err(|_| format!("environment variable {} is not set", env))?;
+                non_empty(key, || format!("environment variable {} is empty", env))
+            }
+            ApiKeySource::File { file } => {
+                let key = std::fs::read_to_string(expand_tilde(file))
+                    .map_err(|e| format!("api_key_file {} could not be read: {}", file, e))?;
+                non_empty(key, || format!("api_key_file {} is empty", file))
+            }
+            ApiKeySource::Command { command, .. } => cached_command_output(command)
+                .unwrap_or_else(|| Err("api_key_command has not run yet".to_string())),
+        }
+    }
+
+    /// Whether this is a command that has not run yet.
+    pub fn is_pending(&self) -> bool {
+        matches!(self, ApiKeySource::Command { command, .. } if cached_command_output(command).is_none())
+    }
+}
+
+/// Non-empty config value, or the first non-empty environment variable of `vars`.
+fn config_or_env(value: &Option<String>, vars: &[&str]) -> Option<String> {
+    value
+        .clone()
+        .filter(|v| !v.is_empty())
+        .or_else(|| {
+            vars.iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|v| !v.is_empty())
+        })
+}
+
+/// Trimmed `value`, or the error from `empty` if nothing is left.
+fn non_empty(value: String, empty: impl FnOnce() -> String) -> Result<SecureString, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(empty());
+    }
+    Ok(SecureString::new(value.to_string()))
+}
+
+/// Last output of `command`, or `None` if it has not run yet. Expired
+/// output is still returned until a refresh replaces it.
+fn cached_command_output(command: &str) -> Option<Result<SecureString, String>> {
+    COMMAND_CACHE.lock().get(command).map(|outcome| outcome.result.clone())
+}
+
+/// Run `command` unless its output is fresh or it is backing off after a
+/// failure. Runs on the blocking pool, without holding the cache lock.
+async fn refresh_command(field: &'static str, command: &str, ttl: Duration) {
+    let needs_run = |command: &str| {
+        COMMAND_CACHE
+            .lock()
+            .get(command)
+            .is_none_or(|outcome| outcome.expired(ttl))
+    };
+    if !needs_run(command) {
+        return;
+    }
+    let run = COMMAND_RUNS.lock().entry(command.to_string()).or_default().clone();
+    let _running = run.lock().await;
+    // Another request may have refreshed it while we waited.
+    if !needs_run(command) {
+        return;
+    }
+
+    let owned = command.to_string();
+    let result = tokio::task::spawn_blocking(move || run_command(field, &owned))
+        .await
+        .unwrap_or_else(|e| Err(format!("{} failed: {}", field, e)));
+    if let Err(reason) = &result {
+        crate::metrics::app_log("credentials", &format!("Credential command failed: {}", reason));
+    }
+
+    let mut cache = COMMAND_CACHE.lock();
+    let failures = match (&result, cache.get(command)) {
+        (Ok(_), _) => 0,
+        (Err(_), Some(previous)) => previous.failures + 1,
+        (Err(_), None) => 1,
+    };
+    cache.insert(
+        command.to_string(),
+        CommandOutcome {
+            result,
+            failures,
+            at: Instant::now(),
+        },
+    );
+}
+
+/// Run `command` through the shell and return the secret it prints.
+///
+/// `field` names the config key in error messages. Blocks until the
+/// command exits; commands running past [`COMMAND_TIMEOUT`] are killed.
+fn run_command(field: &str, command: &str) -> Result<SecureString, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{} failed to start: {}", field, e))?;
+
+    // Drain both pipes while waiting, so a command printing more than
+    // the pipe buffer holds is not blocked on a write it never finishes.
+    let stdout = child.stdout.take().map(|pipe| std::thread::spawn(move || read_pipe(pipe)));
+    let stderr = child.stderr.take().map(|pipe| std::thread::spawn(move || read_pipe(pipe)));
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= COMMAND_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} timed out after {}s", field, COMMAND_TIMEOUT.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(format!("{} failed: {}", field, e)),
+        }
+    };
+
+    if !status.success() {
+        let stderr = join_reader(stderr);
+        return Err(format!("{} failed ({}): {}", field, status, stderr.trim()));
+    }
+    non_empty(join_reader(stdout), || format!("{} printed nothing", field))
+}
+
+/// Everything written to a child's output pipe until it closed.
+fn read_pipe(mut pipe: impl Read) -> String {
+    let mut out = String::new();
+    let _ = pipe.read_to_string(&mut out);
+    out
+}
 
+/// Output collected by a [`read_pipe`] thread.
+fn join_reader(reader: Option<std::thread::JoinHandle<String>>) -> String {
+    reader.and_then(|r| r.join().ok()).unwrap_or_default()
 }
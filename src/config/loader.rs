@@ -49,6 +49,18 @@ impl Config {
         Self::load_from(&Self::config_path())
     }
 
+    /// Loads configuration for `--replay`, which never contacts a backend:
+    /// like [`load`](Self::load), but backends need no credentials.
+    pub fn load_for_replay() -> Result<Self, ConfigError> {
+        let path = Self::config_path();
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let config = Self::read_from(&path)?;
+        config.validate_with(false)?;
+        Ok(config)
+    }
+
     /// Loads configuration from a specific path.
     ///
     /// - If the file doesn't exist, returns `Config::default()`.
@@ -59,7 +71,13 @@ impl Config {
             let config = Config::default();
             return Ok(config);
         }
+        let config = Self::read_from(path)?;
+        config.validate()?;
+        Ok(config)
+    }
 
+    /// Parses the existing config file at `path` without validating it.
+    fn read_from(path: &Path) -> Result<Self, ConfigError> {
         // Open file and acquire shared lock for reading
         let file = File::open(path).map_err(|e| ConfigError::ReadError {
             path: path.to_path_buf(),
@@ -88,7 +106,6 @@ impl Config {
             source: e,
         })?;
 
-        config.validate()?;
         Ok(config)
     }
 
@@ -99,6 +116,12 @@ impl Config {
     /// - The active backend exists in the backends list
     /// - The active backend has valid credentials (or doesn't require them)
     pub fn validate(&self) -> Result<(), ConfigError> {
+        self.validate_with(true)
+    }
+
+    /// [`validate`](Self::validate), checking credentials only when
+    /// `check_credentials` is set.
+    fn validate_with(&self, check_credentials: bool) -> Result<(), ConfigError> {
         if self.backends.is_empty() {
             return Err(ConfigError::ValidationError {
                 message: "At least one backend must be configured".to_string(),
@@ -107,9 +130,10 @@ impl Config {
 
         let active = &self.defaults.active;
         let active_backend = self.backends.iter().find(|b| &b.name == active);
+        let active_group = self.backend_groups.iter().find(|g| &g.name == active);
 
-        match active_backend {
-            None => {
+        match (active_backend, active_group) {
+            (None, None) => {
                 return Err(ConfigError::ValidationError {
                     message: format!(
                         "Active backend '{}' not found in configured backends",
@@ -117,8 +141,8 @@ impl Config {
                     ),
                 });
             }
-            Some(backend) => {
-                if !backend.is_configured() {
+            (Some(backend), _) => {
+                if check_credentials && !backend.is_configured() {
                     return Err(ConfigError::ValidationError {
                         message: format!(
                             "Active backend '{}' is not configured - set api_key in config",
@@ -127,10 +151,25 @@ impl Config {
                     });
                 }
             }
+            (None, Some(group)) => {
+                let any_configured = group.members.iter().any(|m| {
+                    self.backends
+                        .iter()
+                        .any(|b| b.name == m.backend && b.is_configured())
+                });
+                if check_credentials && !any_configured {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Active backend group '{}' has no configured members - set api_key in config",
+                            group.name
+                        ),
+                    });
+                }
+            }
         }
 
         if let Some(ref at) = self.agents {
-            if !self.backends.iter().any(|b| b.name == at.teammate_backend) {
+            if !self.has_backend_or_group(&at.teammate_backend) {
                 return Err(ConfigError::ValidationError {
                     message: format!(
                         "agents.teammate_backend '{}' not found in configured backends",
@@ -139,7 +178,7 @@ impl Config {
                 });
             }
             if let Some(ref sb) = at.subagent_backend {
-                if !self.backends.iter().any(|b| b.name == *sb) {
+                if !self.has_backend_or_group(sb) {
                     return Err(ConfigError::ValidationError {
                         message: format!(
                             "agents.subagent_backend '{}' not found in configured backends",
@@ -150,9 +189,161 @@ impl Config {
             }
         }
 
+        for backend in &self.backends {
+            for target in &backend.failover {
+                if target == &backend.name {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend '{}' lists itself in failover",
+                            backend.name
+                        ),
+                    });
+                }
+                if !self.backends.iter().any(|b| &b.name == target) {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend '{}' failover target '{}' not found in configured backends",
+                            backend.name, target
+                        ),
+                    });
+                }
+            }
+        }
+
+        for backend in &self.backends {
+            for entry in backend.model_map.iter().filter(|entry| entry.regex) {
+                if let Err(e) = entry.compile() {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend '{}' model_map pattern '{}' is not a valid regex: {}",
+                            backend.name, entry.from, e
+                        ),
+                    });
+                }
+            }
+            let rules = &backend.headers;
+            for (name, value) in rules.set.iter().chain(&rules.append) {
+                if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend '{}' header '{}' is not a valid header name",
+                            backend.name, name
+                        ),
+                    });
+                }
+                if value.chars().any(char::is_control) {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend '{}' header '{}' value contains control characters",
+                            backend.name, name
+                        ),
+                    });
+                }
+            }
+        }
+
+        for group in &self.backend_groups {
+            if self.backends.iter().any(|b| b.name == group.name) {
+                return Err(ConfigError::ValidationError {
+                    message: format!(
+                        "Backend group '{}' has the same name as a backend",
+                        group.name
+                    ),
+                });
+            }
+            if self.backend_groups.iter().filter(|g| g.name == group.name).count() > 1 {
+                return Err(ConfigError::ValidationError {
+                    message: format!("Backend group '{}' is defined more than once", group.name),
+                });
+            }
+            if group.members.is_empty() {
+                return Err(ConfigError::ValidationError {
+                    message: format!("Backend group '{}' has no members", group.name),
+                });
+            }
+            for member in &group.members {
+                if member.weight == 0 {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend group '{}' member '{}' has weight 0",
+                            group.name, member.backend
+                        ),
+                    });
+                }
+                if !self.backends.iter().any(|b| b.name == member.backend) {
+                    return Err(ConfigError::ValidationError {
+                        message: format!(
+                            "Backend group '{}' member '{}' not found in configured backends",
+                            group.name, member.backend
+                        ),
+                    });
+                }
+            }
+        }
+
+        for rule in &self.routing.rules {
+            if !self.has_backend_or_group(&rule.backend) {
+                return Err(ConfigError::ValidationError {
+                    message: format!(
+                        "Routing rule '{}' backend '{}' not found in configured backends",
+                        rule.name, rule.backend
+                    ),
+                });
+            }
+        }
+
+        let budgets = &self.budgets;
+        if !(budgets.warn_at > 0.0 && budgets.warn_at <= 1.0) {
+            return Err(ConfigError::ValidationError {
+                message: format!("budgets.warn_at must be in (0, 1], got {}", budgets.warn_at),
+            });
+        }
+        for (name, limit) in &budgets.backends {
+            let Some(backend) = self.backends.iter().find(|b| b.name == *name) else {
+                return Err(ConfigError::ValidationError {
+                    message: format!("Budget backend '{}' not found in configured backends", name),
+                });
+            };
+            // Spend is only priced with the backend's `pricing`, so a USD
+            // limit without it would never be reached.
+            if limit.usd.is_some() && backend.pricing.is_none() {
+                return Err(ConfigError::ValidationError {
+                    message: format!(
+                        "Budget backend '{}' has a usd limit but the backend has no pricing",
+                        name
+                    ),
+                });
+            }
+        }
+        let scopes = [("session", &budgets.session), ("day", &budgets.day)];
+        for (scope, limit) in scopes {
+            let has_usd = limit.is_some_and(|l| l.usd.is_some());
+            if has_usd && self.backends.iter().all(|b| b.pricing.is_none()) {
+                return Err(ConfigError::ValidationError {
+                    message: format!("budgets.{}.usd is set but no backend has pricing", scope),
+                });
+            }
+        }
+        if let Some(fallback) = &budgets.fallback_backend {
+            if !self.backends.iter().any(|b| b.name == *fallback) {
+                return Err(ConfigError::ValidationError {
+                    message: format!(
+                        "Budget fallback_backend '{}' not found in configured backends",
+                        fallback
+                    ),
+                });
+            }
+        }
+
         Ok(())
     }
 
+    /// Whether `name` is a configured backend or backend group.
+    fn has_backend_or_group(&self, name: &str) -> bool {
+        self.backends.iter().any(|b| b.name == name)
+            || self.backend_groups.iter().any(|g| g.name == name)
+    }
+
     /// Log the status of all backends at startup.
     ///
     /// Logs warnings for unconfigured backends and info for configured ones.
@@ -173,6 +364,9 @@ impl Config {
                 CredentialStatus::NoAuth => {
                     eprintln!("Backend '{}' configured (no auth required)", backend.name);
                 }
+                CredentialStatus::Pending => {
+                    eprintln!("Backend '{}' configured (credential command runs on first request)", backend.name);
+                }
             }
         }
     }
@@ -192,6 +386,14 @@ impl Config {
 
 /// Save claude_settings section to the config file.
 ///
+/// `path` with a leading `~/` replaced by the home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 /// Loads the existing Config, updates the `claude_settings` field,
 /// and writes the full config back. If the file doesn't exist,
 /// starts from defaults.
@@ -8,18 +8,20 @@ mod auth;
 pub mod claude_settings;
 mod credentials;
 mod loader;
+mod model_map;
 mod store;
 mod types;
 
-pub use auth::{build_auth_header, AuthHeader};
+pub use auth::{build_auth_header, build_auth_header_with_key, AuthHeader};
 pub use claude_settings::{
     ClaudeSettingsManager, SettingDef, SettingId, SettingSection, SettingsFieldSnapshot,
 };
-pub use credentials::{AuthType, CredentialStatus, SecureString};
-pub use loader::{save_claude_settings, ConfigError};
+pub use credentials::{AuthType, AwsCredentials, CredentialStatus, SecureString};
+pub use loader::{expand_tilde, save_claude_settings, ConfigError};
 pub use store::ConfigStore;
 pub use types::{
-    AgentsConfig, Backend, BackendPricing, Config, DebugLogDestination, DebugLogFormat,
+    AgentsConfig, ApiKeySource, Backend, BackendGroup, BudgetLimit, BudgetsConfig, BodyRules, BackendPricing, EmulateRules, BackendProtocol, CircuitBreakerConfig, Config, DebugLogDestination, DebugLogFormat,
     DebugLogLevel, DebugLogRotation, DebugLogRotationMode, DebugLoggingConfig, Defaults,
-    ProxyConfig, TerminalConfig,
+    GroupMember, GroupStrategy, HeaderConfig, HeaderRules, HeaderSegment, HealthCheckConfig, HealthCheckMethod, KeyRotation, ModelMapEntry, OtlpFormat, ProxyConfig, RetryConfig, RoutingConfig, RoutingRule, TerminalConfig, TracingConfig,
+    UiConfig, UsageLedgerConfig,
 };
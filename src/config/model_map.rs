@@ -0,0 +1,41 @@
+//! `[[backends.model_map]]` matching.
+//!
+//! Regex patterns are compiled once and cached, keyed by pattern. They must
+//! match the whole model name.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+use regex::Regex;
+
+use super::types::ModelMapEntry;
+
+/// Compiled `from` patterns; `None` for patterns that do not compile.
+static REGEX_CACHE: LazyLock<Mutex<HashMap<String, Option<Regex>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl ModelMapEntry {
+    /// The model to send for a request for `model`, if this entry matches.
+    pub fn apply(&self, model: &str) -> Option<Cow<'_, str>> {
+        if !self.regex {
+            return (self.from == model).then_some(Cow::Borrowed(self.to.as_str()));
+        }
+
+        let regex = REGEX_CACHE
+            .lock()
+            .entry(self.from.clone())
+            .or_insert_with(|| self.compile().ok())
+            .clone()?;
+        let captures = regex.captures(model)?;
+        let mut target = String::new();
+        captures.expand(&self.to, &mut target);
+        Some(Cow::Owned(target))
+    }
+
+    /// Compile `from` as a regex anchored to the whole model name.
+    pub fn compile(&self) -> Result<Regex, regex::Error> {
+        Regex::new(&format!("^(?:{})$", self.from))
+    }
+}
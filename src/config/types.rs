@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Root configuration container.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,15 +9,42 @@ pub struct Config {
     pub proxy: ProxyConfig,
     #[serde(default)]
     pub terminal: TerminalConfig,
+    /// Layout of the UI chrome.
+    #[serde(default)]
+    pub ui: UiConfig,
     #[serde(default)]
     pub debug_logging: DebugLoggingConfig,
     /// Claude Code settings (toggle-based, persisted as string→bool map).
     #[serde(default)]
     pub claude_settings: HashMap<String, bool>,
     pub backends: Vec<Backend>,
+    /// Load-balanced groups of backends, selectable like a backend.
+    #[serde(default)]
+    pub backend_groups: Vec<BackendGroup>,
     /// Agents routing configuration.
     #[serde(default)]
     pub agents: Option<AgentsConfig>,
+    /// Per-backend circuit breaker settings.
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Active health probing of configured backends.
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+    /// Rule-based request routing.
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Retries of rate-limited and overloaded upstream responses.
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// OpenTelemetry trace export of proxied requests.
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    /// Persistent per-request usage records.
+    #[serde(default)]
+    pub usage_ledger: UsageLedgerConfig,
+    /// Spending limits per session, day and backend.
+    #[serde(default)]
+    pub budgets: BudgetsConfig,
 }
 
 /// Default settings for the application.
@@ -66,6 +93,306 @@ pub struct TerminalConfig {
     pub scrollback_lines: usize,
 }
 
+/// UI settings (`[ui]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub header: HeaderConfig,
+}
+
+/// Header bar layout (`[ui.header]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderConfig {
+    /// Segments shown after the status, in priority order: narrow
+    /// terminals drop them from the end (default: all of them).
+    #[serde(default = "default_header_segments")]
+    pub segments: Vec<HeaderSegment>,
+}
+
+/// One item of the header bar.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderSegment {
+    /// Active backend.
+    Backend,
+    /// Requests served.
+    Requests,
+    /// Cost of the session from backend `pricing`.
+    Cost,
+    /// Input, output and cache tokens of the session.
+    Tokens,
+    /// Request in progress: elapsed time and, when streaming, tokens so far.
+    InFlight,
+    /// Share of non-2xx responses per backend.
+    Errors,
+    /// Time since the proxy started.
+    Uptime,
+}
+
+/// Circuit breaker settings, applied to every backend independently.
+///
+/// Failures are timeouts, connection errors, 429 and 5xx responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Enable circuit breaking (default: true).
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Consecutive failures that open the circuit (default: 5).
+    #[serde(default = "default_cb_consecutive_failures")]
+    pub consecutive_failures: u32,
+    /// Failure ratio over the window that opens the circuit (default: 0.5).
+    #[serde(default = "default_cb_error_rate")]
+    pub error_rate: f64,
+    /// Number of recent outcomes in the error-rate window (default: 20).
+    #[serde(default = "default_cb_window_size")]
+    pub window_size: usize,
+    /// Outcomes required before the error rate is evaluated (default: 10).
+    #[serde(default = "default_cb_min_requests")]
+    pub min_requests: usize,
+    /// Seconds an open circuit waits before letting a probe through (default: 30).
+    #[serde(default = "default_cb_open_seconds")]
+    pub open_seconds: u64,
+}
+
+/// Active health probe settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
+    /// Enable background probing (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds between probe rounds (default: 60).
+    #[serde(default = "default_health_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Per-probe timeout in seconds (default: 5).
+    #[serde(default = "default_health_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// What each probe sends (default: "models").
+    #[serde(default)]
+    pub method: HealthCheckMethod,
+    /// Model for "message" probes, subject to the backend's model mapping.
+    #[serde(default = "default_health_probe_model")]
+    pub probe_model: String,
+}
+
+/// Status-based retry settings.
+///
+/// Applied to the last backend a request can go to; earlier backends in a
+/// failover chain hand the request on instead of waiting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Upstream statuses retried on the same backend (default: [429, 529]).
+    #[serde(default = "default_retry_on_status")]
+    pub on_status: Vec<u16>,
+    /// Retries per request for those statuses (default: 2, 0 disables).
+    #[serde(default = "default_max_status_retries")]
+    pub max_status_retries: u32,
+    /// Cap on the total time a request spends waiting between `on_status`
+    /// retries, in seconds (default: 20). A `retry-after` beyond it is not
+    /// waited for. Connection retries are bounded by `max_retries` alone.
+    #[serde(default = "default_retry_max_wait_seconds")]
+    pub max_wait_seconds: u64,
+    /// Re-issue a streaming request that fails before any content block
+    /// started, and end later failures with an SSE `error` event instead of
+    /// a truncated body (default: false).
+    #[serde(default)]
+    pub resume_streams: bool,
+    /// Re-issues per streaming request with `resume_streams` (default: 2).
+    #[serde(default = "default_max_stream_resumes")]
+    pub max_stream_resumes: u32,
+}
+
+/// OTLP/HTTP trace export: one trace per proxied request, with a child
+/// span per pipeline stage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingConfig {
+    /// Export traces and send `traceparent` upstream (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+    /// Collector traces endpoint (default: "http://127.0.0.1:4318/v1/traces").
+    #[serde(default = "default_otlp_endpoint")]
+    pub endpoint: String,
+    /// Payload encoding (default: "protobuf").
+    #[serde(default)]
+    pub format: OtlpFormat,
+    /// Extra headers for the collector, e.g. an auth token.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// `service.name` resource attribute (default: "anyclaude").
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+}
+
+/// Append-only JSONL ledger of finished requests, read by `anyclaude --usage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageLedgerConfig {
+    /// Record every proxied request (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+    /// Ledger file (default: "~/.config/anyclaude/usage.jsonl").
+    #[serde(default = "default_usage_ledger_path")]
+    pub path: String,
+}
+
+/// Spending limits (`[budgets]`).
+///
+/// Spend is the measured cost from backend `pricing` and the tokens
+/// reported by the backend. Day and backend totals reset at midnight UTC.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetsConfig {
+    /// Limit for one Claude Code session.
+    #[serde(default)]
+    pub session: Option<BudgetLimit>,
+    /// Limit for all traffic in a UTC day.
+    #[serde(default)]
+    pub day: Option<BudgetLimit>,
+    /// Daily limit per backend name.
+    #[serde(default)]
+    pub backends: BTreeMap<String, BudgetLimit>,
+    /// Share of a limit that posts a warning (default: 0.8).
+    #[serde(default = "default_budget_warn_at")]
+    pub warn_at: f64,
+    /// Backend that serves requests once a limit is reached, instead of
+    /// rejecting them. Its own `backends` limit still applies.
+    #[serde(default)]
+    pub fallback_backend: Option<String>,
+}
+
+impl BudgetsConfig {
+    /// Whether any limit is set.
+    pub fn is_enabled(&self) -> bool {
+        self.session.is_some() || self.day.is_some() || !self.backends.is_empty()
+    }
+}
+
+/// USD and/or token cap; whichever is reached first applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetLimit {
+    #[serde(default)]
+    pub usd: Option<f64>,
+    /// Input (including cache) plus output tokens.
+    #[serde(default)]
+    pub tokens: Option<u64>,
+}
+
+/// OTLP/HTTP payload encoding.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OtlpFormat {
+    /// `application/x-protobuf`.
+    #[default]
+    Protobuf,
+    /// `application/json`.
+    Json,
+}
+
+/// Health probe request kind.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthCheckMethod {
+    /// `GET /v1/models`.
+    #[default]
+    Models,
+    /// A 1-token `/v1/messages` request, translated for non-Anthropic
+    /// backends.
+    Message,
+    /// Plain TCP connect to the backend host.
+    Connect,
+}
+
+/// Declarative request routing, evaluated in stage 2.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Rules checked in order; the first match picks the backend.
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+}
+
+/// Route requests matching every set condition to `backend`.
+///
+/// Unset conditions match anything; token bounds are inclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// Rule name, recorded as the routing decision reason.
+    pub name: String,
+    /// Target backend (must exist in [[backends]]).
+    pub backend: String,
+    /// Substring of the requested model, e.g. a family: "haiku".
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Tool names that must all be declared in the request.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Minimum requested `max_tokens`.
+    #[serde(default)]
+    pub max_tokens_min: Option<u64>,
+    /// Maximum requested `max_tokens`.
+    #[serde(default)]
+    pub max_tokens_max: Option<u64>,
+    /// Minimum estimated input tokens.
+    #[serde(default)]
+    pub input_tokens_min: Option<u64>,
+    /// Maximum estimated input tokens.
+    #[serde(default)]
+    pub input_tokens_max: Option<u64>,
+    /// Request path, e.g. "/v1/messages/count_tokens".
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Whether extended thinking is enabled (or adaptive).
+    #[serde(default)]
+    pub thinking: Option<bool>,
+}
+
+/// A named set of backends that requests are spread across.
+///
+/// Usable wherever a backend name is: active backend, `--backend`, agent
+/// backends and routing rules. Each request is sent to one member.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackendGroup {
+    /// Group identifier (must not clash with a backend name).
+    pub name: String,
+    /// Name shown in the UI (default: `name`).
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// How the member for a request is chosen (default: "round_robin").
+    #[serde(default)]
+    pub strategy: GroupStrategy,
+    /// Member backends (must exist in [[backends]]).
+    pub members: Vec<GroupMember>,
+}
+
+impl BackendGroup {
+    /// Display name, falling back to the group name.
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// One backend of a [`BackendGroup`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMember {
+    /// Backend name.
+    pub backend: String,
+    /// Relative share for the weighted strategies (default: 1).
+    #[serde(default = "default_group_member_weight")]
+    pub weight: u32,
+}
+
+/// Member selection strategy of a [`BackendGroup`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupStrategy {
+    /// Cycle through members, each taking `weight` turns per cycle.
+    #[default]
+    RoundRobin,
+    /// Pick at random, proportionally to `weight`.
+    WeightedRandom,
+    /// Member with the fewest requests currently in flight.
+    LeastInFlight,
+    /// Member with the lowest p50 latency over recent requests; members
+    /// without samples are tried first.
+    LowestLatency,
+}
+
 /// Debug logging configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DebugLoggingConfig {
@@ -193,6 +520,86 @@ fn default_debug_rotation_max_files() -> usize {
     5
 }
 
+fn default_cb_consecutive_failures() -> u32 {
+    5
+}
+
+fn default_cb_error_rate() -> f64 {
+    0.5
+}
+
+fn default_cb_window_size() -> usize {
+    20
+}
+
+fn default_cb_min_requests() -> usize {
+    10
+}
+
+fn default_cb_open_seconds() -> u64 {
+    30
+}
+
+fn default_retry_on_status() -> Vec<u16> {
+    vec![429, 529]
+}
+
+fn default_max_status_retries() -> u32 {
+    2
+}
+
+fn default_retry_max_wait_seconds() -> u64 {
+    20
+}
+
+fn default_max_stream_resumes() -> u32 {
+    2
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://127.0.0.1:4318/v1/traces".to_string()
+}
+
+fn default_otlp_service_name() -> String {
+    "anyclaude".to_string()
+}
+
+fn default_header_segments() -> Vec<HeaderSegment> {
+    vec![
+        HeaderSegment::Backend,
+        HeaderSegment::InFlight,
+        HeaderSegment::Cost,
+        HeaderSegment::Tokens,
+        HeaderSegment::Requests,
+        HeaderSegment::Errors,
+        HeaderSegment::Uptime,
+    ]
+}
+
+fn default_budget_warn_at() -> f64 {
+    0.8
+}
+
+fn default_usage_ledger_path() -> String {
+    "~/.config/anyclaude/usage.jsonl".to_string()
+}
+
+fn default_health_interval_seconds() -> u64 {
+    60
+}
+
+fn default_health_timeout_seconds() -> u64 {
+    5
+}
+
+fn default_health_probe_model() -> String {
+    "claude-haiku-4-5".to_string()
+}
+
+fn default_group_member_weight() -> u32 {
+    1
+}
+
 fn default_proxy_bind_addr() -> String {
     "127.0.0.1:8080".to_string()
 }
@@ -210,12 +617,42 @@ pub struct Backend {
     pub display_name: String,
     /// Base URL for API (e.g., "https://api.anthropic.com").
     pub base_url: String,
-    /// Authentication type: "api_key", "bearer", "none".
+    /// Authentication type: "api_key", "bearer", "bedrock", "vertex",
+    /// "passthrough".
     #[serde(rename = "auth_type")]
     pub auth_type_str: String,
     /// Direct API key for this backend.
     #[serde(default)]
     pub api_key: Option<String>,
+    /// Environment variable holding the API key.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// File containing the API key (`~/` is expanded).
+    #[serde(default)]
+    pub api_key_file: Option<String>,
+    /// Shell command printing the API key (e.g. `pass show anthropic`).
+    #[serde(default)]
+    pub api_key_command: Option<String>,
+    /// How long an `api_key_command` result is reused, in seconds
+    /// (default: 300, 0 runs the command for every request).
+    #[serde(default)]
+    pub api_key_command_ttl_seconds: Option<u64>,
+    /// More keys for the same backend, rotated per request and on rate
+    /// limits. The key from the `api_key*` fields above, if any, comes first.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeySource>,
+    /// How requests are spread over several keys (default: "round_robin").
+    #[serde(default)]
+    pub key_rotation: KeyRotation,
+    /// Extra upstream headers and rewrites of client headers.
+    #[serde(default)]
+    pub headers: HeaderRules,
+    /// Request body fields to clamp, force or drop for this backend.
+    #[serde(default)]
+    pub body: BodyRules,
+    /// Endpoints the proxy answers itself instead of forwarding.
+    #[serde(default)]
+    pub emulate: EmulateRules,
     /// Optional pricing per million tokens.
     #[serde(default)]
     pub pricing: Option<BackendPricing>,
@@ -237,12 +674,171 @@ pub struct Backend {
     /// Model name to use for haiku-family requests on this backend.
     #[serde(default)]
     pub model_haiku: Option<String>,
+    /// Exact and regex model mappings, tried in order before the family
+    /// fields above.
+    #[serde(default)]
+    pub model_map: Vec<ModelMapEntry>,
+    /// Model for requests no mapping above matches.
+    #[serde(default)]
+    pub default_model: Option<String>,
+    /// Ordered failover chain: backends tried in turn when this one
+    /// fails with a connection error or a failover status.
+    #[serde(default)]
+    pub failover: Vec<String>,
+    /// Upstream statuses that trigger failover.
+    /// None = default overload set (429, 500, 502, 503, 504, 529).
+    #[serde(default)]
+    pub failover_on_status: Option<Vec<u16>>,
+    /// Wire protocol spoken by the backend (default: "anthropic").
+    #[serde(default)]
+    pub protocol: BackendProtocol,
+    /// AWS region for `bedrock` auth.
+    /// None = `AWS_REGION` / `AWS_DEFAULT_REGION`, then the base_url host.
+    #[serde(default)]
+    pub aws_region: Option<String>,
+    /// AWS access key id for `bedrock` auth (default: `AWS_ACCESS_KEY_ID`).
+    #[serde(default)]
+    pub aws_access_key_id: Option<String>,
+    /// AWS secret access key for `bedrock` auth (default: `AWS_SECRET_ACCESS_KEY`).
+    #[serde(default)]
+    pub aws_secret_access_key: Option<String>,
+    /// AWS session token for `bedrock` auth (default: `AWS_SESSION_TOKEN`).
+    #[serde(default)]
+    pub aws_session_token: Option<String>,
+    /// Google Cloud project for `vertex` auth.
+    #[serde(default)]
+    pub vertex_project: Option<String>,
+    /// Vertex AI location for `vertex` auth (default: "global").
+    #[serde(default)]
+    pub vertex_region: Option<String>,
+    /// Command printing a bearer token for `vertex` auth.
+    /// Default: `gcloud auth print-access-token`.
+    #[serde(default)]
+    pub token_command: Option<String>,
+}
+
+/// One `[[backends.model_map]]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModelMapEntry {
+    /// Model name requested by the client, or a regex matching the whole
+    /// name when `regex` is set.
+    pub from: String,
+    /// Model sent to the backend. With `regex`, `$1`/`${name}` insert
+    /// capture groups.
+    pub to: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+/// `[backends.headers]`: header rewrites applied to upstream requests.
+///
+/// Values of `set` and `append` may contain `{session_id}`, `{request_id}`
+/// and `{agent_id}`, which are replaced per request (empty when unknown).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HeaderRules {
+    /// Headers set on every request, replacing any client value.
+    pub set: BTreeMap<String, String>,
+    /// Headers added alongside any client value.
+    pub append: BTreeMap<String, String>,
+    /// Client headers to drop. A trailing `*` matches by prefix
+    /// (e.g. "x-stainless-*").
+    pub remove: Vec<String>,
+    /// `anthropic-beta` entries to drop; a trailing `*` matches by prefix.
+    pub strip_beta: Vec<String>,
+    /// `anthropic-beta` entries to add when missing.
+    pub add_beta: Vec<String>,
+}
+
+/// `[backends.body]`: request body overrides for providers that reject or
+/// mishandle some fields. Applied to Anthropic-format bodies before any
+/// protocol translation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BodyRules {
+    /// Upper bound for `max_tokens`.
+    pub max_tokens: Option<u64>,
+    /// Upper bound for `thinking.budget_tokens`.
+    pub max_thinking_budget: Option<u64>,
+    /// `temperature` sent on every request.
+    pub temperature: Option<f64>,
+    /// `top_p` sent on every request.
+    pub top_p: Option<f64>,
+    /// `top_k` sent on every request.
+    pub top_k: Option<u64>,
+    /// Top-level fields to remove (e.g. "metadata", "context_management",
+    /// "top_k"). "cache_control" is removed from every block instead.
+    pub drop: Vec<String>,
+    /// Tools to remove from `tools`, by name.
+    pub drop_tools: Vec<String>,
+}
+
+/// `[backends.emulate]`: Anthropic endpoints answered by the proxy for
+/// providers that do not implement them. The response is never sent
+/// upstream.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EmulateRules {
+    /// `POST /v1/messages/count_tokens`, estimated locally.
+    pub count_tokens: bool,
+    /// `GET /v1/models` and `GET /v1/models/{id}`, listing the models the
+    /// backend's model mapping serves.
+    pub models: bool,
+}
+
+/// One entry of a backend's `api_keys`: the key itself, or where to read it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ApiKeySource {
+    /// The key itself.
+    Literal(String),
+    /// `{ env = "VAR" }`: an environment variable.
+    Env { env: String },
+    /// `{ file = "path" }`: a file (`~/` is expanded).
+    File { file: String },
+    /// `{ command = "...", ttl_seconds = 300 }`: a shell command's output.
+    Command {
+        command: String,
+        #[serde(default)]
+        ttl_seconds: Option<u64>,
+    },
+}
+
+/// Key selection for backends with several API keys.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRotation {
+    /// Each request takes the next key that is not cooling down.
+    #[default]
+    RoundRobin,
+    /// Prefer the key whose last rate limit is oldest (or that never hit one).
+    LeastRecentlyThrottled,
+}
+
+/// API protocol of a backend.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendProtocol {
+    /// Anthropic Messages API; requests are forwarded as-is.
+    #[default]
+    Anthropic,
+    /// OpenAI Chat Completions; `/v1/messages` is translated both ways.
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Google Gemini `generateContent`; `/v1/messages` is translated both ways.
+    Gemini,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackendPricing {
     pub input_per_million: f64,
     pub output_per_million: f64,
+    /// Rate for prompt cache reads (default: 10% of `input_per_million`).
+    #[serde(default)]
+    pub cache_read_per_million: Option<f64>,
+    /// Rate for prompt cache writes (default: 125% of `input_per_million`).
+    #[serde(default)]
+    pub cache_write_per_million: Option<f64>,
 }
 
 /// Agents routing configuration.
@@ -265,12 +861,33 @@ impl Default for Backend {
             base_url: "https://api.anthropic.com".to_string(),
             auth_type_str: "passthrough".to_string(),
             api_key: None,
+            api_key_env: None,
+            api_key_file: None,
+            api_key_command: None,
+            api_key_command_ttl_seconds: None,
+            api_keys: Vec::new(),
+            key_rotation: KeyRotation::default(),
+            headers: HeaderRules::default(),
+            body: BodyRules::default(),
+            emulate: EmulateRules::default(),
             pricing: None,
             thinking_compat: None,
             thinking_budget_tokens: None,
             model_opus: None,
             model_sonnet: None,
             model_haiku: None,
+            model_map: Vec::new(),
+            default_model: None,
+            failover: Vec::new(),
+            failover_on_status: None,
+            protocol: BackendProtocol::default(),
+            aws_region: None,
+            aws_access_key_id: None,
+            aws_secret_access_key: None,
+            aws_session_token: None,
+            vertex_project: None,
+            vertex_region: None,
+            token_command: None,
         }
     }
 }
@@ -296,10 +913,19 @@ impl Default for Config {
             defaults: Defaults::default(),
             proxy: ProxyConfig::default(),
             terminal: TerminalConfig::default(),
+            ui: UiConfig::default(),
             debug_logging: DebugLoggingConfig::default(),
             claude_settings: HashMap::new(),
             backends: vec![Backend::default()],
+            backend_groups: Vec::new(),
             agents: None,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            health_check: HealthCheckConfig::default(),
+            routing: RoutingConfig::default(),
+            retry: RetryConfig::default(),
+            tracing: TracingConfig::default(),
+            usage_ledger: UsageLedgerConfig::default(),
+            budgets: BudgetsConfig::default(),
         }
     }
 }
@@ -313,6 +939,84 @@ impl Default for ProxyConfig {
     }
 }
 
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            consecutive_failures: default_cb_consecutive_failures(),
+            error_rate: default_cb_error_rate(),
+            window_size: default_cb_window_size(),
+            min_requests: default_cb_min_requests(),
+            open_seconds: default_cb_open_seconds(),
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            on_status: default_retry_on_status(),
+            max_status_retries: default_max_status_retries(),
+            max_wait_seconds: default_retry_max_wait_seconds(),
+            resume_streams: false,
+            max_stream_resumes: default_max_stream_resumes(),
+        }
+    }
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_otlp_endpoint(),
+            format: OtlpFormat::default(),
+            headers: BTreeMap::new(),
+            service_name: default_otlp_service_name(),
+        }
+    }
+}
+
+impl Default for UsageLedgerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_usage_ledger_path(),
+        }
+    }
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            segments: default_header_segments(),
+        }
+    }
+}
+
+impl Default for BudgetsConfig {
+    fn default() -> Self {
+        Self {
+            session: None,
+            day: None,
+            backends: BTreeMap::new(),
+            warn_at: default_budget_warn_at(),
+            fallback_backend: None,
+        }
+    }
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: default_health_interval_seconds(),
+            timeout_seconds: default_health_timeout_seconds(),
+            method: HealthCheckMethod::default(),
+            probe_model: default_health_probe_model(),
+        }
+    }
+}
+
 impl Default for TerminalConfig {
     fn default() -> Self {
         Self {
@@ -312,6 +312,15 @@ impl ErrorRegistry {
         }
     }
 
+    /// Stop tracking a recovery operation that gave up.
+    ///
+    /// Unlike [`recovery_failed`](Self::recovery_failed), the operation is
+    /// removed; record an error if the failure should stay visible.
+    pub fn end_recovery(&self, operation: &str) {
+        let mut inner = self.inner.write();
+        inner.recoveries.retain(|r| r.operation != operation);
+    }
+
     /// Mark recovery as failed.
     pub fn recovery_failed(&self, operation: &str) {
         let mut inner = self.inner.write();
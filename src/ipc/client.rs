@@ -1,9 +1,11 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use tokio::sync::{mpsc, oneshot};
 
 use crate::backend::BackendError;
 use crate::config::DebugLoggingConfig;
+use crate::metrics::har::HarExport;
 use crate::metrics::MetricsSnapshot;
 
 use super::types::{BackendInfo, IpcCommand, IpcError, ProxyStatus};
@@ -96,6 +98,19 @@ impl IpcClient {
         result
     }
 
+    pub async fn export_har(
+        &self,
+        path: Option<PathBuf>,
+    ) -> Result<Result<HarExport, String>, IpcError> {
+        let (respond_to, receiver) = oneshot::channel();
+        self.sender
+            .send(IpcCommand::ExportHar { path, respond_to })
+            .await
+            .map_err(|_| IpcError::Disconnected)?;
+
+        recv_with_timeout(receiver).await
+    }
+
 }
 
 async fn recv_with_timeout<T>(receiver: oneshot::Receiver<T>) -> Result<T, IpcError> {
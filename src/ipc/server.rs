@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 
 use tokio::sync::mpsc;
 
-use crate::backend::BackendState;
+use crate::backend::{BackendState, CircuitState};
+use crate::config::{Backend, CredentialStatus, DebugLogLevel};
+use crate::metrics::har::{self, HarExport};
 use crate::metrics::{app_log, DebugLogger, MetricsSnapshot, ObservabilityHub};
 use crate::proxy::shutdown::ShutdownManager;
 use crate::proxy::thinking::TransformerRegistry;
@@ -74,14 +76,58 @@ impl IpcServer {
                 }
                 IpcCommand::ListBackends { respond_to } => {
                     let (config, active_backend) = backend_state.get_config_and_active_backend();
-                    let mut backends = Vec::with_capacity(config.backends.len());
-                    for backend in config.backends {
+                    let circuit_breakers = backend_state.circuit_breakers();
+                    let probe_results = backend_state.probe_results();
+                    let key_rotator = backend_state.key_rotator();
+                    let mut backends = Vec::with_capacity(
+                        config.backends.len() + config.backend_groups.len(),
+                    );
+                    for backend in &config.backends {
+                        let credential_error = match backend.resolve_credential() {
+                            CredentialStatus::Unconfigured { reason } => Some(reason),
+                            CredentialStatus::Configured(_)
+                            | CredentialStatus::NoAuth
+                            | CredentialStatus::Pending => None,
+                        };
                         backends.push(BackendInfo {
                             id: backend.name.clone(),
                             display_name: backend.display_name.clone(),
                             is_active: backend.name == active_backend,
-                            is_configured: backend.is_configured(),
+                            is_configured: credential_error.is_none(),
+                            credential_error,
                             base_url: backend.base_url.clone(),
+                            circuit: circuit_breakers.state(&backend.name),
+                            probe: probe_results.get(&backend.name),
+                            members: Vec::new(),
+                            api_keys: key_rotator.status(backend),
+                        });
+                    }
+                    for group in &config.backend_groups {
+                        let members: Vec<&Backend> = group
+                            .members
+                            .iter()
+                            .filter_map(|m| config.backends.iter().find(|b| b.name == m.backend))
+                            .collect();
+                        // A group is only tripped when every member is.
+                        let circuit = if members
+                            .iter()
+                            .all(|b| circuit_breakers.state(&b.name) == CircuitState::Open)
+                        {
+                            CircuitState::Open
+                        } else {
+                            CircuitState::Closed
+                        };
+                        backends.push(BackendInfo {
+                            id: group.name.clone(),
+                            display_name: group.display_name().to_string(),
+                            is_active: group.name == active_backend,
+                            is_configured: members.iter().any(|b| b.is_configured()),
+                            credential_error: None,
+                            base_url: String::new(),
+                            circuit,
+                            probe: None,
+                            members: members.iter().map(|b| b.name.clone()).collect(),
+                            api_keys: Vec::new(),
                         });
                     }
                     if respond_to.send(backends).is_err() {
@@ -100,6 +146,38 @@ impl IpcServer {
                         app_log("ipc","IPC: SetDebugLogging response dropped (receiver gone)");
                     }
                 }
+                IpcCommand::ExportHar { path, respond_to } => {
+                    let debug_config = debug_logger.config();
+                    let include_bodies =
+                        debug_config.level >= DebugLogLevel::Full && debug_config.full_body;
+                    let path = path.unwrap_or_else(|| {
+                        har::default_har_path(&debug_config.file_path, SystemTime::now())
+                    });
+                    let config = backend_state.get_config();
+                    let records = observability.snapshot().recent;
+                    let document = har::to_har(&records, include_bodies, |record| {
+                        config
+                            .backends
+                            .iter()
+                            .find(|backend| backend.name == record.backend)
+                            .map_or_else(|| config.proxy.base_url.clone(), |b| b.base_url.clone())
+                    });
+                    let result = match har::write_har(&path, &document) {
+                        Ok(()) => {
+                            app_log("ipc", &format!(
+                                "Exported {} requests to {}", records.len(), path.display()
+                            ));
+                            Ok(HarExport {
+                                path,
+                                entries: records.len(),
+                            })
+                        }
+                        Err(e) => Err(format!("Failed to write {}: {}", path.display(), e)),
+                    };
+                    if respond_to.send(result).is_err() {
+                        app_log("ipc","IPC: ExportHar response dropped (receiver gone)");
+                    }
+                }
             }
         }
     }
@@ -114,6 +192,7 @@ fn filter_metrics(snapshot: MetricsSnapshot, backend_id: Option<&str>) -> Metric
         generated_at: snapshot.generated_at,
         per_backend: HashMap::new(),
         recent: Vec::new(),
+        in_flight: Vec::new(),
     };
 
     if let Some(metrics) = snapshot.per_backend.get(backend_id) {
@@ -127,6 +206,11 @@ fn filter_metrics(snapshot: MetricsSnapshot, backend_id: Option<&str>) -> Metric
         .into_iter()
         .filter(|record| record.backend == backend_id)
         .collect();
+    filtered.in_flight = snapshot
+        .in_flight
+        .into_iter()
+        .filter(|request| request.backend == backend_id)
+        .collect();
 
     filtered
 }
@@ -1,7 +1,10 @@
+use std::path::PathBuf;
+
 use tokio::sync::oneshot;
 
-use crate::backend::BackendError;
+use crate::backend::{ApiKeyStatus, BackendError, BackendProbe, CircuitState};
 use crate::config::DebugLoggingConfig;
+use crate::metrics::har::HarExport;
 use crate::metrics::MetricsSnapshot;
 
 #[derive(Debug)]
@@ -35,7 +38,15 @@ pub struct BackendInfo {
     pub display_name: String,
     pub is_active: bool,
     pub is_configured: bool,
+    /// Why the backend's credential could not be resolved.
+    pub credential_error: Option<String>,
     pub base_url: String,
+    pub circuit: CircuitState,
+    pub probe: Option<BackendProbe>,
+    /// Member backend IDs when this entry is a backend group.
+    pub members: Vec<String>,
+    /// Usage of each key when the backend rotates several API keys.
+    pub api_keys: Vec<ApiKeyStatus>,
 }
 
 pub enum IpcCommand {
@@ -60,4 +71,10 @@ pub enum IpcCommand {
         config: DebugLoggingConfig,
         respond_to: oneshot::Sender<Result<(), IpcError>>,
     },
+    /// Write the request ring as a HAR file; `None` picks a file next to
+    /// the debug log.
+    ExportHar {
+        path: Option<PathBuf>,
+        respond_to: oneshot::Sender<Result<HarExport, String>>,
+    },
 }
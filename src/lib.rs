@@ -11,3 +11,4 @@ pub mod shim;
 pub mod shutdown;
 pub mod sse;
 pub mod ui;
+pub mod utc;
@@ -1,22 +1,76 @@
 use clap::Parser;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyclaude::config::Config;
+use anyclaude::metrics::ledger::{self, GroupBy, UsageQuery};
+use anyclaude::proxy::recording::{MatchMode, Recorder, Recording, Replayer};
 
 #[derive(Parser)]
 #[command(name = "anyclaude", version)]
 #[command(about = "TUI wrapper for Claude Code with multi-backend support")]
 struct Cli {
-    /// Override default backend (see config for available backends)
+    /// Report token usage and cost from the usage ledger instead of starting
+    /// claude; the arguments after it are its options (see `--usage --help`)
+    #[arg(
+        long,
+        value_name = "OPTIONS",
+        num_args = 0..,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["backend", "record", "replay", "args"]
+    )]
+    usage: Option<Vec<String>>,
+
+    /// Override default backend or backend group (see config)
     #[arg(long, value_name = "NAME")]
     backend: Option<String>,
 
+    /// Record every upstream request and response to DIR
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serve responses recorded with --record from DIR instead of contacting any backend
+    #[arg(long, value_name = "DIR")]
+    replay: Option<PathBuf>,
+
+    /// How --replay matches requests to recorded ones
+    #[arg(long, value_enum, value_name = "MODE", default_value = "strict", requires = "replay")]
+    replay_match: MatchMode,
+
     /// Arguments passed to claude
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     args: Vec<String>,
 }
 
+/// Options of `anyclaude --usage`, parsed from the arguments after it so
+/// they never shadow claude's own.
+#[derive(Parser)]
+#[command(name = "anyclaude --usage")]
+#[command(about = "Report token usage and cost from the usage ledger")]
+struct UsageArgs {
+    /// Group totals by these columns, e.g. `--by day,backend`
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "day")]
+    by: Vec<GroupBy>,
+
+    /// First day to include (YYYY-MM-DD, UTC)
+    #[arg(long, value_name = "DAY", value_parser = ledger::parse_day)]
+    since: Option<String>,
+
+    /// Last day to include (YYYY-MM-DD, UTC)
+    #[arg(long, value_name = "DAY", value_parser = ledger::parse_day)]
+    until: Option<String>,
+
+    /// Print CSV instead of a table
+    #[arg(long)]
+    csv: bool,
+
+    /// Ledger file (default: `path` in [usage_ledger])
+    #[arg(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+}
+
 fn main() -> io::Result<()> {
     // Enter raw mode IMMEDIATELY to capture any early input from tmux send-keys.
     // Without this, input arriving before setup_terminal() is lost in cooked mode.
@@ -41,8 +95,15 @@ fn main() -> io::Result<()> {
 fn run_main() -> io::Result<()> {
     let cli = Cli::parse();
 
-    // Load config — fail fast on invalid config
-    let config = match Config::load() {
+    if let Some(options) = cli.usage {
+        let _ = disable_raw_mode();
+        let args = UsageArgs::parse_from(std::iter::once("anyclaude --usage".to_string()).chain(options));
+        return usage_report(args);
+    }
+
+    // Load config — fail fast on invalid config. Replay needs no credentials.
+    let load = if cli.replay.is_some() { Config::load_for_replay } else { Config::load };
+    let config = match load() {
         Ok(config) => config,
         Err(e) => {
             let _ = disable_raw_mode();
@@ -53,11 +114,17 @@ fn run_main() -> io::Result<()> {
     };
 
     if let Some(ref backend_name) = cli.backend {
-        let exists = config.backends.iter().any(|b| &b.name == backend_name);
+        let exists = config.backends.iter().any(|b| &b.name == backend_name)
+            || config.backend_groups.iter().any(|g| &g.name == backend_name);
         if !exists {
             // Must exit raw mode before printing errors
             let _ = disable_raw_mode();
-            let available: Vec<_> = config.backends.iter().map(|b| b.name.as_str()).collect();
+            let available: Vec<_> = config
+                .backends
+                .iter()
+                .map(|b| b.name.as_str())
+                .chain(config.backend_groups.iter().map(|g| g.name.as_str()))
+                .collect();
             eprintln!("Error: Backend '{}' not found in config", backend_name);
             if available.is_empty() {
                 eprintln!("No backends configured");
@@ -68,5 +135,66 @@ fn run_main() -> io::Result<()> {
         }
     }
 
-    anyclaude::ui::run(cli.backend, cli.args)
+    let recording = match open_recording(&cli) {
+        Ok(recording) => recording,
+        Err(e) => {
+            let _ = disable_raw_mode();
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    anyclaude::ui::run(cli.backend, cli.args, recording)
+}
+
+/// `--record` / `--replay`: open the recording directory.
+fn open_recording(cli: &Cli) -> Result<Recording, String> {
+    if let Some(dir) = &cli.record {
+        let recorder = Recorder::new(dir.clone())
+            .map_err(|e| format!("Failed to create recording directory {}: {}", dir.display(), e))?;
+        return Ok(Recording::Record(Arc::new(recorder)));
+    }
+    if let Some(dir) = &cli.replay {
+        let replayer = Replayer::load(dir.clone(), cli.replay_match)
+            .map_err(|e| format!("Failed to read recording {}: {}", dir.display(), e))?;
+        if replayer.is_empty() {
+            return Err(format!("No recorded exchanges in {}", dir.display()));
+        }
+        return Ok(Recording::Replay(Arc::new(replayer)));
+    }
+    Ok(Recording::Off)
+}
+
+/// `anyclaude --usage`: print ledger totals.
+fn usage_report(args: UsageArgs) -> io::Result<()> {
+    let path = match args.file {
+        Some(path) => path,
+        None => match Config::load() {
+            Ok(config) => anyclaude::config::expand_tilde(&config.usage_ledger.path),
+            Err(e) => {
+                eprintln!("Error: Failed to load config: {}", e);
+                eprintln!("Config file: {}", Config::config_path().display());
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let entries = ledger::read_ledger(&path).map_err(|e| {
+        io::Error::new(e.kind(), format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let query = UsageQuery {
+        group_by: args.by,
+        since: args.since,
+        until: args.until,
+    };
+    let rows = ledger::summarize(&entries, &query);
+
+    if args.csv {
+        print!("{}", ledger::render_csv(&query.group_by, &rows));
+    } else if rows.is_empty() {
+        println!("No usage recorded in {}", path.display());
+    } else {
+        print!("{}", ledger::render_table(&query.group_by, &rows));
+    }
+    Ok(())
 }
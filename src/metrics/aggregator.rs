@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use super::types::{BackendMetrics, RequestRecord};
+use super::types::{BackendMetrics, LatencyHistogram, RequestRecord};
 
 #[derive(Default, Clone)]
 pub struct BackendAccumulator {
@@ -9,10 +9,13 @@ pub struct BackendAccumulator {
     pub(crate) client_error_4xx: u64,
     pub(crate) server_error_5xx: u64,
     pub(crate) timeouts: u64,
-    latency_total_ms: u64,
-    latency_samples: u64,
-    ttfb_total_ms: u64,
-    ttfb_samples: u64,
+    pub(crate) latency: LatencyHistogram,
+    pub(crate) ttfb: LatencyHistogram,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) cache_creation_input_tokens: u64,
+    pub(crate) cache_read_input_tokens: u64,
+    pub(crate) cost_usd: f64,
 }
 
 impl BackendAccumulator {
@@ -34,28 +37,28 @@ impl BackendAccumulator {
         }
 
         if let Some(latency_ms) = record.latency_ms {
-            self.latency_total_ms = self.latency_total_ms.saturating_add(latency_ms);
-            self.latency_samples += 1;
+            self.latency.observe(latency_ms);
         }
 
         if let Some(ttfb_ms) = record.ttfb_ms {
-            self.ttfb_total_ms = self.ttfb_total_ms.saturating_add(ttfb_ms);
-            self.ttfb_samples += 1;
+            self.ttfb.observe(ttfb_ms);
+        }
+
+        if let Some(usage) = &record.usage {
+            self.input_tokens += usage.input_tokens;
+            self.output_tokens += usage.output_tokens;
+            self.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+            self.cache_read_input_tokens += usage.cache_read_input_tokens;
+            self.cost_usd += usage.cost_usd.unwrap_or(0.0);
         }
     }
 
     pub fn avg_latency_ms(&self) -> f64 {
-        if self.latency_samples == 0 {
-            return 0.0;
-        }
-        self.latency_total_ms as f64 / self.latency_samples as f64
+        self.latency.mean_ms()
     }
 
     pub fn avg_ttfb_ms(&self) -> f64 {
-        if self.ttfb_samples == 0 {
-            return 0.0;
-        }
-        self.ttfb_total_ms as f64 / self.ttfb_samples as f64
+        self.ttfb.mean_ms()
     }
 }
 
@@ -10,12 +10,12 @@ use parking_lot::RwLock;
 use serde_json::json;
 
 use crate::config::{
-    DebugLogDestination, DebugLogFormat, DebugLogLevel, DebugLogRotationMode, DebugLoggingConfig,
+    expand_tilde, DebugLogDestination, DebugLogFormat, DebugLogLevel, DebugLogRotationMode, DebugLoggingConfig,
 };
 use crate::metrics::types::{RequestMeta, ResponseMeta};
 use crate::metrics::{
     ObservabilityPlugin, PostResponseContext, RequestAnalysis, RequestRecord, ResponseAnalysis,
-    RoutingDecision,
+    RoutingDecision, TokenUsage,
 };
 
 const LOG_CHANNEL_SIZE: usize = 512;
@@ -42,6 +42,7 @@ pub struct DebugLogEvent {
     pub response_bytes: u64,
     pub request_analysis: Option<RequestAnalysis>,
     pub response_analysis: Option<ResponseAnalysis>,
+    pub usage: Option<TokenUsage>,
     pub routing_decision: Option<RoutingDecision>,
     pub request_meta: Option<RequestMeta>,
     pub response_meta: Option<ResponseMeta>,
@@ -169,6 +170,7 @@ impl DebugLogEvent {
             response_bytes: record.response_bytes,
             request_analysis: record.request_analysis.clone(),
             response_analysis: record.response_analysis.clone(),
+            usage: record.usage.clone(),
             routing_decision: record.routing_decision.clone(),
             request_meta: record.request_meta.clone(),
             response_meta: record.response_meta.clone(),
@@ -289,14 +291,27 @@ fn format_console(event: &DebugLogEvent, level: DebugLogLevel, use_color: bool)
         let routing = event
             .routing_decision
             .as_ref()
-            .map(|decision| format!("{}:{}", decision.backend, decision.reason))
+            .map(|decision| {
+                let synthesized = if decision.synthesized { ":synthesized" } else { "" };
+                format!("{}:{}{}", decision.backend, decision.reason, synthesized)
+            })
             .unwrap_or_else(|| "-".to_string());
 
+        let (cache_read, cache_write) = match &event.usage {
+            Some(usage) => (
+                usage.cache_read_input_tokens.to_string(),
+                usage.cache_creation_input_tokens.to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+
         line.push_str(&format!(
-            "\nmodel={} input_tokens={} output_tokens={} images={} stop_reason={} routing={} cost_usd={}",
+            "\nmodel={} input_tokens={} output_tokens={} cache_read_tokens={} cache_write_tokens={} images={} stop_reason={} routing={} cost_usd={}",
             model,
             input_tokens,
             output_tokens,
+            cache_read,
+            cache_write,
             images,
             stop_reason,
             routing,
@@ -452,11 +467,14 @@ fn format_json(event: &DebugLogEvent, level: DebugLogLevel) -> String {
         "model": event.request_analysis.as_ref().and_then(|analysis| analysis.model.clone()),
         "input_tokens": input_tokens,
         "output_tokens": output_tokens,
+        "cache_read_input_tokens": event.usage.as_ref().map(|usage| usage.cache_read_input_tokens),
+        "cache_creation_input_tokens": event.usage.as_ref().map(|usage| usage.cache_creation_input_tokens),
         "images": event.request_analysis.as_ref().map(|analysis| analysis.image_count),
         "stop_reason": stop_reason,
         "routing": event.routing_decision.as_ref().map(|decision| json!({
             "backend": decision.backend,
             "reason": decision.reason,
+            "synthesized": decision.synthesized,
         })),
         "cost_usd": cost_usd,
         "request": event.request_meta.clone(),
@@ -468,9 +486,9 @@ fn format_json(event: &DebugLogEvent, level: DebugLogLevel) -> String {
 
 fn tokens_summary(event: &DebugLogEvent) -> (String, String, String, String) {
     let input_tokens = event
-        .response_analysis
+        .usage
         .as_ref()
-        .and_then(|analysis| analysis.input_tokens)
+        .map(|usage| usage.input_tokens)
         .or_else(|| {
             event
                 .request_analysis
@@ -481,9 +499,9 @@ fn tokens_summary(event: &DebugLogEvent) -> (String, String, String, String) {
         .unwrap_or_else(|| "-".to_string());
 
     let output_tokens = event
-        .response_analysis
+        .usage
         .as_ref()
-        .and_then(|analysis| analysis.output_tokens)
+        .map(|usage| usage.output_tokens)
         .map(|v| v.to_string())
         .unwrap_or_else(|| "-".to_string());
 
@@ -496,9 +514,9 @@ fn tokens_summary(event: &DebugLogEvent) -> (String, String, String, String) {
         .to_string();
 
     let cost = event
-        .response_analysis
+        .usage
         .as_ref()
-        .and_then(|analysis| analysis.cost_usd)
+        .and_then(|usage| usage.cost_usd)
         .map(|v| format!("{:.6}", v))
         .unwrap_or_else(|| "-".to_string());
 
@@ -522,7 +540,7 @@ struct RotatingFile {
 
 impl RotatingFile {
     fn new(path: String, config: DebugLoggingConfig) -> Self {
-        let path = expand_tilde(Path::new(&path));
+        let path = expand_tilde(&path);
         if let Some(parent) = path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
@@ -585,16 +603,6 @@ impl RotatingFile {
     }
 }
 
-fn expand_tilde(path: &Path) -> PathBuf {
-    let path_str = path.to_string_lossy();
-    if let Some(rest) = path_str.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(rest);
-        }
-    }
-    path.to_path_buf()
-}
-
 fn rotated_path(base: &Path) -> PathBuf {
     let suffix = SystemTime::now()
         .duration_since(UNIX_EPOCH)
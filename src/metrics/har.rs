@@ -0,0 +1,198 @@
+//! HAR 1.2 export of the request ring, for attaching to support tickets.
+//!
+//! Headers and bodies are the redacted captures of the debug logger, so
+//! they only exist with `[debug_logging] level = "full"`. Bodies are only
+//! exported with `full_body`, when they are complete; streamed responses
+//! then keep their event text rather than the log's summary.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::RequestRecord;
+use crate::config::expand_tilde;
+use crate::utc::format_utc;
+
+/// A written HAR file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HarExport {
+    pub path: PathBuf,
+    /// Requests in the file.
+    pub entries: usize,
+}
+
+/// HAR document of `records`. `base_url` gives the URL a record's path is
+/// relative to, e.g. its backend's `base_url`.
+pub fn to_har(
+    records: &[RequestRecord],
+    include_bodies: bool,
+    base_url: impl Fn(&RequestRecord) -> String,
+) -> Value {
+    let entries: Vec<Value> = records
+        .iter()
+        .map(|record| entry(record, include_bodies, &base_url(record)))
+        .collect();
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "anyclaude",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        }
+    })
+}
+
+/// Write `har` to `path`, creating its directory.
+pub fn write_har(path: &Path, har: &Value) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let text = serde_json::to_string_pretty(har).map_err(io::Error::other)?;
+    std::fs::write(path, text)
+}
+
+/// `anyclaude-<UTC time>.har` next to the debug log at `debug_log_path`.
+pub fn default_har_path(debug_log_path: &str, now: SystemTime) -> PathBuf {
+    let stamp: String = format_utc(now)
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect();
+    let file_name = format!("anyclaude-{}.har", stamp);
+    match expand_tilde(debug_log_path).parent() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+fn entry(record: &RequestRecord, include_bodies: bool, base_url: &str) -> Value {
+    let request_meta = record.request_meta.as_ref();
+    let response_meta = record.response_meta.as_ref();
+    let method = request_meta.map_or(record.trace.method.as_str(), |m| m.method.as_str());
+    let path = request_meta.map_or(record.trace.path.as_str(), |m| m.path.as_str());
+    let query = request_meta.and_then(|m| m.query.as_deref());
+    let mut url = format!("{}{}", base_url.trim_end_matches('/'), path);
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+
+    let request_headers = request_meta.and_then(|m| m.headers.as_deref()).unwrap_or_default();
+    let response_headers = response_meta.and_then(|m| m.headers.as_deref()).unwrap_or_default();
+
+    let mut request = json!({
+        "method": method,
+        "url": url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": headers(request_headers),
+        "queryString": query_string(query),
+        "headersSize": -1,
+        "bodySize": record.request_bytes,
+    });
+    if let Some(body) = request_meta
+        .and_then(|m| m.body_preview.as_deref())
+        .filter(|_| include_bodies)
+    {
+        request["postData"] = json!({
+            "mimeType": header(request_headers, "content-type").unwrap_or("application/json"),
+            "text": body,
+        });
+    }
+
+    let sse_text = response_meta.and_then(|m| m.sse_text.as_deref());
+    let mime_type = header(response_headers, "content-type")
+        .or(sse_text.map(|_| "text/event-stream"))
+        .unwrap_or("application/json");
+    let mut content = json!({
+        "size": record.response_bytes,
+        "mimeType": mime_type,
+    });
+    if let Some(text) = sse_text
+        .or(response_meta.and_then(|m| m.body_preview.as_deref()))
+        .filter(|_| include_bodies)
+    {
+        content["text"] = json!(text);
+    }
+    // 0 marks a request that got no response.
+    let status = record.status.unwrap_or(0);
+    let status_text = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or("");
+
+    let time = record.latency_ms.unwrap_or(0);
+    let wait = record.ttfb_ms.unwrap_or(time);
+    let mut entry = json!({
+        "startedDateTime": format_utc_millis(record.started_at),
+        "time": time,
+        "request": request,
+        "response": {
+            "status": status,
+            "statusText": status_text,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(response_headers),
+            "content": content,
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": record.response_bytes,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": wait,
+            "receive": time.saturating_sub(wait),
+        },
+        "_requestId": record.id,
+        "_backend": record.backend,
+        "_agent": record.agent.as_str(),
+    });
+    if let Some(decision) = &record.routing_decision {
+        entry["_routing"] = json!(decision.reason);
+    }
+    if let Some(model) = &record.trace.upstream_model {
+        entry["_upstreamModel"] = json!(model);
+    }
+    if record.timed_out {
+        entry["_timedOut"] = json!(true);
+    }
+    entry
+}
+
+fn headers(headers: &[(String, String)]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn query_string(query: Option<&str>) -> Vec<Value> {
+    query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+/// RFC 3339 UTC timestamp with millisecond precision.
+fn format_utc_millis(time: SystemTime) -> String {
+    let millis = time.duration_since(UNIX_EPOCH).unwrap_or_default().subsec_millis();
+    let seconds = format_utc(time);
+    format!("{}.{:03}Z", seconds.trim_end_matches('Z'), millis)
+}
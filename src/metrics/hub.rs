@@ -11,8 +11,10 @@ use super::aggregator::{apply_percentiles, BackendAccumulator};
 use super::plugin::ObservabilityPlugin;
 use super::ring::RequestRingBuffer;
 use super::span::{finalize_record, RequestSpan, RequestStart};
+use super::trace::RequestTrace;
 use super::types::{
-    BackendMetrics, MetricsSnapshot, PostResponseContext, PreRequestContext, RequestRecord,
+    AgentKind, BackendMetrics, InFlightRequest, MetricsSnapshot, PostResponseContext, PreRequestContext,
+    RequestRecord,
 };
 
 #[derive(Clone)]
@@ -23,6 +25,8 @@ pub struct ObservabilityHub {
 struct ObservabilityInner {
     ring: RequestRingBuffer,
     aggregates: RwLock<HashMap<String, BackendAccumulator>>,
+    /// Started but not yet finished requests, by id.
+    in_flight: RwLock<HashMap<String, InFlightRequest>>,
     plugins: Vec<Arc<dyn ObservabilityPlugin>>,
 }
 
@@ -32,6 +36,7 @@ impl ObservabilityHub {
             inner: Arc::new(ObservabilityInner {
                 ring: RequestRingBuffer::new(capacity),
                 aggregates: RwLock::new(HashMap::new()),
+                in_flight: RwLock::new(HashMap::new()),
                 plugins: Vec::new(),
             }),
         }
@@ -59,15 +64,20 @@ impl ObservabilityHub {
             latency_ms: None,
             ttfb_ms: None,
             backend: active_backend.to_string(),
+            session_id: None,
             status: None,
             timed_out: false,
             request_bytes: 0,
             response_bytes: 0,
             request_analysis: None,
             response_analysis: None,
+            usage: None,
             routing_decision: None,
+            agent: AgentKind::Main,
+            agent_id: None,
             request_meta: None,
             response_meta: None,
+            trace: RequestTrace::for_request(request),
         };
 
         let mut backend_override = None;
@@ -84,6 +94,17 @@ impl ObservabilityHub {
             }
         }
 
+        self.inner.in_flight.write().insert(
+            request_id.clone(),
+            InFlightRequest {
+                id: request_id,
+                backend: record.backend.clone(),
+                started_at,
+                streaming: false,
+                output_tokens: 0,
+            },
+        );
+
         RequestStart {
             span: RequestSpan::new(record),
             backend_override,
@@ -103,10 +124,27 @@ impl ObservabilityHub {
             plugin.post_response(&mut ctx);
         }
 
+        self.inner.in_flight.write().remove(&request_id);
         self.update_aggregates(&span.record);
         self.inner.ring.push(span.record);
     }
 
+    /// Note streaming progress of an unfinished request. Streams report
+    /// at most every 250ms, so this stays off the per-chunk path.
+    ///
+    /// Requests that already finished are left out of the in-flight list.
+    pub fn stream_progress(&self, record: &RequestRecord, output_tokens: u64) {
+        let mut in_flight = self.inner.in_flight.write();
+        let Some(entry) = in_flight.get_mut(&record.id) else {
+            return;
+        };
+        if entry.backend != record.backend {
+            entry.backend.clone_from(&record.backend);
+        }
+        entry.streaming = true;
+        entry.output_tokens = output_tokens;
+    }
+
     pub fn finish_error(&self, mut span: RequestSpan, status: Option<u16>) {
         span.record.status = status.or(span.record.status);
         self.finish_request(span);
@@ -127,6 +165,13 @@ impl ObservabilityHub {
                 timeouts: acc.timeouts,
                 avg_latency_ms: acc.avg_latency_ms(),
                 avg_ttfb_ms: acc.avg_ttfb_ms(),
+                latency_histogram: acc.latency.clone(),
+                ttfb_histogram: acc.ttfb.clone(),
+                input_tokens: acc.input_tokens,
+                output_tokens: acc.output_tokens,
+                cache_creation_input_tokens: acc.cache_creation_input_tokens,
+                cache_read_input_tokens: acc.cache_read_input_tokens,
+                cost_usd: acc.cost_usd,
                 ..Default::default()
             };
             per_backend.insert(backend, metrics);
@@ -134,10 +179,15 @@ impl ObservabilityHub {
 
         apply_percentiles(&mut per_backend, &recent);
 
+        let mut in_flight: Vec<InFlightRequest> =
+            self.inner.in_flight.read().values().cloned().collect();
+        in_flight.sort_by_key(|request| request.started_at);
+
         MetricsSnapshot {
             generated_at: SystemTime::now(),
             per_backend,
             recent,
+            in_flight,
         }
     }
 
@@ -0,0 +1,302 @@
+//! Usage ledger entries and the `anyclaude --usage` report.
+//!
+//! The ledger is a JSONL file with one [`LedgerEntry`] per finished
+//! request, appended by the proxy and never rewritten, so it survives
+//! restarts and can be reconciled against provider invoices.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::RequestRecord;
+use crate::utc::format_utc;
+
+/// One finished request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Request start, RFC 3339 in UTC (`2026-01-31T12:00:00Z`).
+    pub ts: String,
+    pub request_id: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub backend: String,
+    /// Model requested by the client.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Model sent to the backend after mapping.
+    #[serde(default)]
+    pub upstream_model: Option<String>,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+}
+
+impl LedgerEntry {
+    pub fn from_record(record: &RequestRecord) -> Self {
+        let usage = record.usage.clone().unwrap_or_default();
+        Self {
+            ts: format_utc(record.started_at),
+            request_id: record.id.clone(),
+            session_id: record.session_id.clone(),
+            backend: record.backend.clone(),
+            model: record.trace.model.clone(),
+            upstream_model: record.trace.upstream_model.clone(),
+            status: record.status,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens,
+            cache_read_input_tokens: usage.cache_read_input_tokens,
+            cost_usd: usage.cost_usd,
+            latency_ms: record.latency_ms,
+        }
+    }
+
+    /// `YYYY-MM-DD` of the request, in UTC.
+    pub fn day(&self) -> &str {
+        self.ts.get(..10).unwrap_or(&self.ts)
+    }
+}
+
+/// All entries of the ledger at `path`; a missing file is empty.
+///
+/// Lines that do not parse (e.g. cut short by a crash) are skipped.
+pub fn read_ledger(path: &Path) -> io::Result<Vec<LedgerEntry>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Report column to group totals by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// UTC day of the request.
+    Day,
+    Backend,
+    /// Upstream model, or the requested one when it was not mapped.
+    Model,
+    /// Claude Code session.
+    Session,
+}
+
+impl GroupBy {
+    fn name(self) -> &'static str {
+        match self {
+            GroupBy::Day => "day",
+            GroupBy::Backend => "backend",
+            GroupBy::Model => "model",
+            GroupBy::Session => "session",
+        }
+    }
+
+    fn key(self, entry: &LedgerEntry) -> String {
+        let value = match self {
+            GroupBy::Day => Some(entry.day()),
+            GroupBy::Backend => Some(entry.backend.as_str()),
+            GroupBy::Model => entry.upstream_model.as_deref().or(entry.model.as_deref()),
+            GroupBy::Session => entry.session_id.as_deref(),
+        };
+        value.unwrap_or("-").to_string()
+    }
+}
+
+/// Totals of one group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageRow {
+    /// One value per [`GroupBy`] column.
+    pub key: Vec<String>,
+    pub requests: u64,
+    /// Requests without a 2xx status.
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageRow {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.requests += 1;
+        if !entry.status.is_some_and(|status| (200..300).contains(&status)) {
+            self.errors += 1;
+        }
+        self.input_tokens += entry.input_tokens;
+        self.output_tokens += entry.output_tokens;
+        self.cache_creation_input_tokens += entry.cache_creation_input_tokens;
+        self.cache_read_input_tokens += entry.cache_read_input_tokens;
+        self.cost_usd += entry.cost_usd.unwrap_or(0.0);
+    }
+}
+
+/// Which entries a report covers and how it groups them.
+#[derive(Debug, Clone, Default)]
+pub struct UsageQuery {
+    pub group_by: Vec<GroupBy>,
+    /// First and last day included, `YYYY-MM-DD`.
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// Totals per group, sorted by key.
+pub fn summarize(entries: &[LedgerEntry], query: &UsageQuery) -> Vec<UsageRow> {
+    let mut groups: BTreeMap<Vec<String>, UsageRow> = BTreeMap::new();
+    for entry in entries {
+        let day = entry.day();
+        if query.since.as_deref().is_some_and(|since| day < since)
+            || query.until.as_deref().is_some_and(|until| day > until)
+        {
+            continue;
+        }
+        let key: Vec<String> = query.group_by.iter().map(|group| group.key(entry)).collect();
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| UsageRow {
+                key,
+                ..Default::default()
+            })
+            .add(entry);
+    }
+    groups.into_values().collect()
+}
+
+const VALUE_COLUMNS: [&str; 7] = [
+    "requests",
+    "errors",
+    "input_tokens",
+    "output_tokens",
+    "cache_read_input_tokens",
+    "cache_creation_input_tokens",
+    "cost_usd",
+];
+
+fn values(row: &UsageRow) -> [String; 7] {
+    [
+        row.requests.to_string(),
+        row.errors.to_string(),
+        row.input_tokens.to_string(),
+        row.output_tokens.to_string(),
+        row.cache_read_input_tokens.to_string(),
+        row.cache_creation_input_tokens.to_string(),
+        format!("{:.4}", row.cost_usd),
+    ]
+}
+
+/// `rows` as CSV with a header line.
+pub fn render_csv(group_by: &[GroupBy], rows: &[UsageRow]) -> String {
+    let mut out = String::new();
+    let header: Vec<&str> = group_by.iter().map(|group| group.name()).chain(VALUE_COLUMNS).collect();
+    let _ = writeln!(out, "{}", header.join(","));
+    for row in rows {
+        let fields: Vec<String> = row
+            .key
+            .iter()
+            .map(|value| csv_field(value))
+            .chain(values(row))
+            .collect();
+        let _ = writeln!(out, "{}", fields.join(","));
+    }
+    out
+}
+
+/// `rows` as an aligned table with a total line.
+pub fn render_table(group_by: &[GroupBy], rows: &[UsageRow]) -> String {
+    let mut total = UsageRow {
+        key: vec![String::new(); group_by.len()],
+        ..Default::default()
+    };
+    if let Some(first) = total.key.first_mut() {
+        *first = "total".to_string();
+    }
+    for row in rows {
+        total.requests += row.requests;
+        total.errors += row.errors;
+        total.input_tokens += row.input_tokens;
+        total.output_tokens += row.output_tokens;
+        total.cache_creation_input_tokens += row.cache_creation_input_tokens;
+        total.cache_read_input_tokens += row.cache_read_input_tokens;
+        total.cost_usd += row.cost_usd;
+    }
+
+    let header: Vec<String> = group_by
+        .iter()
+        .map(|group| group.name())
+        .chain(VALUE_COLUMNS)
+        .map(str::to_string)
+        .collect();
+    let lines: Vec<Vec<String>> = std::iter::once(header)
+        .chain(rows.iter().chain([&total]).map(|row| {
+            row.key.iter().cloned().chain(values(row)).collect()
+        }))
+        .collect();
+
+    let mut widths = vec![0; group_by.len() + VALUE_COLUMNS.len()];
+    for line in &lines {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for line in &lines {
+        let cells: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                // Keys left-aligned, numbers right-aligned.
+                if i < group_by.len() {
+                    format!("{:<width$}", cell, width = width)
+                } else {
+                    format!("{:>width$}", cell, width = width)
+                }
+            })
+            .collect();
+        let _ = writeln!(out, "{}", cells.join("  ").trim_end());
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Validate a `YYYY-MM-DD` day argument.
+pub fn parse_day(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let valid = bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit());
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("expected YYYY-MM-DD, got '{}'", value))
+    }
+}
@@ -1,6 +1,8 @@
 pub mod aggregator;
 pub mod debug_logger;
+pub mod har;
 pub mod hub;
+pub mod ledger;
 pub mod plugin;
 pub mod redaction;
 pub mod request_parser;
@@ -8,7 +10,9 @@ pub mod response_parser;
 pub mod ring;
 pub mod span;
 pub mod stream;
+pub mod trace;
 pub mod types;
+pub mod usage;
 
 pub use debug_logger::{
     app_log, app_log_error, init_global_logger, AuxiliaryLogEvent, DebugLogEvent, DebugLogger,
@@ -16,12 +20,17 @@ pub use debug_logger::{
 };
 pub use hub::ObservabilityHub;
 pub use plugin::ObservabilityPlugin;
-pub use redaction::{redact_body, redact_body_preview, redact_headers};
+pub use redaction::{redact_body, redact_body_preview, redact_headers, redact_sse_text};
 pub use request_parser::{RequestAnalysis, RequestParser};
 pub use response_parser::ResponseParser;
 pub use span::{RequestSpan, RequestStart};
-pub use stream::{ChunkRewriter, ObservedStream, ResponseCompleteCallback, ResponsePreview, StreamError};
+pub use trace::{HopTiming, RequestTrace, StageTiming};
+pub use stream::{
+    chain_rewriters, ChunkRewriter, ObservedStream, ResponseCompleteCallback, ResponsePreview, StreamError,
+};
 pub use types::{
-    BackendMetrics, BackendOverride, MetricsSnapshot, PostResponseContext, PreRequestContext,
+    AgentKind, BackendMetrics, BackendOverride, InFlightRequest, LatencyHistogram, MetricsSnapshot, PostResponseContext, PreRequestContext,
     RequestMeta, RequestRecord, ResponseAnalysis, ResponseMeta, RoutingDecision,
+    LATENCY_BUCKETS_MS,
 };
+pub use usage::{SseUsageParser, TokenUsage};
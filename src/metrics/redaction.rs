@@ -67,6 +67,33 @@ pub fn redact_body(
     Some(mask_tokens(&String::from_utf8_lossy(data)))
 }
 
+/// Raw text of an SSE stream with secrets masked. JSON `data:` payloads
+/// are redacted like JSON bodies; lines that need no masking are kept as
+/// received.
+pub fn redact_sse_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .split('\n')
+        .map(|line| {
+            let json = line
+                .strip_prefix("data:")
+                .and_then(|data| serde_json::from_str::<Value>(data).ok());
+            match json {
+                Some(value) => {
+                    let mut redacted = value.clone();
+                    redact_json_value(&mut redacted);
+                    if redacted == value {
+                        line.to_string()
+                    } else {
+                        format!("data: {}", redacted)
+                    }
+                }
+                None => mask_tokens(line),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Parse SSE stream and return a structured summary instead of raw events.
 fn summarize_sse_stream(bytes: &[u8], pretty: bool) -> String {
     let events = crate::sse::parse_sse_events(bytes);
@@ -2,7 +2,7 @@ use crate::metrics::{ObservabilityPlugin, PreRequestContext};
 use serde::Serialize;
 use serde_json::Value;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[derive(Default)]
 pub struct RequestAnalysis {
     pub model: Option<String>,
@@ -39,7 +39,12 @@ impl RequestParser {
             Err(_) => return RequestAnalysis::default(),
         };
 
-        self.parse_model_info(&json)
+        self.analyze(&json)
+    }
+
+    /// Analyze an already-parsed request body.
+    pub fn analyze(&self, json: &Value) -> RequestAnalysis {
+        self.parse_model_info(json)
     }
 
     fn parse_model_info(&self, json: &Value) -> RequestAnalysis {
@@ -87,6 +92,11 @@ impl RequestParser {
                 analysis.thinking_enabled = enabled;
             }
 
+            // Anthropic format: {"type": "enabled" | "adaptive" | "disabled"}
+            if let Some(kind) = thinking.get("type").and_then(|v| v.as_str()) {
+                analysis.thinking_enabled = matches!(kind, "enabled" | "adaptive");
+            }
+
             if let Some(budget) = thinking.get("budget_tokens").and_then(|v| v.as_u64()) {
                 analysis.thinking_budget = Some(budget);
             }
@@ -130,47 +140,84 @@ impl RequestParser {
         }
     }
 
-    fn estimate_tokens(&self, json: &Value) -> Option<u64> {
-        let mut total_chars = 0u64;
+    /// Rough input token count: about four characters per token over the
+    /// system prompt, message text, thinking, tool calls and results, and
+    /// tool definitions, plus [`IMAGE_TOKENS`] per image.
+    pub fn estimate_tokens(&self, json: &Value) -> Option<u64> {
+        let mut count = ContentCount::default();
 
+        if let Some(system) = json.get("system") {
+            count.add(system);
+        }
         if let Some(messages) = json.get("messages").and_then(|v| v.as_array()) {
-            for msg in messages {
-                if let Some(content) = msg.get("content") {
-                    match content {
-                        Value::String(text) => {
-                            total_chars += text.chars().count() as u64;
-                        }
-                        Value::Array(items) => {
-                            for item in items {
-                                if let Some(obj) = item.as_object() {
-                                    if let Some(type_field) =
-                                        obj.get("type").and_then(|v| v.as_str())
-                                    {
-                                        if type_field == "text" {
-                                            if let Some(text) =
-                                                obj.get("text").and_then(|v| v.as_str())
-                                            {
-                                                total_chars += text.chars().count() as u64;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+            for content in messages.iter().filter_map(|msg| msg.get("content")) {
+                count.add(content);
+            }
+        }
+        if let Some(tools) = json.get("tools").and_then(|v| v.as_array()) {
+            for tool in tools {
+                count.chars += tool.to_string().chars().count() as u64;
             }
         }
 
-        if total_chars > 0 {
-            Some((total_chars / 4) + 1)
+        if count.chars > 0 || count.images > 0 {
+            Some((count.chars / 4) + 1 + count.images * IMAGE_TOKENS)
         } else {
             None
         }
     }
 }
 
+/// Tokens counted per image; about what a 1.15 megapixel image costs.
+const IMAGE_TOKENS: u64 = 1600;
+
+/// Characters and images in request content.
+#[derive(Default)]
+struct ContentCount {
+    chars: u64,
+    images: u64,
+}
+
+impl ContentCount {
+    /// Count a `content` or `system` value: a string or an array of blocks.
+    fn add(&mut self, content: &Value) {
+        match content {
+            Value::String(text) => self.add_text(text),
+            Value::Array(blocks) => {
+                for block in blocks {
+                    self.add_block(block);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_block(&mut self, block: &Value) {
+        let text = |field: &str| block.get(field).and_then(|v| v.as_str());
+        match block.get("type").and_then(|v| v.as_str()) {
+            Some("text") => self.add_text(text("text").unwrap_or_default()),
+            Some("thinking") => self.add_text(text("thinking").unwrap_or_default()),
+            Some("tool_use") => {
+                self.add_text(text("name").unwrap_or_default());
+                if let Some(input) = block.get("input") {
+                    self.chars += input.to_string().chars().count() as u64;
+                }
+            }
+            Some("tool_result") => {
+                if let Some(content) = block.get("content") {
+                    self.add(content);
+                }
+            }
+            Some("image") => self.images += 1,
+            _ => {}
+        }
+    }
+
+    fn add_text(&mut self, text: &str) {
+        self.chars += text.chars().count() as u64;
+    }
+}
+
 
 impl ObservabilityPlugin for RequestParser {
     fn pre_request(
@@ -1,5 +1,6 @@
 use std::time::{Duration, Instant, SystemTime};
 
+use super::trace::{HopTiming, StageTiming};
 use super::types::{BackendOverride, RequestRecord};
 
 pub struct RequestStart {
@@ -49,6 +50,33 @@ impl RequestSpan {
         self.record.timed_out = true;
     }
 
+    /// Record that pipeline stage `name` ran from `started_at` until now.
+    pub fn record_stage(&mut self, name: &'static str, started_at: SystemTime) {
+        self.record.trace.stages.push(StageTiming {
+            name,
+            started_at,
+            ended_at: SystemTime::now(),
+        });
+    }
+
+    /// Record that the attempt on `backend` failed; it ran from the end of
+    /// the previous hop, or the start of the request, until now.
+    pub fn record_hop(&mut self, backend: &str, status: u16, timed_out: bool) {
+        let started_at = self
+            .record
+            .trace
+            .hops
+            .last()
+            .map_or(self.record.started_at, |hop| hop.ended_at);
+        self.record.trace.hops.push(HopTiming {
+            backend: backend.to_string(),
+            started_at,
+            ended_at: SystemTime::now(),
+            status: Some(status),
+            timed_out,
+        });
+    }
+
     pub fn record_mut(&mut self) -> &mut RequestRecord {
         &mut self.record
     }
@@ -8,20 +8,50 @@ use futures_core::Stream;
 use tokio::time::{Instant, Sleep};
 
 use super::hub::ObservabilityHub;
-use super::redaction::redact_body;
+use super::redaction::{redact_body, redact_sse_text};
 use super::span::RequestSpan;
 use super::types::ResponseMeta;
+use super::usage::SseUsageParser;
+use crate::config::BackendPricing;
+
+/// Minimum time between streaming progress updates to the hub.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
 
 /// Callback type for response completion notification.
 pub type ResponseCompleteCallback = Box<dyn Fn(&[u8]) + Send + Sync>;
 
 /// Callback that rewrites a chunk, returning modified bytes (or original if unchanged).
+///
+/// Once the upstream ends it is called with an empty chunk, so rewriters
+/// that buffer can flush what is left.
 pub type ChunkRewriter = Box<dyn FnMut(Bytes) -> Bytes + Send>;
 
+/// Rewriter applying `first`, then `second`, flushing both at the end.
+pub fn chain_rewriters(mut first: ChunkRewriter, mut second: ChunkRewriter) -> ChunkRewriter {
+    Box::new(move |bytes: Bytes| {
+        let end = bytes.is_empty();
+        let rewritten = first(bytes);
+        if !end {
+            return second(rewritten);
+        }
+        let mut out = if rewritten.is_empty() {
+            Vec::new()
+        } else {
+            second(rewritten).to_vec()
+        };
+        out.extend_from_slice(&second(Bytes::new()));
+        Bytes::from(out)
+    })
+}
+
 /// Stream wrapper that adds observability and idle timeout to SSE streams.
 ///
 /// If no data is received within `idle_timeout`, the stream returns an error
 /// to prevent indefinite hangs during API stalls.
+///
+/// With [`with_recovery`](Self::with_recovery) an error leaves the span
+/// open, and the owner either [`resume`](Self::resume)s the stream from a new
+/// upstream or [`fail`](Self::fail)s it.
 pub struct ObservedStream<S> {
     inner: S,
     span: Option<RequestSpan>,
@@ -35,6 +65,20 @@ pub struct ObservedStream<S> {
     response_buffer: Vec<u8>,
     /// Optional chunk rewriter applied to each chunk before forwarding to client.
     chunk_rewriter: Option<ChunkRewriter>,
+    /// Rewriter dropping events the client already has, applied after
+    /// usage is read so their counts are kept.
+    event_filter: Option<ChunkRewriter>,
+    /// Whether errors are left to the owner to resume or fail.
+    recoverable: bool,
+    /// Usage reported by `message_start` and `message_delta`.
+    usage: SseUsageParser,
+    /// Pricing of the backend currently streaming.
+    pricing: Option<BackendPricing>,
+    /// Whether the upstream has ended and the rewriter was flushed.
+    ended: bool,
+    /// When progress was last sent to the hub, at most every
+    /// [`PROGRESS_INTERVAL`].
+    progress_at: Option<Instant>,
 }
 
 pub struct ResponsePreview {
@@ -105,6 +149,12 @@ impl<S> ObservedStream<S> {
             on_complete: None,
             response_buffer: Vec::new(),
             chunk_rewriter: None,
+            event_filter: None,
+            recoverable: false,
+            usage: SseUsageParser::default(),
+            pricing: None,
+            ended: false,
+            progress_at: None,
         }
     }
 
@@ -120,6 +170,56 @@ impl<S> ObservedStream<S> {
         self
     }
 
+    /// Price the stream's token usage with `pricing`.
+    pub fn with_pricing(mut self, pricing: Option<BackendPricing>) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Leave the span open when the upstream fails or goes idle.
+    pub fn with_recovery(mut self) -> Self {
+        self.recoverable = true;
+        self
+    }
+
+    /// Continue from a new upstream after an error, keeping the span,
+    /// preview and accumulated bytes. Usage is counted from the new
+    /// upstream alone, including events `event_filter` drops.
+    pub fn resume(
+        &mut self,
+        inner: S,
+        chunk_rewriter: Option<ChunkRewriter>,
+        event_filter: ChunkRewriter,
+        on_complete: Option<ResponseCompleteCallback>,
+        pricing: Option<BackendPricing>,
+    ) {
+        self.inner = inner;
+        self.chunk_rewriter = chunk_rewriter;
+        self.event_filter = Some(event_filter);
+        self.usage = SseUsageParser::default();
+        self.on_complete = on_complete;
+        self.pricing = pricing;
+        self.ended = false;
+        self.progress_at = None;
+        self.reset_deadline();
+    }
+
+    /// The request span, until the stream finishes.
+    pub fn span_mut(&mut self) -> Option<&mut RequestSpan> {
+        self.span.as_mut()
+    }
+
+    /// Finish the span after an unrecovered `error`, recording `status`.
+    pub fn fail(&mut self, error: &StreamError, status: u16) {
+        if let Some(span) = &mut self.span {
+            if matches!(error, StreamError::IdleTimeout { .. }) {
+                span.mark_timed_out();
+            }
+            span.set_status(status);
+        }
+        self.finish();
+    }
+
     fn finish(&mut self) {
         // Call the completion callback with accumulated response bytes
         if let Some(callback) = self.on_complete.take() {
@@ -129,7 +229,14 @@ impl<S> ObservedStream<S> {
         }
 
         if let Some(mut span) = self.span.take() {
+            if let Some(mut usage) = self.usage.usage() {
+                usage.apply_pricing(self.pricing.as_ref());
+                span.record_mut().usage = Some(usage);
+            }
             if let Some(preview) = self.response_preview.take() {
+                let sse_text = (preview.limit.is_none()
+                    && preview.content_type.contains("text/event-stream"))
+                .then(|| redact_sse_text(&preview.buffer));
                 let preview_value = redact_body(
                     &preview.buffer,
                     &preview.content_type,
@@ -142,13 +249,63 @@ impl<S> ObservedStream<S> {
                     .get_or_insert(ResponseMeta {
                         headers: None,
                         body_preview: None,
+                        sse_text: None,
                     });
                 meta.body_preview = preview_value;
+                meta.sse_text = sse_text;
             }
             self.hub.finish_request(span);
         }
     }
 
+    /// Account for a chunk about to be sent to the client.
+    fn observe(&mut self, bytes: &Bytes) {
+        if let Some(span) = &mut self.span {
+            span.mark_first_byte();
+            span.add_response_bytes(bytes.len());
+        }
+        let now = Instant::now();
+        let due = self
+            .progress_at
+            .is_none_or(|at| now.duration_since(at) >= PROGRESS_INTERVAL);
+        if let Some(span) = self.span.as_mut().filter(|_| due) {
+            self.progress_at = Some(now);
+            self.hub
+                .stream_progress(span.record_mut(), self.usage.output_tokens_so_far());
+        }
+        if let Some(preview) = &mut self.response_preview {
+            preview.push(bytes);
+        }
+        // Accumulate bytes for completion callback
+        if self.on_complete.is_some() {
+            self.response_buffer.extend_from_slice(bytes);
+        }
+    }
+
+    /// Rewrite an upstream chunk and read its usage before the event
+    /// filter runs. `end` flushes both rewriters.
+    fn rewrite(&mut self, bytes: Bytes, end: bool) -> Bytes {
+        // Empty chunks mid-stream are kept from the rewriters, as they mean the end.
+        let bytes = match &mut self.chunk_rewriter {
+            Some(rewriter) if end || !bytes.is_empty() => rewriter(bytes),
+            _ => bytes,
+        };
+        self.usage.push(&bytes);
+        let Some(filter) = &mut self.event_filter else {
+            return bytes;
+        };
+        if !end {
+            return if bytes.is_empty() { bytes } else { filter(bytes) };
+        }
+        let mut out = if bytes.is_empty() {
+            Vec::new()
+        } else {
+            filter(bytes).to_vec()
+        };
+        out.extend_from_slice(&filter(Bytes::new()));
+        Bytes::from(out)
+    }
+
     fn reset_deadline(&mut self) {
         self.deadline
             .as_mut()
@@ -163,14 +320,21 @@ where
     type Item = Result<Bytes, StreamError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.ended {
+            self.finish();
+            return Poll::Ready(None);
+        }
+
         // Check if idle timeout has expired
         if self.deadline.as_mut().poll(cx).is_ready() {
             let duration = self.idle_timeout.as_secs();
             crate::metrics::app_log("stream", &format!("SSE stream idle timeout exceeded ({}s)", duration));
-            if let Some(span) = &mut self.span {
-                span.mark_timed_out();
+            if !self.recoverable {
+                if let Some(span) = &mut self.span {
+                    span.mark_timed_out();
+                }
+                self.finish();
             }
-            self.finish();
             return Poll::Ready(Some(Err(StreamError::IdleTimeout { duration })));
         }
 
@@ -178,32 +342,26 @@ where
             Poll::Ready(Some(Ok(bytes))) => {
                 // Reset deadline on successful data receipt
                 self.reset_deadline();
-                // Apply chunk rewriter if present (e.g. reverse model mapping)
-                let bytes = if let Some(ref mut rewriter) = self.chunk_rewriter {
-                    (rewriter)(bytes)
-                } else {
-                    bytes
-                };
-                if let Some(span) = &mut self.span {
-                    span.mark_first_byte();
-                    span.add_response_bytes(bytes.len());
-                }
-                if let Some(preview) = &mut self.response_preview {
-                    preview.push(&bytes);
-                }
-                // Accumulate bytes for completion callback
-                if self.on_complete.is_some() {
-                    self.response_buffer.extend_from_slice(&bytes);
-                }
+                // Apply chunk rewriter if present (e.g. reverse model mapping).
+                let bytes = self.rewrite(bytes, false);
+                self.observe(&bytes);
                 Poll::Ready(Some(Ok(bytes)))
             }
             Poll::Ready(Some(Err(err))) => {
-                self.finish();
+                if !self.recoverable {
+                    self.finish();
+                }
                 Poll::Ready(Some(Err(StreamError::Upstream(err))))
             }
             Poll::Ready(None) => {
-                self.finish();
-                Poll::Ready(None)
+                self.ended = true;
+                let rest = self.rewrite(Bytes::new(), true);
+                if rest.is_empty() {
+                    self.finish();
+                    return Poll::Ready(None);
+                }
+                self.observe(&rest);
+                Poll::Ready(Some(Ok(rest)))
             }
             Poll::Pending => Poll::Pending,
         }
@@ -0,0 +1,128 @@
+//! Trace identity and stage timings of a request, for OTLP export.
+//!
+//! Every request gets a W3C trace context: the trace and parent span come
+//! from the client's `traceparent` when it sends a valid one, otherwise a
+//! new trace is started.
+
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+/// Trace data recorded on a [`RequestRecord`](super::RequestRecord).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestTrace {
+    /// HTTP method and path as received, e.g. "POST" and "/v1/messages".
+    pub method: String,
+    pub path: String,
+    pub trace_id: [u8; 16],
+    /// Span of the whole request.
+    pub span_id: [u8; 8],
+    /// Span of the client's `traceparent`, if it sent one.
+    pub parent_span_id: Option<[u8; 8]>,
+    /// Pipeline stages in the order they ran; stages that repeat for
+    /// failover hops appear once per hop.
+    pub stages: Vec<StageTiming>,
+    /// Model requested by the client.
+    pub model: Option<String>,
+    /// Model sent upstream after mapping.
+    pub upstream_model: Option<String>,
+    /// Thinking blocks filtered from the request.
+    pub thinking_blocks_filtered: u32,
+    /// Upstream attempts beyond the first, over all backends tried.
+    pub retries: u32,
+    /// Backends that failed before the one that answered, in order.
+    pub hops: Vec<HopTiming>,
+}
+
+/// Start and end of one pipeline stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageTiming {
+    pub name: &'static str,
+    pub started_at: SystemTime,
+    pub ended_at: SystemTime,
+}
+
+/// A failed attempt on one backend, after which the request moved on
+/// (failover or stream re-issue).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopTiming {
+    pub backend: String,
+    pub started_at: SystemTime,
+    pub ended_at: SystemTime,
+    pub status: Option<u16>,
+    pub timed_out: bool,
+}
+
+impl RequestTrace {
+    /// Trace of `request`: continues the client's `traceparent`, or starts
+    /// a new trace.
+    pub fn for_request<B>(request: &axum::http::Request<B>) -> Self {
+        let traceparent = request
+            .headers()
+            .get("traceparent")
+            .and_then(|v| v.to_str().ok());
+        Self {
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            ..Self::from_traceparent(traceparent)
+        }
+    }
+
+    /// Continue the trace of a `traceparent` header value, or start a new
+    /// one.
+    pub fn from_traceparent(header: Option<&str>) -> Self {
+        let parent = header.and_then(parse_traceparent);
+        Self {
+            trace_id: parent.map_or_else(|| *Uuid::new_v4().as_bytes(), |(trace_id, _)| trace_id),
+            span_id: new_span_id(),
+            parent_span_id: parent.map(|(_, span_id)| span_id),
+            ..Default::default()
+        }
+    }
+
+    /// `traceparent` for the upstream request, with the request span as
+    /// parent.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", hex(&self.trace_id), hex(&self.span_id))
+    }
+}
+
+/// A random, non-zero span id.
+pub fn new_span_id() -> [u8; 8] {
+    loop {
+        let bytes = Uuid::new_v4();
+        let mut span_id = [0u8; 8];
+        span_id.copy_from_slice(&bytes.as_bytes()[..8]);
+        if span_id != [0; 8] {
+            return span_id;
+        }
+    }
+}
+
+/// Lowercase hex of `bytes`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Trace and span id of a version 00 `traceparent`; `None` when malformed
+/// or all-zero.
+fn parse_traceparent(value: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if version != "00" || parts.next().is_some() || flags.len() != 2 {
+        return None;
+    }
+    let trace_id: [u8; 16] = unhex(trace_id)?.try_into().ok()?;
+    let span_id: [u8; 8] = unhex(span_id)?.try_into().ok()?;
+    (trace_id != [0; 16] && span_id != [0; 8]).then_some((trace_id, span_id))
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
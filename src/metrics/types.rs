@@ -6,7 +6,7 @@ use serde::Serialize;
 use axum::body::Body;
 use axum::http::Request;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RequestRecord {
     pub id: String,
     pub started_at: SystemTime,
@@ -15,45 +15,82 @@ pub struct RequestRecord {
     pub latency_ms: Option<u64>,
     pub ttfb_ms: Option<u64>,
     pub backend: String,
+    /// Claude Code session the request belongs to, if it says.
+    pub session_id: Option<String>,
     pub status: Option<u16>,
     pub timed_out: bool,
     pub request_bytes: u64,
     pub response_bytes: u64,
     pub request_analysis: Option<super::RequestAnalysis>,
     pub response_analysis: Option<ResponseAnalysis>,
+    /// Tokens reported by the backend, for streaming and non-streaming
+    /// responses alike.
+    pub usage: Option<super::TokenUsage>,
     pub routing_decision: Option<RoutingDecision>,
+    /// Which kind of agent sent the request, per routing.
+    pub agent: AgentKind,
+    /// Subagent or teammate id, when routing found one.
+    pub agent_id: Option<String>,
     pub request_meta: Option<RequestMeta>,
     pub response_meta: Option<ResponseMeta>,
+    pub trace: super::RequestTrace,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ResponseAnalysis {
     pub summary: String,
-    pub input_tokens: Option<u64>,
-    pub output_tokens: Option<u64>,
     pub stop_reason: Option<String>,
-    pub cost_usd: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Sender of a request: Claude Code itself, a subagent routed by its AC
+/// marker, or a teammate routed by its URL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentKind {
+    #[default]
+    Main,
+    Subagent,
+    Teammate,
+}
+
+impl AgentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgentKind::Main => "main",
+            AgentKind::Subagent => "subagent",
+            AgentKind::Teammate => "teammate",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct RoutingDecision {
     pub backend: String,
     pub reason: String,
+    /// Answered by the proxy (`[backends.emulate]`) without contacting the
+    /// backend.
+    pub synthesized: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct RequestMeta {
     pub method: String,
     pub path: String,
     pub query: Option<String>,
     pub headers: Option<Vec<(String, String)>>,
     pub body_preview: Option<String>,
+    /// Whether `body_preview` was cut at `body_preview_bytes`.
+    pub body_truncated: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ResponseMeta {
     pub headers: Option<Vec<(String, String)>>,
     pub body_preview: Option<String>,
+    /// Redacted event text of a streamed response, kept with `full_body`
+    /// for HAR export; `body_preview` holds its summary.
+    #[serde(skip)]
+    pub sse_text: Option<String>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -68,6 +105,47 @@ pub struct BackendMetrics {
     pub p50_latency_ms: Option<u64>,
     pub p95_latency_ms: Option<u64>,
     pub p99_latency_ms: Option<u64>,
+    pub latency_histogram: LatencyHistogram,
+    pub ttfb_histogram: LatencyHistogram,
+    /// Tokens and cost of responses that reported `usage`.
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Upper bounds of the [`LatencyHistogram`] buckets, in milliseconds.
+pub const LATENCY_BUCKETS_MS: [u64; 12] =
+    [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000, 120_000, 300_000];
+
+/// Latency samples bucketed by [`LATENCY_BUCKETS_MS`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    /// Samples per bucket (not cumulative); the last one counts samples
+    /// above every bound.
+    pub buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    pub sum_ms: u64,
+    pub count: u64,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, ms: u64) {
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.sum_ms = self.sum_ms.saturating_add(ms);
+        self.count += 1;
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum_ms as f64 / self.count as f64
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +153,21 @@ pub struct MetricsSnapshot {
     pub generated_at: SystemTime,
     pub per_backend: HashMap<String, BackendMetrics>,
     pub recent: Vec<RequestRecord>,
+    /// Requests not finished yet, oldest first.
+    pub in_flight: Vec<InFlightRequest>,
+}
+
+/// A request still being served.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlightRequest {
+    pub id: String,
+    pub backend: String,
+    pub started_at: SystemTime,
+    /// Streaming response that has sent its first bytes.
+    pub streaming: bool,
+    /// Output tokens streamed so far, estimated until the backend reports
+    /// its count.
+    pub output_tokens: u64,
 }
 
 pub struct BackendOverride {
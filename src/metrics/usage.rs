@@ -0,0 +1,155 @@
+//! Token usage measured from responses, and its cost.
+//!
+//! Non-streaming responses carry `usage` in the body. Streams report it
+//! in `message_start` (input and cache tokens) and `message_delta`
+//! (cumulative output tokens, sometimes updated input counts), which
+//! [`SseUsageParser`] picks up as chunks pass through. Content deltas give
+//! an estimate of the output tokens while the stream is still running.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::BackendPricing;
+
+/// Share of the input rate charged for cache reads without an explicit
+/// `cache_read_per_million`.
+const CACHE_READ_INPUT_RATIO: f64 = 0.1;
+
+/// Share of the input rate charged for cache writes without an explicit
+/// `cache_write_per_million`.
+const CACHE_WRITE_INPUT_RATIO: f64 = 1.25;
+
+/// Token counts reported by the backend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TokenUsage {
+    /// Uncached input tokens.
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens written to the prompt cache.
+    pub cache_creation_input_tokens: u64,
+    /// Input tokens read from the prompt cache.
+    pub cache_read_input_tokens: u64,
+    /// Cost from the backend's `pricing`, if it has one.
+    pub cost_usd: Option<f64>,
+}
+
+impl TokenUsage {
+    /// Usage of a non-streaming Messages response body.
+    pub fn from_response(body: &[u8]) -> Option<Self> {
+        let json: Value = serde_json::from_slice(body).ok()?;
+        let mut usage = Self::default();
+        usage.merge(json.get("usage")?);
+        Some(usage)
+    }
+
+    /// Overwrite the counts present in an Anthropic `usage` object.
+    pub fn merge(&mut self, usage: &Value) {
+        let fields = [
+            ("input_tokens", &mut self.input_tokens),
+            ("output_tokens", &mut self.output_tokens),
+            ("cache_creation_input_tokens", &mut self.cache_creation_input_tokens),
+            ("cache_read_input_tokens", &mut self.cache_read_input_tokens),
+        ];
+        for (name, count) in fields {
+            if let Some(value) = usage.get(name).and_then(|v| v.as_u64()) {
+                *count = value;
+            }
+        }
+    }
+
+    /// Input tokens including cache reads and writes.
+    pub fn total_input_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+
+    /// Set `cost_usd` from `pricing`; clears it without pricing.
+    pub fn apply_pricing(&mut self, pricing: Option<&BackendPricing>) {
+        self.cost_usd = pricing.map(|pricing| {
+            let cache_read = pricing
+                .cache_read_per_million
+                .unwrap_or(pricing.input_per_million * CACHE_READ_INPUT_RATIO);
+            let cache_write = pricing
+                .cache_write_per_million
+                .unwrap_or(pricing.input_per_million * CACHE_WRITE_INPUT_RATIO);
+            (self.input_tokens as f64 * pricing.input_per_million
+                + self.output_tokens as f64 * pricing.output_per_million
+                + self.cache_read_input_tokens as f64 * cache_read
+                + self.cache_creation_input_tokens as f64 * cache_write)
+                / 1_000_000.0
+        });
+    }
+}
+
+/// Characters per token when estimating output still being streamed.
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// Collects usage from an Anthropic SSE stream fed in arbitrary chunks.
+#[derive(Debug, Default)]
+pub struct SseUsageParser {
+    /// Bytes of the current, unterminated line.
+    line: Vec<u8>,
+    usage: Option<TokenUsage>,
+    /// Characters of text, thinking and tool input streamed so far.
+    streamed_chars: u64,
+}
+
+impl SseUsageParser {
+    pub fn push(&mut self, bytes: &[u8]) {
+        for chunk in bytes.split_inclusive(|b| *b == b'\n') {
+            self.line.extend_from_slice(chunk);
+            if chunk.ends_with(b"\n") {
+                let line = std::mem::take(&mut self.line);
+                self.parse_line(&line);
+            }
+        }
+    }
+
+    /// Usage seen so far, including a final line without a newline.
+    pub fn usage(&mut self) -> Option<TokenUsage> {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.parse_line(&line);
+        }
+        self.usage.clone()
+    }
+
+    /// Output tokens so far: the backend's count once it reports more
+    /// than the streamed content suggests, an estimate until then.
+    pub fn output_tokens_so_far(&self) -> u64 {
+        let reported = self.usage.as_ref().map_or(0, |usage| usage.output_tokens);
+        reported.max(self.streamed_chars.div_ceil(CHARS_PER_TOKEN))
+    }
+
+    fn parse_line(&mut self, line: &[u8]) {
+        let Some(data) = line.strip_prefix(b"data:") else {
+            return;
+        };
+        // Only the usage-carrying events and content deltas are worth parsing.
+        let is_event = |name: &[u8]| data.windows(name.len()).any(|w| w == name);
+        if !is_event(b"\"message_start\"")
+            && !is_event(b"\"message_delta\"")
+            && !is_event(b"\"content_block_delta\"")
+        {
+            return;
+        }
+        let Ok(event) = serde_json::from_slice::<Value>(data.trim_ascii()) else {
+            return;
+        };
+        let usage = match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => event.get("message").and_then(|m| m.get("usage")),
+            Some("message_delta") => event.get("usage"),
+            Some("content_block_delta") => {
+                let delta = event.get("delta");
+                let text = ["text", "thinking", "partial_json"]
+                    .iter()
+                    .find_map(|field| delta.and_then(|d| d.get(field)).and_then(|t| t.as_str()));
+                self.streamed_chars += text.map_or(0, |t| t.chars().count() as u64);
+                None
+            }
+            _ => None,
+        };
+        if let Some(usage) = usage {
+            self.usage.get_or_insert_with(TokenUsage::default).merge(usage);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! AWS Bedrock hosting of Anthropic models.
+//!
+//! Backends with `auth_type = "bedrock"` receive `/v1/messages` requests as
+//! `InvokeModel` (or `InvokeModelWithResponseStream`) calls:
+//!
+//! - **Path**: `/model/{model}/invoke[-with-response-stream]`, with the
+//!   mapped model in the path instead of the body
+//! - **Body**: the Anthropic body without `model` and `stream`, plus
+//!   `anthropic_version`
+//! - **Auth**: SigV4, applied in the forward stage (see [`crate::proxy::sigv4`])
+//! - **Streaming response**: AWS event stream frames, each wrapping one
+//!   base64-encoded Anthropic event, decoded back into SSE
+
+use std::time::SystemTime;
+
+use axum::body::Bytes;
+use base64::Engine;
+use reqwest::Url;
+use serde_json::{json, Value};
+
+use crate::config::Backend;
+use crate::metrics::ChunkRewriter;
+use crate::proxy::protocol::write_event;
+use crate::proxy::sigv4::{self, uri_encode, SigningParams};
+
+/// `anthropic_version` body field expected by Bedrock.
+pub const ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// Content type of Bedrock streaming responses.
+pub const EVENTSTREAM_CONTENT_TYPE: &str = "application/vnd.amazon.eventstream";
+
+/// SigV4 service name.
+pub const SIGNING_SERVICE: &str = "bedrock";
+
+/// Request path for `model`, e.g. `/model/anthropic.claude-sonnet-4-5-v1%3A0/invoke`.
+pub fn request_path(model: &str, streaming: bool) -> String {
+    let action = if streaming { "invoke-with-response-stream" } else { "invoke" };
+    format!("/model/{}/{}", uri_encode(model), action)
+}
+
+/// Anthropic Messages body → `InvokeModel` body.
+pub fn to_invoke_body(body: &Value) -> Value {
+    let mut body = body.clone();
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("model");
+        obj.remove("stream");
+        obj.entry("anthropic_version").or_insert_with(|| json!(ANTHROPIC_VERSION));
+    }
+    body
+}
+
+/// SigV4 headers for a request to `url` with `body`, signed now.
+///
+/// `content-type`, when given, is covered by the signature. Fails when the
+/// backend has no AWS credentials or region.
+pub fn sign_request(
+    backend: &Backend,
+    method: &str,
+    url: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Vec<(String, String)>, String> {
+    let credentials = backend
+        .aws_credentials()
+        .ok_or("aws_access_key_id / aws_secret_access_key are not set")?;
+    let region = backend.aws_region().ok_or("aws_region is not set")?;
+    let url = Url::parse(url).map_err(|e| format!("invalid upstream URL '{}': {}", url, e))?;
+    let headers: Vec<(String, String)> = content_type
+        .map(|ct| ("content-type".to_string(), ct.to_string()))
+        .into_iter()
+        .collect();
+
+    Ok(sigv4::sign(method, &url, &headers, body, &SigningParams {
+        credentials: &credentials,
+        region: &region,
+        service: SIGNING_SERVICE,
+        time: SystemTime::now(),
+    }))
+}
+
+/// Create a stateful chunk rewriter that decodes Bedrock event stream
+/// frames into Anthropic SSE events.
+///
+/// Frames split across chunks are buffered. `exception` frames become
+/// `error` events.
+pub fn make_stream_translator() -> ChunkRewriter {
+    let mut decoder = EventStreamDecoder::default();
+    Box::new(move |bytes: Bytes| Bytes::from(decoder.push(&bytes)))
+}
+
+/// Length of the frame prelude: total length, headers length, prelude CRC.
+const PRELUDE_LEN: usize = 12;
+/// Length of the trailing message CRC.
+const CRC_LEN: usize = 4;
+
+#[derive(Default)]
+struct EventStreamDecoder {
+    pending: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(bytes);
+        let mut out = String::new();
+
+        while self.pending.len() >= PRELUDE_LEN {
+            let total_len = read_u32(&self.pending[0..4]);
+            let headers_len = read_u32(&self.pending[4..8]);
+            if total_len < PRELUDE_LEN + headers_len + CRC_LEN {
+                // Not an event stream; nothing after this can be framed.
+                self.pending.clear();
+                break;
+            }
+            if self.pending.len() < total_len {
+                break;
+            }
+
+            let frame: Vec<u8> = self.pending.drain(..total_len).collect();
+            let headers = parse_headers(&frame[PRELUDE_LEN..PRELUDE_LEN + headers_len]);
+            let payload = &frame[PRELUDE_LEN + headers_len..total_len - CRC_LEN];
+            frame_events(&headers, payload, &mut out);
+        }
+
+        out.into_bytes()
+    }
+}
+
+/// Append the Anthropic events carried by one frame.
+fn frame_events(headers: &[(String, String)], payload: &[u8], out: &mut String) {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let payload: Value = serde_json::from_slice(payload).unwrap_or(Value::Null);
+
+    match header(":message-type") {
+        Some("event") => {
+            let event = payload
+                .get("bytes")
+                .and_then(|b| b.as_str())
+                .and_then(|b| base64::engine::general_purpose::STANDARD.decode(b).ok())
+                .and_then(|b| serde_json::from_slice::<Value>(&b).ok());
+            if let Some(event) = event {
+                write_event(out, event);
+            }
+        }
+        Some("exception") => {
+            let exception = header(":exception-type").unwrap_or("exception");
+            let message = payload
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or(exception);
+            write_event(out, json!({
+                "type": "error",
+                "error": {"type": exception_error_type(exception), "message": message},
+            }));
+        }
+        _ => {}
+    }
+}
+
+/// Anthropic error `type` for a Bedrock stream exception.
+fn exception_error_type(exception: &str) -> &'static str {
+    match exception {
+        "throttlingException" => "rate_limit_error",
+        "serviceUnavailableException" | "modelNotReadyException" => "overloaded_error",
+        "validationException" => "invalid_request_error",
+        _ => "api_error",
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> usize {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+}
+
+/// String-valued frame headers. Other header value types are skipped.
+fn parse_headers(mut bytes: &[u8]) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    while let Some((&name_len, rest)) = bytes.split_first() {
+        let name_len = name_len as usize;
+        let Some((&value_type, rest)) = rest.get(name_len..).and_then(|r| r.split_first()) else {
+            break;
+        };
+        let name = String::from_utf8_lossy(&bytes[1..1 + name_len]).into_owned();
+
+        let (value_len, value_start) = match value_type {
+            0 | 1 => (0, 0),
+            2 => (1, 0),
+            3 => (2, 0),
+            4 => (4, 0),
+            5 | 8 => (8, 0),
+            9 => (16, 0),
+            6 | 7 if rest.len() >= 2 => (u16::from_be_bytes([rest[0], rest[1]]) as usize, 2),
+            _ => break,
+        };
+        let Some(value) = rest.get(value_start..value_start + value_len) else {
+            break;
+        };
+        if value_type == 7 {
+            headers.push((name, String::from_utf8_lossy(value).into_owned()));
+        }
+        bytes = &rest[value_start + value_len..];
+    }
+    headers
+}
@@ -53,6 +53,15 @@ pub enum ProxyError {
     #[error("Subagent '{id}' not registered in affinity registry")]
     SubagentNotRegistered { id: String },
 
+    /// `--replay` has no recorded response for the request
+    #[error("No recorded response for {method} {path} (key {key}) in {dir}")]
+    ReplayMiss {
+        method: String,
+        path: String,
+        key: String,
+        dir: String,
+    },
+
     /// Internal server error
     #[error("Internal error: {0}")]
     Internal(String),
@@ -83,6 +92,7 @@ impl ProxyError {
                 StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY)
             }
             ProxyError::SubagentNotRegistered { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ProxyError::ReplayMiss { .. } => StatusCode::NOT_FOUND,
             ProxyError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ProxyError::Http(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -100,6 +110,7 @@ impl ProxyError {
             ProxyError::InvalidRequest(_) => "invalid_request",
             ProxyError::UpstreamError { .. } => "upstream_error",
             ProxyError::SubagentNotRegistered { .. } => "subagent_not_registered",
+            ProxyError::ReplayMiss { .. } => "replay_miss",
             ProxyError::Internal(_) => "internal_error",
             ProxyError::Http(_) => "http_error",
         }
@@ -0,0 +1,33 @@
+//! Google Gemini protocol translation.
+//!
+//! Backends with `protocol = "gemini"` receive Anthropic `/v1/messages`
+//! requests as `generateContent` (or `streamGenerateContent?alt=sse`), and
+//! their JSON and SSE responses are translated back into Anthropic shapes:
+//!
+//! - **Request** (stage 4): system, messages, tool_use/tool_result blocks,
+//!   images, tools, thinking and generation settings
+//! - **Non-streaming response** (stage 7): candidates → `message`, with
+//!   thought parts as `thinking` blocks
+//! - **Streaming response** (stage 7): a `ChunkRewriter` that emits
+//!   `message_start` / `content_block_*` / `message_delta` / `message_stop`
+//!
+//! `api_key` auth is sent as `x-goog-api-key`. Dispatch and error
+//! translation live in [`crate::proxy::protocol`].
+
+mod request;
+mod response;
+mod stream;
+
+pub use request::to_generate_content;
+pub use response::to_message;
+pub use stream::make_stream_translator;
+
+/// Request path for `model`, e.g. `/v1beta/models/gemini-2.5-pro:generateContent`.
+pub fn request_path(model: &str, streaming: bool) -> String {
+    let model = model.strip_prefix("models/").unwrap_or(model);
+    if streaming {
+        format!("/v1beta/models/{}:streamGenerateContent?alt=sse", model)
+    } else {
+        format!("/v1beta/models/{}:generateContent", model)
+    }
+}
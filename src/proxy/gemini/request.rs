@@ -0,0 +1,191 @@
+//! Anthropic Messages request → Gemini `generateContent` request.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::proxy::protocol::{block_type, text_content};
+
+/// Translate an Anthropic `/v1/messages` request body into a Gemini
+/// `generateContent` body. The model travels in the URL, not the body.
+///
+/// Thinking text from earlier turns is dropped; its signature is re-attached
+/// as `thoughtSignature` to the part that follows it, which is how Gemini
+/// expects thought signatures back.
+pub fn to_generate_content(body: &Value) -> Value {
+    let mut out = Map::new();
+
+    if let Some(system) = body
+        .get("system")
+        .and_then(text_content)
+        .filter(|s| !s.is_empty())
+    {
+        out.insert("systemInstruction".to_string(), json!({"parts": [{"text": system}]}));
+    }
+
+    // tool_result blocks only carry the tool_use id; Gemini wants the name.
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+    let contents: Vec<Value> = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|message| translate_message(message, &mut tool_names))
+        .collect();
+    out.insert("contents".to_string(), Value::Array(contents));
+
+    let mut generation = Map::new();
+    for (from, to) in [
+        ("max_tokens", "maxOutputTokens"),
+        ("temperature", "temperature"),
+        ("top_p", "topP"),
+        ("top_k", "topK"),
+        ("stop_sequences", "stopSequences"),
+    ] {
+        if let Some(value) = body.get(from) {
+            generation.insert(to.to_string(), value.clone());
+        }
+    }
+    if let Some(thinking) = body.get("thinking").and_then(translate_thinking) {
+        generation.insert("thinkingConfig".to_string(), thinking);
+    }
+    if !generation.is_empty() {
+        out.insert("generationConfig".to_string(), Value::Object(generation));
+    }
+
+    let declarations: Vec<Value> = body
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(translate_tool)
+        .collect();
+    if !declarations.is_empty() {
+        out.insert("tools".to_string(), json!([{"functionDeclarations": declarations}]));
+        if let Some(config) = body.get("tool_choice").and_then(translate_tool_choice) {
+            out.insert("toolConfig".to_string(), json!({"functionCallingConfig": config}));
+        }
+    }
+
+    Value::Object(out)
+}
+
+/// Build the Gemini `content` for one Anthropic message, or `None` if it
+/// has no translatable parts.
+fn translate_message(message: &Value, tool_names: &mut HashMap<String, String>) -> Option<Value> {
+    let role = match message.get("role").and_then(|r| r.as_str()) {
+        Some("assistant") => "model",
+        _ => "user",
+    };
+
+    let parts = match message.get("content")? {
+        Value::String(text) => vec![json!({"text": text})],
+        Value::Array(blocks) => {
+            let mut parts = Vec::new();
+            let mut signature: Option<Value> = None;
+            for block in blocks {
+                let part = match block_type(block) {
+                    "thinking" => {
+                        signature = block
+                            .get("signature")
+                            .filter(|s| s.as_str().is_some_and(|s| !s.is_empty()))
+                            .cloned();
+                        continue;
+                    }
+                    "text" => json!({"text": block.get("text").cloned().unwrap_or(Value::Null)}),
+                    "image" => match block.get("source").and_then(image_part) {
+                        Some(part) => part,
+                        None => continue,
+                    },
+                    "tool_use" => {
+                        let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                        if let Some(id) = block.get("id").and_then(|i| i.as_str()) {
+                            tool_names.insert(id.to_string(), name.to_string());
+                        }
+                        json!({"functionCall": {
+                            "name": name,
+                            "args": block.get("input").cloned().unwrap_or_else(|| json!({})),
+                        }})
+                    }
+                    "tool_result" => {
+                        let id = block.get("tool_use_id").and_then(|i| i.as_str()).unwrap_or("");
+                        let name = tool_names.get(id).map(String::as_str).unwrap_or(id);
+                        let text = block.get("content").and_then(text_content).unwrap_or_default();
+                        let is_error = block.get("is_error").and_then(|e| e.as_bool()) == Some(true);
+                        let response = if is_error {
+                            json!({"error": text})
+                        } else {
+                            json!({"content": text})
+                        };
+                        json!({"functionResponse": {"name": name, "response": response}})
+                    }
+                    _ => continue,
+                };
+                parts.push(with_signature(part, signature.take()));
+            }
+            parts
+        }
+        _ => return None,
+    };
+
+    if parts.is_empty() {
+        return None;
+    }
+    Some(json!({"role": role, "parts": parts}))
+}
+
+fn with_signature(mut part: Value, signature: Option<Value>) -> Value {
+    if let Some(signature) = signature {
+        part["thoughtSignature"] = signature;
+    }
+    part
+}
+
+/// Image `source` → `inlineData` or `fileData` part.
+fn image_part(source: &Value) -> Option<Value> {
+    match source.get("type")?.as_str()? {
+        "base64" => Some(json!({"inlineData": {
+            "mimeType": source.get("media_type")?,
+            "data": source.get("data")?,
+        }})),
+        "url" => Some(json!({"fileData": {"fileUri": source.get("url")?}})),
+        _ => None,
+    }
+}
+
+/// Anthropic `thinking` → Gemini `thinkingConfig`.
+fn translate_thinking(thinking: &Value) -> Option<Value> {
+    match thinking.get("type")?.as_str()? {
+        "enabled" => Some(json!({
+            "includeThoughts": true,
+            "thinkingBudget": thinking.get("budget_tokens").cloned().unwrap_or(json!(-1)),
+        })),
+        // Dynamic budget.
+        "adaptive" => Some(json!({"includeThoughts": true, "thinkingBudget": -1})),
+        _ => None,
+    }
+}
+
+fn translate_tool(tool: &Value) -> Option<Value> {
+    // Server tools (web search etc.) carry no schema and cannot be forwarded.
+    let schema = tool.get("input_schema")?;
+    let mut declaration = json!({
+        "name": tool.get("name")?,
+        // Full JSON Schema; `parameters` only accepts an OpenAPI subset.
+        "parametersJsonSchema": schema,
+    });
+    if let Some(description) = tool.get("description") {
+        declaration["description"] = description.clone();
+    }
+    Some(declaration)
+}
+
+fn translate_tool_choice(choice: &Value) -> Option<Value> {
+    match choice.get("type")?.as_str()? {
+        "auto" => Some(json!({"mode": "AUTO"})),
+        "any" => Some(json!({"mode": "ANY"})),
+        "none" => Some(json!({"mode": "NONE"})),
+        "tool" => Some(json!({"mode": "ANY", "allowedFunctionNames": [choice.get("name")?]})),
+        _ => None,
+    }
+}
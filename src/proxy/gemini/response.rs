@@ -0,0 +1,119 @@
+//! Gemini `generateContent` response → Anthropic Messages response.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+/// Translate a non-streaming `generateContent` body into an Anthropic
+/// `message` body.
+///
+/// Thought parts become `thinking` blocks. A `thoughtSignature` on any other
+/// part becomes a signature-only thinking block in front of it, so the
+/// signature survives the round trip through the client.
+pub fn to_message(body: &Value) -> Value {
+    let candidate = body.get("candidates").and_then(|c| c.get(0));
+    let response_id = body.get("responseId").and_then(|i| i.as_str());
+
+    let mut content = Vec::new();
+    let mut has_tool_use = false;
+    for (i, part) in parts(candidate).iter().enumerate() {
+        let signature = part.get("thoughtSignature").and_then(|s| s.as_str());
+        if is_thought(part) {
+            content.push(json!({
+                "type": "thinking",
+                "thinking": part.get("text").and_then(|t| t.as_str()).unwrap_or(""),
+                "signature": signature.unwrap_or(""),
+            }));
+            continue;
+        }
+        if let Some(signature) = signature {
+            content.push(json!({"type": "thinking", "thinking": "", "signature": signature}));
+        }
+        if let Some(call) = part.get("functionCall") {
+            has_tool_use = true;
+            content.push(json!({
+                "type": "tool_use",
+                "id": tool_use_id(call, response_id, i),
+                "name": call.get("name").cloned().unwrap_or(Value::Null),
+                "input": call.get("args").cloned().unwrap_or_else(|| json!({})),
+            }));
+        } else if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+            content.push(json!({"type": "text", "text": text}));
+        }
+    }
+
+    let finish_reason = candidate
+        .and_then(|c| c.get("finishReason"))
+        .and_then(|f| f.as_str());
+    let blocked = candidate.is_none()
+        && body.pointer("/promptFeedback/blockReason").is_some();
+
+    json!({
+        "id": response_id.map(|id| format!("msg_{}", id)),
+        "type": "message",
+        "role": "assistant",
+        "model": body.get("modelVersion").cloned().unwrap_or(Value::Null),
+        "content": content,
+        "stop_reason": if blocked { "refusal" } else { stop_reason(finish_reason, has_tool_use) },
+        "stop_sequence": null,
+        "usage": usage(body.get("usageMetadata")),
+    })
+}
+
+/// Parts of a candidate's content.
+pub(super) fn parts(candidate: Option<&Value>) -> Vec<Value> {
+    candidate
+        .and_then(|c| c.pointer("/content/parts"))
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub(super) fn is_thought(part: &Value) -> bool {
+    part.get("thought").and_then(|t| t.as_bool()) == Some(true)
+}
+
+/// Map a Gemini `finishReason` to an Anthropic `stop_reason`.
+///
+/// Gemini reports `STOP` after function calls, so tool use is detected from
+/// the parts instead.
+pub(super) fn stop_reason(finish_reason: Option<&str>, has_tool_use: bool) -> &'static str {
+    match finish_reason {
+        Some("MAX_TOKENS") => "max_tokens",
+        Some("SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII") => "refusal",
+        _ if has_tool_use => "tool_use",
+        _ => "end_turn",
+    }
+}
+
+/// Map Gemini `usageMetadata` to Anthropic `usage`.
+///
+/// Thought tokens are billed as output; cached tokens are reported
+/// separately, as Anthropic's `input_tokens` excludes cache reads.
+pub(super) fn usage(metadata: Option<&Value>) -> Value {
+    let field = |name: &str| metadata.and_then(|m| m.get(name)).and_then(|v| v.as_u64()).unwrap_or(0);
+    let cached = field("cachedContentTokenCount");
+    json!({
+        "input_tokens": field("promptTokenCount").saturating_sub(cached),
+        "output_tokens": field("candidatesTokenCount") + field("thoughtsTokenCount"),
+        "cache_read_input_tokens": cached,
+    })
+}
+
+/// Tool use id for a function call: Gemini's own id when present, otherwise
+/// one derived from the response id (or the clock) and the part index.
+pub(super) fn tool_use_id(call: &Value, response_id: Option<&str>, index: usize) -> String {
+    if let Some(id) = call.get("id").and_then(|i| i.as_str()) {
+        return id.to_string();
+    }
+    match response_id {
+        Some(response_id) => format!("toolu_{}_{}", response_id, index),
+        None => {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            format!("toolu_{:x}_{}", nanos, index)
+        }
+    }
+}
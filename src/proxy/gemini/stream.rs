@@ -0,0 +1,228 @@
+//! Gemini `streamGenerateContent?alt=sse` stream → Anthropic Messages SSE stream.
+
+use axum::body::Bytes;
+use serde_json::{json, Value};
+
+use super::response::{is_thought, parts, stop_reason, tool_use_id, usage};
+use crate::metrics::ChunkRewriter;
+use crate::proxy::protocol::{write_event, SseLines};
+
+/// Create a stateful chunk rewriter that turns Gemini stream chunks into
+/// Anthropic stream events.
+///
+/// # Lifecycle
+///
+/// ```text
+/// first chunk            --> message_start
+/// thought part           --> content_block_start(thinking) + thinking_delta
+/// thoughtSignature       --> signature_delta on the thinking block
+/// text part              --> content_block_start(text) + text_delta
+/// functionCall part      --> content_block_start(tool_use) + input_json_delta + stop
+/// finishReason           --> content_block_stop + message_delta + message_stop
+/// ```
+///
+/// Gemini sends no end-of-stream marker; the chunk carrying `finishReason`
+/// ends the message. Partial lines are buffered across chunks. A stream
+/// that ends without `finishReason` is finished on the end-of-stream flush.
+pub fn make_stream_translator() -> ChunkRewriter {
+    let mut translator = StreamTranslator::default();
+    Box::new(move |bytes: Bytes| Bytes::from(translator.push(&bytes)))
+}
+
+/// Kind of content block currently open on the Anthropic side.
+#[derive(Clone, Copy, PartialEq)]
+enum BlockKind {
+    Thinking,
+    Text,
+}
+
+#[derive(Default)]
+struct StreamTranslator {
+    lines: SseLines,
+    started: bool,
+    finished: bool,
+    /// Open thinking or text block and its index.
+    open_block: Option<(BlockKind, u64)>,
+    next_index: u64,
+    tool_calls: usize,
+    /// Latest `usageMetadata`, for a stream that ends without `finishReason`.
+    usage: Option<Value>,
+}
+
+impl StreamTranslator {
+    /// Translate `bytes`; an empty chunk marks the end of the stream.
+    fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = String::new();
+        if bytes.is_empty() {
+            for data in self.lines.flush() {
+                self.data(&data, &mut out);
+            }
+            if self.started {
+                self.finish(&mut out, None);
+            }
+            return out.into_bytes();
+        }
+        for data in self.lines.push(bytes) {
+            self.data(&data, &mut out);
+        }
+        out.into_bytes()
+    }
+
+    fn data(&mut self, data: &str, out: &mut String) {
+        if self.finished {
+            return;
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("upstream stream error");
+            write_event(out, json!({
+                "type": "error",
+                "error": {"type": "api_error", "message": message},
+            }));
+            return;
+        }
+
+        let response_id = chunk.get("responseId").and_then(|i| i.as_str());
+        if !self.started {
+            self.started = true;
+            write_event(out, json!({
+                "type": "message_start",
+                "message": {
+                    "id": response_id.map(|id| format!("msg_{}", id)),
+                    "type": "message",
+                    "role": "assistant",
+                    "model": chunk.get("modelVersion").cloned().unwrap_or(Value::Null),
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0},
+                },
+            }));
+        }
+
+        if let Some(usage) = chunk.get("usageMetadata") {
+            self.usage = Some(usage.clone());
+        }
+
+        let candidate = chunk.get("candidates").and_then(|c| c.get(0));
+        for part in parts(candidate) {
+            let signature = part.get("thoughtSignature").and_then(|s| s.as_str());
+            let text = part.get("text").and_then(|t| t.as_str()).unwrap_or("");
+
+            if is_thought(&part) {
+                let index = self.ensure(out, BlockKind::Thinking);
+                if !text.is_empty() {
+                    delta(out, index, json!({"type": "thinking_delta", "thinking": text}));
+                }
+                if let Some(signature) = signature {
+                    delta(out, index, json!({"type": "signature_delta", "signature": signature}));
+                }
+                continue;
+            }
+
+            // Signature on a non-thought part: attach it to a thinking block
+            // in front of the part.
+            if let Some(signature) = signature {
+                let index = self.ensure(out, BlockKind::Thinking);
+                delta(out, index, json!({"type": "signature_delta", "signature": signature}));
+            }
+
+            if let Some(call) = part.get("functionCall") {
+                self.close(out);
+                let index = self.next();
+                let id = tool_use_id(call, response_id, self.tool_calls);
+                self.tool_calls += 1;
+                write_event(out, json!({
+                    "type": "content_block_start",
+                    "index": index,
+                    "content_block": {
+                        "type": "tool_use",
+                        "id": id,
+                        "name": call.get("name").cloned().unwrap_or(Value::Null),
+                        "input": {},
+                    },
+                }));
+                let args = call.get("args").cloned().unwrap_or_else(|| json!({}));
+                delta(out, index, json!({"type": "input_json_delta", "partial_json": args.to_string()}));
+                write_event(out, json!({"type": "content_block_stop", "index": index}));
+            } else if !text.is_empty() {
+                let index = self.ensure(out, BlockKind::Text);
+                delta(out, index, json!({"type": "text_delta", "text": text}));
+            }
+        }
+
+        let finish_reason = candidate
+            .and_then(|c| c.get("finishReason"))
+            .and_then(|f| f.as_str());
+        if finish_reason.is_some() {
+            self.finish(out, finish_reason);
+        }
+    }
+
+    /// Close the open block and end the message.
+    fn finish(&mut self, out: &mut String, finish_reason: Option<&str>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.close(out);
+        write_event(out, json!({
+            "type": "message_delta",
+            "delta": {
+                "stop_reason": stop_reason(finish_reason, self.tool_calls > 0),
+                "stop_sequence": null,
+            },
+            "usage": usage(self.usage.as_ref()),
+        }));
+        write_event(out, json!({"type": "message_stop"}));
+    }
+
+    /// Index of the open block of `kind`, opening one if needed.
+    fn ensure(&mut self, out: &mut String, kind: BlockKind) -> u64 {
+        if let Some((open, index)) = self.open_block {
+            if open == kind {
+                return index;
+            }
+        }
+
+        self.close(out);
+        let index = self.next();
+        let content_block = match kind {
+            BlockKind::Thinking => json!({"type": "thinking", "thinking": "", "signature": ""}),
+            BlockKind::Text => json!({"type": "text", "text": ""}),
+        };
+        write_event(out, json!({
+            "type": "content_block_start",
+            "index": index,
+            "content_block": content_block,
+        }));
+        self.open_block = Some((kind, index));
+        index
+    }
+
+    fn next(&mut self) -> u64 {
+        let index = self.next_index;
+        self.next_index += 1;
+        index
+    }
+
+    fn close(&mut self, out: &mut String) {
+        if let Some((_, index)) = self.open_block.take() {
+            write_event(out, json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+}
+
+fn delta(out: &mut String, index: u64, delta: Value) {
+    write_event(out, json!({
+        "type": "content_block_delta",
+        "index": index,
+        "delta": delta,
+    }));
+}
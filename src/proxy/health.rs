@@ -1,25 +1,57 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
 use axum::response::Response;
 use axum::response::IntoResponse;
 use axum::Json;
 use serde::Serialize;
 
+use crate::backend::{BackendState, CircuitState};
+
 #[derive(Debug, Serialize)]
 pub struct HealthStatus {
     pub status: String,
     pub service: String,
+    /// Per-backend health, keyed by backend name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backends: Option<BTreeMap<String, BackendHealth>>,
+}
+
+/// Health of one backend: latest active probe plus circuit state.
+///
+/// Probe fields are `null` until the first probe completes or when
+/// probing is disabled.
+#[derive(Debug, Serialize)]
+pub struct BackendHealth {
+    pub healthy: Option<bool>,
+    pub reachable: Option<bool>,
+    pub latency_ms: Option<u64>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub checked_seconds_ago: Option<u64>,
+    pub circuit: CircuitState,
 }
 
-pub struct HealthHandler;
+pub struct HealthHandler {
+    backend_state: Option<BackendState>,
+}
 
 impl HealthHandler {
     pub fn new() -> Self {
-        Self
+        Self { backend_state: None }
+    }
+
+    /// Include per-backend health from `backend_state` in responses.
+    pub fn with_backend_state(mut self, backend_state: BackendState) -> Self {
+        self.backend_state = Some(backend_state);
+        self
     }
 
     pub async fn handle(&self) -> Response {
         let health = HealthStatus {
             status: "healthy".to_string(),
             service: "anyclaude".to_string(),
+            backends: self.backend_state.as_ref().map(backend_health),
         };
 
         Json(health).into_response()
@@ -31,3 +63,29 @@ impl Default for HealthHandler {
         Self::new()
     }
 }
+
+fn backend_health(backend_state: &BackendState) -> BTreeMap<String, BackendHealth> {
+    let probes = backend_state.probe_results().all();
+    let circuit_breakers = backend_state.circuit_breakers();
+    let now = SystemTime::now();
+
+    backend_state
+        .list_backends()
+        .into_iter()
+        .map(|name| {
+            let probe = probes.get(&name);
+            let health = BackendHealth {
+                healthy: probe.map(|p| p.healthy),
+                reachable: probe.map(|p| p.reachable),
+                latency_ms: probe.and_then(|p| p.latency_ms),
+                status: probe.and_then(|p| p.status),
+                error: probe.and_then(|p| p.error.clone()),
+                checked_seconds_ago: probe.map(|p| {
+                    now.duration_since(p.checked_at).unwrap_or_default().as_secs()
+                }),
+                circuit: circuit_breakers.state(&name),
+            };
+            (name, health)
+        })
+        .collect()
+}
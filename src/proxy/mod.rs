@@ -1,14 +1,26 @@
+pub mod bedrock;
 pub mod connection;
 pub mod error;
+pub mod gemini;
 pub mod health;
 pub mod hooks;
 pub mod model_rewrite;
+pub mod openai;
+pub mod otlp;
 pub mod pool;
+pub mod prober;
+pub mod prometheus;
+pub mod protocol;
+pub mod recording;
+pub mod retry;
 pub mod router;
 pub mod server;
 pub mod shutdown;
+pub mod sigv4;
 pub mod thinking;
 pub mod timeout;
+pub mod usage_ledger;
+pub mod vertex;
 pub mod pipeline;
 
 pub use server::{ProxyHandle, ProxyServer};
@@ -0,0 +1,25 @@
+//! OpenAI Chat Completions protocol translation.
+//!
+//! Backends with `protocol = "openai"` receive Anthropic `/v1/messages`
+//! requests as `/v1/chat/completions`, and their JSON and SSE responses
+//! are translated back into Anthropic shapes:
+//!
+//! - **Request** (stage 4): system, messages, tool_use/tool_result blocks,
+//!   images, tools, stop sequences and max_tokens
+//! - **Non-streaming response** (stage 7): `chat.completion` → `message`
+//! - **Streaming response** (stage 7): a `ChunkRewriter` that emits
+//!   `message_start` / `content_block_*` / `message_delta` / `message_stop`
+//!
+//! Dispatch and error translation live in [`crate::proxy::protocol`].
+
+mod request;
+mod response;
+mod stream;
+
+pub use request::to_chat_completions;
+pub use response::to_message;
+pub use stream::make_stream_translator;
+
+/// Chat Completions endpoint that translated requests are sent to.
+pub const CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
+
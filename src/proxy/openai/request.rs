@@ -0,0 +1,199 @@
+//! Anthropic Messages request → OpenAI Chat Completions request.
+
+use serde_json::{json, Map, Value};
+
+use crate::proxy::protocol::{block_type, text_content};
+
+/// Translate an Anthropic `/v1/messages` request body into a
+/// `/v1/chat/completions` request body.
+///
+/// Thinking configuration and thinking blocks are dropped: Chat Completions
+/// has no equivalent that round-trips signatures.
+pub fn to_chat_completions(body: &Value) -> Value {
+    let mut out = Map::new();
+
+    if let Some(model) = body.get("model") {
+        out.insert("model".to_string(), model.clone());
+    }
+
+    let mut messages = Vec::new();
+    if let Some(system) = body
+        .get("system")
+        .and_then(text_content)
+        .filter(|s| !s.is_empty())
+    {
+        messages.push(json!({"role": "system", "content": system}));
+    }
+    for message in body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+    {
+        translate_message(message, &mut messages);
+    }
+    out.insert("messages".to_string(), Value::Array(messages));
+
+    for field in ["max_tokens", "temperature", "top_p"] {
+        if let Some(value) = body.get(field) {
+            out.insert(field.to_string(), value.clone());
+        }
+    }
+    if let Some(stop) = body.get("stop_sequences") {
+        out.insert("stop".to_string(), stop.clone());
+    }
+
+    let streaming = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+    if streaming {
+        out.insert("stream".to_string(), json!(true));
+        // Usage otherwise never appears in streamed responses.
+        out.insert("stream_options".to_string(), json!({"include_usage": true}));
+    }
+
+    let tools: Vec<Value> = body
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(translate_tool)
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".to_string(), Value::Array(tools));
+        if let Some(choice) = body.get("tool_choice") {
+            if let Some(translated) = translate_tool_choice(choice) {
+                out.insert("tool_choice".to_string(), translated);
+            }
+            if choice.get("disable_parallel_tool_use").and_then(|d| d.as_bool()) == Some(true) {
+                out.insert("parallel_tool_calls".to_string(), json!(false));
+            }
+        }
+    }
+
+    Value::Object(out)
+}
+
+/// Append the Chat Completions messages for one Anthropic message.
+///
+/// `tool_result` blocks become separate `tool` role messages, emitted before
+/// the rest of the user turn so they directly follow the assistant's
+/// `tool_calls`. `tool` messages only carry text, so a result's images join
+/// the user turn, and an error result is marked in its text.
+fn translate_message(message: &Value, out: &mut Vec<Value>) {
+    let role = message.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+    let content = message.get("content");
+
+    let Some(blocks) = content.and_then(|c| c.as_array()) else {
+        out.push(json!({"role": role, "content": content.cloned().unwrap_or(Value::Null)}));
+        return;
+    };
+
+    if role == "assistant" {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in blocks {
+            match block_type(block) {
+                "text" => text.push_str(block.get("text").and_then(|t| t.as_str()).unwrap_or("")),
+                "tool_use" => tool_calls.push(json!({
+                    "id": block.get("id").cloned().unwrap_or(Value::Null),
+                    "type": "function",
+                    "function": {
+                        "name": block.get("name").cloned().unwrap_or(Value::Null),
+                        "arguments": block.get("input").map(|i| i.to_string()).unwrap_or_else(|| "{}".to_string()),
+                    },
+                })),
+                _ => {}
+            }
+        }
+        let mut msg = json!({
+            "role": "assistant",
+            "content": if text.is_empty() { Value::Null } else { Value::String(text) },
+        });
+        if !tool_calls.is_empty() {
+            msg["tool_calls"] = Value::Array(tool_calls);
+        }
+        out.push(msg);
+        return;
+    }
+
+    let mut parts = Vec::new();
+    for block in blocks {
+        match block_type(block) {
+            "tool_result" => {
+                let content = block.get("content");
+                let mut text = content.and_then(text_content).unwrap_or_default();
+                if block.get("is_error").and_then(|e| e.as_bool()) == Some(true) {
+                    text = format!("Error: {}", text);
+                }
+                out.push(json!({
+                    "role": "tool",
+                    "tool_call_id": block.get("tool_use_id").cloned().unwrap_or(Value::Null),
+                    "content": text,
+                }));
+                parts.extend(
+                    content
+                        .and_then(|c| c.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(image_part),
+                );
+            }
+            "text" => parts.push(json!({
+                "type": "text",
+                "text": block.get("text").cloned().unwrap_or(Value::Null),
+            })),
+            "image" => parts.extend(image_part(block)),
+            _ => {}
+        }
+    }
+    if !parts.is_empty() {
+        out.push(json!({"role": role, "content": parts}));
+    }
+}
+
+fn translate_tool(tool: &Value) -> Option<Value> {
+    // Server tools (web search etc.) carry no schema and cannot be forwarded.
+    let schema = tool.get("input_schema")?;
+    let mut function = json!({
+        "name": tool.get("name")?,
+        "parameters": schema,
+    });
+    if let Some(description) = tool.get("description") {
+        function["description"] = description.clone();
+    }
+    Some(json!({"type": "function", "function": function}))
+}
+
+fn translate_tool_choice(choice: &Value) -> Option<Value> {
+    match choice.get("type")?.as_str()? {
+        "auto" => Some(json!("auto")),
+        "any" => Some(json!("required")),
+        "none" => Some(json!("none")),
+        "tool" => Some(json!({
+            "type": "function",
+            "function": {"name": choice.get("name")?},
+        })),
+        _ => None,
+    }
+}
+
+/// `image` block → `image_url` part.
+fn image_part(block: &Value) -> Option<Value> {
+    if block_type(block) != "image" {
+        return None;
+    }
+    let url = image_url(block.get("source")?)?;
+    Some(json!({"type": "image_url", "image_url": {"url": url}}))
+}
+
+/// Image `source` → URL usable in an `image_url` part.
+fn image_url(source: &Value) -> Option<String> {
+    match source.get("type")?.as_str()? {
+        "base64" => Some(format!(
+            "data:{};base64,{}",
+            source.get("media_type")?.as_str()?,
+            source.get("data")?.as_str()?
+        )),
+        "url" => Some(source.get("url")?.as_str()?.to_string()),
+        _ => None,
+    }
+}
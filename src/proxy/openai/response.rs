@@ -0,0 +1,81 @@
+//! OpenAI Chat Completions response → Anthropic Messages response.
+
+use serde_json::{json, Value};
+
+/// Translate a non-streaming `chat.completion` body into an Anthropic
+/// `message` body.
+pub fn to_message(body: &Value) -> Value {
+    let choice = body
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .cloned()
+        .unwrap_or(Value::Null);
+    let message = choice.get("message").cloned().unwrap_or(Value::Null);
+
+    let mut content = Vec::new();
+    if let Some(text) = message
+        .get("content")
+        .and_then(|c| c.as_str())
+        .filter(|t| !t.is_empty())
+    {
+        content.push(json!({"type": "text", "text": text}));
+    }
+    for call in message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let function = call.get("function").cloned().unwrap_or(Value::Null);
+        content.push(json!({
+            "type": "tool_use",
+            "id": call.get("id").cloned().unwrap_or(Value::Null),
+            "name": function.get("name").cloned().unwrap_or(Value::Null),
+            "input": parse_arguments(function.get("arguments").and_then(|a| a.as_str()).unwrap_or("")),
+        }));
+    }
+
+    json!({
+        "id": body.get("id").cloned().unwrap_or(Value::Null),
+        "type": "message",
+        "role": "assistant",
+        "model": body.get("model").cloned().unwrap_or(Value::Null),
+        "content": content,
+        "stop_reason": stop_reason(choice.get("finish_reason").and_then(|f| f.as_str())),
+        "stop_sequence": null,
+        "usage": usage(body.get("usage")),
+    })
+}
+
+/// Map an OpenAI `finish_reason` to an Anthropic `stop_reason`.
+pub(super) fn stop_reason(finish_reason: Option<&str>) -> &'static str {
+    match finish_reason {
+        Some("length") => "max_tokens",
+        Some("tool_calls") | Some("function_call") => "tool_use",
+        Some("content_filter") => "refusal",
+        _ => "end_turn",
+    }
+}
+
+/// Map OpenAI `usage` to Anthropic `usage`.
+///
+/// Cached prompt tokens are reported separately, as Anthropic's
+/// `input_tokens` excludes cache reads.
+pub(super) fn usage(usage: Option<&Value>) -> Value {
+    let field = |name: &str| usage.and_then(|u| u.get(name)).and_then(|v| v.as_u64()).unwrap_or(0);
+    let cached = usage
+        .and_then(|u| u.get("prompt_tokens_details"))
+        .and_then(|d| d.get("cached_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    json!({
+        "input_tokens": field("prompt_tokens").saturating_sub(cached),
+        "output_tokens": field("completion_tokens"),
+        "cache_read_input_tokens": cached,
+    })
+}
+
+/// Parse tool call arguments; malformed JSON yields `{}`.
+fn parse_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| json!({}))
+}
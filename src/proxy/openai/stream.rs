@@ -0,0 +1,235 @@
+//! OpenAI Chat Completions SSE stream → Anthropic Messages SSE stream.
+
+use axum::body::Bytes;
+use serde_json::{json, Value};
+
+use super::response::{stop_reason, usage};
+use crate::metrics::ChunkRewriter;
+use crate::proxy::protocol::{write_event, SseLines};
+
+/// Create a stateful chunk rewriter that turns `chat.completion.chunk`
+/// events into Anthropic stream events.
+///
+/// # Lifecycle
+///
+/// ```text
+/// first chunk            --> message_start
+/// delta.content          --> content_block_start(text) + text_delta
+/// delta.tool_calls[i]    --> content_block_start(tool_use) + input_json_delta
+/// finish_reason          --> content_block_stop for every open block
+/// next chunk, [DONE]
+///   or end of stream     --> message_delta (stop_reason, usage) + message_stop
+/// ```
+///
+/// The message ends with the chunk after `finish_reason`, which carries the
+/// usage when `include_usage` is honoured, or earlier if that chunk already
+/// had usage. Each tool call keeps its own block, so parallel calls whose
+/// deltas interleave are not split. Partial lines are buffered across
+/// chunks, so a chunk may translate to nothing.
+pub fn make_stream_translator() -> ChunkRewriter {
+    let mut translator = StreamTranslator::default();
+    Box::new(move |bytes: Bytes| Bytes::from(translator.push(&bytes)))
+}
+
+#[derive(Default)]
+struct StreamTranslator {
+    lines: SseLines,
+    started: bool,
+    finished: bool,
+    /// Index of the open text block.
+    text_block: Option<u64>,
+    /// Open tool_use blocks: tool call index and block index.
+    tool_blocks: Vec<(u64, u64)>,
+    next_index: u64,
+    stop_reason: Option<&'static str>,
+    usage: Option<Value>,
+}
+
+impl StreamTranslator {
+    /// Translate `bytes`; an empty chunk marks the end of the stream.
+    fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = String::new();
+        if bytes.is_empty() {
+            for data in self.lines.flush() {
+                self.data(&data, &mut out);
+            }
+            if self.started {
+                self.finish(&mut out);
+            }
+            return out.into_bytes();
+        }
+        for data in self.lines.push(bytes) {
+            self.data(&data, &mut out);
+        }
+        out.into_bytes()
+    }
+
+    fn data(&mut self, data: &str, out: &mut String) {
+        if data == "[DONE]" {
+            if self.started {
+                self.finish(out);
+            }
+            return;
+        }
+        if self.finished {
+            return;
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("upstream stream error");
+            write_event(out, json!({
+                "type": "error",
+                "error": {"type": "api_error", "message": message},
+            }));
+            return;
+        }
+
+        if !self.started {
+            self.started = true;
+            write_event(out, json!({
+                "type": "message_start",
+                "message": {
+                    "id": chunk.get("id").cloned().unwrap_or(Value::Null),
+                    "type": "message",
+                    "role": "assistant",
+                    "model": chunk.get("model").cloned().unwrap_or(Value::Null),
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0},
+                },
+            }));
+        }
+
+        let usage = chunk.get("usage").filter(|u| !u.is_null());
+        if let Some(u) = usage {
+            self.usage = Some(u.clone());
+        }
+        // The chunk after finish_reason ends the message.
+        if self.stop_reason.is_some() {
+            self.finish(out);
+            return;
+        }
+
+        let Some(choice) = chunk.get("choices").and_then(|c| c.get(0)) else {
+            return;
+        };
+        let delta = choice.get("delta");
+
+        if let Some(text) = delta
+            .and_then(|d| d.get("content"))
+            .and_then(|c| c.as_str())
+            .filter(|t| !t.is_empty())
+        {
+            let index = match self.text_block {
+                Some(index) => index,
+                None => {
+                    let index = self.open(out, json!({"type": "text", "text": ""}));
+                    self.text_block = Some(index);
+                    index
+                }
+            };
+            write_event(out, json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {"type": "text_delta", "text": text},
+            }));
+        }
+
+        for call in delta
+            .and_then(|d| d.get("tool_calls"))
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let call_index = call.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+            let function = call.get("function");
+            let open = self.tool_blocks.iter().find(|(call, _)| *call == call_index);
+            let index = match open {
+                Some(&(_, index)) => index,
+                None => {
+                    // Text before a tool call is complete.
+                    self.close_text(out);
+                    let index = self.open(
+                        out,
+                        json!({
+                            "type": "tool_use",
+                            "id": call.get("id").cloned().unwrap_or(Value::Null),
+                            "name": function.and_then(|f| f.get("name")).cloned().unwrap_or(Value::Null),
+                            "input": {},
+                        }),
+                    );
+                    self.tool_blocks.push((call_index, index));
+                    index
+                }
+            };
+            if let Some(arguments) = function
+                .and_then(|f| f.get("arguments"))
+                .and_then(|a| a.as_str())
+                .filter(|a| !a.is_empty())
+            {
+                write_event(out, json!({
+                    "type": "content_block_delta",
+                    "index": index,
+                    "delta": {"type": "input_json_delta", "partial_json": arguments},
+                }));
+            }
+        }
+
+        if let Some(reason) = choice.get("finish_reason").and_then(|f| f.as_str()) {
+            self.stop_reason = Some(stop_reason(Some(reason)));
+            self.close_all(out);
+            if usage.is_some() {
+                self.finish(out);
+            }
+        }
+    }
+
+    /// Start a new block; returns its index.
+    fn open(&mut self, out: &mut String, content_block: Value) -> u64 {
+        let index = self.next_index;
+        self.next_index += 1;
+        write_event(out, json!({
+            "type": "content_block_start",
+            "index": index,
+            "content_block": content_block,
+        }));
+        index
+    }
+
+    fn close_text(&mut self, out: &mut String) {
+        if let Some(index) = self.text_block.take() {
+            write_event(out, json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+
+    fn close_all(&mut self, out: &mut String) {
+        self.close_text(out);
+        for (_, index) in std::mem::take(&mut self.tool_blocks) {
+            write_event(out, json!({"type": "content_block_stop", "index": index}));
+        }
+    }
+
+    fn finish(&mut self, out: &mut String) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.close_all(out);
+        write_event(out, json!({
+            "type": "message_delta",
+            "delta": {
+                "stop_reason": self.stop_reason.unwrap_or("end_turn"),
+                "stop_sequence": null,
+            },
+            "usage": usage(self.usage.as_ref()),
+        }));
+        write_event(out, json!({"type": "message_stop"}));
+    }
+}
@@ -0,0 +1,412 @@
+//! OpenTelemetry trace export (`[tracing]`).
+//!
+//! Every finished request becomes an OTLP span with a child span per
+//! pipeline stage that ran. The observability plugin queues the spans and
+//! [`TraceExporter::run`] posts them to the collector in batches over
+//! OTLP/HTTP, encoded as protobuf or JSON.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tokio::sync::Notify;
+
+use crate::backend::BackendState;
+use crate::config::{OtlpFormat, TracingConfig};
+use crate::metrics::trace::{hex, new_span_id};
+use crate::metrics::{HopTiming, ObservabilityPlugin, PostResponseContext, RequestRecord, RequestTrace};
+use crate::proxy::shutdown::ShutdownManager;
+
+/// Time between exports while spans trickle in.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Queued spans that trigger an export right away.
+const BATCH_SIZE: usize = 256;
+
+/// Spans kept while the collector is slow or down; newer ones are dropped.
+const MAX_QUEUED_SPANS: usize = 8192;
+
+/// `Span.SpanKind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+}
+
+/// `Status.StatusCode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCode {
+    Unset = 0,
+    Error = 2,
+}
+
+/// Attribute value of an exported span.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+}
+
+/// One span, independent of the wire encoding.
+#[derive(Debug, Clone)]
+pub struct ExportSpan {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_unix_nanos: u64,
+    pub end_unix_nanos: u64,
+    pub attributes: Vec<(&'static str, AttributeValue)>,
+    pub status: StatusCode,
+}
+
+/// Queues finished requests as spans and exports them.
+///
+/// Cheap to clone; all clones share the queue.
+#[derive(Clone)]
+pub struct TraceExporter {
+    inner: Arc<ExporterInner>,
+}
+
+struct ExporterInner {
+    backend_state: BackendState,
+    queue: Mutex<Vec<ExportSpan>>,
+    notify: Notify,
+    client: reqwest::Client,
+}
+
+impl TraceExporter {
+    pub fn new(backend_state: BackendState) -> Self {
+        Self {
+            inner: Arc::new(ExporterInner {
+                backend_state,
+                queue: Mutex::new(Vec::new()),
+                notify: Notify::new(),
+                client: reqwest::Client::new(),
+            }),
+        }
+    }
+
+    /// Export loop. Re-reads `[tracing]` for every batch so config reloads
+    /// apply; flushes once more and exits on proxy shutdown.
+    pub async fn run(self, shutdown: Arc<ShutdownManager>) {
+        loop {
+            let stopping = tokio::select! {
+                _ = tokio::time::sleep(EXPORT_INTERVAL) => false,
+                _ = self.inner.notify.notified() => false,
+                _ = shutdown.wait_for_shutdown() => true,
+            };
+            self.flush().await;
+            if stopping {
+                break;
+            }
+        }
+    }
+
+    /// Send every queued span to the collector.
+    pub async fn flush(&self) {
+        let spans = std::mem::take(&mut *self.inner.queue.lock());
+        if spans.is_empty() {
+            return;
+        }
+        let config = self.inner.backend_state.tracing_config();
+        let (content_type, body) = match config.format {
+            OtlpFormat::Protobuf => ("application/x-protobuf", encode_protobuf(&config, &spans)),
+            OtlpFormat::Json => ("application/json", encode_json(&config, &spans).to_string().into_bytes()),
+        };
+
+        let mut request = self
+            .inner
+            .client
+            .post(&config.endpoint)
+            .timeout(Duration::from_secs(10))
+            .header("content-type", content_type);
+        for (name, value) in &config.headers {
+            request = request.header(name, value);
+        }
+        let result = match request.body(body).send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!("status {}", resp.status().as_u16())),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(reason) = result {
+            crate::metrics::app_log(
+                "otlp",
+                &format!("Dropped {} spans, export to {} failed: {}", spans.len(), config.endpoint, reason),
+            );
+        }
+    }
+
+    fn enqueue(&self, spans: Vec<ExportSpan>) {
+        let mut queue = self.inner.queue.lock();
+        let room = MAX_QUEUED_SPANS.saturating_sub(queue.len());
+        queue.extend(spans.into_iter().take(room));
+        if queue.len() >= BATCH_SIZE {
+            self.inner.notify.notify_one();
+        }
+    }
+}
+
+impl ObservabilityPlugin for TraceExporter {
+    fn post_response(&self, ctx: &mut PostResponseContext<'_>) {
+        if self.inner.backend_state.tracing_config().enabled {
+            self.enqueue(spans_from_record(ctx.record));
+        }
+    }
+}
+
+/// The request span of `record` followed by its stage spans and, for
+/// requests that failed over, one child span per backend tried.
+pub fn spans_from_record(record: &RequestRecord) -> Vec<ExportSpan> {
+    let trace = &record.trace;
+    let end = record.completed_at.unwrap_or_else(SystemTime::now);
+
+    let mut attributes = vec![
+        ("http.request.method", AttributeValue::String(trace.method.clone())),
+        ("url.path", AttributeValue::String(trace.path.clone())),
+        ("anyclaude.request_id", AttributeValue::String(record.id.clone())),
+        ("anyclaude.backend", AttributeValue::String(record.backend.clone())),
+        ("anyclaude.retries", AttributeValue::Int(trace.retries.into())),
+        (
+            "anyclaude.thinking.blocks_filtered",
+            AttributeValue::Int(trace.thinking_blocks_filtered.into()),
+        ),
+    ];
+    if let Some(status) = record.status {
+        attributes.push(("http.response.status_code", AttributeValue::Int(status.into())));
+    }
+    if let Some(decision) = &record.routing_decision {
+        attributes.push(("anyclaude.routing.reason", AttributeValue::String(decision.reason.clone())));
+        attributes.push(("anyclaude.synthesized", AttributeValue::Bool(decision.synthesized)));
+    }
+    if let Some(model) = &trace.model {
+        attributes.push(("gen_ai.request.model", AttributeValue::String(model.clone())));
+    }
+    if let Some(model) = &trace.upstream_model {
+        attributes.push(("anyclaude.upstream.model", AttributeValue::String(model.clone())));
+    }
+    if let Some(usage) = &record.usage {
+        for (key, tokens) in [
+            ("gen_ai.usage.input_tokens", usage.input_tokens),
+            ("gen_ai.usage.output_tokens", usage.output_tokens),
+            ("gen_ai.usage.cache_read.input_tokens", usage.cache_read_input_tokens),
+            ("gen_ai.usage.cache_creation.input_tokens", usage.cache_creation_input_tokens),
+        ] {
+            attributes.push((key, AttributeValue::Int(tokens as i64)));
+        }
+    }
+    if record.timed_out {
+        attributes.push(("anyclaude.timed_out", AttributeValue::Bool(true)));
+    }
+
+    let failed = record.timed_out || record.status.is_none_or(|status| status >= 500);
+    let mut spans = vec![ExportSpan {
+        trace_id: trace.trace_id,
+        span_id: trace.span_id,
+        parent_span_id: trace.parent_span_id,
+        name: format!("{} {}", trace.method, trace.path),
+        kind: SpanKind::Server,
+        start_unix_nanos: unix_nanos(record.started_at),
+        end_unix_nanos: unix_nanos(end),
+        attributes,
+        status: if failed { StatusCode::Error } else { StatusCode::Unset },
+    }];
+    spans.extend(trace.stages.iter().map(|stage| ExportSpan {
+        trace_id: trace.trace_id,
+        span_id: new_span_id(),
+        parent_span_id: Some(trace.span_id),
+        name: format!("pipeline.{}", stage.name),
+        kind: SpanKind::Internal,
+        start_unix_nanos: unix_nanos(stage.started_at),
+        end_unix_nanos: unix_nanos(stage.ended_at),
+        attributes: Vec::new(),
+        status: StatusCode::Unset,
+    }));
+    if let Some(last) = trace.hops.last() {
+        let answered = HopTiming {
+            backend: record.backend.clone(),
+            started_at: last.ended_at,
+            ended_at: end,
+            status: record.status,
+            timed_out: record.timed_out,
+        };
+        spans.extend(trace.hops.iter().chain([&answered]).map(|hop| hop_span(trace, hop)));
+    }
+    spans
+}
+
+/// Child span of the request span for the attempt on one backend.
+fn hop_span(trace: &RequestTrace, hop: &HopTiming) -> ExportSpan {
+    let mut attributes = vec![("anyclaude.backend", AttributeValue::String(hop.backend.clone()))];
+    if let Some(status) = hop.status {
+        attributes.push(("http.response.status_code", AttributeValue::Int(status.into())));
+    }
+    if hop.timed_out {
+        attributes.push(("anyclaude.timed_out", AttributeValue::Bool(true)));
+    }
+    let failed = hop.timed_out || hop.status.is_none_or(|status| status >= 500);
+    ExportSpan {
+        trace_id: trace.trace_id,
+        span_id: new_span_id(),
+        parent_span_id: Some(trace.span_id),
+        name: format!("upstream {}", hop.backend),
+        kind: SpanKind::Client,
+        start_unix_nanos: unix_nanos(hop.started_at),
+        end_unix_nanos: unix_nanos(hop.ended_at),
+        attributes,
+        status: if failed { StatusCode::Error } else { StatusCode::Unset },
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// `ExportTraceServiceRequest` in the OTLP/JSON encoding.
+pub fn encode_json(config: &TracingConfig, spans: &[ExportSpan]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": hex(&span.trace_id),
+                "spanId": hex(&span.span_id),
+                "name": span.name,
+                "kind": span.kind as u8,
+                "startTimeUnixNano": span.start_unix_nanos.to_string(),
+                "endTimeUnixNano": span.end_unix_nanos.to_string(),
+                "attributes": span.attributes.iter().map(|(key, value)| json_attribute(key, value)).collect::<Vec<_>>(),
+                "status": {"code": span.status as u8},
+            });
+            if let Some(parent) = span.parent_span_id {
+                value["parentSpanId"] = json!(hex(&parent));
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [json_attribute("service.name", &AttributeValue::String(config.service_name.clone()))],
+            },
+            "scopeSpans": [{
+                "scope": {"name": "anyclaude", "version": env!("CARGO_PKG_VERSION")},
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn json_attribute(key: &str, value: &AttributeValue) -> Value {
+    let value = match value {
+        AttributeValue::String(s) => json!({"stringValue": s}),
+        AttributeValue::Bool(b) => json!({"boolValue": b}),
+        AttributeValue::Int(i) => json!({"intValue": i.to_string()}),
+    };
+    json!({"key": key, "value": value})
+}
+
+/// `ExportTraceServiceRequest` in the protobuf encoding.
+pub fn encode_protobuf(config: &TracingConfig, spans: &[ExportSpan]) -> Vec<u8> {
+    let mut request = Proto::default();
+    // ExportTraceServiceRequest.resource_spans
+    request.message(1, |resource_spans| {
+        // ResourceSpans.resource
+        resource_spans.message(1, |resource| {
+            resource.message(1, |kv| {
+                proto_attribute(kv, "service.name", &AttributeValue::String(config.service_name.clone()))
+            });
+        });
+        // ResourceSpans.scope_spans
+        resource_spans.message(2, |scope_spans| {
+            scope_spans.message(1, |scope| {
+                scope.string(1, "anyclaude");
+                scope.string(2, env!("CARGO_PKG_VERSION"));
+            });
+            for span in spans {
+                scope_spans.message(2, |out| proto_span(out, span));
+            }
+        });
+    });
+    request.0
+}
+
+fn proto_span(out: &mut Proto, span: &ExportSpan) {
+    out.bytes(1, &span.trace_id);
+    out.bytes(2, &span.span_id);
+    if let Some(parent) = &span.parent_span_id {
+        out.bytes(4, parent);
+    }
+    out.string(5, &span.name);
+    out.varint_field(6, span.kind as u64);
+    out.fixed64(7, span.start_unix_nanos);
+    out.fixed64(8, span.end_unix_nanos);
+    for (key, value) in &span.attributes {
+        out.message(9, |kv| proto_attribute(kv, key, value));
+    }
+    out.message(15, |status| {
+        if span.status != StatusCode::Unset {
+            status.varint_field(3, span.status as u64);
+        }
+    });
+}
+
+/// `KeyValue` with an `AnyValue`.
+fn proto_attribute(kv: &mut Proto, key: &str, value: &AttributeValue) {
+    kv.string(1, key);
+    kv.message(2, |any| match value {
+        AttributeValue::String(s) => any.string(1, s),
+        AttributeValue::Bool(b) => any.varint_field(2, *b as u64),
+        AttributeValue::Int(i) => any.varint_field(3, *i as u64),
+    });
+}
+
+/// Minimal protobuf writer for the messages above.
+#[derive(Default)]
+struct Proto(Vec<u8>);
+
+impl Proto {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint(u64::from(field) << 3 | u64::from(wire_type));
+    }
+
+    fn varint_field(&mut self, field: u32, value: u64) {
+        self.key(field, 0);
+        self.varint(value);
+    }
+
+    fn fixed64(&mut self, field: u32, value: u64) {
+        self.key(field, 1);
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, field: u32, data: &[u8]) {
+        self.key(field, 2);
+        self.varint(data.len() as u64);
+        self.0.extend_from_slice(data);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, build: impl FnOnce(&mut Proto)) {
+        let mut inner = Proto::default();
+        build(&mut inner);
+        self.bytes(field, &inner.0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Budget enforcement (`[budgets]`).
+//!
+//! Runs after routing: once a session, daily or backend limit is reached,
+//! the request either moves to the budget's `fallback_backend` or is
+//! rejected with an Anthropic `billing_error` and never forwarded. Failover
+//! hops and stream re-issues are checked again against their own backend.
+
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Response, StatusCode};
+
+use crate::backend::{BudgetExceeded, BudgetScope};
+use crate::config::Backend;
+use crate::metrics::RoutingDecision;
+use crate::proxy::error::ProxyError;
+use crate::proxy::pipeline::{PipelineConfig, PipelineContext};
+use crate::proxy::protocol;
+
+/// Apply `[budgets]` to the resolved backend.
+///
+/// Replaces `backend` with the fallback backend when a limit is reached and
+/// the fallback's own limit is not; otherwise returns the rejection.
+pub fn enforce_budget(
+    backend: &mut Backend,
+    config: &PipelineConfig,
+    ctx: &mut PipelineContext,
+) -> Result<Option<Response<Body>>, ProxyError> {
+    let budgets = config.backend_state.budgets();
+    let Some(exceeded) = budgets.exceeded(ctx.session_id.as_deref(), &backend.name) else {
+        return Ok(None);
+    };
+
+    let fallback = config
+        .backend_state
+        .budgets_config()
+        .fallback_backend
+        .filter(|name| *name != backend.name)
+        .filter(|name| budgets.check(&BudgetScope::Backend(name.clone())).is_none())
+        .and_then(|name| config.backend_state.get_backend_config(&name).ok())
+        .filter(|fallback| config.backend_state.is_configured(fallback));
+    if let Some(fallback) = fallback {
+        config
+            .backend_state
+            .group_balancer()
+            .begin(ctx.span.request_id(), &fallback.name);
+        ctx.span.set_backend(fallback.name.clone());
+        ctx.span.record_mut().routing_decision = Some(RoutingDecision {
+            backend: fallback.name.clone(),
+            reason: format!("budget fallback from {} ({})", backend.name, exceeded),
+            synthesized: false,
+        });
+        *backend = fallback;
+        return Ok(None);
+    }
+
+    reject(exceeded, ctx).map(Some)
+}
+
+/// The limit that keeps a request from being sent to `backend`, checked
+/// before every attempt. The budget's fallback backend answers once the
+/// session or daily limit is reached, so only its own limit applies.
+pub fn hop_exceeded(
+    backend: &Backend,
+    config: &PipelineConfig,
+    ctx: &PipelineContext,
+) -> Option<BudgetExceeded> {
+    let budgets = config.backend_state.budgets();
+    let is_fallback = config.backend_state.budgets_config().fallback_backend.as_deref() == Some(backend.name.as_str());
+    if is_fallback {
+        budgets.check(&BudgetScope::Backend(backend.name.clone()))
+    } else {
+        budgets.exceeded(ctx.session_id.as_deref(), &backend.name)
+    }
+}
+
+/// Finish the request with a `billing_error` for `exceeded`.
+pub fn reject(exceeded: BudgetExceeded, ctx: &mut PipelineContext) -> Result<Response<Body>, ProxyError> {
+    let message = format!("anyclaude: {}", exceeded);
+    crate::metrics::app_log("budget", &format!("Rejected request: {}", message));
+    let body = serde_json::to_vec(&protocol::to_error(402, message.as_bytes()))
+        .map_err(|e| ProxyError::Internal(e.to_string()))?;
+    ctx.span.set_status(StatusCode::PAYMENT_REQUIRED.as_u16());
+    ctx.span.add_response_bytes(body.len());
+    ctx.observability.finish_request(ctx.span.clone());
+    ctx.span_finalized = true;
+
+    Response::builder()
+        .status(StatusCode::PAYMENT_REQUIRED)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .map_err(|e| ProxyError::Internal(e.to_string()))
+}
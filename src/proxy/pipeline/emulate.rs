@@ -0,0 +1,144 @@
+//! Endpoints the proxy answers itself (`[backends.emulate]`).
+//!
+//! Runs between stages 2 and 3: once the backend is resolved, a
+//! `count_tokens` or model listing request it emulates is answered here and
+//! never forwarded. The routing decision is marked as synthesized.
+
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Method, Response, StatusCode, Uri};
+use serde_json::{json, Value};
+
+use crate::config::Backend;
+use crate::metrics::RequestParser;
+use crate::proxy::error::ProxyError;
+use crate::proxy::pipeline::PipelineContext;
+use crate::proxy::protocol;
+
+const COUNT_TOKENS_PATH: &str = "/v1/messages/count_tokens";
+const MODELS_PATH: &str = "/v1/models";
+
+/// `created_at` of listed models; the proxy has no release dates.
+const MODEL_CREATED_AT: &str = "1970-01-01T00:00:00Z";
+
+/// Answer the request locally if `backend` emulates its endpoint.
+///
+/// Returns `None` for requests that go upstream as usual.
+pub fn emulate_endpoint(
+    method: &Method,
+    uri: &Uri,
+    parsed_body: Option<&Value>,
+    backend: &Backend,
+    ctx: &mut PipelineContext,
+) -> Result<Option<Response<Body>>, ProxyError> {
+    let path = uri.path();
+    let (status, body) = if backend.emulate.count_tokens
+        && method == Method::POST
+        && path == COUNT_TOKENS_PATH
+    {
+        let body = parsed_body.ok_or_else(|| {
+            ProxyError::InvalidRequest("count_tokens needs a JSON request body".to_string())
+        })?;
+        (StatusCode::OK, count_tokens(body))
+    } else if backend.emulate.models && method == Method::GET && path == MODELS_PATH {
+        (StatusCode::OK, list_models(backend))
+    } else if let Some(id) = path
+        .strip_prefix(MODELS_PATH)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .filter(|id| backend.emulate.models && method == Method::GET && !id.is_empty())
+    {
+        match model_entries(backend).into_iter().find(|model| model["id"] == id) {
+            Some(model) => (StatusCode::OK, model),
+            None => (
+                StatusCode::NOT_FOUND,
+                protocol::to_error(404, format!("model: {}", id).as_bytes()),
+            ),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    crate::metrics::app_log(
+        "emulate",
+        &format!("Answered {} {} for backend '{}' locally", method, path, backend.name),
+    );
+    if let Some(decision) = ctx.span.record_mut().routing_decision.as_mut() {
+        decision.synthesized = true;
+    }
+
+    let body = serde_json::to_vec(&body).map_err(|e| ProxyError::Internal(e.to_string()))?;
+    ctx.span.set_status(status.as_u16());
+    ctx.span.add_response_bytes(body.len());
+    ctx.observability.finish_request(ctx.span.clone());
+    ctx.span_finalized = true;
+
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .map_err(|e| ProxyError::Internal(e.to_string()))
+        .map(Some)
+}
+
+/// `count_tokens` response with the request parser's estimate.
+fn count_tokens(body: &Value) -> Value {
+    let input_tokens = RequestParser::new().estimate_tokens(body).unwrap_or(0);
+    json!({"input_tokens": input_tokens})
+}
+
+/// `/v1/models` page listing every model of `backend`.
+fn list_models(backend: &Backend) -> Value {
+    let data = model_entries(backend);
+    json!({
+        "data": data,
+        "has_more": false,
+        "first_id": data.first().map(|model| model["id"].clone()),
+        "last_id": data.last().map(|model| model["id"].clone()),
+    })
+}
+
+/// Models a client can ask `backend` for: exact `model_map` names, then the
+/// models the mapping sends upstream that pass through it unchanged. Regex
+/// entries are only listed by target, and only without capture references.
+fn model_entries(backend: &Backend) -> Vec<Value> {
+    let mut ids: Vec<(String, Option<String>)> = Vec::new();
+    let mut push = |id: &str, target: Option<&str>| {
+        if !ids.iter().any(|(seen, _)| seen == id) {
+            ids.push((id.to_string(), target.map(String::from)));
+        }
+    };
+
+    for entry in backend.model_map.iter().filter(|entry| !entry.regex) {
+        push(&entry.from, Some(&entry.to));
+    }
+    let targets = backend
+        .model_map
+        .iter()
+        .map(|entry| entry.to.as_str())
+        .filter(|to| !to.contains('$'))
+        .chain(backend.model_opus.as_deref())
+        .chain(backend.model_sonnet.as_deref())
+        .chain(backend.model_haiku.as_deref())
+        .chain(backend.default_model.as_deref());
+    for target in targets {
+        // A target that the mapping itself would rename cannot be asked for.
+        if backend.resolve_model(target).is_none_or(|mapped| mapped == target) {
+            push(target, None);
+        }
+    }
+
+    ids.into_iter()
+        .map(|(id, target)| {
+            let display_name = match target {
+                Some(target) if target != id => format!("{} → {}", id, target),
+                _ => id.clone(),
+            };
+            json!({
+                "type": "model",
+                "id": id,
+                "display_name": display_name,
+                "created_at": MODEL_CREATED_AT,
+            })
+        })
+        .collect()
+}
@@ -1,10 +1,12 @@
 //! Stage 1: Extract request components.
 //!
-//! Extracts body bytes and parses JSON if content-type is application/json.
+//! Extracts body bytes and parses JSON if content-type is application/json,
+//! and notes the Claude Code session the request belongs to.
 
 use axum::body::Body;
 use axum::extract::Request;
 use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
 use http_body_util::BodyExt;
 use serde_json::Value;
 
@@ -78,6 +80,7 @@ pub async fn extract_request(
                 query,
                 headers: None,
                 body_preview: None,
+                body_truncated: false,
             }
         });
         meta.headers = Some(redact_headers(&headers));
@@ -94,6 +97,7 @@ pub async fn extract_request(
                 query,
                 headers: None,
                 body_preview: None,
+                body_truncated: false,
             }
         });
         let limit = if debug_config.full_body {
@@ -107,6 +111,7 @@ pub async fn extract_request(
             limit,
             debug_config.pretty_print,
         );
+        meta.body_truncated = limit.is_some_and(|limit| body_bytes.len() > limit);
     }
 
     // Request analysis for verbose logging
@@ -117,6 +122,9 @@ pub async fn extract_request(
         ctx.span.record_mut().request_analysis = Some(analysis);
     }
 
+    ctx.session_id = session_id(&headers, parsed_body.as_ref());
+    ctx.span.record_mut().session_id = ctx.session_id.clone();
+
     Ok(ExtractedRequest {
         method,
         uri,
@@ -126,3 +134,22 @@ pub async fn extract_request(
         content_type,
     })
 }
+
+/// Session ID from the `x-claude-code-session-id` header, or from the
+/// `..._session_<id>` suffix of `metadata.user_id` in the body.
+fn session_id(headers: &HeaderMap, body: Option<&Value>) -> Option<String> {
+    let header = headers
+        .get("x-claude-code-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    if let Some(id) = header {
+        return Some(id.to_string());
+    }
+    body?
+        .pointer("/metadata/user_id")?
+        .as_str()?
+        .rsplit_once("_session_")
+        .map(|(_, id)| id.to_string())
+        .filter(|id| !id.is_empty())
+}
@@ -1,39 +1,138 @@
 //! Stage 6: Forward request with retry.
 //!
 //! Sends the request to the upstream backend with retry logic for
-//! connection errors and timeouts.
+//! connection errors and timeouts, and on the last backend of a chain also
+//! for `[retry] on_status` responses. Failover to other backends is driven
+//! by the pipeline, which re-runs stages 2-6 per hop.
+//!
+//! Under `--replay` the recorded response is served in place of the
+//! upstream one; under `--record` the upstream response is recorded as its
+//! body is read.
+//!
+//! On backends with several API keys, a rate-limited key is put to rest and
+//! a 429 is retried at once with the next key that is not cooling down.
+
+use std::time::{Duration, Instant, SystemTime};
 
 use axum::http::{Method, Uri};
-use reqwest::Client;
 use tokio::time::sleep;
 
-use crate::config::Backend;
+use crate::backend::DEFAULT_KEY_COOLDOWN;
+use crate::config::{AuthType, Backend, CredentialStatus};
+use crate::error::{ErrorCategory, ErrorSeverity};
 use crate::proxy::error::ProxyError;
+use crate::proxy::pipeline::headers::set_api_key;
 use crate::proxy::pipeline::{PipelineConfig, PipelineContext};
+use crate::proxy::recording::Recording;
+use crate::proxy::{bedrock, retry};
 
 /// Stage 6: Forward request to upstream with retry logic.
 ///
-/// Returns the raw upstream response for Stage 7 to handle.
+/// Returns the raw upstream response for Stage 7 to handle. Rate-limited
+/// and overloaded responses are retried per `[retry]`, honouring the
+/// backend's `retry-after` hints. On error the observability span is
+/// finalized; use [`send_with_retry`] when the caller may still fail over
+/// to another backend.
 pub async fn forward_with_retry(
-    client: &Client,
     method: Method,
     uri: Uri,
     headers: Vec<(String, String)>,
     body_bytes: Vec<u8>,
     is_streaming: bool,
     backend: &Backend,
+    api_key: Option<usize>,
     config: &PipelineConfig,
     ctx: &mut PipelineContext,
 ) -> Result<reqwest::Response, ProxyError> {
-    // Validate backend is configured
-    if !backend.is_configured() {
-        let err = ProxyError::BackendNotConfigured {
-            backend: backend.name.clone(),
-            reason: "api_key is not set".to_string(),
-        };
-        ctx.observability.finish_error(ctx.span.clone(), Some(err.status_code().as_u16()));
+    let started = SystemTime::now();
+    let mut retries = 0;
+    let result = send_upstream(
+        method,
+        uri,
+        headers,
+        body_bytes,
+        is_streaming,
+        backend,
+        api_key,
+        config,
+        true,
+        ctx.span.request_id(),
+        &mut retries,
+    )
+    .await;
+    ctx.span.record_stage("forward", started);
+    ctx.span.record_mut().trace.retries += retries;
+
+    if let Err(err) = &result {
+        let mut span = ctx.span.clone();
+        if matches!(err, ProxyError::RequestTimeout { .. }) {
+            span.mark_timed_out();
+        }
+        ctx.observability.finish_error(span, Some(err.status_code().as_u16()));
         ctx.span_finalized = true;
-        return Err(err);
+    }
+
+    result
+}
+
+/// Send the request to `backend` via `config.http_client`, retrying
+/// connection errors and timeouts.
+///
+/// Unlike [`forward_with_retry`], leaves the observability span open so the
+/// pipeline can hand the request to a failover backend, and returns error
+/// statuses at once for the same reason. Attempts beyond the first are
+/// added to `retries`.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_with_retry(
+    method: Method,
+    uri: Uri,
+    headers: Vec<(String, String)>,
+    body_bytes: Vec<u8>,
+    is_streaming: bool,
+    backend: &Backend,
+    api_key: Option<usize>,
+    config: &PipelineConfig,
+    request_id: &str,
+    retries: &mut u32,
+) -> Result<reqwest::Response, ProxyError> {
+    send_upstream(
+        method,
+        uri,
+        headers,
+        body_bytes,
+        is_streaming,
+        backend,
+        api_key,
+        config,
+        false,
+        request_id,
+        retries,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_upstream(
+    method: Method,
+    uri: Uri,
+    mut headers: Vec<(String, String)>,
+    body_bytes: Vec<u8>,
+    is_streaming: bool,
+    backend: &Backend,
+    mut api_key: Option<usize>,
+    config: &PipelineConfig,
+    retry_status: bool,
+    request_id: &str,
+    retries: &mut u32,
+) -> Result<reqwest::Response, ProxyError> {
+    // Validate backend is configured; replay needs no credentials.
+    if let CredentialStatus::Unconfigured { reason } = backend.resolve_credential() {
+        if !config.recording.is_replay() {
+            return Err(ProxyError::BackendNotConfigured {
+                backend: backend.name.clone(),
+                reason,
+            });
+        }
     }
 
     let path_and_query = uri
@@ -42,27 +141,131 @@ pub async fn forward_with_retry(
         .unwrap_or("/");
     let upstream_uri = format!("{}{}", backend.base_url, path_and_query);
 
+    let retry_config = config.backend_state.retry_config();
+    let max_wait = Duration::from_secs(retry_config.max_wait_seconds);
     let mut attempt = 0u32;
+    let mut status_retries = 0u32;
+    let mut waited = Duration::ZERO;
+    let mut recovery = Recovery::new(config, backend, request_id);
+    let key_rotator = config.backend_state.key_rotator();
 
     let upstream_resp = loop {
-        let mut builder = client.request(method.clone(), &upstream_uri);
+        let send_result = match &config.recording {
+            Recording::Replay(replayer) => {
+                match replayer.respond(&method, path_and_query, &body_bytes).await {
+                    Ok(response) => Ok(response),
+                    Err(err) => {
+                        recovery.finish(true);
+                        config.error_registry.record(
+                            ErrorSeverity::Error,
+                            ErrorCategory::Network,
+                            err.to_string(),
+                        );
+                        return Err(err);
+                    }
+                }
+            }
+            recording => {
+                let request = build_request(
+                    &method,
+                    &upstream_uri,
+                    &headers,
+                    &body_bytes,
+                    is_streaming,
+                    backend,
+                    config,
+                )?;
+                let sent_at = Instant::now();
+                let result = request.send().await;
+                match recording {
+                    Recording::Record(recorder) => result.map(|response| {
+                        recorder.record(&method, path_and_query, &body_bytes, sent_at.elapsed(), response)
+                    }),
+                    _ => result,
+                }
+            }
+        };
 
-        // Add all headers
-        for (name, value) in &headers {
-            builder = builder.header(name, value);
-        }
+        match send_result {
+            Ok(response) => {
+                let status = response.status().as_u16();
 
-        // For streaming requests: skip reqwest timeout entirely.
-        // connect_timeout is set on Client, idle_timeout on ObservedStream.
-        // For non-streaming: apply request timeout to the full response.
-        if !is_streaming {
-            builder = builder.timeout(config.timeout_config.request);
-        }
+                if let Some(index) = api_key {
+                    let throttled = status == 429 || retry::rate_limit_exhausted(response.headers());
+                    if throttled {
+                        // At least a second, so a key is never retried in a loop,
+                        // and no longer than a request would wait for it.
+                        let cooldown = retry::retry_after(response.headers(), SystemTime::now())
+                            .unwrap_or(DEFAULT_KEY_COOLDOWN)
+                            .min(max_wait.max(DEFAULT_KEY_COOLDOWN))
+                            .max(Duration::from_secs(1));
+                        key_rotator.throttled(&backend.name, index, cooldown);
+                    }
+                    let next = (status == 429)
+                        .then(|| key_rotator.next_available(backend, index))
+                        .flatten();
+                    if let Some(next) = next {
+                        crate::metrics::app_log(
+                            "upstream",
+                            &format!(
+                                "API key #{} of '{}' rate limited, retrying with key #{}",
+                                index + 1,
+                                backend.name,
+                                next.index + 1
+                            ),
+                        );
+                        set_api_key(&mut headers, backend, &next.key);
+                        api_key = Some(next.index);
+                        *retries += 1;
+                        continue;
+                    }
+                }
 
-        let send_result = builder.body(body_bytes.clone()).send().await;
+                let retryable = retry_status
+                    && retry_config.on_status.contains(&status)
+                    && status_retries < retry_config.max_status_retries;
+                if !retryable {
+                    recovery.finish(retry_status && retry_config.on_status.contains(&status));
+                    break response;
+                }
 
-        match send_result {
-            Ok(response) => break response,
+                let hint = retry::retry_after(response.headers(), SystemTime::now());
+                let delay = hint.unwrap_or_else(|| {
+                    retry::backoff(config.pool_config.retry_backoff_base, status_retries)
+                });
+                if waited + delay > max_wait {
+                    crate::metrics::app_log(
+                        "upstream",
+                        &format!(
+                            "Upstream returned {}, not retrying: backend='{}', wait_ms={} exceeds remaining budget_ms={}",
+                            status,
+                            backend.name,
+                            delay.as_millis(),
+                            max_wait.saturating_sub(waited).as_millis()
+                        ),
+                    );
+                    recovery.finish(true);
+                    break response;
+                }
+
+                crate::metrics::app_log(
+                    "upstream",
+                    &format!(
+                        "Upstream returned {}, retrying: backend='{}', attempt={}/{}, wait_ms={}{}",
+                        status,
+                        backend.name,
+                        status_retries + 1,
+                        retry_config.max_status_retries,
+                        delay.as_millis(),
+                        if hint.is_some() { " (retry-after)" } else { "" }
+                    ),
+                );
+                status_retries += 1;
+                *retries += 1;
+                recovery.retrying(status_retries + 1, retry_config.max_status_retries + 1, delay);
+                sleep(delay).await;
+                waited += delay;
+            }
             Err(err) => {
                 crate::metrics::app_log_error(
                     "upstream",
@@ -78,11 +281,8 @@ pub async fn forward_with_retry(
                 );
 
                 let should_retry = err.is_connect() || err.is_timeout();
+                let backoff = retry::backoff(config.pool_config.retry_backoff_base, attempt);
                 if should_retry && attempt < config.pool_config.max_retries {
-                    let backoff = config
-                        .pool_config
-                        .retry_backoff_base
-                        .saturating_mul(1u32 << attempt);
                     crate::metrics::app_log(
                         "upstream",
                         &format!(
@@ -94,32 +294,125 @@ pub async fn forward_with_retry(
                             err
                         ),
                     );
-                    sleep(backoff).await;
                     attempt += 1;
+                    *retries += 1;
+                    recovery.retrying(attempt + 1, config.pool_config.max_retries + 1, backoff);
+                    sleep(backoff).await;
                     continue;
                 }
 
+                recovery.finish(true);
                 if err.is_timeout() {
-                    let timeout_err = ProxyError::RequestTimeout {
+                    return Err(ProxyError::RequestTimeout {
                         duration: config.timeout_config.request.as_secs(),
-                    };
-                    let mut span = ctx.span.clone();
-                    span.mark_timed_out();
-                    ctx.observability.finish_error(span, Some(timeout_err.status_code().as_u16()));
-                    ctx.span_finalized = true;
-                    return Err(timeout_err);
+                    });
                 }
 
-                let conn_err = ProxyError::ConnectionError {
+                return Err(ProxyError::ConnectionError {
                     backend: backend.name.clone(),
                     source: err,
-                };
-                ctx.observability.finish_error(ctx.span.clone(), Some(conn_err.status_code().as_u16()));
-                ctx.span_finalized = true;
-                return Err(conn_err);
+                });
             }
         }
     };
 
     Ok(upstream_resp)
 }
+
+/// One attempt at the upstream request, signed for Bedrock backends.
+#[allow(clippy::too_many_arguments)]
+fn build_request(
+    method: &Method,
+    upstream_uri: &str,
+    headers: &[(String, String)],
+    body_bytes: &[u8],
+    is_streaming: bool,
+    backend: &Backend,
+    config: &PipelineConfig,
+) -> Result<reqwest::RequestBuilder, ProxyError> {
+    let mut builder = config.http_client.request(method.clone(), upstream_uri);
+
+    // Add all headers
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+
+    // Bedrock signatures are time-bound, so sign every attempt.
+    if backend.auth_type() == AuthType::Bedrock {
+        let content_type = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str());
+        let signed = bedrock::sign_request(
+            backend,
+            method.as_str(),
+            upstream_uri,
+            content_type,
+            body_bytes,
+        )
+        .map_err(|reason| ProxyError::BackendNotConfigured {
+            backend: backend.name.clone(),
+            reason,
+        })?;
+        for (name, value) in signed {
+            builder = builder.header(name, value);
+        }
+    }
+
+    // For streaming requests: skip reqwest timeout entirely.
+    // connect_timeout is set on Client, idle_timeout on ObservedStream.
+    // For non-streaming: apply request timeout to the full response.
+    if !is_streaming {
+        builder = builder.timeout(config.timeout_config.request);
+    }
+
+    Ok(builder.body(body_bytes.to_vec()))
+}
+
+/// Upstream retries of one request, shown as a recovery in the UI header.
+/// Keyed by request, so concurrent requests to a backend don't end each
+/// other's recovery.
+struct Recovery<'a> {
+    config: &'a PipelineConfig,
+    operation: String,
+    active: bool,
+}
+
+impl<'a> Recovery<'a> {
+    fn new(config: &'a PipelineConfig, backend: &Backend, request_id: &str) -> Self {
+        Self {
+            config,
+            operation: format!("Upstream '{}' request {}", backend.name, request_id),
+            active: false,
+        }
+    }
+
+    /// About to wait `delay` before `attempt` of `max_attempts`.
+    fn retrying(&mut self, attempt: u32, max_attempts: u32, delay: Duration) {
+        let registry = &self.config.error_registry;
+        if !self.active {
+            registry.start_recovery(self.operation.clone(), max_attempts);
+            self.active = true;
+        }
+        registry.update_recovery(&self.operation, attempt, SystemTime::now().checked_add(delay));
+    }
+
+    /// Retries are over; `failed` when the last outcome was still retryable.
+    fn finish(&mut self, failed: bool) {
+        if !self.active {
+            return;
+        }
+        let registry = &self.config.error_registry;
+        if failed {
+            registry.end_recovery(&self.operation);
+            registry.record(
+                ErrorSeverity::Warning,
+                ErrorCategory::Network,
+                format!("{} still failing after retries", self.operation),
+            );
+        } else {
+            registry.recovery_succeeded(&self.operation);
+        }
+        self.active = false;
+    }
+}
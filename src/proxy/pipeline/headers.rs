@@ -4,13 +4,19 @@
 //! - Filters out HOST and CONTENT_LENGTH (set by HTTP client)
 //! - Strips auth headers when backend uses own credentials
 //! - Patches anthropic-beta header for non-Anthropic backends
+//! - Applies the backend's `[backends.headers]` rules: drops client headers
+//!   matching `remove`, edits `anthropic-beta` per `strip_beta`/`add_beta`
 //! - Adds backend's own auth header if configured
+//! - Sets and appends the configured headers, with `{session_id}`,
+//!   `{request_id}` and `{agent_id}` filled in
+//! - Swaps in the key chosen by the [`KeyRotator`] for backends with several
+//!   API keys, unless a `set` rule already replaced the auth header
 
 use axum::http::header::{AUTHORIZATION, CONTENT_LENGTH, HOST};
 use axum::http::HeaderMap;
 
-use crate::config::Backend;
-use crate::config::build_auth_header;
+use crate::backend::KeyRotator;
+use crate::config::{build_auth_header, build_auth_header_with_key, Backend, HeaderRules, SecureString};
 use crate::proxy::error::ProxyError;
 use crate::proxy::pipeline::PipelineContext;
 
@@ -37,6 +43,11 @@ pub fn build_headers(
             continue;
         }
 
+        // Drop client headers the backend is configured to reject
+        if backend.headers.remove.iter().any(|pattern| matches_pattern(pattern, name_str)) {
+            continue;
+        }
+
         // Strip auth headers when backend uses its own credentials (bearer/api_key)
         // Passthrough mode forwards all headers unchanged
         if strip_auth_headers
@@ -76,14 +87,111 @@ pub fn build_headers(
         }
     }
 
+    let rules = &backend.headers;
+    if !rules.strip_beta.is_empty() || !rules.add_beta.is_empty() {
+        rewrite_beta(&mut headers, rules);
+    }
+
     // Add backend's own auth header (for bearer/api_key modes)
     if let Some((name, value)) = build_auth_header(backend) {
         headers.push((name, value));
     }
 
+    for (name, template) in &rules.set {
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        headers.push((name.clone(), render_template(template, ctx)));
+    }
+    for (name, template) in &rules.append {
+        headers.push((name.clone(), render_template(template, ctx)));
+    }
+
     Ok(headers)
 }
 
+/// Case-insensitive match of `value` against `pattern`; a trailing `*`
+/// matches any suffix.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+        None => value.eq_ignore_ascii_case(pattern),
+    }
+}
+
+/// Apply `strip_beta` and `add_beta` to the `anthropic-beta` header,
+/// merging multiple header lines into one.
+fn rewrite_beta(headers: &mut Vec<(String, String)>, rules: &HeaderRules) {
+    let mut betas: Vec<String> = Vec::new();
+    headers.retain(|(name, value)| {
+        if !name.eq_ignore_ascii_case("anthropic-beta") {
+            return true;
+        }
+        betas.extend(value.split(',').map(|b| b.trim().to_string()).filter(|b| !b.is_empty()));
+        false
+    });
+
+    betas.retain(|beta| !rules.strip_beta.iter().any(|pattern| matches_pattern(pattern, beta)));
+    for beta in &rules.add_beta {
+        if !betas.contains(beta) {
+            betas.push(beta.clone());
+        }
+    }
+
+    if !betas.is_empty() {
+        headers.push(("anthropic-beta".to_string(), betas.join(",")));
+    }
+}
+
+/// Fill in `{session_id}`, `{request_id}` and `{agent_id}`; unknown values
+/// become empty. Control characters are dropped from the values, as the
+/// session ID may come from the request body.
+fn render_template(template: &str, ctx: &PipelineContext) -> String {
+    let value = |v: Option<&str>| -> String {
+        v.unwrap_or_default().chars().filter(|c| !c.is_control()).collect()
+    };
+    template
+        .replace("{session_id}", &value(ctx.session_id.as_deref()))
+        .replace("{request_id}", &value(Some(ctx.span.request_id())))
+        .replace("{agent_id}", &value(ctx.agent_id.as_deref()))
+}
+
+/// Use the key `rotator` picks for `backend` in `headers`.
+///
+/// Returns the key's index, or `None` when the backend has a single key or
+/// a `set` rule owns its auth header, and `headers` is left alone.
+pub fn select_api_key(
+    headers: &mut Vec<(String, String)>,
+    backend: &Backend,
+    rotator: &KeyRotator,
+) -> Option<usize> {
+    let choice = rotator.select(backend)?;
+    set_api_key(headers, backend, &choice.key).then_some(choice.index)
+}
+
+/// Replace the backend auth header in `headers` with one carrying `key`.
+///
+/// Returns `false` without touching `headers` when the backend sends no
+/// auth header or a `[backends.headers] set` rule sets it.
+pub(crate) fn set_api_key(headers: &mut Vec<(String, String)>, backend: &Backend, key: &SecureString) -> bool {
+    let Some((name, value)) = build_auth_header_with_key(backend, key) else {
+        return false;
+    };
+    if backend.headers.set.keys().any(|set| set.eq_ignore_ascii_case(&name)) {
+        return false;
+    }
+    headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+    headers.push((name, value));
+    true
+}
+
+/// Replace any client `traceparent` with the request's own, so the
+/// upstream call joins the proxy's trace.
+pub(crate) fn set_traceparent(headers: &mut Vec<(String, String)>, traceparent: String) {
+    headers.retain(|(name, _)| !name.eq_ignore_ascii_case("traceparent"));
+    headers.push(("traceparent".to_string(), traceparent));
+}
+
 /// Rewrite anthropic-beta header for non-Anthropic backends:
 /// strip `adaptive-thinking-*` and ensure `interleaved-thinking-2025-05-14` is present.
 fn patch_anthropic_beta_header(value: &str) -> String {
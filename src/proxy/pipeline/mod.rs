@@ -2,28 +2,63 @@
 //!
 //! The pipeline processes each request through explicit linear stages:
 //! extract → routing → thinking → transform → headers → forward → response.
+//!
+//! When the resolved backend has a `failover` chain, routing → thinking →
+//! transform → headers → forward repeat against the next backend on
+//! connection errors and failover statuses. Backends with an open circuit
+//! are skipped while the chain has another target.
+//!
+//! Backends with `protocol = "openai"` or `"gemini"` have `/v1/messages`
+//! translated to their native API in transform and back in response;
+//! `auth_type = "bedrock"` and `"vertex"` backends get their hosted
+//! endpoint shape the same way, and Bedrock requests are signed in forward.
+//!
+//! Endpoints a backend emulates (`[backends.emulate]`) are answered right
+//! after stage 2 without contacting it (see `emulate`). Requests past a
+//! `[budgets]` limit are then rejected or moved to the budget's fallback
+//! backend (see `budget`).
+//!
+//! Under `--replay`, forward serves recorded responses instead of
+//! contacting the backend, and under `--record` it saves them (see
+//! `recording`).
+//!
+//! With `[retry] resume_streams`, a streaming response that breaks before
+//! its first content block re-runs stages 3-6 from stage 7 (see `resume`).
 
 use axum::body::Body;
-use axum::http::{Request, Response};
+use axum::http::{HeaderMap, Request, Response, Uri};
+use serde_json::Value;
 use std::sync::Arc;
+use std::time::SystemTime;
 
-use crate::backend::{BackendState, AgentRegistry};
+use crate::backend::{BackendState, AgentRegistry, CircuitBreakers};
+use crate::config::Backend;
+use crate::error::ErrorRegistry;
 use crate::metrics::{BackendOverride, DebugLogger, ObservabilityHub, RequestSpan};
-use crate::proxy::thinking::TransformerRegistry;
+use crate::proxy::error::ProxyError;
+use crate::proxy::model_rewrite::ModelMapping;
+use crate::proxy::protocol;
+use crate::proxy::recording::Recording;
+use crate::proxy::thinking::{ThinkingSession, TransformerRegistry};
 
+mod budget;
+mod emulate;
 mod extract;
 mod forward;
 mod headers;
 mod response;
+mod resume;
 mod routing;
 mod thinking;
 mod transform;
 
+pub use emulate::emulate_endpoint;
 pub use extract::extract_request;
-pub use forward::forward_with_retry;
-pub use headers::build_headers;
+pub use forward::{forward_with_retry, send_with_retry};
+pub use headers::{build_headers, select_api_key};
 pub use response::handle_response;
-pub use routing::{extract_ac_marker, resolve_backend};
+pub use resume::StreamResume;
+pub use routing::{extract_ac_marker, next_failover, record_failover, resolve_backend};
 pub use thinking::create_thinking;
 pub use transform::transform_body;
 
@@ -40,6 +75,10 @@ pub struct PipelineContext {
     pub observability: ObservabilityHub,
     /// Debug logger for auxiliary logging
     pub debug_logger: Arc<DebugLogger>,
+    /// Claude Code session the request belongs to, if it says (Stage 1)
+    pub session_id: Option<String>,
+    /// Teammate or subagent the request comes from, if known (router / Stage 2)
+    pub agent_id: Option<String>,
     /// Whether the observability span has been finalized
     /// (finish_request or finish_error already called by a late stage).
     pub(crate) span_finalized: bool,
@@ -51,6 +90,8 @@ impl PipelineContext {
             span,
             observability,
             debug_logger,
+            session_id: None,
+            agent_id: None,
             span_finalized: false,
         }
     }
@@ -71,6 +112,10 @@ pub struct PipelineConfig {
     pub pool_config: crate::proxy::pool::PoolConfig,
     /// HTTP client for upstream requests
     pub http_client: reqwest::Client,
+    /// Shared with the UI, which shows upstream retries as recoveries
+    pub error_registry: ErrorRegistry,
+    /// `--record` / `--replay` of upstream exchanges
+    pub recording: Recording,
 }
 
 impl PipelineConfig {
@@ -95,6 +140,8 @@ impl PipelineConfig {
             timeout_config,
             pool_config,
             http_client,
+            error_registry: ErrorRegistry::default(),
+            recording: Recording::Off,
         }
     }
 }
@@ -140,71 +187,289 @@ async fn execute_pipeline_inner(
     is_teammate: bool,
 ) -> Result<Response<Body>, crate::proxy::error::ProxyError> {
     // Stage 1: Extract request
+    let started = SystemTime::now();
     let extracted = extract::extract_request(req, ctx).await?;
+    ctx.span.record_stage("extract", started);
 
     // Stage 2: Resolve backend
-    let backend = routing::resolve_backend(
+    let started = SystemTime::now();
+    let mut backend = routing::resolve_backend(
         &config.backend_state,
         backend_override,
         plugin_override,
         extracted.parsed_body.as_ref(),
+        extracted.uri.path(),
         &config.agent_registry,
         ctx,
     )?;
+    ctx.span.record_stage("routing", started);
+
+    if let Some(response) = emulate::emulate_endpoint(
+        &extracted.method,
+        &extracted.uri,
+        extracted.parsed_body.as_ref(),
+        &backend,
+        ctx,
+    )? {
+        return Ok(response);
+    }
+
+    if let Some(response) = budget::enforce_budget(&mut backend, config, ctx)? {
+        return Ok(response);
+    }
+
+    // Stages 3-6 run once per backend in the failover chain. A hop re-runs
+    // them against the next backend so model mapping, thinking compat and
+    // auth follow the backend that actually serves the request.
+    let primary = backend.clone();
+    let mut tried = vec![backend.name.clone()];
+    let mut body_bytes = extracted.body_bytes;
+    let mut parsed_body = extracted.parsed_body;
+    let circuit_breakers = config.backend_state.circuit_breakers();
+
+    // Streams that fail before their first content block are re-issued
+    // from a copy of the original request.
+    let retry_config = config.backend_state.retry_config();
+    let replay = (retry_config.resume_streams && retry_config.max_stream_resumes > 0)
+        .then(|| (body_bytes.clone(), parsed_body.clone()));
+
+    let (upstream_resp, thinking_session, model_mapping, translate, is_streaming) = loop {
+        let next = routing::next_failover(&config.backend_state, &primary, &tried);
+
+        // Skip a tripped backend while the chain still has somewhere to go.
+        if let Some(next) = next.as_ref().filter(|_| !circuit_breakers.allows(&backend.name)) {
+            routing::record_failover(&config.backend_state, &backend, next, "circuit open", ctx);
+            tried.push(next.name.clone());
+            backend = next.clone();
+            continue;
+        }
+
+        // A hop onto a backend whose budget is used up moves on down the
+        // chain, or is rejected at its end.
+        if let Some(exceeded) = budget::hop_exceeded(&backend, config, ctx) {
+            let Some(next) = next else {
+                return budget::reject(exceeded, ctx);
+            };
+            routing::record_failover(&config.backend_state, &backend, &next, &exceeded.to_string(), ctx);
+            tried.push(next.name.clone());
+            backend = next;
+            continue;
+        }
+
+        // Keep the original body around while a failover target remains.
+        let (stage_body, stage_parsed) = if next.is_some() {
+            (body_bytes.clone(), parsed_body.clone())
+        } else {
+            (std::mem::take(&mut body_bytes), parsed_body.take())
+        };
+        let PreparedAttempt {
+            uri,
+            headers,
+            body,
+            is_streaming,
+            thinking_session,
+            model_mapping,
+            translate,
+            api_key,
+        } = prepare_attempt(
+            &extracted.uri,
+            &extracted.headers,
+            stage_body,
+            stage_parsed,
+            &backend,
+            is_teammate,
+            config,
+            ctx,
+        )
+        .await?;
+
+        // Stage 6: Forward with retry
+        let Some(next) = next else {
+            let upstream_resp = forward::forward_with_retry(
+                extracted.method.clone(),
+                uri,
+                headers,
+                body,
+                is_streaming,
+                &backend,
+                api_key,
+                config,
+                ctx,
+            ).await?;
+            break (upstream_resp, thinking_session, model_mapping, translate, is_streaming);
+        };
+
+        let started = SystemTime::now();
+        let mut retries = 0;
+        let sent = forward::send_with_retry(
+            extracted.method.clone(),
+            uri,
+            headers,
+            body,
+            is_streaming,
+            &backend,
+            api_key,
+            config,
+            ctx.span.request_id(),
+            &mut retries,
+        ).await;
+        ctx.span.record_stage("forward", started);
+        ctx.span.record_mut().trace.retries += retries;
+
+        let cause = match sent {
+            Ok(resp) if backend.should_failover_on_status(resp.status().as_u16()) => {
+                finish_failed_attempt(config, ctx, &backend, resp.status().as_u16(), false);
+                format!("status {}", resp.status().as_u16())
+            }
+            Ok(resp) => break (resp, thinking_session, model_mapping, translate, is_streaming),
+            Err(e @ (ProxyError::ConnectionError { .. } | ProxyError::RequestTimeout { .. })) => {
+                let timed_out = matches!(e, ProxyError::RequestTimeout { .. });
+                finish_failed_attempt(config, ctx, &backend, e.status_code().as_u16(), timed_out);
+                e.to_string()
+            }
+            Err(e) => return Err(e),
+        };
+
+        routing::record_failover(&config.backend_state, &backend, &next, &cause, ctx);
+        tried.push(next.name.clone());
+        backend = next;
+    };
+
+    let resume = replay.filter(|_| is_streaming).map(|(body_bytes, parsed_body)| {
+        StreamResume::new(
+            config.clone(),
+            ctx.clone(),
+            resume::ReplayRequest {
+                method: extracted.method,
+                uri: extracted.uri,
+                headers: extracted.headers,
+                body_bytes,
+                parsed_body,
+            },
+            primary,
+            backend.clone(),
+            tried,
+            is_teammate,
+            retry_config.max_stream_resumes,
+        )
+    });
+
+    // Stage 7: Handle response
+    let response = response::handle_response(
+        upstream_resp,
+        backend,
+        thinking_session,
+        model_mapping,
+        translate,
+        resume,
+        config,
+        ctx,
+    ).await?;
+
+    Ok(response)
+}
+
+/// Output of stages 3-5 for one backend, ready to forward.
+struct PreparedAttempt {
+    uri: Uri,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    is_streaming: bool,
+    thinking_session: Option<ThinkingSession>,
+    model_mapping: Option<ModelMapping>,
+    translate: bool,
+    /// Index of the API key in use, for backends with several.
+    api_key: Option<usize>,
+}
+
+/// Stages 3-5 against `backend`: thinking session, body transform and
+/// headers. Credential commands that are due run first.
+#[allow(clippy::too_many_arguments)]
+async fn prepare_attempt(
+    uri: &Uri,
+    headers: &HeaderMap,
+    body_bytes: Vec<u8>,
+    parsed_body: Option<Value>,
+    backend: &Backend,
+    is_teammate: bool,
+    config: &PipelineConfig,
+    ctx: &mut PipelineContext,
+) -> Result<PreparedAttempt, ProxyError> {
+    backend.refresh_credentials().await;
 
     // Stage 3: Create thinking session (after routing, before transform)
     // Teammate requests (those with backend_override) skip thinking.
+    let started = SystemTime::now();
     let thinking_session = if is_teammate {
         None
     } else {
         thinking::create_thinking(
             &config.transformer_registry,
-            &backend,
+            backend,
             ctx,
         )
     };
+    ctx.span.record_stage("thinking", started);
 
     // Stage 4: Transform body
-    let (transformed_body, is_streaming, model_mapping) = transform::transform_body(
-        extracted.body_bytes,
-        extracted.parsed_body,
-        &backend,
+    let started = SystemTime::now();
+    let translate = protocol::translates(backend, uri);
+    let uri = if translate {
+        protocol::upstream_uri(backend, parsed_body.as_ref())?
+    } else {
+        uri.clone()
+    };
+    let (body, is_streaming, model_mapping) = transform::transform_body(
+        body_bytes,
+        parsed_body,
+        backend,
         thinking_session.as_ref(),
+        translate,
         ctx,
     )?;
 
     // Update span with request bytes after transformation
-    ctx.span.set_request_bytes(transformed_body.len());
+    ctx.span.set_request_bytes(body.len());
+    ctx.span.record_stage("transform", started);
 
     // Stage 5: Build headers
-    let headers = headers::build_headers(
-        &extracted.headers,
-        &backend,
+    let started = SystemTime::now();
+    let mut headers = headers::build_headers(
+        headers,
+        backend,
         ctx,
     )?;
+    let api_key = headers::select_api_key(&mut headers, backend, &config.backend_state.key_rotator());
+    if config.backend_state.tracing_config().enabled {
+        headers::set_traceparent(&mut headers, ctx.span.record_mut().trace.traceparent());
+    }
+    ctx.span.record_stage("headers", started);
 
-    // Stage 6: Forward with retry
-    let upstream_resp = forward::forward_with_retry(
-        &config.http_client,
-        extracted.method,
-        extracted.uri,
+    Ok(PreparedAttempt {
+        uri,
         headers,
-        transformed_body,
+        body,
         is_streaming,
-        &backend,
-        config,
-        ctx,
-    ).await?;
-
-    // Stage 7: Handle response
-    let response = response::handle_response(
-        upstream_resp,
-        backend,
         thinking_session,
         model_mapping,
-        config,
-        ctx,
-    ).await?;
+        translate,
+        api_key,
+    })
+}
 
-    Ok(response)
+/// Report a failed failover hop to `backend`'s circuit breaker and the
+/// request's trace. The request span stays open for the next backend in the
+/// chain and is finished once, so the hop adds no request record of its own.
+fn finish_failed_attempt(
+    config: &PipelineConfig,
+    ctx: &mut PipelineContext,
+    backend: &Backend,
+    status: u16,
+    timed_out: bool,
+) {
+    ctx.span.record_hop(&backend.name, status, timed_out);
+    config
+        .backend_state
+        .circuit_breakers()
+        .record(&backend.name, CircuitBreakers::is_failure(status, timed_out));
 }
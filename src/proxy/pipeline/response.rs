@@ -4,42 +4,56 @@
 //! - Detects streaming vs non-streaming
 //! - For streaming: creates ObservedStream with callbacks
 //! - For non-streaming: reads full body, applies thinking registration
+//! - Translates OpenAI and Gemini responses (and Bedrock event streams)
+//!   back to Anthropic shapes
 //! - Applies reverse model mapping if needed
+//! - Hands streams to `resume` when they may be re-issued
 //! - Handles debug logging and observability
 
 use axum::body::Body;
-use axum::http::header::CONTENT_LENGTH;
-use axum::http::Response;
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderMap, Response};
+use std::time::SystemTime;
 
 use crate::config::Backend;
 use crate::config::DebugLogLevel;
-use crate::metrics::{ObservedStream, redact_body, redact_headers, ResponseMeta, ResponsePreview};
+use crate::metrics::{
+    chain_rewriters, ChunkRewriter, ObservedStream, redact_body, redact_headers, ResponseCompleteCallback, ResponseMeta,
+    ResponsePreview, TokenUsage,
+};
 use crate::proxy::error::ProxyError;
 use crate::proxy::model_rewrite::{make_reverse_model_rewriter, ModelMapping, reverse_model_in_response};
+use crate::proxy::{bedrock, protocol};
 use crate::proxy::thinking::ThinkingSession;
-use crate::proxy::pipeline::{PipelineConfig, PipelineContext};
+use crate::proxy::pipeline::resume::{ResumingStream, UpstreamStream};
+use crate::proxy::pipeline::{PipelineConfig, PipelineContext, StreamResume};
 
 /// Stage 7: Handle upstream response.
 ///
 /// Converts the upstream response into an Axum response, handling both
-/// streaming and non-streaming cases.
+/// streaming and non-streaming cases. With `translate`, the upstream speaks
+/// the backend's protocol and is translated back to Anthropic shapes.
+/// With `resume`, a stream that breaks is re-issued or ended with an SSE
+/// `error` event.
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_response(
     upstream_resp: reqwest::Response,
     backend: Backend,
     thinking: Option<ThinkingSession>,
     model_mapping: Option<ModelMapping>,
+    translate: bool,
+    resume: Option<StreamResume>,
     config: &PipelineConfig,
     ctx: &mut PipelineContext,
 ) -> Result<Response<Body>, ProxyError> {
+    let started = SystemTime::now();
     let content_type = upstream_resp
         .headers()
-        .get(axum::http::header::CONTENT_TYPE)
+        .get(CONTENT_TYPE)
         .and_then(|v| v.to_str().ok())
         .map(|v| v.to_string());
 
-    let is_streaming = content_type
-        .as_deref()
-        .is_some_and(|ct| ct.contains("text/event-stream"));
+    let is_streaming = is_event_stream(upstream_resp.headers(), translate);
 
     let status = upstream_resp.status();
     let response_headers = upstream_resp.headers().clone();
@@ -56,6 +70,7 @@ pub async fn handle_response(
             .get_or_insert(ResponseMeta {
                 headers: None,
                 body_preview: None,
+                sse_text: None,
             });
         meta.headers = Some(redact_headers(&response_headers));
     }
@@ -75,9 +90,14 @@ pub async fn handle_response(
 
     let mut response_builder = Response::builder().status(status);
 
-    // Copy response headers, stripping Content-Length if model mapping is active
+    // Copy response headers, stripping Content-Length if the body is rewritten.
+    // Translated streams are always SSE.
     for (name, value) in response_headers.iter() {
-        if model_mapping.is_some() && name == CONTENT_LENGTH {
+        if (model_mapping.is_some() || translate) && name == CONTENT_LENGTH {
+            continue;
+        }
+        if translate && is_streaming && name == CONTENT_TYPE {
+            response_builder = response_builder.header(name, "text/event-stream");
             continue;
         }
         response_builder = response_builder.header(name, value);
@@ -85,7 +105,7 @@ pub async fn handle_response(
 
     if is_streaming {
         // Streaming response path
-        let stream = upstream_resp.bytes_stream();
+        let stream: UpstreamStream = Box::pin(upstream_resp.bytes_stream());
 
         let response_preview = if debug_config.level >= DebugLogLevel::Full {
             let ct = content_type.clone().unwrap_or_default();
@@ -98,32 +118,31 @@ pub async fn handle_response(
             None
         };
 
-        // Register thinking blocks from SSE stream (main agent only)
-        let on_complete = thinking.map(|session| {
-            Box::new(move |bytes: &[u8]| {
-                let events = crate::sse::parse_sse_events(bytes);
-                session.register_from_sse(&events);
-            }) as crate::metrics::ResponseCompleteCallback
-        });
-
+        // The stream outlives this stage; the request span covers it.
+        ctx.span.record_stage("response", started);
         let mut observed = ObservedStream::new(
             stream,
             ctx.span.clone(),
             ctx.observability.clone(),
             config.timeout_config.idle,
             response_preview,
-        );
+        )
+        .with_pricing(backend.pricing.clone());
 
-        if let Some(cb) = on_complete {
-            observed = observed.with_on_complete(cb);
+        // Register thinking blocks from SSE stream (main agent only)
+        if let Some(session) = thinking {
+            observed = observed.with_on_complete(thinking_callback(session));
         }
 
-        // Reverse model mapping: rewrite model in message_start back to original
-        if let Some(mapping) = model_mapping {
-            observed = observed.with_chunk_rewriter(make_reverse_model_rewriter(mapping));
+        if let Some(rewriter) = stream_rewriter(&backend, translate, model_mapping) {
+            observed = observed.with_chunk_rewriter(rewriter);
         }
 
-        Ok(response_builder.body(Body::from_stream(observed))?)
+        let body = match resume {
+            Some(resume) => Body::from_stream(ResumingStream::new(observed.with_recovery(), resume)),
+            None => Body::from_stream(observed),
+        };
+        Ok(response_builder.body(body)?)
     } else {
         // Non-streaming response path
         ctx.span.mark_first_byte();
@@ -137,30 +156,27 @@ pub async fn handle_response(
             }
         };
 
+        let body_bytes = if translate {
+            protocol::translate_response(&backend, status.as_u16(), &body_bytes)
+        } else {
+            body_bytes
+        };
+
         // Register thinking blocks from non-streaming response (main agent only)
         if let Some(ref session) = thinking {
             session.register_from_response(&body_bytes);
         }
 
+        if let Some(mut usage) = TokenUsage::from_response(&body_bytes) {
+            usage.apply_pricing(backend.pricing.as_ref());
+            ctx.span.record_mut().usage = Some(usage);
+        }
+
         // Response analysis for verbose logging
         if debug_config.level >= DebugLogLevel::Verbose {
             use crate::metrics::ResponseParser;
             let parser = ResponseParser::new();
-            let mut analysis = parser.parse_response(&body_bytes);
-            if analysis.cost_usd.is_none() {
-                analysis.cost_usd = compute_cost_usd(
-                    &backend,
-                    analysis.input_tokens.or_else(|| {
-                        ctx.span
-                            .record_mut()
-                            .request_analysis
-                            .as_ref()
-                            .and_then(|a| a.estimated_input_tokens)
-                    }),
-                    analysis.output_tokens,
-                );
-            }
-            ctx.span.record_mut().response_analysis = Some(analysis);
+            ctx.span.record_mut().response_analysis = Some(parser.parse_response(&body_bytes));
         }
 
         // Response body preview for full logging
@@ -172,6 +188,7 @@ pub async fn handle_response(
                 .get_or_insert(ResponseMeta {
                     headers: None,
                     body_preview: None,
+                    sse_text: None,
                 });
             let limit = if debug_config.full_body {
                 None
@@ -211,6 +228,7 @@ pub async fn handle_response(
         };
 
         ctx.span.add_response_bytes(body_bytes.len());
+        ctx.span.record_stage("response", started);
         ctx.observability.finish_request(ctx.span.clone());
         ctx.span_finalized = true;
 
@@ -218,15 +236,40 @@ pub async fn handle_response(
     }
 }
 
-fn compute_cost_usd(
+/// Whether an upstream response with `headers` is streamed as events.
+pub(super) fn is_event_stream(headers: &HeaderMap, translate: bool) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| {
+            ct.contains("text/event-stream")
+                || (translate && ct.contains(bedrock::EVENTSTREAM_CONTENT_TYPE))
+        })
+}
+
+/// Chunk rewriter turning `backend`'s stream into the client's Anthropic
+/// SSE, if it needs one.
+pub(super) fn stream_rewriter(
     backend: &Backend,
-    input_tokens: Option<u64>,
-    output_tokens: Option<u64>,
-) -> Option<f64> {
-    let pricing = backend.pricing.as_ref()?;
-    let input_tokens = input_tokens.unwrap_or(0) as f64;
-    let output_tokens = output_tokens.unwrap_or(0) as f64;
-    let cost = (input_tokens * pricing.input_per_million + output_tokens * pricing.output_per_million)
-        / 1_000_000.0;
-    Some(cost)
+    translate: bool,
+    model_mapping: Option<ModelMapping>,
+) -> Option<ChunkRewriter> {
+    // Translate chunks first so reverse model mapping sees the
+    // Anthropic message_start event.
+    let translator = translate
+        .then(|| protocol::stream_translator(backend))
+        .flatten();
+    let reverse = model_mapping.map(make_reverse_model_rewriter);
+    match (translator, reverse) {
+        (Some(translator), Some(reverse)) => Some(chain_rewriters(translator, reverse)),
+        (translator, reverse) => translator.or(reverse),
+    }
+}
+
+/// Completion callback registering the stream's thinking blocks.
+pub(super) fn thinking_callback(session: ThinkingSession) -> ResponseCompleteCallback {
+    Box::new(move |bytes: &[u8]| {
+        let events = crate::sse::parse_sse_events(bytes);
+        session.register_from_sse(&events);
+    })
 }
@@ -0,0 +1,390 @@
+//! Mid-stream recovery for streaming responses (`[retry] resume_streams`).
+//!
+//! A stream that fails (upstream error or idle timeout) is handled by how
+//! far it got:
+//! - Only `message_start`/`ping` sent: stages 3-6 are re-run against the next
+//!   failover backend, or the same one, and the new stream is spliced in
+//!   without its own `message_start`
+//! - Content already sent, or no re-issue left: the stream ends with an
+//!   Anthropic `error` event, so the client reports a clean overloaded or
+//!   API error instead of a truncated body
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::body::Bytes;
+use axum::http::{HeaderMap, Method, Uri};
+use futures_core::Stream;
+use serde_json::Value;
+
+use crate::config::{Backend, BackendPricing};
+use crate::metrics::{ChunkRewriter, ObservedStream, RequestSpan, ResponseCompleteCallback, StreamError};
+use crate::proxy::pipeline::{
+    budget, finish_failed_attempt, forward, prepare_attempt, response, routing, PipelineConfig,
+    PipelineContext, PreparedAttempt,
+};
+
+/// Upstream body as streamed to the client.
+pub(super) type UpstreamStream =
+    Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>;
+
+type ReissueFuture = Pin<Box<dyn Future<Output = (StreamResume, Result<Resumed, StreamError>)> + Send>>;
+
+/// The original request, kept so it can be sent again.
+pub(super) struct ReplayRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body_bytes: Vec<u8>,
+    pub parsed_body: Option<Value>,
+}
+
+/// Everything stage 7 needs to re-issue a streaming request.
+pub struct StreamResume {
+    config: PipelineConfig,
+    ctx: PipelineContext,
+    request: ReplayRequest,
+    primary: Backend,
+    backend: Backend,
+    tried: Vec<String>,
+    is_teammate: bool,
+    remaining: u32,
+}
+
+/// A replacement upstream stream and how to rewrite it.
+struct Resumed {
+    stream: UpstreamStream,
+    rewriter: Option<ChunkRewriter>,
+    on_complete: Option<ResponseCompleteCallback>,
+    pricing: Option<BackendPricing>,
+}
+
+impl StreamResume {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new(
+        config: PipelineConfig,
+        ctx: PipelineContext,
+        request: ReplayRequest,
+        primary: Backend,
+        backend: Backend,
+        tried: Vec<String>,
+        is_teammate: bool,
+        max_resumes: u32,
+    ) -> Self {
+        Self {
+            config,
+            ctx,
+            request,
+            primary,
+            backend,
+            tried,
+            is_teammate,
+            remaining: max_resumes,
+        }
+    }
+
+    /// Record the attempt that failed with `error` and send the request
+    /// again. Hands `error` back if that fails too.
+    async fn reissue(mut self, error: StreamError) -> (Self, Result<Resumed, StreamError>) {
+        self.remaining -= 1;
+        let (status, _) = failure_status(&error);
+        finish_failed_attempt(
+            &self.config,
+            &mut self.ctx,
+            &self.backend,
+            status,
+            matches!(error, StreamError::IdleTimeout { .. }),
+        );
+
+        let cause = format!("stream {}", error);
+        let next = routing::next_failover(&self.config.backend_state, &self.primary, &self.tried)
+            .filter(|next| self.config.backend_state.circuit_breakers().allows(&next.name))
+            .filter(|next| budget::hop_exceeded(next, &self.config, &self.ctx).is_none());
+        match next {
+            Some(next) => {
+                routing::record_failover(&self.config.backend_state, &self.backend, &next, &cause, &mut self.ctx);
+                self.tried.push(next.name.clone());
+                self.backend = next;
+            }
+            None => {
+                crate::metrics::app_log(
+                    "stream",
+                    &format!("Re-issuing request to '{}' ({})", self.backend.name, cause),
+                );
+                self.config
+                    .backend_state
+                    .group_balancer()
+                    .begin(self.ctx.span.request_id(), &self.backend.name);
+            }
+        }
+
+        let resumed = self.send().await.ok_or(error);
+        (self, resumed)
+    }
+
+    async fn send(&mut self) -> Option<Resumed> {
+        if let Some(exceeded) = budget::hop_exceeded(&self.backend, &self.config, &self.ctx) {
+            self.log_failure(&exceeded.to_string());
+            return None;
+        }
+
+        let PreparedAttempt {
+            uri,
+            headers,
+            body,
+            is_streaming,
+            thinking_session,
+            model_mapping,
+            translate,
+            api_key,
+        } = prepare_attempt(
+            &self.request.uri,
+            &self.request.headers,
+            self.request.body_bytes.clone(),
+            self.request.parsed_body.clone(),
+            &self.backend,
+            self.is_teammate,
+            &self.config,
+            &mut self.ctx,
+        )
+        .await
+        .inspect_err(|e| self.log_failure(&e.to_string()))
+        .ok()?;
+
+        let request_id = self.ctx.span.request_id().to_string();
+        let resp = forward::send_with_retry(
+            self.request.method.clone(),
+            uri,
+            headers,
+            body,
+            is_streaming,
+            &self.backend,
+            api_key,
+            &self.config,
+            &request_id,
+            &mut self.ctx.span.record_mut().trace.retries,
+        )
+        .await
+        .inspect_err(|e| self.log_failure(&e.to_string()))
+        .ok()?;
+
+        if !resp.status().is_success() || !response::is_event_stream(resp.headers(), translate) {
+            self.log_failure(&format!("status {}", resp.status().as_u16()));
+            return None;
+        }
+
+        Some(Resumed {
+            stream: Box::pin(resp.bytes_stream()),
+            rewriter: response::stream_rewriter(&self.backend, translate, model_mapping),
+            on_complete: thinking_session.map(response::thinking_callback),
+            pricing: self.backend.pricing.clone(),
+        })
+    }
+
+    fn log_failure(&self, cause: &str) {
+        crate::metrics::app_log(
+            "stream",
+            &format!("Re-issued request to '{}' failed: {}", self.backend.name, cause),
+        );
+    }
+
+    /// Carry the routing and failed hops of the re-issued request over to
+    /// the client's span.
+    fn update_span(&mut self, span: &mut RequestSpan) {
+        span.set_backend(self.backend.name.clone());
+        let resumed = self.ctx.span.record_mut();
+        let record = span.record_mut();
+        record.routing_decision = resumed.routing_decision.clone();
+        record.trace.hops.clone_from(&resumed.trace.hops);
+    }
+}
+
+enum State {
+    Streaming(Box<StreamResume>),
+    Reissuing(ReissueFuture),
+    Done,
+}
+
+/// Streaming body that re-issues the request when it breaks early.
+pub(super) struct ResumingStream {
+    observed: ObservedStream<UpstreamStream>,
+    progress: StreamProgress,
+    state: State,
+}
+
+impl ResumingStream {
+    /// `observed` must have been built [`with_recovery`](ObservedStream::with_recovery).
+    pub(super) fn new(observed: ObservedStream<UpstreamStream>, resume: StreamResume) -> Self {
+        Self {
+            observed,
+            progress: StreamProgress::default(),
+            state: State::Streaming(Box::new(resume)),
+        }
+    }
+
+    /// End the stream with an `error` event describing `error`.
+    fn fail(&mut self, error: &StreamError) -> Bytes {
+        let (status, kind) = failure_status(error);
+        crate::metrics::app_log(
+            "stream",
+            &format!("Ending broken stream with {} event: {}", kind, error),
+        );
+        self.observed.fail(error, status);
+        self.state = State::Done;
+        error_event(kind, &format!("Upstream stream failed: {}", error))
+    }
+}
+
+impl Stream for ResumingStream {
+    type Item = Result<Bytes, StreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            match std::mem::replace(&mut this.state, State::Done) {
+                State::Done => return Poll::Ready(None),
+                State::Streaming(resume) => {
+                    let error = match Pin::new(&mut this.observed).poll_next(cx) {
+                        Poll::Ready(Some(Ok(bytes))) => {
+                            this.progress.push(&bytes);
+                            this.state = State::Streaming(resume);
+                            return Poll::Ready(Some(Ok(bytes)));
+                        }
+                        Poll::Ready(Some(Err(error))) => error,
+                        Poll::Ready(None) => return Poll::Ready(None),
+                        Poll::Pending => {
+                            this.state = State::Streaming(resume);
+                            return Poll::Pending;
+                        }
+                    };
+
+                    if this.progress.content_started || resume.remaining == 0 {
+                        return Poll::Ready(Some(Ok(this.fail(&error))));
+                    }
+                    this.state = State::Reissuing(Box::pin((*resume).reissue(error)));
+                }
+                State::Reissuing(mut future) => match future.as_mut().poll(cx) {
+                    Poll::Pending => {
+                        this.state = State::Reissuing(future);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready((resume, Ok(resumed))) => {
+                        let mut resume = Box::new(resume);
+                        if let Some(span) = this.observed.span_mut() {
+                            resume.update_span(span);
+                        }
+                        this.observed.resume(
+                            resumed.stream,
+                            resumed.rewriter,
+                            skip_message_start(),
+                            resumed.on_complete,
+                            resumed.pricing,
+                        );
+                        this.state = State::Streaming(resume);
+                    }
+                    Poll::Ready((mut resume, Err(error))) => {
+                        if let Some(span) = this.observed.span_mut() {
+                            resume.update_span(span);
+                        }
+                        return Poll::Ready(Some(Ok(this.fail(&error))));
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Span status and Anthropic error type for a stream ending in `error`.
+fn failure_status(error: &StreamError) -> (u16, &'static str) {
+    match error {
+        StreamError::IdleTimeout { .. } => (504, "overloaded_error"),
+        StreamError::Upstream(_) => (502, "api_error"),
+    }
+}
+
+/// An Anthropic SSE `error` event.
+fn error_event(kind: &str, message: &str) -> Bytes {
+    let data = serde_json::json!({
+        "type": "error",
+        "error": { "type": kind, "message": message },
+    });
+    Bytes::from(format!("event: error\ndata: {}\n\n", data))
+}
+
+/// Tracks whether the client has been sent anything beyond
+/// `message_start` and `ping`.
+#[derive(Default)]
+struct StreamProgress {
+    line: Vec<u8>,
+    content_started: bool,
+}
+
+impl StreamProgress {
+    fn push(&mut self, bytes: &[u8]) {
+        if self.content_started {
+            return;
+        }
+        for &byte in bytes {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let started = crate::sse::parse_sse_events(&self.line)
+                .iter()
+                .any(|event| !matches!(event.event_type.as_str(), "message_start" | "ping"));
+            self.line.clear();
+            if started {
+                self.content_started = true;
+                return;
+            }
+        }
+    }
+}
+
+/// Rewriter dropping the first `message_start` event of a re-issued stream;
+/// the client already has one. Everything after it passes through as is,
+/// and bytes still held when the stream ends are flushed.
+fn skip_message_start() -> ChunkRewriter {
+    let mut pending: Vec<u8> = Vec::new();
+    let mut skipped = false;
+    Box::new(move |bytes: Bytes| {
+        if skipped {
+            return bytes;
+        }
+        if bytes.is_empty() {
+            return Bytes::from(std::mem::take(&mut pending));
+        }
+        pending.extend_from_slice(&bytes);
+
+        let mut out = Vec::with_capacity(pending.len());
+        while let Some(end) = event_end(&pending) {
+            let event: Vec<u8> = pending.drain(..end).collect();
+            let is_start = crate::sse::parse_sse_events(&event)
+                .iter()
+                .any(|e| e.event_type == "message_start");
+            if is_start {
+                skipped = true;
+                out.append(&mut pending);
+                break;
+            }
+            out.extend_from_slice(&event);
+        }
+        Bytes::from(out)
+    })
+}
+
+/// Length of the first complete SSE event in `buf`, including the blank
+/// line ending it (`\n\n` or `\r\n\r\n`).
+fn event_end(buf: &[u8]) -> Option<usize> {
+    let find = |separator: &[u8]| {
+        buf.windows(separator.len())
+            .position(|w| w == separator)
+            .map(|pos| (pos, pos + separator.len()))
+    };
+    [find(b"\n\n"), find(b"\r\n\r\n")]
+        .into_iter()
+        .flatten()
+        .min()
+        .map(|(_, end)| end)
+}
@@ -5,13 +5,16 @@
 //! - Plugin routing decisions
 //! - AC marker in request body (session affinity from hook)
 //! - Marker model prefixes (marker-*, anyclaude-*)
+//! - `[[routing.rules]]` from config
 //! - Active backend from backend_state
+//!
+//! A resolved `[[backend_groups]]` name is then narrowed to one member.
 
 use serde_json::Value;
 
 use crate::backend::{BackendState, AgentRegistry};
-use crate::config::Backend;
-use crate::metrics::{BackendOverride, RoutingDecision};
+use crate::config::{Backend, GroupStrategy, RoutingRule};
+use crate::metrics::{AgentKind, BackendOverride, RequestAnalysis, RequestParser, RoutingDecision};
 use crate::proxy::error::ProxyError;
 use crate::proxy::pipeline::PipelineContext;
 
@@ -22,21 +25,28 @@ use crate::proxy::pipeline::PipelineContext;
 /// 2. Explicit backend_override parameter (teammate routes)
 /// 3. AC marker in request body (session affinity from hook)
 /// 4. Marker model detection (marker-*, anyclaude-* prefixes, direct backend name)
-/// 5. Active backend from backend_state
+/// 5. First matching routing rule, with the rule name as the reason
+/// 6. Active backend from backend_state
+///
+/// When the result names a backend group, a member is picked per request.
+/// Main-agent requests (everything except teammate routes and AC marker
+/// subagents) stay pinned to one member, so the thinking registry doesn't
+/// see a backend switch, and drop signed thinking blocks, on every request.
 pub fn resolve_backend(
     backend_state: &BackendState,
     backend_override: Option<String>,
     plugin_override: Option<BackendOverride>,
     parsed_body: Option<&Value>,
+    path: &str,
     registry: &AgentRegistry,
     ctx: &mut PipelineContext,
 ) -> Result<Backend, ProxyError> {
     // Resolve with documented priority.
     // Higher-priority overrides short-circuit — no body parsing needed.
-    let (backend_id, routing_reason) = if let Some(ovr) = plugin_override {
-        (ovr.backend, ovr.reason)
+    let (backend_id, mut routing_reason, agent) = if let Some(ovr) = plugin_override {
+        (ovr.backend, ovr.reason, AgentKind::Main)
     } else if let Some(bo) = backend_override {
-        (bo, "teammate route".into())
+        (bo, "teammate route".into(), AgentKind::Teammate)
     } else if let Some(id) = (!registry.is_empty())
         .then(|| parsed_body.and_then(extract_ac_marker))
         .flatten()
@@ -44,32 +54,158 @@ pub fn resolve_backend(
         let b = registry.lookup(&id).ok_or_else(|| {
             ProxyError::SubagentNotRegistered { id: id.clone() }
         })?;
-        (b, "ac marker session affinity".into())
+        ctx.agent_id.get_or_insert_with(|| id.clone());
+        (b, "ac marker session affinity".into(), AgentKind::Subagent)
     } else if let Some(mb) = parsed_body
         .and_then(|body| body.get("model"))
         .and_then(|m| m.as_str())
         .and_then(|model| detect_marker_model(model, backend_state))
     {
-        (mb, "marker model".into())
+        (mb, "marker model".into(), AgentKind::Main)
+    } else if let Some(rule) = match_routing_rule(backend_state, parsed_body, path) {
+        (rule.backend, rule.name, AgentKind::Main)
     } else {
-        (backend_state.get_active_backend(), "active backend".into())
+        (backend_state.get_active_backend(), "active backend".into(), AgentKind::Main)
     };
 
-    let backend = backend_state
-        .get_backend_config(&backend_id)
-        .map_err(|e| ProxyError::BackendNotFound {
-            backend: e.to_string(),
-        })?;
+    let backend = match backend_state.get_group(&backend_id) {
+        Some(group) => {
+            let snapshot = (group.strategy == GroupStrategy::LowestLatency)
+                .then(|| ctx.observability.snapshot());
+            let p50_latency = |name: &str| {
+                snapshot
+                    .as_ref()
+                    .and_then(|s| s.per_backend.get(name))
+                    .and_then(|m| m.p50_latency_ms)
+            };
+            routing_reason = format!("{} via group {}", routing_reason, group.name);
+            backend_state.select_group_member(&group, agent == AgentKind::Main, p50_latency)
+        }
+        None => backend_state.get_backend_config(&backend_id),
+    }
+    .map_err(|e| ProxyError::BackendNotFound {
+        backend: e.to_string(),
+    })?;
+    backend_state
+        .group_balancer()
+        .begin(ctx.span.request_id(), &backend.name);
 
-    ctx.span.set_backend(backend_id.clone());
-    ctx.span.record_mut().routing_decision = Some(RoutingDecision {
-        backend: backend_id,
+    ctx.span.set_backend(backend.name.clone());
+    let agent_id = ctx.agent_id.clone();
+    let record = ctx.span.record_mut();
+    record.routing_decision = Some(RoutingDecision {
+        backend: backend.name.clone(),
         reason: routing_reason,
+        synthesized: false,
     });
+    record.agent = agent;
+    record.agent_id = agent_id;
 
     Ok(backend)
 }
 
+/// First routing rule matching the request whose backend is configured.
+fn match_routing_rule(
+    backend_state: &BackendState,
+    parsed_body: Option<&Value>,
+    path: &str,
+) -> Option<RoutingRule> {
+    let rules = backend_state.routing_rules();
+    if rules.is_empty() {
+        return None;
+    }
+
+    let analysis = parsed_body
+        .map(|body| RequestParser::new().analyze(body))
+        .unwrap_or_default();
+    rules
+        .into_iter()
+        .filter(|rule| rule_matches(rule, &analysis, path))
+        .find(|rule| {
+            let usable = match backend_state.get_group(&rule.backend) {
+                Some(group) => group.members.iter().any(|m| {
+                    backend_state
+                        .get_backend_config(&m.backend)
+                        .is_ok_and(|b| backend_state.is_configured(&b))
+                }),
+                None => backend_state
+                    .get_backend_config(&rule.backend)
+                    .is_ok_and(|b| backend_state.is_configured(&b)),
+            };
+            if !usable {
+                crate::metrics::app_log(
+                    "routing",
+                    &format!(
+                        "Routing rule '{}' matched but backend '{}' is not configured, skipping",
+                        rule.name, rule.backend
+                    ),
+                );
+            }
+            usable
+        })
+}
+
+/// Whether every condition set on `rule` holds for the request.
+fn rule_matches(rule: &RoutingRule, analysis: &RequestAnalysis, path: &str) -> bool {
+    let model = analysis.model.as_deref().unwrap_or_default();
+    rule.model.as_deref().is_none_or(|m| model.contains(m))
+        && rule.tools.iter().all(|tool| analysis.tool_names.contains(tool))
+        && within(analysis.max_tokens, rule.max_tokens_min, rule.max_tokens_max)
+        && within(analysis.estimated_input_tokens, rule.input_tokens_min, rule.input_tokens_max)
+        && rule.path.as_deref().is_none_or(|p| p == path)
+        && rule.thinking.is_none_or(|t| t == analysis.thinking_enabled)
+}
+
+/// Inclusive bounds check; a missing value only matches when unbounded.
+fn within(value: Option<u64>, min: Option<u64>, max: Option<u64>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    value.is_some_and(|v| min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max))
+}
+
+/// Stage 2 (failover): pick the next backend from `primary`'s failover chain.
+///
+/// Skips backends that were already tried, no longer exist, or have no
+/// credentials (any backend will do under `--replay`). Returns `None` when the chain is exhausted.
+pub fn next_failover(
+    backend_state: &BackendState,
+    primary: &Backend,
+    tried: &[String],
+) -> Option<Backend> {
+    primary
+        .failover
+        .iter()
+        .filter(|name| !tried.contains(name))
+        .filter_map(|name| backend_state.get_backend_config(name).ok())
+        .find(|backend| backend_state.is_configured(backend))
+}
+
+/// Record a failover hop from `from` to `to` in the span and switch history.
+pub fn record_failover(
+    backend_state: &BackendState,
+    from: &Backend,
+    to: &Backend,
+    cause: &str,
+    ctx: &mut PipelineContext,
+) {
+    crate::metrics::app_log(
+        "failover",
+        &format!("Failing over: '{}' -> '{}' ({})", from.name, to.name, cause),
+    );
+    backend_state.record_failover(&from.name, &to.name);
+    backend_state
+        .group_balancer()
+        .begin(ctx.span.request_id(), &to.name);
+
+    ctx.span.set_backend(to.name.clone());
+    ctx.span.record_mut().routing_decision = Some(RoutingDecision {
+        backend: to.name.clone(),
+        reason: format!("failover from {} ({})", from.name, cause),
+        synthesized: false,
+    });
+}
+
 /// CC wrapper prefix that always precedes our marker in `additionalContext`.
 /// Without this prefix the marker is ignored — prevents false positives
 /// from user text that happens to contain `⟨AC:...⟩`.
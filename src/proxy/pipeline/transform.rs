@@ -3,13 +3,18 @@
 //! Applies transformations to the request body:
 //! - Model rewriting (family-based mapping)
 //! - Thinking compatibility conversion (adaptive -> enabled)
+//! - Per-backend `[backends.body]` overrides (clamps, forced sampling
+//!   parameters, dropped fields and tools)
 //! - Thinking block filtering (via ThinkingSession)
+//! - Protocol translation for OpenAI and Gemini backends, and the
+//!   Bedrock / Vertex AI request shape
 
 use serde_json::Value;
 
-use crate::config::Backend;
+use crate::config::{Backend, BodyRules};
 use crate::proxy::error::ProxyError;
 use crate::proxy::model_rewrite::ModelMapping;
+use crate::proxy::protocol;
 use crate::proxy::thinking::ThinkingSession;
 use crate::proxy::pipeline::PipelineContext;
 
@@ -17,12 +22,14 @@ use crate::proxy::pipeline::PipelineContext;
 /// Stage 4: Transform request body.
 ///
 /// Applies all body transformations and returns the transformed bytes
-/// along with metadata about the transformation.
+/// along with metadata about the transformation. With `translate`, the
+/// final Anthropic body is converted to the backend's protocol.
 pub fn transform_body(
     body_bytes: Vec<u8>,
     parsed_body: Option<Value>,
     backend: &Backend,
     thinking: Option<&ThinkingSession>,
+    translate: bool,
     ctx: &mut PipelineContext,
 ) -> Result<(Vec<u8>, bool, Option<ModelMapping>), ProxyError> {
     let needs_thinking_compat = backend.needs_thinking_compat();
@@ -87,13 +94,48 @@ pub fn transform_body(
         }
     }
 
-    // 3. Filter thinking blocks (main agent only - ThinkingSession present)
+    // 3. Apply the backend's body overrides
+    let overrides = apply_body_rules(&mut json_body, &backend.body);
+    for change in &overrides {
+        ctx.debug_logger.log_auxiliary(
+            "body_override",
+            None,
+            None,
+            Some(&format!("{} for backend '{}'", change, backend.name)),
+            None,
+        );
+    }
+
+    // 4. Filter thinking blocks (main agent only - ThinkingSession present)
     if let Some(session) = thinking {
         filtered_count = session.filter(&mut json_body);
     }
 
+    let trace = &mut ctx.span.record_mut().trace;
+    trace.thinking_blocks_filtered += filtered_count;
+    trace.upstream_model = json_body.get("model").and_then(|m| m.as_str()).map(String::from);
+    trace.model = model_mapping
+        .as_ref()
+        .map(|mapping| mapping.original.clone())
+        .or_else(|| trace.upstream_model.clone());
+
+    // 5. Translate to the backend's protocol (OpenAI, Gemini, Bedrock, Vertex)
+    if translate {
+        json_body = protocol::translate_request(backend, &json_body);
+        ctx.debug_logger.log_auxiliary(
+            "protocol",
+            None,
+            None,
+            Some(&format!(
+                "Translated /v1/messages for backend '{}' (protocol={:?}, auth_type={:?})",
+                backend.name, backend.protocol, backend.auth_type()
+            )),
+            None,
+        );
+    }
+
     // Re-serialize body if any transformation occurred
-    if model_rewritten || thinking_converted || filtered_count > 0 {
+    if model_rewritten || thinking_converted || !overrides.is_empty() || filtered_count > 0 || translate {
         if thinking_converted {
             let thinking_json = json_body
                 .get("thinking")
@@ -157,3 +199,120 @@ fn convert_adaptive_thinking(body: &mut Value, configured_budget: Option<u32>) -
     );
     Some(true)
 }
+
+/// Apply `rules` to `body`, returning a description of each change made.
+fn apply_body_rules(body: &mut Value, rules: &BodyRules) -> Vec<String> {
+    let mut changes = Vec::new();
+    let Some(obj) = body.as_object_mut() else {
+        return changes;
+    };
+
+    for field in &rules.drop {
+        if field == "cache_control" {
+            let removed = strip_cache_control(obj);
+            if removed > 0 {
+                changes.push(format!("Dropped {} cache_control marker(s)", removed));
+            }
+        } else if obj.remove(field).is_some() {
+            changes.push(format!("Dropped '{}'", field));
+        }
+    }
+
+    let forced = [
+        ("temperature", rules.temperature.map(Value::from)),
+        ("top_p", rules.top_p.map(Value::from)),
+        ("top_k", rules.top_k.map(Value::from)),
+    ];
+    for (field, value) in forced {
+        let Some(value) = value else { continue };
+        if obj.get(field) != Some(&value) {
+            let previous = obj.insert(field.to_string(), value.clone());
+            changes.push(format!(
+                "Set {} {} (was {})",
+                field,
+                value,
+                previous.map_or_else(|| "unset".to_string(), |v| v.to_string())
+            ));
+        }
+    }
+
+    if let Some(cap) = rules.max_tokens {
+        if let Some(max_tokens) = obj.get("max_tokens").and_then(Value::as_u64).filter(|&m| m > cap) {
+            obj.insert("max_tokens".to_string(), Value::from(cap));
+            changes.push(format!("Clamped max_tokens {} -> {}", max_tokens, cap));
+        }
+    }
+
+    // The budget must stay below max_tokens, which may just have been clamped.
+    let below_max = rules.max_tokens.and_then(|_| {
+        obj.get("max_tokens").and_then(Value::as_u64).map(|m| m.saturating_sub(1))
+    });
+    let budget_cap = rules.max_thinking_budget.into_iter().chain(below_max).min();
+    if let Some(cap) = budget_cap {
+        let budget = obj
+            .get_mut("thinking")
+            .and_then(|t| t.get_mut("budget_tokens"))
+            .filter(|b| b.as_u64().is_some_and(|b| b > cap));
+        if let Some(budget) = budget {
+            changes.push(format!("Capped thinking.budget_tokens {} -> {}", budget, cap));
+            *budget = Value::from(cap);
+        }
+    }
+
+    if !rules.drop_tools.is_empty() {
+        if let Some(tools) = obj.get_mut("tools").and_then(Value::as_array_mut) {
+            let before = tools.len();
+            tools.retain(|tool| {
+                let name = tool.get("name").and_then(Value::as_str).unwrap_or_default();
+                !rules.drop_tools.iter().any(|dropped| dropped == name)
+            });
+            let removed = before - tools.len();
+            let now_empty = tools.is_empty();
+            if removed > 0 {
+                changes.push(format!("Dropped {} tool(s)", removed));
+                if now_empty {
+                    obj.remove("tools");
+                }
+                let chosen = obj
+                    .get("tool_choice")
+                    .and_then(|c| c.get("name"))
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| rules.drop_tools.iter().any(|dropped| dropped == name));
+                if (now_empty || chosen) && obj.remove("tool_choice").is_some() {
+                    changes.push("Dropped 'tool_choice'".to_string());
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+/// Remove `cache_control` from the body and from its system, tool and
+/// message content blocks (including nested tool results). Returns how
+/// many were removed.
+fn strip_cache_control(obj: &mut serde_json::Map<String, Value>) -> usize {
+    fn strip_blocks(blocks: Option<&mut Value>) -> usize {
+        let Some(blocks) = blocks.and_then(Value::as_array_mut) else {
+            return 0;
+        };
+        blocks
+            .iter_mut()
+            .filter_map(Value::as_object_mut)
+            .map(|block| {
+                usize::from(block.remove("cache_control").is_some())
+                    + strip_blocks(block.get_mut("content"))
+            })
+            .sum()
+    }
+
+    let mut removed = usize::from(obj.remove("cache_control").is_some());
+    removed += strip_blocks(obj.get_mut("system"));
+    removed += strip_blocks(obj.get_mut("tools"));
+    if let Some(messages) = obj.get_mut("messages").and_then(Value::as_array_mut) {
+        for message in messages {
+            removed += strip_blocks(message.get_mut("content"));
+        }
+    }
+    removed
+}
@@ -0,0 +1,214 @@
+//! Background active health prober.
+//!
+//! Periodically sends a cheap request to every configured backend (see
+//! [`HealthCheckMethod`]) using the backend's own auth, and stores the
+//! outcome in [`ProbeResults`] for `/health` and the status popup.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+use crate::backend::{BackendProbe, BackendState};
+use crate::config::{build_auth_header, AuthType, Backend, HealthCheckConfig, HealthCheckMethod};
+use crate::proxy::{bedrock, protocol};
+use crate::proxy::shutdown::ShutdownManager;
+
+/// `anthropic-version` sent with HTTP probes.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Probes configured backends on an interval.
+#[derive(Clone)]
+pub struct HealthProber {
+    backend_state: BackendState,
+    client: reqwest::Client,
+}
+
+impl HealthProber {
+    pub fn new(backend_state: BackendState) -> Self {
+        Self {
+            backend_state,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Probe loop. Re-reads `[health_check]` each round so config reloads
+    /// apply; exits on proxy shutdown.
+    pub async fn run(self, shutdown: Arc<ShutdownManager>) {
+        loop {
+            let health_check = self.backend_state.get_config().health_check;
+            if health_check.enabled {
+                self.probe_all().await;
+            }
+
+            let interval = Duration::from_secs(health_check.interval_seconds.max(1));
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.wait_for_shutdown() => break,
+            }
+        }
+    }
+
+    /// Probe every configured backend once, concurrently.
+    pub async fn probe_all(&self) {
+        let config = self.backend_state.get_config();
+        let results = self.backend_state.probe_results();
+        let names: Vec<String> = config.backends.iter().map(|b| b.name.clone()).collect();
+        results.retain(&names);
+
+        let mut tasks = JoinSet::new();
+        for backend in config.backends.into_iter().filter(|b| b.is_configured()) {
+            let client = self.client.clone();
+            let health_check = config.health_check.clone();
+            tasks.spawn(async move {
+                let probe = probe_backend(&client, &backend, &health_check).await;
+                (backend.name, probe)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((name, probe)) = joined else { continue };
+            if !probe.healthy {
+                crate::metrics::app_log(
+                    "health",
+                    &format!(
+                        "Health probe failed: backend='{}', status={:?}, error={:?}",
+                        name, probe.status, probe.error
+                    ),
+                );
+            }
+            results.update(&name, probe);
+        }
+    }
+}
+
+/// Probe a single backend.
+pub async fn probe_backend(
+    client: &reqwest::Client,
+    backend: &Backend,
+    health_check: &HealthCheckConfig,
+) -> BackendProbe {
+    let timeout = Duration::from_secs(health_check.timeout_seconds.max(1));
+    let started = Instant::now();
+
+    if health_check.method == HealthCheckMethod::Connect {
+        let result = match connect_addr(&backend.base_url) {
+            Some(addr) => tokio::time::timeout(timeout, TcpStream::connect(addr))
+                .await
+                .map_err(|_| "connect timed out".to_string())
+                .and_then(|r| r.map(|_| ()).map_err(|e| e.to_string())),
+            None => Err(format!("invalid base_url '{}'", backend.base_url)),
+        };
+        let reachable = result.is_ok();
+        return BackendProbe {
+            reachable,
+            healthy: reachable,
+            latency_ms: reachable.then(|| started.elapsed().as_millis() as u64),
+            status: None,
+            error: result.err(),
+            checked_at: SystemTime::now(),
+        };
+    }
+
+    backend.refresh_credentials().await;
+    let unreachable = |error: String| BackendProbe {
+        reachable: false,
+        healthy: false,
+        latency_ms: None,
+        status: None,
+        error: Some(error),
+        checked_at: SystemTime::now(),
+    };
+
+    let (method, url, body) = match health_check.method {
+        HealthCheckMethod::Message => {
+            let mut body = serde_json::json!({
+                "model": health_check.probe_model,
+                "max_tokens": 1,
+                "messages": [{"role": "user", "content": "ping"}],
+            });
+            // Maps the model itself, so it is given the requested name.
+            let path = match protocol::upstream_uri(backend, Some(&body)) {
+                Ok(uri) => uri.to_string(),
+                Err(err) => return unreachable(err.to_string()),
+            };
+            if let Some(model) = backend.resolve_model(&health_check.probe_model) {
+                body["model"] = serde_json::json!(model);
+            }
+            let body = serde_json::to_vec(&protocol::translate_request(backend, &body))
+                .unwrap_or_default();
+            (reqwest::Method::POST, format!("{}{}", backend.base_url, path), body)
+        }
+        HealthCheckMethod::Models | HealthCheckMethod::Connect => (
+            reqwest::Method::GET,
+            format!("{}{}", backend.base_url, protocol::models_path(backend)),
+            Vec::new(),
+        ),
+    };
+
+    let content_type = (method == reqwest::Method::POST).then_some("application/json");
+    let mut builder = client
+        .request(method.clone(), &url)
+        .timeout(timeout)
+        .header("anthropic-version", ANTHROPIC_VERSION);
+    if let Some(content_type) = content_type {
+        builder = builder.header("content-type", content_type);
+    }
+    if let Some((name, value)) = build_auth_header(backend) {
+        builder = builder.header(name, value);
+    }
+    if backend.auth_type() == AuthType::Bedrock {
+        match bedrock::sign_request(backend, method.as_str(), &url, content_type, &body) {
+            Ok(signed) => {
+                for (name, value) in signed {
+                    builder = builder.header(name, value);
+                }
+            }
+            Err(err) => return unreachable(err),
+        }
+    }
+    if !body.is_empty() {
+        builder = builder.body(body);
+    }
+
+    match builder.send().await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let healthy = is_healthy_status(backend, status);
+            BackendProbe {
+                reachable: true,
+                healthy,
+                latency_ms: Some(latency_ms),
+                status: Some(status),
+                error: (!healthy).then(|| format!("HTTP {}", status)),
+                checked_at: SystemTime::now(),
+            }
+        }
+        Err(err) => unreachable(if err.is_timeout() {
+            "probe timed out".to_string()
+        } else {
+            err.to_string()
+        }),
+    }
+}
+
+/// Whether an HTTP probe status means the backend can serve requests.
+///
+/// 429 and 5xx mean overloaded or down. 401/403 mean bad credentials, but
+/// only for backends that send their own; passthrough probes carry no auth.
+fn is_healthy_status(backend: &Backend, status: u16) -> bool {
+    if status == 429 || status >= 500 {
+        return false;
+    }
+    !(backend.auth_type().uses_own_credentials() && matches!(status, 401 | 403))
+}
+
+/// Resolve `host:port` from a base URL for TCP probes.
+fn connect_addr(base_url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(base_url).ok()?;
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    Some(format!("{}:{}", host, port))
+}
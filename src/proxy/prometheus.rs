@@ -0,0 +1,182 @@
+//! `GET /metrics`: observability aggregates in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+
+use crate::metrics::{BackendMetrics, LatencyHistogram, ObservabilityHub, LATENCY_BUCKETS_MS};
+use crate::proxy::shutdown::ShutdownManager;
+use crate::proxy::thinking::TransformerRegistry;
+
+const CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub struct MetricsHandler {
+    observability: ObservabilityHub,
+    transformer_registry: Arc<TransformerRegistry>,
+    shutdown: Arc<ShutdownManager>,
+}
+
+impl MetricsHandler {
+    pub fn new(
+        observability: ObservabilityHub,
+        transformer_registry: Arc<TransformerRegistry>,
+        shutdown: Arc<ShutdownManager>,
+    ) -> Self {
+        Self {
+            observability,
+            transformer_registry,
+            shutdown,
+        }
+    }
+
+    pub async fn handle(&self) -> Response {
+        ([(CONTENT_TYPE, CONTENT_TYPE_TEXT)], self.render()).into_response()
+    }
+
+    /// All series, with backends in name order.
+    pub fn render(&self) -> String {
+        let snapshot = self.observability.snapshot();
+        let backends: BTreeMap<&String, &BackendMetrics> = snapshot.per_backend.iter().collect();
+        let mut out = String::new();
+
+        header(&mut out, "anyclaude_requests_total", "counter", "Requests by backend and status class.");
+        for (backend, m) in &backends {
+            let other = m.total - m.success_2xx - m.client_error_4xx - m.server_error_5xx;
+            for (class, count) in [
+                ("2xx", m.success_2xx),
+                ("4xx", m.client_error_4xx),
+                ("5xx", m.server_error_5xx),
+                ("other", other),
+            ] {
+                sample(
+                    &mut out,
+                    "anyclaude_requests_total",
+                    &[("backend", backend), ("status_class", class)],
+                    count,
+                );
+            }
+        }
+
+        header(&mut out, "anyclaude_request_timeouts_total", "counter", "Requests that timed out.");
+        for (backend, m) in &backends {
+            sample(&mut out, "anyclaude_request_timeouts_total", &[("backend", backend)], m.timeouts);
+        }
+
+        header(&mut out, "anyclaude_request_duration_seconds", "histogram", "Time until the response completed.");
+        for (backend, m) in &backends {
+            histogram(&mut out, "anyclaude_request_duration_seconds", backend, &m.latency_histogram);
+        }
+
+        header(&mut out, "anyclaude_time_to_first_byte_seconds", "histogram", "Time until the first response byte.");
+        for (backend, m) in &backends {
+            histogram(&mut out, "anyclaude_time_to_first_byte_seconds", backend, &m.ttfb_histogram);
+        }
+
+        header(&mut out, "anyclaude_input_tokens_total", "counter", "Uncached input tokens reported by backends.");
+        for (backend, m) in &backends {
+            sample(&mut out, "anyclaude_input_tokens_total", &[("backend", backend)], m.input_tokens);
+        }
+
+        header(&mut out, "anyclaude_output_tokens_total", "counter", "Output tokens reported by backends.");
+        for (backend, m) in &backends {
+            sample(&mut out, "anyclaude_output_tokens_total", &[("backend", backend)], m.output_tokens);
+        }
+
+        header(
+            &mut out,
+            "anyclaude_cache_read_input_tokens_total",
+            "counter",
+            "Input tokens read from the prompt cache.",
+        );
+        for (backend, m) in &backends {
+            sample(
+                &mut out,
+                "anyclaude_cache_read_input_tokens_total",
+                &[("backend", backend)],
+                m.cache_read_input_tokens,
+            );
+        }
+
+        header(
+            &mut out,
+            "anyclaude_cache_creation_input_tokens_total",
+            "counter",
+            "Input tokens written to the prompt cache.",
+        );
+        for (backend, m) in &backends {
+            sample(
+                &mut out,
+                "anyclaude_cache_creation_input_tokens_total",
+                &[("backend", backend)],
+                m.cache_creation_input_tokens,
+            );
+        }
+
+        header(&mut out, "anyclaude_cost_usd_total", "counter", "Cost in USD from backend pricing.");
+        for (backend, m) in &backends {
+            sample(&mut out, "anyclaude_cost_usd_total", &[("backend", backend)], m.cost_usd);
+        }
+
+        header(&mut out, "anyclaude_active_connections", "gauge", "Open client connections to the proxy.");
+        sample(&mut out, "anyclaude_active_connections", &[], self.shutdown.active_connections());
+
+        let cache = self.transformer_registry.thinking_cache_stats();
+        header(&mut out, "anyclaude_thinking_cache_blocks", "gauge", "Cached thinking blocks by confirmation.");
+        for (status, count) in [("confirmed", cache.confirmed), ("unconfirmed", cache.unconfirmed)] {
+            sample(&mut out, "anyclaude_thinking_cache_blocks", &[("status", status)], count);
+        }
+        header(
+            &mut out,
+            "anyclaude_thinking_cache_session_blocks",
+            "gauge",
+            "Cached thinking blocks by thinking session.",
+        );
+        for (session, count) in [("current", cache.current_session), ("old", cache.old_session)] {
+            sample(&mut out, "anyclaude_thinking_cache_session_blocks", &[("session", session)], count);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+        return;
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+        .collect();
+    let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+}
+
+/// Cumulative `_bucket` series plus `_sum` and `_count`, in seconds.
+fn histogram(out: &mut String, name: &str, backend: &str, histogram: &LatencyHistogram) {
+    let bucket_name = format!("{}_bucket", name);
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(histogram.buckets) {
+        cumulative += count;
+        let le = (*bound as f64 / 1000.0).to_string();
+        sample(out, &bucket_name, &[("backend", backend), ("le", &le)], cumulative);
+    }
+    sample(out, &bucket_name, &[("backend", backend), ("le", "+Inf")], histogram.count);
+    sample(out, &format!("{}_sum", name), &[("backend", backend)], histogram.sum_ms as f64 / 1000.0);
+    sample(out, &format!("{}_count", name), &[("backend", backend)], histogram.count);
+}
+
+/// Escape a label value per the text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
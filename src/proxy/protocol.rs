@@ -0,0 +1,221 @@
+//! Wire protocol translation for non-Anthropic backends.
+//!
+//! Backends with `protocol = "openai"` or `protocol = "gemini"` receive
+//! Anthropic `/v1/messages` requests translated into their native API, and
+//! their JSON and SSE responses translated back into Anthropic shapes. This
+//! module dispatches on [`BackendProtocol`]; the per-protocol mapping lives
+//! in [`crate::proxy::openai`] and [`crate::proxy::gemini`].
+//!
+//! Anthropic models hosted on AWS Bedrock (`auth_type = "bedrock"`) and
+//! Vertex AI (`auth_type = "vertex"`) go through the same hooks: the model
+//! moves into the path, the body gains `anthropic_version`, and Bedrock's
+//! event stream is decoded into SSE ([`crate::proxy::bedrock`],
+//! [`crate::proxy::vertex`]).
+
+use axum::body::Bytes;
+use axum::http::Uri;
+use serde_json::{json, Value};
+
+use crate::config::{AuthType, Backend, BackendProtocol};
+use crate::metrics::ChunkRewriter;
+use crate::proxy::error::ProxyError;
+use crate::proxy::{bedrock, gemini, openai, vertex};
+
+/// Anthropic endpoint that is translated for non-Anthropic backends.
+const MESSAGES_PATH: &str = "/v1/messages";
+
+/// Whether a request to `uri` on `backend` goes through translation.
+///
+/// Other paths (e.g. `/v1/models`) are forwarded unchanged.
+pub fn translates(backend: &Backend, uri: &Uri) -> bool {
+    let hosted = matches!(backend.auth_type(), AuthType::Bedrock | AuthType::Vertex);
+    (backend.protocol != BackendProtocol::Anthropic || hosted) && uri.path() == MESSAGES_PATH
+}
+
+/// Upstream URI for a translated request, from the Anthropic request body
+/// (Gemini, Bedrock and Vertex put the mapped model in the path).
+pub fn upstream_uri(backend: &Backend, body: Option<&Value>) -> Result<Uri, ProxyError> {
+    let model = body
+        .and_then(|b| b.get("model"))
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    let mapped = backend.resolve_model(model);
+    let model = mapped.as_deref().unwrap_or(model);
+    let streaming = body
+        .and_then(|b| b.get("stream"))
+        .and_then(|s| s.as_bool())
+        .unwrap_or(false);
+
+    let path = match (backend.protocol, backend.auth_type()) {
+        (BackendProtocol::OpenAi, _) => openai::CHAT_COMPLETIONS_PATH.to_string(),
+        (BackendProtocol::Gemini, _) => gemini::request_path(model, streaming),
+        (BackendProtocol::Anthropic, AuthType::Bedrock) => bedrock::request_path(model, streaming),
+        (BackendProtocol::Anthropic, AuthType::Vertex) => {
+            let project = backend.vertex_project.as_deref().ok_or_else(|| {
+                ProxyError::InvalidRequest(format!("backend '{}' has no vertex_project", backend.name))
+            })?;
+            let region = backend.vertex_region.as_deref().unwrap_or(vertex::DEFAULT_REGION);
+            vertex::request_path(project, region, model, streaming)
+        }
+        (BackendProtocol::Anthropic, _) => MESSAGES_PATH.to_string(),
+    };
+    path.parse()
+        .map_err(|_| ProxyError::InvalidRequest(format!("cannot build upstream path '{}'", path)))
+}
+
+/// Path listing models on `backend`, used by health probes.
+pub fn models_path(backend: &Backend) -> &'static str {
+    match backend.protocol {
+        BackendProtocol::Gemini => "/v1beta/models",
+        BackendProtocol::Anthropic | BackendProtocol::OpenAi => "/v1/models",
+    }
+}
+
+/// Translate an Anthropic Messages request body for `backend`.
+pub fn translate_request(backend: &Backend, body: &Value) -> Value {
+    match (backend.protocol, backend.auth_type()) {
+        (BackendProtocol::OpenAi, _) => openai::to_chat_completions(body),
+        (BackendProtocol::Gemini, _) => gemini::to_generate_content(body),
+        (BackendProtocol::Anthropic, AuthType::Bedrock) => bedrock::to_invoke_body(body),
+        (BackendProtocol::Anthropic, AuthType::Vertex) => vertex::to_raw_predict_body(body),
+        (BackendProtocol::Anthropic, _) => body.clone(),
+    }
+}
+
+/// Translate a non-streaming response body (or error) to Anthropic.
+/// Unparseable success bodies and Anthropic error envelopes are passed
+/// through unchanged.
+pub fn translate_response(backend: &Backend, status: u16, body_bytes: &Bytes) -> Bytes {
+    let is_anthropic_error = || {
+        serde_json::from_slice::<Value>(body_bytes)
+            .is_ok_and(|v| v.get("type").and_then(|t| t.as_str()) == Some("error"))
+    };
+    let translated = if (200..300).contains(&status) {
+        let Ok(json) = serde_json::from_slice::<Value>(body_bytes) else {
+            return body_bytes.clone();
+        };
+        match backend.protocol {
+            BackendProtocol::Anthropic => return body_bytes.clone(),
+            BackendProtocol::OpenAi => openai::to_message(&json),
+            BackendProtocol::Gemini => gemini::to_message(&json),
+        }
+    } else if is_anthropic_error() {
+        return body_bytes.clone();
+    } else {
+        to_error(status, body_bytes)
+    };
+    serde_json::to_vec(&translated)
+        .map(Bytes::from)
+        .unwrap_or_else(|_| body_bytes.clone())
+}
+
+/// Chunk rewriter translating the backend's response stream into Anthropic
+/// SSE events.
+pub fn stream_translator(backend: &Backend) -> Option<ChunkRewriter> {
+    match backend.protocol {
+        BackendProtocol::Anthropic => (backend.auth_type() == AuthType::Bedrock)
+            .then(bedrock::make_stream_translator),
+        BackendProtocol::OpenAi => Some(openai::make_stream_translator()),
+        BackendProtocol::Gemini => Some(gemini::make_stream_translator()),
+    }
+}
+
+/// Translate an error body into Anthropic's error envelope.
+///
+/// OpenAI- and Gemini-style `{"error": {"message": ...}}` keeps its message
+/// (Gemini streams may wrap it in an array), as does Bedrock's
+/// `{"message": ...}`; anything else is passed as the message verbatim.
+pub fn to_error(status: u16, body: &[u8]) -> Value {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|v| {
+            let v = v.get(0).cloned().unwrap_or(v);
+            let message = match v.get("error") {
+                Some(error) => error.get("message").or(Some(error)),
+                None => v.get("message"),
+            };
+            message.and_then(|m| m.as_str()).map(String::from)
+        })
+        .unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+
+    json!({
+        "type": "error",
+        "error": {"type": error_type(status), "message": message},
+    })
+}
+
+/// Anthropic error `type` for an HTTP status.
+fn error_type(status: u16) -> &'static str {
+    match status {
+        400 | 404 | 422 => "invalid_request_error",
+        401 => "authentication_error",
+        402 => "billing_error",
+        403 => "permission_error",
+        413 => "request_too_large",
+        429 => "rate_limit_error",
+        503 | 529 => "overloaded_error",
+        _ => "api_error",
+    }
+}
+
+/// Flatten a string or an array of content blocks into plain text.
+pub(crate) fn text_content(content: &Value) -> Option<String> {
+    match content {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(blocks) => Some(
+            blocks
+                .iter()
+                .filter(|b| block_type(b) == "text")
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    }
+}
+
+pub(crate) fn block_type(block: &Value) -> &str {
+    block.get("type").and_then(|t| t.as_str()).unwrap_or("")
+}
+
+/// Splits an SSE byte stream into complete lines, buffering the trailing
+/// partial line across chunks.
+#[derive(Default)]
+pub(crate) struct SseLines {
+    pending: Vec<u8>,
+}
+
+impl SseLines {
+    /// Append a chunk and return the `data:` payloads of all complete lines.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+        payloads
+    }
+
+    /// The `data:` payload of a last line left without a newline.
+    pub(crate) fn flush(&mut self) -> Vec<String> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        self.pending.push(b'\n');
+        self.push(&[])
+    }
+}
+
+/// Append one Anthropic SSE event to `out`.
+pub(crate) fn write_event(out: &mut String, data: Value) {
+    let event = data.get("type").and_then(|t| t.as_str()).unwrap_or("message");
+    out.push_str("event: ");
+    out.push_str(event);
+    out.push_str("\ndata: ");
+    out.push_str(&data.to_string());
+    out.push_str("\n\n");
+}
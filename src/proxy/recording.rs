@@ -0,0 +1,497 @@
+//! Record and replay of upstream traffic (`--record` / `--replay`).
+//!
+//! Recording appends every upstream exchange, with the arrival time of
+//! each response chunk, to `<dir>/<key>.jsonl`, where `<key>` hashes the
+//! normalized request. Replay serves the recorded responses at their
+//! recorded pace instead of contacting any backend.
+//!
+//! Requests are normalized to method, upstream path and JSON body, without
+//! the body's `metadata` (Claude Code puts its per-session user id there).
+//! Strict matching needs the whole normalized request to match. Fuzzy
+//! matching only compares the model, the number of messages and the last
+//! message, ignoring `cache_control` and thinking `signature`s, so a
+//! session replays when the system prompt or earlier turns differ. Several
+//! exchanges with the same key are served in recorded order, the last one
+//! repeating.
+//!
+//! Recordings are written by a background thread so file I/O never runs on
+//! the request path.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::body::Bytes;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use base64::Engine;
+use futures_core::Stream;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::time::{Instant, Sleep};
+
+use crate::proxy::error::ProxyError;
+
+const RECORDING_CHANNEL_SIZE: usize = 256;
+
+/// Response headers that describe the wire encoding rather than the body
+/// reqwest hands us, so they are not recorded.
+const SKIPPED_HEADERS: [&str; 4] = [
+    "content-length",
+    "content-encoding",
+    "transfer-encoding",
+    "connection",
+];
+
+/// How `--replay` matches requests to recorded exchanges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MatchMode {
+    /// The whole normalized request must match.
+    #[default]
+    Strict,
+    /// Model, message count and last message must match.
+    Fuzzy,
+}
+
+/// Whether upstream traffic is recorded, replayed, or neither.
+#[derive(Clone, Default)]
+pub enum Recording {
+    #[default]
+    Off,
+    Record(Arc<Recorder>),
+    Replay(Arc<Replayer>),
+}
+
+impl Recording {
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Recording::Replay(_))
+    }
+}
+
+/// One upstream request and the response it got.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Unix milliseconds when the request was sent.
+    pub recorded_at_ms: u64,
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Upstream path and query.
+    pub path: String,
+    /// JSON body, or the body as a string when it is not JSON.
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Milliseconds from sending the request to the response headers.
+    pub wait_ms: u64,
+    pub chunks: Vec<Chunk>,
+    /// Error that ended the body early, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A piece of the response body as it arrived.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Milliseconds after the response headers.
+    pub at_ms: u64,
+    /// The chunk, when it is valid UTF-8 (SSE and JSON bodies).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The chunk in base64 otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+}
+
+impl Chunk {
+    pub fn new(at_ms: u64, bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self {
+                at_ms,
+                text: Some(text.to_string()),
+                base64: None,
+            },
+            Err(_) => Self {
+                at_ms,
+                text: None,
+                base64: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+            },
+        }
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        match (&self.text, &self.base64) {
+            (Some(text), _) => text.as_bytes().to_vec(),
+            (None, Some(encoded)) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .unwrap_or_default(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+/// Request body as stored in a recording.
+pub fn request_body(bytes: &[u8]) -> Value {
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// Key of a request under `mode`; [`MatchMode::Strict`] keys name the
+/// recording files.
+pub fn request_key(mode: MatchMode, method: &str, path: &str, body: &Value) -> String {
+    let mut body = body.clone();
+    if let Some(object) = body.as_object_mut() {
+        object.remove("metadata");
+    }
+    let normalized = match mode {
+        MatchMode::Strict => json!([method, path, body]),
+        MatchMode::Fuzzy => {
+            strip_volatile(&mut body);
+            let path = path.split('?').next().unwrap_or(path);
+            match body.get("messages").and_then(Value::as_array) {
+                Some(messages) => json!([
+                    method,
+                    path,
+                    body.get("model"),
+                    messages.len(),
+                    messages.last(),
+                ]),
+                None => json!([method, path, body]),
+            }
+        }
+    };
+    // serde_json orders object keys, so equal requests serialize alike.
+    let digest = Sha256::digest(normalized.to_string().as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Drop fields that change between otherwise equal turns.
+fn strip_volatile(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove("cache_control");
+            map.remove("signature");
+            map.values_mut().for_each(strip_volatile);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_volatile),
+        _ => {}
+    }
+}
+
+/// Writes upstream exchanges to a recording directory.
+pub struct Recorder {
+    dir: PathBuf,
+    sender: SyncSender<(PathBuf, Exchange)>,
+}
+
+impl Recorder {
+    /// Record into `dir`, creating it.
+    pub fn new(dir: PathBuf) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let (sender, receiver) = sync_channel(RECORDING_CHANNEL_SIZE);
+        std::thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || writer_loop(receiver))?;
+        Ok(Self { dir, sender })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Pass `response` through, recording it once its body ends (or is
+    /// dropped). `wait` is the time from sending the request to the
+    /// response headers.
+    pub fn record(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+        wait: Duration,
+        response: reqwest::Response,
+    ) -> reqwest::Response {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let request = RecordedRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: request_body(body),
+        };
+        let key = request_key(MatchMode::Strict, &request.method, &request.path, &request.body);
+        let exchange = Exchange {
+            recorded_at_ms: unix_millis(SystemTime::now().checked_sub(wait).unwrap_or(UNIX_EPOCH)),
+            request,
+            response: RecordedResponse {
+                status: status.as_u16(),
+                headers: headers
+                    .iter()
+                    .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+                    .map(|(name, value)| {
+                        (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned())
+                    })
+                    .collect(),
+                wait_ms: wait.as_millis() as u64,
+                chunks: Vec::new(),
+                error: None,
+            },
+        };
+        let stream = RecordingStream {
+            inner: Box::pin(response.bytes_stream()),
+            started: Instant::now(),
+            pending: Some((self.dir.join(format!("{}.jsonl", key)), exchange)),
+            sender: self.sender.clone(),
+        };
+        build_response(status, headers, reqwest::Body::wrap_stream(stream))
+    }
+}
+
+/// Append exchanges until every sender is gone.
+fn writer_loop(receiver: Receiver<(PathBuf, Exchange)>) {
+    while let Ok((path, exchange)) = receiver.recv() {
+        if let Err(e) = append(&path, &exchange) {
+            crate::metrics::app_log_error(
+                "recording",
+                &format!("Failed to write {}", path.display()),
+                &e.to_string(),
+            );
+        }
+    }
+}
+
+fn append(path: &Path, exchange: &Exchange) -> io::Result<()> {
+    let mut line = serde_json::to_string(exchange).map_err(io::Error::other)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Upstream body that notes each chunk for the recording.
+struct RecordingStream {
+    inner: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    started: Instant,
+    /// Recording file and exchange, until sent to the writer.
+    pending: Option<(PathBuf, Exchange)>,
+    sender: SyncSender<(PathBuf, Exchange)>,
+}
+
+impl RecordingStream {
+    fn finish(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            // Dropped rather than blocking the response if the writer is
+            // far behind, but never silently.
+            if let Err(e) = self.sender.try_send(pending) {
+                let (reason, (_, exchange)) = match e {
+                    TrySendError::Full(pending) => ("recording writer is behind", pending),
+                    TrySendError::Disconnected(pending) => ("recording writer has stopped", pending),
+                };
+                crate::metrics::app_log_error(
+                    "recording",
+                    &format!(
+                        "Exchange not recorded: {} {}",
+                        exchange.request.method, exchange.request.path
+                    ),
+                    reason,
+                );
+            }
+        }
+    }
+}
+
+impl Stream for RecordingStream {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let item = match this.inner.as_mut().poll_next(cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => return Poll::Pending,
+        };
+        match &item {
+            Some(Ok(bytes)) => {
+                let at_ms = this.started.elapsed().as_millis() as u64;
+                if let Some((_, exchange)) = &mut this.pending {
+                    exchange.response.chunks.push(Chunk::new(at_ms, bytes));
+                }
+            }
+            Some(Err(err)) => {
+                if let Some((_, exchange)) = &mut this.pending {
+                    exchange.response.error = Some(err.to_string());
+                }
+                this.finish();
+            }
+            None => this.finish(),
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for RecordingStream {
+    /// Keep what arrived when the body is not read to the end, e.g. after
+    /// `message_stop` or a client disconnect.
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Serves recorded exchanges from a recording directory.
+pub struct Replayer {
+    dir: PathBuf,
+    mode: MatchMode,
+    /// Exchanges not yet served, by key, in recorded order.
+    exchanges: Mutex<HashMap<String, VecDeque<Exchange>>>,
+}
+
+impl Replayer {
+    /// Load every recording in `dir`. Lines that do not parse are skipped.
+    pub fn load(dir: PathBuf, mode: MatchMode) -> io::Result<Self> {
+        let mut all = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                let text = std::fs::read_to_string(&path)?;
+                all.extend(
+                    text.lines()
+                        .filter_map(|line| serde_json::from_str::<Exchange>(line).ok()),
+                );
+            }
+        }
+        all.sort_by_key(|exchange| exchange.recorded_at_ms);
+
+        let mut exchanges: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+        for exchange in all {
+            let request = &exchange.request;
+            let key = request_key(mode, &request.method, &request.path, &request.body);
+            exchanges.entry(key).or_default().push_back(exchange);
+        }
+        Ok(Self {
+            dir,
+            mode,
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Recorded exchanges not yet served.
+    pub fn len(&self) -> usize {
+        self.exchanges.lock().values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The recorded response to a request, after its recorded wait; the
+    /// body follows its recorded chunk timing.
+    pub async fn respond(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+    ) -> Result<reqwest::Response, ProxyError> {
+        let key = request_key(self.mode, method.as_str(), path, &request_body(body));
+        let exchange = {
+            let mut exchanges = self.exchanges.lock();
+            let queue = exchanges.get_mut(&key).filter(|queue| !queue.is_empty());
+            match queue {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        let Some(exchange) = exchange else {
+            return Err(ProxyError::ReplayMiss {
+                method: method.to_string(),
+                path: path.to_string(),
+                key,
+                dir: self.dir.display().to_string(),
+            });
+        };
+
+        let response = exchange.response;
+        tokio::time::sleep(Duration::from_millis(response.wait_ms)).await;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::BAD_GATEWAY);
+        let stream = ReplayStream {
+            chunks: response
+                .chunks
+                .iter()
+                .map(|chunk| (Duration::from_millis(chunk.at_ms), Bytes::from(chunk.bytes())))
+                .collect(),
+            error: response.error,
+            started: Instant::now(),
+            sleep: None,
+        };
+        Ok(build_response(status, headers, reqwest::Body::wrap_stream(stream)))
+    }
+}
+
+/// Recorded body, released chunk by chunk at its recorded offsets.
+struct ReplayStream {
+    chunks: VecDeque<(Duration, Bytes)>,
+    error: Option<String>,
+    started: Instant,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Stream for ReplayStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let Some((at, _)) = this.chunks.front() else {
+            return Poll::Ready(this.error.take().map(|e| Err(io::Error::other(e))));
+        };
+        let due = this.started + *at;
+        if Instant::now() < due {
+            let sleep = this
+                .sleep
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(due)));
+            sleep.as_mut().reset(due);
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(this.chunks.pop_front().map(|(_, bytes)| Ok(bytes)))
+    }
+}
+
+fn build_response(status: StatusCode, headers: HeaderMap, body: reqwest::Body) -> reqwest::Response {
+    let mut response = axum::http::Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    reqwest::Response::from(response)
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
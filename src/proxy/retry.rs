@@ -0,0 +1,123 @@
+//! Retry timing for upstream requests.
+//!
+//! - **Backoff**: exponential from the configured base, with "equal jitter"
+//!   (half fixed, half random) so concurrent requests don't retry in lockstep
+//! - **Server hints**: how long a rate-limited or overloaded backend asks
+//!   us to wait, from `retry-after-ms`, `retry-after` (seconds or HTTP date)
+//!   or the `anthropic-ratelimit-*-reset` timestamps of exhausted limits
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::HeaderMap;
+
+use crate::utc::days_from_civil;
+
+/// Rate limits reported by Anthropic, each with `-remaining` and `-reset`.
+const RATELIMIT_KINDS: [&str; 4] = ["requests", "tokens", "input-tokens", "output-tokens"];
+
+/// Jittered delay before retry number `attempt` (0-based).
+pub fn backoff(base: Duration, attempt: u32) -> Duration {
+    let full = base.saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+    let half = full / 2;
+    let jitter_ms = half.as_millis() as u64;
+    let jitter = if jitter_ms == 0 {
+        0
+    } else {
+        (uuid::Uuid::new_v4().as_u128() % u128::from(jitter_ms + 1)) as u64
+    };
+    half + Duration::from_millis(jitter)
+}
+
+/// Wait requested by the upstream response headers, if any.
+///
+/// `retry-after-ms` and `retry-after` take precedence. Otherwise the latest
+/// reset time among rate limits with nothing remaining is used. Values too
+/// large to represent saturate; callers clamp them to `[retry]
+/// max_wait_seconds`.
+pub fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| parse_wait(v, 1)) {
+        return Some(ms);
+    }
+    if let Some(value) = header("retry-after") {
+        if let Some(secs) = parse_wait(value, 1_000) {
+            return Some(secs);
+        }
+        if let Some(at) = parse_http_date(value) {
+            return Some(at.duration_since(now).unwrap_or_default());
+        }
+    }
+
+    RATELIMIT_KINDS
+        .iter()
+        .filter(|kind| header(&format!("anthropic-ratelimit-{}-remaining", kind)) == Some("0"))
+        .filter_map(|kind| header(&format!("anthropic-ratelimit-{}-reset", kind)))
+        .filter_map(parse_rfc3339)
+        .max()
+        .map(|at| at.duration_since(now).unwrap_or_default())
+}
+
+/// A non-negative count of `unit_ms` milliseconds, e.g. `3` or `2.5`.
+fn parse_wait(value: &str, unit_ms: u64) -> Option<Duration> {
+    if let Ok(count) = value.parse::<u64>() {
+        return Some(Duration::from_millis(count.saturating_mul(unit_ms)));
+    }
+    let count = value.parse::<f64>().ok().filter(|count| !count.is_nan())?;
+    let secs = count.max(0.0) * unit_ms as f64 / 1000.0;
+    Some(Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
+}
+
+/// Whether any Anthropic rate limit reports nothing remaining.
+pub fn rate_limit_exhausted(headers: &HeaderMap) -> bool {
+    RATELIMIT_KINDS.iter().any(|kind| {
+        headers
+            .get(format!("anthropic-ratelimit-{}-remaining", kind))
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim() == "0")
+    })
+}
+
+/// `2026-01-01T00:00:30Z`, optionally with fractional seconds or an offset.
+fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    let (date, time) = value.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let split = time.rfind(['+', '-'])?;
+        let (time, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (h, m) = offset[1..].split_once(':')?;
+        (time, sign * (h.parse::<i64>().ok()? * 3_600 + m.parse::<i64>().ok()? * 60))
+    };
+    let mut hms = time.splitn(3, ':');
+    let (h, m) = (hms.next()?.parse::<i64>().ok()?, hms.next()?.parse::<i64>().ok()?);
+    let secs = hms.next()?.parse::<f64>().ok()?;
+
+    let whole = days_from_civil(year, month, day) * 86_400 + h * 3_600 + m * 60 - offset_secs;
+    to_system_time(whole as f64 + secs)
+}
+
+/// IMF-fixdate, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let month = MONTHS.iter().position(|m| m == month)? as i64 + 1;
+    let mut hms = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (h, m, s) = (hms.next()??, hms.next()??, hms.next()??);
+
+    let days = days_from_civil(year.parse().ok()?, month, day.parse().ok()?);
+    to_system_time((days * 86_400 + h * 3_600 + m * 60 + s) as f64)
+}
+
+fn to_system_time(secs: f64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(secs).ok()?)
+}
@@ -11,12 +11,16 @@ use uuid::Uuid;
 
 use crate::backend::{BackendState, AgentBackendState, AgentRegistry};
 use crate::config::DebugLogLevel;
+use crate::error::ErrorRegistry;
 use crate::proxy::error::ErrorResponse;
 use crate::proxy::hooks::HookState;
 use crate::metrics::{DebugLogger, ObservabilityHub, RequestMeta};
 use crate::proxy::health::HealthHandler;
 use crate::proxy::pipeline::{PipelineConfig, PipelineContext};
 use crate::proxy::pool::PoolConfig;
+use crate::proxy::prometheus::MetricsHandler;
+use crate::proxy::recording::Recording;
+use crate::proxy::shutdown::ShutdownManager;
 use crate::proxy::thinking::TransformerRegistry;
 use crate::proxy::timeout::TimeoutConfig;
 
@@ -39,6 +43,7 @@ pub struct TeammateMarker;
 #[derive(Clone)]
 pub struct RouterEngine {
     health: Arc<HealthHandler>,
+    metrics: Arc<MetricsHandler>,
     pub(crate) backend_state: BackendState,
     pub(crate) subagent_backend: AgentBackendState,
     pub(crate) teammate_backend: AgentBackendState,
@@ -59,6 +64,7 @@ impl RouterEngine {
         observability: ObservabilityHub,
         debug_logger: Arc<DebugLogger>,
         transformer_registry: Arc<TransformerRegistry>,
+        shutdown: Arc<ShutdownManager>,
         session_token: Option<String>,
     ) -> Self {
         let pipeline_config = PipelineConfig::new(
@@ -70,7 +76,12 @@ impl RouterEngine {
         );
 
         Self {
-            health: Arc::new(HealthHandler::new()),
+            health: Arc::new(HealthHandler::new().with_backend_state(backend_state.clone())),
+            metrics: Arc::new(MetricsHandler::new(
+                observability.clone(),
+                transformer_registry.clone(),
+                shutdown,
+            )),
             backend_state,
             subagent_backend,
             teammate_backend,
@@ -80,6 +91,16 @@ impl RouterEngine {
             session_token,
         }
     }
+
+    /// Registry the pipeline reports upstream retries to.
+    pub fn error_registry(&self) -> ErrorRegistry {
+        self.pipeline_config.error_registry.clone()
+    }
+
+    /// Record or replay upstream exchanges in the forward stage.
+    pub fn set_recording(&mut self, recording: Recording) {
+        self.pipeline_config.recording = recording;
+    }
 }
 
 /// Auth middleware — validates session token for proxy requests.
@@ -134,6 +155,7 @@ pub fn build_router(
 
     let mut router = Router::new()
         .route("/health", get(health_handler))
+        .route("/metrics", get(metrics_handler))
         .with_state(engine.clone())
         .merge(hook_routes);
 
@@ -164,6 +186,10 @@ async fn health_handler(
     state.health.handle().await
 }
 
+async fn metrics_handler(State(state): State<RouterEngine>) -> Response {
+    state.metrics.handle().await
+}
+
 async fn proxy_handler(
     State(state): State<RouterEngine>,
     RawQuery(query): RawQuery,
@@ -183,6 +209,7 @@ async fn proxy_handler(
     // the URI before forwarding. If the first segment is not a registered
     // agent_id, it is left in place (graceful fallback).
     let is_teammate = req.extensions().get::<TeammateMarker>().is_some();
+    let mut agent_id = None;
     let teammate_backend = if is_teammate {
         // Extract candidate agent_id from first path segment: /{agent_id}/v1/messages
         let path = req.uri().path();
@@ -211,6 +238,7 @@ async fn proxy_handler(
         // Registry lookup determines backend; fallback to teammate backend.
         let resolved = candidate.as_ref()
             .and_then(|id| state.pipeline_config.agent_registry.lookup(id));
+        agent_id = candidate.clone();
 
         if let Some(backend) = resolved {
             Some(backend)
@@ -247,6 +275,7 @@ async fn proxy_handler(
             },
             headers: None,
             body_preview: None,
+            body_truncated: false,
         });
     }
 
@@ -259,6 +288,7 @@ async fn proxy_handler(
         state.observability.clone(),
         state.debug_logger.clone(),
     );
+    pipeline_ctx.agent_id = agent_id;
 
     match execute_pipeline(req, &pipeline_config, &mut pipeline_ctx, backend_override, start.backend_override).await {
         Ok(resp) => resp,
@@ -6,14 +6,20 @@ use std::time::Duration;
 use tokio::net::TcpListener;
 
 use crate::backend::{BackendState, AgentBackendState, AgentRegistry};
-use crate::config::ConfigStore;
+use crate::config::{expand_tilde, ConfigStore};
+use crate::error::ErrorRegistry;
+use crate::metrics::ledger::read_ledger;
 use crate::metrics::{DebugLogger, ObservabilityHub};
 use crate::proxy::connection::ConnectionCounter;
+use crate::proxy::otlp::TraceExporter;
 use crate::proxy::pool::PoolConfig;
+use crate::proxy::prober::HealthProber;
+use crate::proxy::recording::Recording;
 use crate::proxy::router::{build_router, RouterEngine};
 use crate::proxy::shutdown::ShutdownManager;
 use crate::proxy::thinking::TransformerRegistry;
 use crate::proxy::timeout::TimeoutConfig;
+use crate::proxy::usage_ledger::UsageLedger;
 
 pub struct ProxyServer {
     pub addr: SocketAddr,
@@ -28,6 +34,8 @@ pub struct ProxyServer {
     observability: ObservabilityHub,
     debug_logger: Arc<DebugLogger>,
     transformer_registry: Arc<TransformerRegistry>,
+    trace_exporter: TraceExporter,
+    recording: Recording,
 }
 
 impl ProxyServer {
@@ -52,9 +60,17 @@ impl ProxyServer {
         let teammate_backend = AgentBackendState::new(teammate_initial);
         let agent_registry = AgentRegistry::new();
 
-        let observability = ObservabilityHub::new(1000)
-            .with_plugins(vec![debug_logger.clone()]);
+        let trace_exporter = TraceExporter::new(backend_state.clone());
+        let observability = ObservabilityHub::new(1000).with_plugins(vec![
+            debug_logger.clone(),
+            Arc::new(backend_state.circuit_breakers()),
+            Arc::new(backend_state.group_balancer()),
+            Arc::new(trace_exporter.clone()),
+            Arc::new(UsageLedger::new(backend_state.clone())),
+            Arc::new(backend_state.budgets()),
+        ]);
         let transformer_registry = Arc::new(TransformerRegistry::new());
+        let shutdown = Arc::new(ShutdownManager::new());
         let router = RouterEngine::new(
             timeout_config,
             pool_config,
@@ -65,22 +81,61 @@ impl ProxyServer {
             observability.clone(),
             debug_logger.clone(),
             transformer_registry.clone(),
+            shutdown.clone(),
             session_token,
         );
+        backend_state.budgets().set_error_registry(router.error_registry());
+        if cfg.budgets.is_enabled() && cfg.usage_ledger.enabled {
+            // Carry today's and each session's spend across restarts.
+            match read_ledger(&expand_tilde(&cfg.usage_ledger.path)) {
+                Ok(entries) => backend_state.budgets().seed(&entries),
+                Err(e) => crate::metrics::app_log_error(
+                    "budget",
+                    "Failed to read usage ledger",
+                    &e.to_string(),
+                ),
+            }
+        }
         Ok(Self {
             addr: SocketAddr::from(([127, 0, 0, 1], 0)),
             listener: None,
             router,
-            shutdown: Arc::new(ShutdownManager::new()),
+            shutdown,
             backend_state,
             subagent_backend,
             teammate_backend,
             observability,
             debug_logger,
             transformer_registry,
+            trace_exporter,
+            recording: Recording::Off,
         })
     }
 
+    /// Record upstream exchanges (`--record`) or serve recorded ones
+    /// instead of contacting backends (`--replay`).
+    pub fn with_recording(mut self, recording: Recording) -> Self {
+        match &recording {
+            Recording::Off => {}
+            Recording::Record(recorder) => crate::metrics::app_log(
+                "proxy",
+                &format!("Recording upstream exchanges to {}", recorder.dir().display()),
+            ),
+            Recording::Replay(replayer) => crate::metrics::app_log(
+                "proxy",
+                &format!(
+                    "Replaying {} recorded exchanges from {}",
+                    replayer.len(),
+                    replayer.dir().display()
+                ),
+            ),
+        }
+        self.backend_state.set_replay(recording.is_replay());
+        self.router.set_recording(recording.clone());
+        self.recording = recording;
+        self
+    }
+
     /// Try to bind to the configured address, falling back to incremental ports if busy.
     /// Returns the bound address and the base URL for Claude Code.
     ///
@@ -156,6 +211,11 @@ impl ProxyServer {
         self.transformer_registry.clone()
     }
 
+    /// Registry the proxy reports upstream retries to, for the UI to share.
+    pub fn error_registry(&self) -> ErrorRegistry {
+        self.router.error_registry()
+    }
+
     pub fn handle(&self) -> ProxyHandle {
         ProxyHandle {
             shutdown: self.shutdown.clone(),
@@ -172,6 +232,12 @@ impl ProxyServer {
 
         crate::metrics::app_log("proxy", &format!("Starting proxy server on {}", self.addr));
 
+        // Replay never contacts backends, health probes included.
+        if !self.recording.is_replay() {
+            tokio::spawn(HealthProber::new(self.backend_state.clone()).run(self.shutdown.clone()));
+        }
+        tokio::spawn(self.trace_exporter.clone().run(self.shutdown.clone()));
+
         let app = build_router(self.router.clone());
         let make_service = app.into_make_service();
         let make_service = ConnectionCounter::new(make_service, self.shutdown.clone());
@@ -66,6 +66,11 @@ impl ShutdownManager {
         self.active_connections.fetch_sub(1, Ordering::SeqCst);
     }
 
+    /// Connections currently open to the proxy.
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::SeqCst)
+    }
+
     pub async fn wait_for_connections(&self, timeout: Duration) {
         let active = self.active_connections.load(Ordering::SeqCst);
         app_log("proxy-shutdown", &format!("Waiting for {} active connections...", active));
@@ -0,0 +1,181 @@
+//! AWS Signature Version 4 request signing.
+//!
+//! Used for `auth_type = "bedrock"` backends. The signature covers the
+//! method, path, query, `host`, the `x-amz-*` headers and any extra headers
+//! passed in, and the SHA-256 of the body, so it must be computed on the
+//! final upstream request (and again on every retry, as it is time-bound).
+
+use std::time::SystemTime;
+
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+use crate::config::AwsCredentials;
+use crate::utc::UtcTime;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Who signs, for which region and service, and when.
+pub struct SigningParams<'a> {
+    pub credentials: &'a AwsCredentials,
+    pub region: &'a str,
+    pub service: &'a str,
+    pub time: SystemTime,
+}
+
+/// Sign a request and return the headers to add to it: `x-amz-date`,
+/// `x-amz-security-token` (with a session token) and `authorization`.
+///
+/// `headers` are extra headers covered by the signature, e.g.
+/// `content-type`; `host` is always signed and taken from `url`.
+pub fn sign(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    body: &[u8],
+    params: &SigningParams<'_>,
+) -> Vec<(String, String)> {
+    let (date, amz_date) = timestamps(params.time);
+
+    let mut added = vec![("x-amz-date".to_string(), amz_date.clone())];
+    if let Some(token) = &params.credentials.session_token {
+        added.push(("x-amz-security-token".to_string(), token.expose().to_string()));
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+    let mut signed: Vec<(String, String)> = headers
+        .iter()
+        .chain(&added)
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .chain(std::iter::once(("host".to_string(), host)))
+        .collect();
+    signed.sort();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        canonical_uri(url.path()),
+        canonical_query(url.query().unwrap_or("")),
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(body)),
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, params.region, params.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    let secret = format!("AWS4{}", params.credentials.secret_access_key.expose());
+    let signing_key = [date.as_str(), params.region, params.service, "aws4_request"]
+        .iter()
+        .fold(secret.into_bytes(), |key, part| hmac(&key, part.as_bytes()));
+    let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+
+    added.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            params.credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    added
+}
+
+/// Path with every segment URI-encoded again, as required for all services
+/// except S3.
+fn canonical_uri(path: &str) -> String {
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+/// Query parameters sorted by name, each name and value encoded once.
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (uri_encode(&percent_decode(name)), uri_encode(&percent_decode(value)))
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters.
+pub(crate) fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex_byte = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex_byte {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(if bytes[i] == b'+' { b' ' } else { bytes[i] });
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `(YYYYMMDD, YYYYMMDDTHHMMSSZ)` in UTC.
+fn timestamps(time: SystemTime) -> (String, String) {
+    let t = UtcTime::from_system_time(time);
+    let date = format!("{:04}{:02}{:02}", t.year, t.month, t.day);
+    let amz_date = format!("{}T{:02}{:02}{:02}Z", date, t.hour, t.minute, t.second);
+    (date, amz_date)
+}
@@ -0,0 +1,94 @@
+//! Appends finished requests to the usage ledger (`[usage_ledger]`).
+//!
+//! Entries are written by a background thread so file I/O never runs on
+//! the request path.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+
+use crate::backend::BackendState;
+use crate::config::expand_tilde;
+use crate::metrics::ledger::LedgerEntry;
+use crate::metrics::{ObservabilityPlugin, PostResponseContext};
+
+const LEDGER_CHANNEL_SIZE: usize = 512;
+
+/// Observability plugin recording every finished request in the ledger.
+pub struct UsageLedger {
+    backend_state: BackendState,
+    sender: SyncSender<(PathBuf, LedgerEntry)>,
+}
+
+impl UsageLedger {
+    pub fn new(backend_state: BackendState) -> Self {
+        let (sender, receiver) = sync_channel(LEDGER_CHANNEL_SIZE);
+        std::thread::Builder::new()
+            .name("usage-ledger".to_string())
+            .spawn(move || writer_loop(receiver))
+            .expect("Failed to spawn usage ledger thread");
+        Self {
+            backend_state,
+            sender,
+        }
+    }
+}
+
+impl ObservabilityPlugin for UsageLedger {
+    fn post_response(&self, ctx: &mut PostResponseContext<'_>) {
+        let config = self.backend_state.usage_ledger_config();
+        if !config.enabled {
+            return;
+        }
+        // Blocks only if the writer is 512 entries behind; entries are
+        // billing records, so they are not dropped.
+        let _ = self
+            .sender
+            .send((expand_tilde(&config.path), LedgerEntry::from_record(ctx.record)));
+    }
+}
+
+/// Append entries until every sender is gone, reopening the file when the
+/// configured path changes.
+fn writer_loop(receiver: Receiver<(PathBuf, LedgerEntry)>) {
+    let mut file: Option<(PathBuf, File)> = None;
+    while let Ok((path, entry)) = receiver.recv() {
+        if file.as_ref().is_none_or(|(open, _)| *open != path) {
+            file = open(&path).map(|f| (path.clone(), f));
+        }
+        let Some((_, out)) = &mut file else {
+            continue;
+        };
+        let Ok(mut line) = serde_json::to_string(&entry) else {
+            continue;
+        };
+        line.push('\n');
+        if let Err(e) = out.write_all(line.as_bytes()) {
+            crate::metrics::app_log_error(
+                "usage_ledger",
+                &format!("Failed to write {}", path.display()),
+                &e.to_string(),
+            );
+            file = None;
+        }
+    }
+}
+
+fn open(path: &Path) -> Option<File> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .inspect_err(|e| {
+            crate::metrics::app_log_error(
+                "usage_ledger",
+                &format!("Failed to open {}", path.display()),
+                &e.to_string(),
+            )
+        })
+        .ok()
+}
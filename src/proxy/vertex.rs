@@ -0,0 +1,37 @@
+//! Google Vertex AI hosting of Anthropic models.
+//!
+//! Backends with `auth_type = "vertex"` receive `/v1/messages` requests as
+//! `rawPredict` (or `streamRawPredict`) calls on the Anthropic publisher
+//! model. Responses are plain Anthropic JSON and SSE, so only the path and
+//! body change:
+//!
+//! - **Path**: `/v1/projects/{project}/locations/{region}/publishers/anthropic/models/{model}:streamRawPredict`
+//! - **Body**: the Anthropic body without `model`, plus `anthropic_version`
+//! - **Auth**: `Authorization: Bearer` with the token printed by `token_command`
+
+use serde_json::{json, Value};
+
+/// `anthropic_version` body field expected by Vertex AI.
+pub const ANTHROPIC_VERSION: &str = "vertex-2023-10-16";
+
+/// Location used when `vertex_region` is not set.
+pub const DEFAULT_REGION: &str = "global";
+
+/// Request path for `model` in `project` / `region`.
+pub fn request_path(project: &str, region: &str, model: &str, streaming: bool) -> String {
+    let method = if streaming { "streamRawPredict" } else { "rawPredict" };
+    format!(
+        "/v1/projects/{}/locations/{}/publishers/anthropic/models/{}:{}",
+        project, region, model, method
+    )
+}
+
+/// Anthropic Messages body → `rawPredict` body.
+pub fn to_raw_predict_body(body: &Value) -> Value {
+    let mut body = body.clone();
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("model");
+        obj.entry("anthropic_version").or_insert_with(|| json!(ANTHROPIC_VERSION));
+    }
+    body
+}
@@ -1,9 +1,11 @@
-use crate::config::{ClaudeSettingsManager, ConfigStore};
-use crate::error::ErrorRegistry;
+use crate::config::{ClaudeSettingsManager, ConfigStore, HeaderSegment};
+use crate::error::{ErrorCategory, ErrorRegistry, ErrorSeverity};
 use crate::ipc::{BackendInfo, ProxyStatus};
+use crate::metrics::har::HarExport;
 use crate::metrics::MetricsSnapshot;
 use crate::pty::PtyHandle;
 use crate::ui::history::{HistoryDialogState, HistoryEntry, HistoryIntent, HistoryReducer};
+use crate::ui::inspector::{curl_command, InspectorDialogState, InspectorIntent, InspectorReducer};
 use crate::ui::mvi::Reducer;
 use crate::ui::pty::{PtyIntent, PtyLifecycleState, PtyReducer};
 use crate::ui::selection::{GridPos, TextSelection};
@@ -20,6 +22,7 @@ pub enum PopupKind {
     Status,
     History,
     Settings,
+    Inspector,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
@@ -45,6 +48,8 @@ pub enum UiCommand {
     RefreshStatus,
     RefreshMetrics { backend_id: Option<String> },
     RefreshBackends,
+    /// Write the proxy's request ring as a HAR file.
+    ExportHar,
     ReloadConfig,
     RestartPty {
         env_vars: Vec<(String, String)>,
@@ -87,6 +92,10 @@ pub struct App {
     history_provider: Option<Arc<dyn Fn() -> Vec<HistoryEntry> + Send + Sync>>,
     /// State of the settings dialog (MVI pattern).
     settings_dialog: SettingsDialogState,
+    /// State of the request inspector (MVI pattern).
+    inspector_dialog: InspectorDialogState,
+    /// URL Claude Code reaches the proxy at, for "copy as curl".
+    proxy_base_url: String,
     /// Claude Code settings manager (registry + current values).
     settings_manager: ClaudeSettingsManager,
     /// Snapshot of values when settings dialog was opened (for dirty check).
@@ -106,6 +115,8 @@ pub struct App {
     teammate_selection: usize,
     /// Current teammate backend (runtime state, from config on start).
     teammate_backend: Option<String>,
+    /// Header bar segments from `[ui.header]`.
+    header_segments: Vec<HeaderSegment>,
 }
 
 impl App {
@@ -124,6 +135,8 @@ impl App {
         let teammate_backend = config.get().agents
             .as_ref()
             .map(|at| at.teammate_backend.clone());
+        let header_segments = config.get().ui.header.segments;
+        let proxy_base_url = config.get().proxy.base_url;
 
         Self {
             should_quit: false,
@@ -145,6 +158,8 @@ impl App {
             history_dialog: HistoryDialogState::default(),
             history_provider: None,
             settings_dialog: SettingsDialogState::default(),
+            inspector_dialog: InspectorDialogState::default(),
+            proxy_base_url,
             settings_manager,
             settings_saved_snapshot,
             pty_generation: 0,
@@ -154,6 +169,7 @@ impl App {
             subagent_backend,
             teammate_selection: 0,
             teammate_backend,
+            header_segments,
         }
     }
 
@@ -162,6 +178,11 @@ impl App {
         &self.error_registry
     }
 
+    /// Share an error registry with another component (e.g. the proxy).
+    pub fn set_error_registry(&mut self, error_registry: ErrorRegistry) {
+        self.error_registry = error_registry;
+    }
+
     pub fn should_quit(&self) -> bool {
         self.should_quit
     }
@@ -410,6 +431,11 @@ impl App {
     }
 
     pub fn update_metrics(&mut self, metrics: MetricsSnapshot) {
+        if self.inspector_dialog.is_visible() {
+            self.dispatch_inspector(InspectorIntent::Refresh {
+                records: metrics.recent.clone(),
+            });
+        }
         self.metrics = Some(metrics);
     }
 
@@ -443,6 +469,10 @@ impl App {
         self.send_command(UiCommand::RefreshBackends);
     }
 
+    pub fn request_har_export(&mut self) {
+        self.send_command(UiCommand::ExportHar);
+    }
+
     pub fn request_config_reload(&mut self) {
         self.send_command(UiCommand::ReloadConfig);
     }
@@ -516,9 +546,25 @@ impl App {
     /// The new config is already available via `self.config.get()`.
     /// This method can update any cached state derived from config.
     pub fn on_config_reload(&mut self) {
-        // Currently just a notification point.
-        // Future: update cached backend list, theme, etc.
-        let _config = self.config.get();
+        self.header_segments = self.config.get().ui.header.segments;
+    }
+
+    pub fn header_segments(&self) -> &[HeaderSegment] {
+        &self.header_segments
+    }
+
+    /// Whether the header shows segments fed by the metrics snapshot, which
+    /// then has to be polled while no popup needs it.
+    pub fn header_uses_metrics(&self) -> bool {
+        self.header_segments.iter().any(|segment| {
+            matches!(
+                segment,
+                HeaderSegment::Cost
+                    | HeaderSegment::Tokens
+                    | HeaderSegment::InFlight
+                    | HeaderSegment::Errors
+            )
+        })
     }
 
     // ========================================================================
@@ -569,6 +615,71 @@ impl App {
         self.focus = Focus::Terminal;
     }
 
+    // ========================================================================
+    // Request inspector methods (MVI pattern)
+    // ========================================================================
+
+    /// Get the current request inspector state.
+    pub fn inspector_dialog(&self) -> &InspectorDialogState {
+        &self.inspector_dialog
+    }
+
+    /// Dispatch an intent to the request inspector reducer.
+    pub fn dispatch_inspector(&mut self, intent: InspectorIntent) {
+        dispatch_mvi!(self, inspector_dialog, InspectorReducer, intent);
+    }
+
+    /// Open the request inspector with the last metrics snapshot.
+    pub fn open_inspector_dialog(&mut self) {
+        let records = self
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.recent.clone())
+            .unwrap_or_default();
+        self.dispatch_inspector(InspectorIntent::Load { records });
+        self.focus = Focus::Popup(PopupKind::Inspector);
+        self.request_metrics_refresh(None);
+    }
+
+    /// Close the request inspector.
+    pub fn close_inspector_dialog(&mut self) {
+        self.dispatch_inspector(InspectorIntent::Close);
+        self.focus = Focus::Terminal;
+    }
+
+    /// Report a finished HAR export in the inspector footer; failures also
+    /// go to the error registry.
+    pub fn on_har_exported(&mut self, result: Result<HarExport, String>) {
+        let message = match result {
+            Ok(export) => format!("Exported {} requests to {}", export.entries, export.path.display()),
+            Err(err) => {
+                self.error_registry
+                    .record(ErrorSeverity::Warning, ErrorCategory::Process, &err);
+                err
+            }
+        };
+        self.dispatch_inspector(InspectorIntent::ShowNotice { message });
+    }
+
+    /// Set the URL the proxy is actually bound to (called from runtime).
+    pub fn set_proxy_base_url(&mut self, base_url: String) {
+        self.proxy_base_url = base_url;
+    }
+
+    /// `curl` command for the selected request, or the one in the open
+    /// detail pane.
+    pub fn inspector_curl(&self) -> Option<String> {
+        let record = match &self.inspector_dialog {
+            InspectorDialogState::Visible {
+                records,
+                detail: Some(pane),
+                ..
+            } => records.iter().find(|record| record.id == pane.request_id),
+            state => state.selected_record(),
+        }?;
+        Some(curl_command(record, &self.proxy_base_url))
+    }
+
     // ========================================================================
     // Settings dialog methods (MVI pattern)
     // ========================================================================
@@ -6,6 +6,7 @@ use std::time::{Duration, Instant};
 use term_input::{InputEvent, TtyReader};
 
 use crate::ipc::{BackendInfo, ProxyStatus};
+use crate::metrics::har::HarExport;
 use crate::metrics::MetricsSnapshot;
 use crate::shutdown::ShutdownHandle;
 
@@ -60,6 +61,8 @@ pub enum AppEvent {
     IpcMetrics(MetricsSnapshot),
     IpcBackends(Vec<BackendInfo>),
     IpcError(String),
+    /// HAR export finished: the written file, or why it failed.
+    HarExported(Result<HarExport, String>),
     /// PTY error occurred
     PtyError(PtyError),
     /// OS signal received (SIGTERM, SIGINT)
@@ -1,11 +1,21 @@
+use std::time::SystemTime;
+
+use crate::config::{HeaderConfig, HeaderSegment};
 use crate::error::{ErrorRegistry, ErrorSeverity};
 use crate::ipc::ProxyStatus;
+use crate::metrics::MetricsSnapshot;
 use crate::ui::theme::{GLOBAL_BORDER, HEADER_TEXT, STATUS_ERROR, STATUS_OK, STATUS_WARNING};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
-pub struct Header;
+/// Header bar: status icon, current error, then the `[ui.header]` segments.
+///
+/// When the line is wider than the terminal, segments switch to their
+/// compact form, then the last ones are dropped until it fits.
+pub struct Header {
+    segments: Vec<HeaderSegment>,
+}
 
 impl Default for Header {
     fn default() -> Self {
@@ -13,16 +23,47 @@ impl Default for Header {
     }
 }
 
+/// Full and compact text of one segment.
+struct SegmentText {
+    full: String,
+    compact: String,
+    style: Style,
+}
+
 impl Header {
     pub fn new() -> Self {
-        Self
+        Self::with_segments(HeaderConfig::default().segments)
+    }
+
+    pub fn with_segments(segments: Vec<HeaderSegment>) -> Self {
+        Self { segments }
     }
 
     pub fn widget(
         &self,
         status: Option<&ProxyStatus>,
         error_registry: &ErrorRegistry,
+        metrics: Option<&MetricsSnapshot>,
+        width: u16,
     ) -> Paragraph<'static> {
+        // Borders take one column on each side.
+        let line = self.line(status, error_registry, metrics, width.saturating_sub(2) as usize);
+
+        Paragraph::new(line).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(GLOBAL_BORDER)),
+        )
+    }
+
+    /// Header content fitted to `width` columns.
+    pub fn line(
+        &self,
+        status: Option<&ProxyStatus>,
+        error_registry: &ErrorRegistry,
+        metrics: Option<&MetricsSnapshot>,
+        width: usize,
+    ) -> Line<'static> {
         let text_style = Style::default().fg(HEADER_TEXT).add_modifier(Modifier::DIM);
 
         // Determine status icon and color based on error registry
@@ -48,15 +89,9 @@ impl Header {
                     None => ("⚪", STATUS_ERROR, None),
                 }
             };
-
-        let backend = status
-            .map(|value| value.active_backend.as_str())
-            .unwrap_or("unknown");
-        let total_requests = status.map(|value| value.total_requests).unwrap_or(0);
-        let uptime = status.map(|value| value.uptime_seconds).unwrap_or(0);
         let status_style = Style::default().fg(status_color);
 
-        let mut spans = vec![
+        let mut prefix = vec![
             Span::styled(" ", text_style),
             Span::styled(icon, status_style),
             Span::styled(" ", text_style),
@@ -65,29 +100,172 @@ impl Header {
         // Show error message in header if present
         if let Some(msg) = error_message {
             // Truncate message if too long
-            let display_msg = if msg.len() > 40 {
-                format!("{}...", &msg[..37])
+            let display_msg = if msg.chars().count() > 40 {
+                format!("{}...", msg.chars().take(37).collect::<String>())
             } else {
                 msg
             };
-            spans.push(Span::styled(display_msg, Style::default().fg(status_color)));
-            spans.push(Span::styled(" │ ", text_style));
+            prefix.push(Span::styled(display_msg, Style::default().fg(status_color)));
         }
 
-        spans.extend([
-            Span::styled(format!("Backend: {backend}"), text_style),
-            Span::styled(" │ ", text_style),
-            Span::styled(format!("Reqs: {total_requests}"), text_style),
-            Span::styled(" │ ", text_style),
-            Span::styled(format!("Uptime: {uptime}s"), text_style),
-        ]);
+        let segments: Vec<SegmentText> = self
+            .segments
+            .iter()
+            .filter_map(|segment| segment_text(*segment, status, metrics, text_style))
+            .collect();
 
-        let line = Line::from(spans);
+        let build = |count: usize, compact: bool| {
+            let mut spans = prefix.clone();
+            for (i, segment) in segments[..count].iter().enumerate() {
+                if i > 0 || spans.len() > 3 {
+                    spans.push(Span::styled(" │ ", text_style));
+                }
+                let text = if compact { &segment.compact } else { &segment.full };
+                spans.push(Span::styled(text.clone(), segment.style));
+            }
+            Line::from(spans)
+        };
 
-        Paragraph::new(line).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(GLOBAL_BORDER)),
-        )
+        let full = build(segments.len(), false);
+        if full.width() <= width {
+            return full;
+        }
+        (0..=segments.len())
+            .rev()
+            .map(|count| build(count, true))
+            .find(|line| line.width() <= width)
+            .unwrap_or_else(|| build(0, true))
+    }
+}
+
+/// Text of `segment`, or `None` when there is nothing to show yet.
+fn segment_text(
+    segment: HeaderSegment,
+    status: Option<&ProxyStatus>,
+    metrics: Option<&MetricsSnapshot>,
+    text_style: Style,
+) -> Option<SegmentText> {
+    let plain = |full: String, compact: String| SegmentText {
+        full,
+        compact,
+        style: text_style,
+    };
+    match segment {
+        HeaderSegment::Backend => {
+            let backend = status
+                .map(|value| value.active_backend.as_str())
+                .unwrap_or("unknown");
+            Some(plain(format!("Backend: {backend}"), backend.to_string()))
+        }
+        HeaderSegment::Requests => {
+            let total_requests = status.map(|value| value.total_requests).unwrap_or(0);
+            Some(plain(format!("Reqs: {total_requests}"), format!("{total_requests} req")))
+        }
+        HeaderSegment::Uptime => {
+            let uptime = format_duration(status.map(|value| value.uptime_seconds).unwrap_or(0));
+            Some(plain(format!("Uptime: {uptime}"), format!("up {uptime}")))
+        }
+        HeaderSegment::Cost => {
+            let cost: f64 = metrics?.per_backend.values().map(|m| m.cost_usd).sum();
+            Some(plain(format!("Cost: ${cost:.2}"), format!("${cost:.2}")))
+        }
+        HeaderSegment::Tokens => {
+            let per_backend = metrics?.per_backend.values();
+            let (mut input, mut output, mut cache) = (0, 0, 0);
+            for m in per_backend {
+                input += m.input_tokens;
+                output += m.output_tokens;
+                cache += m.cache_read_input_tokens + m.cache_creation_input_tokens;
+            }
+            let (input, output) = (format_tokens(input), format_tokens(output));
+            let mut full = format!("Tokens: {input} in / {output} out");
+            if cache > 0 {
+                full.push_str(&format!(" / {} cache", format_tokens(cache)));
+            }
+            Some(plain(full, format!("{input}/{output}")))
+        }
+        HeaderSegment::InFlight => {
+            let in_flight = &metrics?.in_flight;
+            // The oldest stream, else the oldest request still waiting.
+            let current = in_flight
+                .iter()
+                .find(|request| request.streaming)
+                .or(in_flight.first())?;
+            let elapsed = SystemTime::now()
+                .duration_since(current.started_at)
+                .unwrap_or_default()
+                .as_secs();
+            let elapsed = format_duration(elapsed);
+            let (full, compact) = if current.streaming {
+                (
+                    format!(
+                        "↓ {} {} {} tok",
+                        current.backend,
+                        elapsed,
+                        format_tokens(current.output_tokens)
+                    ),
+                    format!("↓ {elapsed}"),
+                )
+            } else {
+                (format!("… {} {}", current.backend, elapsed), format!("… {elapsed}"))
+            };
+            let more = match in_flight.len() {
+                1 => String::new(),
+                n => format!(" +{}", n - 1),
+            };
+            Some(SegmentText {
+                full: full + &more,
+                compact: compact + &more,
+                style: Style::default().fg(HEADER_TEXT),
+            })
+        }
+        HeaderSegment::Errors => {
+            let mut rates: Vec<(&str, u64)> = metrics?
+                .per_backend
+                .iter()
+                .filter(|(_, m)| m.total > 0)
+                .map(|(name, m)| {
+                    let errors = m.total.saturating_sub(m.success_2xx);
+                    (name.as_str(), (errors * 100).div_ceil(m.total))
+                })
+                .collect();
+            if rates.is_empty() {
+                return None;
+            }
+            rates.sort();
+            let worst = rates.iter().map(|(_, rate)| *rate).max().unwrap_or(0);
+            let full = rates
+                .iter()
+                .map(|(name, rate)| format!("{name} {rate}%"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(SegmentText {
+                full: format!("Err: {full}"),
+                compact: format!("Err: {worst}%"),
+                style: if worst > 0 {
+                    Style::default().fg(STATUS_WARNING)
+                } else {
+                    text_style
+                },
+            })
+        }
+    }
+}
+
+/// `950`, `12.3k`, `4.1M`.
+fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f64 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// `45s`, `12m05s`, `3h02m`.
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3_599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3_600, secs % 3_600 / 60),
     }
 }
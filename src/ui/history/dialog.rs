@@ -34,6 +34,9 @@ pub fn render_history_dialog(frame: &mut Frame, state: &HistoryDialogState) {
         .map(|entry| {
             let description = match &entry.from_backend {
                 None => format!("Started on {}", entry.to_backend),
+                Some(from) if entry.failover => {
+                    format!("{} → {} (failover)", from, entry.to_backend)
+                }
                 Some(from) => format!("{} → {}", from, entry.to_backend),
             };
             let time = format_time(entry.timestamp);
@@ -66,7 +69,8 @@ pub fn render_history_dialog(frame: &mut Frame, state: &HistoryDialogState) {
     dialog.render(frame, frame.area());
 }
 
-fn format_time(timestamp: SystemTime) -> String {
+/// Local wall-clock time, `HH:MM:SS`.
+pub(crate) fn format_time(timestamp: SystemTime) -> String {
     let duration = timestamp
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default();
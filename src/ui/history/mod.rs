@@ -3,6 +3,7 @@ mod intent;
 mod reducer;
 mod state;
 
+pub(crate) use dialog::format_time;
 pub use dialog::render_history_dialog;
 pub use intent::HistoryIntent;
 pub use reducer::{HistoryReducer, MAX_VISIBLE_ROWS};
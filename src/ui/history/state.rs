@@ -6,6 +6,7 @@ pub struct HistoryEntry {
     pub timestamp: SystemTime,
     pub from_backend: Option<String>,
     pub to_backend: String,
+    pub failover: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
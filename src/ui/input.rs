@@ -1,7 +1,8 @@
 use crate::ui::app::{App, BackendPopupSection, PopupKind};
 use crate::ui::history::HistoryIntent;
+use crate::ui::inspector::{InspectorDialogState, InspectorIntent};
 use crate::ui::settings::SettingsIntent;
-use term_input::{Direction, KeyInput, KeyKind};
+use term_input::{Direction, KeyInput, KeyKind, NavKey};
 
 /// Action to take after processing a key event.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -10,6 +11,8 @@ pub enum InputAction {
     None,
     /// Forward raw bytes to PTY.
     Forward,
+    /// Put text on the system clipboard.
+    Copy(String),
 }
 
 /// Classify a key input: hotkey, popup navigation, or forward to PTY.
@@ -78,7 +81,8 @@ fn handle_popup_key(app: &mut App, key: &KeyInput) -> InputAction {
         PopupKind::History => handle_history_key(app, key),
         PopupKind::Settings => handle_settings_key(app, key),
         PopupKind::BackendSwitch => handle_backend_switch_key(app, key),
-        PopupKind::Status => handle_generic_popup_key(app, key),
+        PopupKind::Inspector => handle_inspector_key(app, key),
+        PopupKind::Status => handle_status_key(app, key),
     }
 }
 
@@ -98,6 +102,55 @@ fn handle_history_key(app: &mut App, key: &KeyInput) -> InputAction {
     InputAction::None
 }
 
+fn handle_inspector_key(app: &mut App, key: &KeyInput) -> InputAction {
+    let in_detail = matches!(
+        app.inspector_dialog(),
+        InspectorDialogState::Visible { detail: Some(_), .. }
+    );
+    match &key.kind {
+        KeyKind::Escape if in_detail => {
+            app.dispatch_inspector(InspectorIntent::CloseDetail);
+        }
+        KeyKind::Escape | KeyKind::Control('s') => {
+            app.close_inspector_dialog();
+        }
+        KeyKind::Arrow(Direction::Up) => {
+            app.dispatch_inspector(InspectorIntent::MoveUp);
+        }
+        KeyKind::Arrow(Direction::Down) => {
+            app.dispatch_inspector(InspectorIntent::MoveDown);
+        }
+        KeyKind::Nav(NavKey::PageUp) => {
+            app.dispatch_inspector(InspectorIntent::PageUp);
+        }
+        KeyKind::Nav(NavKey::PageDown) => {
+            app.dispatch_inspector(InspectorIntent::PageDown);
+        }
+        KeyKind::Enter => {
+            app.dispatch_inspector(InspectorIntent::OpenDetail);
+        }
+        KeyKind::Char('b') => {
+            app.dispatch_inspector(InspectorIntent::CycleBackendFilter);
+        }
+        KeyKind::Char('s') => {
+            app.dispatch_inspector(InspectorIntent::CycleStatusFilter);
+        }
+        KeyKind::Char('a') => {
+            app.dispatch_inspector(InspectorIntent::CycleAgentFilter);
+        }
+        KeyKind::Char('c') => {
+            if let Some(command) = app.inspector_curl() {
+                return InputAction::Copy(command);
+            }
+        }
+        KeyKind::Char('e') => {
+            app.request_har_export();
+        }
+        _ => {}
+    }
+    InputAction::None
+}
+
 fn handle_settings_key(app: &mut App, key: &KeyInput) -> InputAction {
     match &key.kind {
         KeyKind::Escape => {
@@ -158,7 +211,7 @@ fn handle_backend_switch_key(app: &mut App, key: &KeyInput) -> InputAction {
                 BackendPopupSection::TeammateBackend => return handle_teammate_backend_enter(app),
             }
         }
-        KeyKind::Backspace | KeyKind::Nav(term_input::NavKey::Delete) => {
+        KeyKind::Backspace | KeyKind::Nav(NavKey::Delete) => {
             match app.backend_popup_section() {
                 BackendPopupSection::SubagentBackend => {
                     app.request_clear_subagent_backend();
@@ -198,6 +251,17 @@ fn handle_backend_switch_key(app: &mut App, key: &KeyInput) -> InputAction {
     InputAction::None
 }
 
+fn handle_status_key(app: &mut App, key: &KeyInput) -> InputAction {
+    match &key.kind {
+        KeyKind::Char('i') => {
+            app.close_popup();
+            app.open_inspector_dialog();
+            InputAction::None
+        }
+        _ => handle_generic_popup_key(app, key),
+    }
+}
+
 fn handle_generic_popup_key(app: &mut App, key: &KeyInput) -> InputAction {
     match &key.kind {
         KeyKind::Escape => {
@@ -0,0 +1,214 @@
+//! Detail pane text and "copy as curl" for one request record.
+
+use std::time::SystemTime;
+
+use crate::metrics::RequestRecord;
+use crate::utc::format_utc;
+
+/// Shown when `[debug_logging]` did not capture headers or a body.
+const NOT_CAPTURED: &str = "Not captured (needs [debug_logging] level = \"full\")";
+
+/// One line of the detail pane.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetailLine {
+    /// Section title, e.g. "Routing".
+    Heading(String),
+    /// Label and value.
+    Field(String, String),
+    /// Preformatted text, e.g. a line of a body.
+    Text(String),
+    Blank,
+}
+
+fn field(label: &str, value: impl Into<String>) -> DetailLine {
+    DetailLine::Field(label.to_string(), value.into())
+}
+
+/// Detail pane of `record`: routing, model mapping, timings, usage, then the
+/// redacted headers and bodies captured by the debug logger.
+pub fn detail_lines(record: &RequestRecord) -> Vec<DetailLine> {
+    let mut lines = vec![DetailLine::Heading("Request".to_string())];
+    lines.push(field("ID", record.id.clone()));
+    lines.push(field("Started", format_utc(record.started_at)));
+    lines.push(field("Method", request_line(record)));
+    if let Some(session_id) = &record.session_id {
+        lines.push(field("Session", session_id.clone()));
+    }
+    let agent = match &record.agent_id {
+        Some(id) => format!("{} {}", record.agent.as_str(), id),
+        None => record.agent.as_str().to_string(),
+    };
+    lines.push(field("Agent", agent));
+    if let Some(analysis) = &record.request_analysis {
+        let mut summary = format!("{} messages", analysis.message_count);
+        if analysis.has_tools {
+            summary.push_str(&format!(", {} tools", analysis.tool_names.len()));
+        }
+        if analysis.image_count > 0 {
+            summary.push_str(&format!(", {} images", analysis.image_count));
+        }
+        lines.push(field("Content", summary));
+    }
+
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::Heading("Routing".to_string()));
+    lines.push(field("Backend", record.backend.clone()));
+    if let Some(decision) = &record.routing_decision {
+        let mut reason = decision.reason.clone();
+        if decision.synthesized {
+            reason.push_str(" (answered by proxy)");
+        }
+        lines.push(field("Reason", reason));
+    }
+    let model = match (&record.trace.model, &record.trace.upstream_model) {
+        (Some(model), Some(upstream)) if model != upstream => format!("{} → {}", model, upstream),
+        (Some(model), _) => model.clone(),
+        (None, Some(upstream)) => format!("→ {}", upstream),
+        (None, None) => "—".to_string(),
+    };
+    lines.push(field("Model", model));
+    lines.push(field(
+        "Thinking",
+        format!("{} blocks filtered", record.trace.thinking_blocks_filtered),
+    ));
+    lines.push(field("Retries", record.trace.retries.to_string()));
+
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::Heading("Response".to_string()));
+    let status = match (record.status, record.timed_out) {
+        (_, true) => "timed out".to_string(),
+        (Some(status), false) => status.to_string(),
+        (None, false) => "—".to_string(),
+    };
+    lines.push(field("Status", status));
+    if let Some(analysis) = &record.response_analysis {
+        if let Some(stop_reason) = &analysis.stop_reason {
+            lines.push(field("Stop", stop_reason.clone()));
+        }
+    }
+    if let Some(usage) = &record.usage {
+        let mut tokens = format!("{} in / {} out", usage.total_input_tokens(), usage.output_tokens);
+        if usage.cache_read_input_tokens > 0 {
+            tokens.push_str(&format!(" ({} cached)", usage.cache_read_input_tokens));
+        }
+        lines.push(field("Tokens", tokens));
+        if let Some(cost) = usage.cost_usd {
+            lines.push(field("Cost", format!("${:.4}", cost)));
+        }
+    }
+    lines.push(field("Sent", format!("{} bytes", record.request_bytes)));
+    lines.push(field("Received", format!("{} bytes", record.response_bytes)));
+
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::Heading("Timings".to_string()));
+    let ms = |value: Option<u64>| value.map_or("—".to_string(), |ms| format!("{} ms", ms));
+    lines.push(field("TTFB", ms(record.ttfb_ms)));
+    lines.push(field("Latency", ms(record.latency_ms)));
+    for stage in &record.trace.stages {
+        let offset = elapsed_ms(record.started_at, stage.started_at);
+        let duration = elapsed_ms(stage.started_at, stage.ended_at);
+        lines.push(field(stage.name, format!("+{} ms, {} ms", offset, duration)));
+    }
+
+    let request = record.request_meta.as_ref();
+    let response = record.response_meta.as_ref();
+    push_headers(&mut lines, "Request Headers", request.and_then(|m| m.headers.as_ref()));
+    push_body(&mut lines, "Request Body", request.and_then(|m| m.body_preview.as_deref()));
+    push_headers(&mut lines, "Response Headers", response.and_then(|m| m.headers.as_ref()));
+    push_body(&mut lines, "Response Body", response.and_then(|m| m.body_preview.as_deref()));
+    lines
+}
+
+fn push_headers(lines: &mut Vec<DetailLine>, title: &str, headers: Option<&Vec<(String, String)>>) {
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::Heading(title.to_string()));
+    match headers {
+        Some(headers) => lines.extend(
+            headers
+                .iter()
+                .map(|(name, value)| DetailLine::Text(format!("{}: {}", name, value))),
+        ),
+        None => lines.push(DetailLine::Text(NOT_CAPTURED.to_string())),
+    }
+}
+
+fn push_body(lines: &mut Vec<DetailLine>, title: &str, body: Option<&str>) {
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::Heading(title.to_string()));
+    match body {
+        Some(body) => lines.extend(pretty_body(body).into_iter().map(DetailLine::Text)),
+        None => lines.push(DetailLine::Text(NOT_CAPTURED.to_string())),
+    }
+}
+
+/// Lines of `body`, pretty-printed when it is JSON. Truncated previews are
+/// not valid JSON and are shown as captured.
+pub fn pretty_body(body: &str) -> Vec<String> {
+    let text = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| body.to_string());
+    text.lines().map(str::to_string).collect()
+}
+
+/// `curl` command replaying `record` against `base_url` (the proxy).
+///
+/// Headers and body are the redacted previews, so masked credentials must
+/// be filled in by hand; without `[debug_logging] level = "full"` only the
+/// method and URL are known. A body cut at `body_preview_bytes` is left out
+/// rather than sent incomplete.
+pub fn curl_command(record: &RequestRecord, base_url: &str) -> String {
+    let meta = record.request_meta.as_ref();
+    let method = meta.map_or(record.trace.method.as_str(), |m| m.method.as_str());
+    let path = meta.map_or(record.trace.path.as_str(), |m| m.path.as_str());
+    let mut url = format!("{}{}", base_url.trim_end_matches('/'), path);
+    if let Some(query) = meta.and_then(|m| m.query.as_deref()) {
+        url.push('?');
+        url.push_str(query);
+    }
+
+    let mut parts = vec![format!("curl -X {} {}", method, shell_quote(&url))];
+    for (name, value) in meta.and_then(|m| m.headers.as_ref()).into_iter().flatten() {
+        if name.eq_ignore_ascii_case("host") || name.eq_ignore_ascii_case("content-length") {
+            continue;
+        }
+        parts.push(format!("-H {}", shell_quote(&format!("{}: {}", name, value))));
+    }
+    let body = meta.and_then(|m| m.body_preview.as_deref().map(|body| (body, m.body_truncated)));
+    let mut command = String::new();
+    match body {
+        Some((_, true)) => {
+            command.push_str("# Request body omitted: its preview was truncated (set full_body = true)\n");
+        }
+        Some((body, false)) => {
+            // Compact JSON so the command stays one argument per line.
+            let body = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|value| serde_json::to_string(&value).ok())
+                .unwrap_or_else(|| body.to_string());
+            parts.push(format!("--data-raw {}", shell_quote(&body)));
+        }
+        None => {}
+    }
+    command.push_str(&parts.join(" \\\n  "));
+    command
+}
+
+fn request_line(record: &RequestRecord) -> String {
+    let meta = record.request_meta.as_ref();
+    let method = meta.map_or(record.trace.method.as_str(), |m| m.method.as_str());
+    let path = meta.map_or(record.trace.path.as_str(), |m| m.path.as_str());
+    match meta.and_then(|m| m.query.as_deref()) {
+        Some(query) => format!("{} {}?{}", method, path, query),
+        None => format!("{} {}", method, path),
+    }
+}
+
+fn elapsed_ms(from: SystemTime, to: SystemTime) -> u128 {
+    to.duration_since(from).unwrap_or_default().as_millis()
+}
+
+/// Single-quote `text` for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
@@ -0,0 +1,142 @@
+use crate::metrics::RequestRecord;
+use crate::ui::components::PopupDialog;
+use crate::ui::history::format_time;
+use crate::ui::inspector::detail::DetailLine;
+use crate::ui::inspector::reducer::{DETAIL_ROWS, LIST_ROWS};
+use crate::ui::inspector::state::{DetailPane, InspectorDialogState};
+use crate::ui::theme::{
+    ACTIVE_HIGHLIGHT, CLAUDE_ORANGE, HEADER_SEPARATOR, HEADER_TEXT, STATUS_ERROR, STATUS_OK,
+};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+
+const MAX_DIALOG_WIDTH: u16 = 100;
+
+pub fn render_inspector_dialog(frame: &mut Frame, state: &InspectorDialogState) {
+    let InspectorDialogState::Visible {
+        records,
+        filter,
+        selected,
+        detail,
+        notice,
+    } = state
+    else {
+        return;
+    };
+
+    let width = MAX_DIALOG_WIDTH.min(frame.area().width.saturating_sub(4));
+    if let Some(pane) = detail {
+        render_detail(frame, pane, notice.as_deref(), width);
+        return;
+    }
+
+    let filtered = state.filtered();
+    let title = format!(
+        "Requests {}/{}  backend: {}  status: {}  agent: {}",
+        filtered.len(),
+        records.len(),
+        filter.backend.as_deref().unwrap_or("all"),
+        filter.status.label(),
+        filter.agent.map_or("all", |agent| agent.as_str()),
+    );
+
+    let scroll_offset = selected.saturating_sub(LIST_ROWS - 1);
+    let backend_width = filtered
+        .iter()
+        .map(|record| record.backend.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Line> = filtered
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .take(LIST_ROWS)
+        .map(|(index, record)| list_row(record, index == *selected, backend_width))
+        .collect();
+    if lines.is_empty() {
+        let message = if records.is_empty() {
+            "  No requests yet"
+        } else {
+            "  No requests match the filters"
+        };
+        lines.push(Line::from(Span::styled(message, Style::default().fg(HEADER_TEXT))));
+    }
+
+    PopupDialog::new(&title, lines)
+        .fixed_width(width)
+        .scrollbar(filtered.len(), scroll_offset)
+        .footer(notice.as_deref().unwrap_or(
+            "Enter: Details  b/s/a: Filter  c: Copy curl  e: Export HAR  Esc: Close",
+        ))
+        .render(frame, frame.area());
+}
+
+fn list_row(record: &RequestRecord, is_selected: bool, backend_width: usize) -> Line<'static> {
+    let base_style = if is_selected {
+        Style::default().bg(ACTIVE_HIGHLIGHT)
+    } else {
+        Style::default()
+    };
+    let (status, status_color) = match (record.status, record.timed_out) {
+        (_, true) => ("T/O".to_string(), STATUS_ERROR),
+        (Some(code), false) if (200..300).contains(&code) => (code.to_string(), STATUS_OK),
+        (Some(code), false) => (code.to_string(), STATUS_ERROR),
+        (None, false) => ("—".to_string(), STATUS_ERROR),
+    };
+    let latency = record
+        .latency_ms
+        .map_or("—".to_string(), |ms| format!("{} ms", ms));
+    let model = record
+        .trace
+        .upstream_model
+        .as_deref()
+        .or(record.trace.model.as_deref())
+        .unwrap_or("—");
+
+    Line::from(vec![
+        Span::styled(
+            if is_selected { " → " } else { "   " },
+            base_style.fg(HEADER_TEXT),
+        ),
+        Span::styled(format_time(record.started_at), base_style.fg(HEADER_SEPARATOR)),
+        Span::styled(format!("  {:>3}", status), base_style.fg(status_color)),
+        Span::styled(format!("  {:>8}", latency), base_style.fg(HEADER_TEXT)),
+        Span::styled(
+            format!("  {:<width$}", record.backend, width = backend_width),
+            base_style.fg(HEADER_TEXT),
+        ),
+        Span::styled(format!("  {:<8}", record.agent.as_str()), base_style.fg(HEADER_SEPARATOR)),
+        Span::styled(format!("  {}", model), base_style.fg(HEADER_TEXT)),
+    ])
+}
+
+fn render_detail(frame: &mut Frame, pane: &DetailPane, notice: Option<&str>, width: u16) {
+    let lines: Vec<Line> = pane
+        .lines
+        .iter()
+        .skip(pane.scroll_offset)
+        .take(DETAIL_ROWS)
+        .map(|line| match line {
+            DetailLine::Heading(title) => Line::from(Span::styled(
+                format!("  ── {} ──", title),
+                Style::default().fg(CLAUDE_ORANGE),
+            )),
+            DetailLine::Field(label, value) => Line::from(vec![
+                Span::styled(format!("  {:<10} ", label), Style::default().fg(HEADER_SEPARATOR)),
+                Span::styled(value.clone(), Style::default().fg(HEADER_TEXT)),
+            ]),
+            DetailLine::Text(text) => {
+                Line::from(Span::styled(format!("  {}", text), Style::default().fg(HEADER_TEXT)))
+            }
+            DetailLine::Blank => Line::from(""),
+        })
+        .collect();
+
+    let title = format!("Request {}", pane.request_id);
+    PopupDialog::new(&title, lines)
+        .fixed_width(width)
+        .scrollbar(pane.lines.len(), pane.scroll_offset)
+        .footer(notice.unwrap_or("Up/Down/PgUp/PgDn: Scroll  c: Copy curl  e: Export HAR  Esc: Back"))
+        .render(frame, frame.area());
+}
@@ -0,0 +1,29 @@
+use crate::metrics::RequestRecord;
+use crate::ui::mvi::Intent;
+
+#[derive(Debug, Clone)]
+pub enum InspectorIntent {
+    /// Open with `MetricsSnapshot.recent` (oldest first).
+    Load { records: Vec<RequestRecord> },
+    /// Replace the records on a metrics update, keeping the selection and
+    /// the open detail pane.
+    Refresh { records: Vec<RequestRecord> },
+    Close,
+    /// Select the previous request, or scroll the detail pane up.
+    MoveUp,
+    /// Select the next request, or scroll the detail pane down.
+    MoveDown,
+    PageUp,
+    PageDown,
+    CycleBackendFilter,
+    CycleStatusFilter,
+    CycleAgentFilter,
+    /// Show the detail pane of the selected request.
+    OpenDetail,
+    /// Back from the detail pane to the list.
+    CloseDetail,
+    /// Show the result of an action in the footer.
+    ShowNotice { message: String },
+}
+
+impl Intent for InspectorIntent {}
@@ -0,0 +1,11 @@
+mod detail;
+mod dialog;
+mod intent;
+mod reducer;
+mod state;
+
+pub use detail::{curl_command, detail_lines, pretty_body, DetailLine};
+pub use dialog::render_inspector_dialog;
+pub use intent::InspectorIntent;
+pub use reducer::{InspectorReducer, DETAIL_ROWS, LIST_ROWS};
+pub use state::{DetailPane, InspectorDialogState, InspectorFilter, StatusFilter};
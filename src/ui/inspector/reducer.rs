@@ -0,0 +1,144 @@
+use crate::metrics::{AgentKind, RequestRecord};
+use crate::ui::inspector::detail::detail_lines;
+use crate::ui::inspector::intent::InspectorIntent;
+use crate::ui::inspector::state::{DetailPane, InspectorDialogState, InspectorFilter};
+use crate::ui::mvi::Reducer;
+
+/// Requests shown at once in the list.
+pub const LIST_ROWS: usize = 14;
+/// Lines shown at once in the detail pane.
+pub const DETAIL_ROWS: usize = 20;
+
+pub struct InspectorReducer;
+
+impl Reducer for InspectorReducer {
+    type State = InspectorDialogState;
+    type Intent = InspectorIntent;
+
+    fn reduce(state: Self::State, intent: Self::Intent) -> Self::State {
+        match intent {
+            InspectorIntent::Load { mut records } => {
+                records.reverse();
+                InspectorDialogState::Visible {
+                    records,
+                    filter: InspectorFilter::default(),
+                    selected: 0,
+                    detail: None,
+                    notice: None,
+                }
+            }
+            InspectorIntent::Close => InspectorDialogState::Hidden,
+            intent => match state {
+                InspectorDialogState::Visible {
+                    records,
+                    filter,
+                    selected,
+                    detail,
+                    notice,
+                } => reduce_visible(records, filter, selected, detail, notice, intent),
+                InspectorDialogState::Hidden => InspectorDialogState::Hidden,
+            },
+        }
+    }
+}
+
+fn reduce_visible(
+    mut records: Vec<RequestRecord>,
+    mut filter: InspectorFilter,
+    mut selected: usize,
+    mut detail: Option<DetailPane>,
+    mut notice: Option<String>,
+    intent: InspectorIntent,
+) -> InspectorDialogState {
+    if !matches!(intent, InspectorIntent::Refresh { .. }) {
+        notice = None;
+    }
+    match (intent, detail.as_mut()) {
+        (InspectorIntent::Refresh { records: mut fresh }, _) => {
+            fresh.reverse();
+            let selected_id = filtered(&records, &filter)
+                .get(selected)
+                .map(|record| record.id.clone());
+            records = fresh;
+            if let Some(position) = selected_id.and_then(|id| {
+                filtered(&records, &filter)
+                    .iter()
+                    .position(|record| record.id == id)
+            }) {
+                selected = position;
+            }
+            if let Some(pane) = detail.as_mut() {
+                if let Some(record) = records.iter().find(|r| r.id == pane.request_id) {
+                    pane.lines = detail_lines(record);
+                }
+            }
+        }
+        (InspectorIntent::MoveUp, Some(pane)) => {
+            pane.scroll_offset = pane.scroll_offset.saturating_sub(1);
+        }
+        (InspectorIntent::MoveDown, Some(pane)) => pane.scroll_offset += 1,
+        (InspectorIntent::PageUp, Some(pane)) => {
+            pane.scroll_offset = pane.scroll_offset.saturating_sub(DETAIL_ROWS);
+        }
+        (InspectorIntent::PageDown, Some(pane)) => pane.scroll_offset += DETAIL_ROWS,
+        (InspectorIntent::MoveUp, None) => selected = selected.saturating_sub(1),
+        (InspectorIntent::MoveDown, None) => selected += 1,
+        (InspectorIntent::PageUp, None) => selected = selected.saturating_sub(LIST_ROWS),
+        (InspectorIntent::PageDown, None) => selected += LIST_ROWS,
+        (InspectorIntent::CycleBackendFilter, None) => {
+            let mut backends: Vec<&str> = records.iter().map(|r| r.backend.as_str()).collect();
+            backends.sort_unstable();
+            backends.dedup();
+            filter.backend = match &filter.backend {
+                None => backends.first(),
+                Some(current) => backends.iter().find(|name| **name > current.as_str()),
+            }
+            .map(|name| name.to_string());
+            selected = 0;
+        }
+        (InspectorIntent::CycleStatusFilter, None) => {
+            filter.status = filter.status.next();
+            selected = 0;
+        }
+        (InspectorIntent::CycleAgentFilter, None) => {
+            filter.agent = match filter.agent {
+                None => Some(AgentKind::Main),
+                Some(AgentKind::Main) => Some(AgentKind::Subagent),
+                Some(AgentKind::Subagent) => Some(AgentKind::Teammate),
+                Some(AgentKind::Teammate) => None,
+            };
+            selected = 0;
+        }
+        (InspectorIntent::OpenDetail, None) => {
+            detail = filtered(&records, &filter)
+                .get(selected)
+                .map(|record| DetailPane {
+                    request_id: record.id.clone(),
+                    lines: detail_lines(record),
+                    scroll_offset: 0,
+                });
+        }
+        (InspectorIntent::CloseDetail, _) => detail = None,
+        (InspectorIntent::ShowNotice { message }, _) => notice = Some(message),
+        // Filters and selection don't change under the detail pane.
+        _ => {}
+    }
+
+    let count = filtered(&records, &filter).len();
+    selected = selected.min(count.saturating_sub(1));
+    if let Some(pane) = detail.as_mut() {
+        let max_offset = pane.lines.len().saturating_sub(DETAIL_ROWS);
+        pane.scroll_offset = pane.scroll_offset.min(max_offset);
+    }
+    InspectorDialogState::Visible {
+        records,
+        filter,
+        selected,
+        detail,
+        notice,
+    }
+}
+
+fn filtered<'a>(records: &'a [RequestRecord], filter: &InspectorFilter) -> Vec<&'a RequestRecord> {
+    records.iter().filter(|record| filter.matches(record)).collect()
+}
@@ -0,0 +1,109 @@
+use crate::metrics::{AgentKind, RequestRecord};
+use crate::ui::inspector::detail::DetailLine;
+use crate::ui::mvi::UiState;
+
+/// Which responses the request list shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusFilter {
+    #[default]
+    All,
+    /// 2xx responses.
+    Success,
+    /// Everything else, including requests that never got a response.
+    Error,
+}
+
+impl StatusFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Success => "2xx",
+            StatusFilter::Error => "errors",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Success,
+            StatusFilter::Success => StatusFilter::Error,
+            StatusFilter::Error => StatusFilter::All,
+        }
+    }
+
+    fn matches(&self, status: Option<u16>) -> bool {
+        let success = status.is_some_and(|code| (200..300).contains(&code));
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Success => success,
+            StatusFilter::Error => !success,
+        }
+    }
+}
+
+/// Filters of the request list; `None` shows every value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InspectorFilter {
+    pub backend: Option<String>,
+    pub status: StatusFilter,
+    pub agent: Option<AgentKind>,
+}
+
+impl InspectorFilter {
+    pub fn matches(&self, record: &RequestRecord) -> bool {
+        self.backend.as_ref().is_none_or(|backend| *backend == record.backend)
+            && self.status.matches(record.status)
+            && self.agent.is_none_or(|agent| agent == record.agent)
+    }
+}
+
+/// Detail pane of one request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailPane {
+    pub request_id: String,
+    pub lines: Vec<DetailLine>,
+    pub scroll_offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum InspectorDialogState {
+    #[default]
+    Hidden,
+    Visible {
+        /// Recent requests, newest first.
+        records: Vec<RequestRecord>,
+        filter: InspectorFilter,
+        /// Index into the filtered list.
+        selected: usize,
+        /// Open detail pane, shown instead of the list.
+        detail: Option<DetailPane>,
+        /// Result of the last action (e.g. HAR export), shown until the
+        /// next key.
+        notice: Option<String>,
+    },
+}
+
+impl UiState for InspectorDialogState {}
+
+impl InspectorDialogState {
+    pub fn is_visible(&self) -> bool {
+        !matches!(self, Self::Hidden)
+    }
+
+    /// Records passing the filter, newest first.
+    pub fn filtered(&self) -> Vec<&RequestRecord> {
+        match self {
+            Self::Visible {
+                records, filter, ..
+            } => records.iter().filter(|record| filter.matches(record)).collect(),
+            Self::Hidden => Vec::new(),
+        }
+    }
+
+    /// The highlighted record of the filtered list.
+    pub fn selected_record(&self) -> Option<&RequestRecord> {
+        match self {
+            Self::Visible { selected, .. } => self.filtered().get(*selected).copied(),
+            Self::Hidden => None,
+        }
+    }
+}
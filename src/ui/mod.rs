@@ -3,6 +3,7 @@ pub mod events;
 pub mod footer;
 pub mod header;
 pub mod history;
+pub mod inspector;
 pub mod input;
 pub mod layout;
 pub mod components;
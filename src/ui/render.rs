@@ -1,3 +1,4 @@
+use crate::backend::CircuitState;
 use crate::config::SettingSection;
 use crate::error::ErrorSeverity;
 use crate::ui::app::{App, PopupKind};
@@ -5,6 +6,7 @@ use crate::ui::components::PopupDialog;
 use crate::ui::footer::Footer;
 use crate::ui::header::Header;
 use crate::ui::history::render_history_dialog;
+use crate::ui::inspector::render_inspector_dialog;
 use crate::ui::layout::layout_regions;
 use crate::ui::settings::SettingsDialogState;
 use crate::ui::terminal::TerminalBody;
@@ -23,9 +25,9 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
     let area = frame.area();
     let (header, body, footer) = layout_regions(area);
 
-    let header_widget = Header::new();
+    let header_widget = Header::with_segments(app.header_segments().to_vec());
     frame.render_widget(
-        header_widget.widget(app.proxy_status(), app.error_registry()),
+        header_widget.widget(app.proxy_status(), app.error_registry(), app.metrics(), header.width),
         header,
     );
     frame.render_widget(Clear, body);
@@ -59,6 +61,10 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
             render_settings_dialog(frame, app.settings_dialog(), body);
             return;
         }
+        if matches!(kind, PopupKind::Inspector) {
+            render_inspector_dialog(frame, app.inspector_dialog());
+            return;
+        }
 
         let (title, lines) = match kind {
             PopupKind::Status => {
@@ -74,15 +80,17 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
                         Span::styled(&backend.display_name, Style::default().fg(HEADER_TEXT)),
                     ]));
 
-                    // URL (truncate if too long)
-                    let url = if backend.base_url.len() > 40 {
-                        format!("{}...", &backend.base_url[..37])
+                    // URL (truncate if too long), or the members of a group
+                    let (label, value) = if !backend.members.is_empty() {
+                        ("  Members:   ", backend.members.join(", "))
+                    } else if backend.base_url.len() > 40 {
+                        ("  URL:       ", format!("{}...", &backend.base_url[..37]))
                     } else {
-                        backend.base_url.clone()
+                        ("  URL:       ", backend.base_url.clone())
                     };
                     lines.push(Line::from(vec![
-                        Span::styled("  URL:       ", Style::default().fg(HEADER_TEXT)),
-                        Span::styled(url, Style::default().fg(HEADER_TEXT)),
+                        Span::styled(label, Style::default().fg(HEADER_TEXT)),
+                        Span::styled(value, Style::default().fg(HEADER_TEXT)),
                     ]));
 
                     // Status
@@ -116,27 +124,41 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
                         Span::styled(latency_str, Style::default().fg(HEADER_TEXT)),
                     ]));
 
-                    // Tokens - estimated from most recent request
+                    // Tokens - reported usage of the most recent request
                     let tokens_str = if let Some(metrics) = app.metrics() {
                         metrics
                             .recent
                             .iter()
                             .rev()
                             .find(|r| r.backend == backend.id)
-                            .map(|r| {
-                                let input = r
-                                    .request_analysis
-                                    .as_ref()
-                                    .and_then(|a| a.estimated_input_tokens)
-                                    .map(|t| format!("{}", t))
-                                    .unwrap_or_else(|| "—".to_string());
-                                // Estimate output tokens from response bytes (rough: ~4 chars per token)
-                                let output = if r.response_bytes > 0 {
-                                    format!("{}", r.response_bytes / 4)
-                                } else {
-                                    "—".to_string()
-                                };
-                                format!("{} in / {} out", input, output)
+                            .map(|r| match &r.usage {
+                                Some(usage) => {
+                                    let mut text = format!(
+                                        "{} in / {} out",
+                                        usage.total_input_tokens(),
+                                        usage.output_tokens
+                                    );
+                                    if usage.cache_read_input_tokens > 0 {
+                                        text.push_str(&format!(
+                                            " ({} cached)",
+                                            usage.cache_read_input_tokens
+                                        ));
+                                    }
+                                    if let Some(cost) = usage.cost_usd {
+                                        text.push_str(&format!("  ${:.4}", cost));
+                                    }
+                                    text
+                                }
+                                // No usage reported: fall back to the request estimate
+                                None => {
+                                    let input = r
+                                        .request_analysis
+                                        .as_ref()
+                                        .and_then(|a| a.estimated_input_tokens)
+                                        .map(|t| format!("~{}", t))
+                                        .unwrap_or_else(|| "—".to_string());
+                                    format!("{} in / — out", input)
+                                }
                             })
                             .unwrap_or_else(|| "— in / — out".to_string())
                     } else {
@@ -147,10 +169,90 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
                         Span::styled(tokens_str, Style::default().fg(HEADER_TEXT)),
                     ]));
 
+                    // Per-key usage when the backend rotates several API keys
+                    if !backend.api_keys.is_empty() {
+                        lines.push(Line::from(""));
+                        lines.push(Line::from(vec![Span::styled(
+                            "  API Keys:",
+                            Style::default().fg(HEADER_TEXT),
+                        )]));
+
+                        let label_width = backend
+                            .api_keys
+                            .iter()
+                            .map(|k| k.label.chars().count())
+                            .max()
+                            .unwrap_or(0);
+                        for key in &backend.api_keys {
+                            let (color, state) = match (&key.error, key.cooling_secs) {
+                                (Some(error), _) => (STATUS_ERROR, error.clone()),
+                                (None, Some(secs)) => (STATUS_WARNING, format!("cooling {}s", secs)),
+                                (None, None) => (STATUS_OK, "ready".to_string()),
+                            };
+                            lines.push(Line::from(vec![
+                                Span::styled("    ● ", Style::default().fg(color)),
+                                Span::styled(
+                                    format!("{:<width$}", key.label, width = label_width),
+                                    Style::default().fg(HEADER_TEXT),
+                                ),
+                                Span::styled(
+                                    format!("  {} req  {} throttled", key.requests, key.throttles),
+                                    Style::default().fg(HEADER_TEXT),
+                                ),
+                                Span::styled(format!("  {}", state), Style::default().fg(color)),
+                            ]));
+                        }
+                    }
                 } else {
                     lines.push(Line::from("  No backend configured"));
                 }
 
+                // Active health probe results for all backends
+                let probed: Vec<_> = app
+                    .backends()
+                    .iter()
+                    .filter_map(|b| b.probe.as_ref().map(|p| (b, p)))
+                    .collect();
+                if !probed.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(vec![Span::styled(
+                        "  Health Checks:",
+                        Style::default().fg(HEADER_TEXT),
+                    )]));
+
+                    let name_width = probed
+                        .iter()
+                        .map(|(b, _)| b.display_name.chars().count())
+                        .max()
+                        .unwrap_or(0);
+                    for (backend, probe) in probed {
+                        let color = if probe.healthy {
+                            STATUS_OK
+                        } else if probe.reachable {
+                            STATUS_WARNING
+                        } else {
+                            STATUS_ERROR
+                        };
+                        let detail = match (&probe.error, probe.latency_ms) {
+                            (Some(error), _) => error.clone(),
+                            (None, Some(ms)) => format!("{} ms", ms),
+                            (None, None) => "ok".to_string(),
+                        };
+                        lines.push(Line::from(vec![
+                            Span::styled("    ● ", Style::default().fg(color)),
+                            Span::styled(
+                                format!("{:<width$}", backend.display_name, width = name_width),
+                                Style::default().fg(HEADER_TEXT),
+                            ),
+                            Span::styled(format!("  {}", detail), Style::default().fg(color)),
+                            Span::styled(
+                                format!("  [{}]", format_time_ago(probe.checked_at)),
+                                Style::default().fg(HEADER_TEXT),
+                            ),
+                        ]));
+                    }
+                }
+
                 // Show recent errors from error registry
                 let recent_errors: Vec<_> = app
                     .error_registry()
@@ -278,8 +380,31 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
                         spans.push(Span::styled("  [", base_style));
                         spans.push(Span::styled(status_text, base_style.fg(status_color)));
                         spans.push(Span::styled("]", base_style));
+                        if !backend.members.is_empty() {
+                            spans.push(Span::styled(
+                                format!(" [Group of {}]", backend.members.len()),
+                                base_style.fg(HEADER_TEXT),
+                            ));
+                        }
+
+                        let circuit_color = match backend.circuit {
+                            CircuitState::Closed => None,
+                            CircuitState::Open => Some(STATUS_ERROR),
+                            CircuitState::HalfOpen => Some(STATUS_WARNING),
+                        };
+                        if let Some(color) = circuit_color {
+                            spans.push(Span::styled(" [", base_style));
+                            spans.push(Span::styled(backend.circuit.label(), base_style.fg(color)));
+                            spans.push(Span::styled("]", base_style));
+                        }
 
                         result.push(Line::from(spans));
+                        if let Some(reason) = &backend.credential_error {
+                            result.push(Line::from(Span::styled(
+                                format!("       {}", reason),
+                                Style::default().fg(STATUS_ERROR),
+                            )));
+                        }
                     }
                     result
                 };
@@ -441,20 +566,22 @@ pub fn draw(frame: &mut Frame<'_>, app: &App) {
 
                 ("Select Backend", lines)
             }
-            PopupKind::History | PopupKind::Settings => unreachable!("handled above"),
+            PopupKind::History | PopupKind::Settings | PopupKind::Inspector => {
+                unreachable!("handled above")
+            }
         };
 
         let mut dialog = PopupDialog::new(title, lines);
         match kind {
             PopupKind::Status => {
-                dialog = dialog.footer("Esc/Ctrl+S: Close");
+                dialog = dialog.footer("i: Requests  Esc/Ctrl+S: Close");
             }
             PopupKind::BackendSwitch => {
                 dialog = dialog
                     .min_width(60)
                     .footer("Tab: Section  Up/Down: Move  Enter: Select  Del: Clear  Esc: Close");
             }
-            PopupKind::History | PopupKind::Settings => unreachable!(),
+            PopupKind::History | PopupKind::Settings | PopupKind::Inspector => unreachable!(),
         }
         dialog.render(frame, body);
 
@@ -4,6 +4,7 @@ use crate::config::{save_claude_settings, ClaudeSettingsManager, Config, ConfigS
 use crate::error::{ErrorCategory, ErrorSeverity};
 use crate::ipc::IpcLayer;
 use crate::metrics::{init_global_logger, DebugLogger};
+use crate::proxy::recording::Recording;
 use crate::proxy::ProxyServer;
 use crate::pty::PtySession;
 use crate::shim::TeammateShim;
@@ -27,15 +28,20 @@ use uuid::Uuid;
 
 const UI_COMMAND_BUFFER: usize = 32;
 const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
-const METRICS_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const METRICS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
 const BACKENDS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
 
-pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Result<()> {
+pub fn run(
+    backend_override: Option<String>,
+    claude_args: Vec<String>,
+    recording: Recording,
+) -> io::Result<()> {
     let (mut terminal, guard) = setup_terminal()?;
     let tick_rate = Duration::from_millis(250);
 
     // Load initial config and apply backend override
-    let mut config = Config::load().map_err(|e| {
+    let load = if recording.is_replay() { Config::load_for_replay } else { Config::load };
+    let mut config = load().map_err(|e| {
         io::Error::new(io::ErrorKind::InvalidData, format!("Failed to load config: {}", e))
     })?;
     if let Some(backend_name) = backend_override {
@@ -112,7 +118,10 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
     app.set_ipc_sender(ui_command_tx.clone());
 
     let mut proxy_server = ProxyServer::new(config_store.clone(), debug_logger.clone(), Some(session_token.clone()))
-        .map_err(|err| io::Error::other(err.to_string()))?;
+        .map_err(|err| io::Error::other(err.to_string()))?
+        .with_recording(recording);
+    // Upstream retries in the proxy show up in the header indicator.
+    app.set_error_registry(proxy_server.error_registry());
 
     // Try to bind and get the actual port, updating the base URL.
     // NOTE: try_bind may bind to a different port than specified in config
@@ -185,6 +194,7 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
                     timestamp: e.timestamp,
                     from_backend: e.old_backend,
                     to_backend: e.new_backend,
+                    failover: e.failover,
                 })
                 .collect()
         });
@@ -234,6 +244,8 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
     app.request_status_refresh();
     app.request_backends_refresh();
 
+    app.set_proxy_base_url(actual_base_url.clone());
+
     // Store base args for restart scenarios (using actual_base_url now that proxy is bound)
     let base_proxy_url = actual_base_url;
     let proxy_port = actual_addr.port();
@@ -295,6 +307,24 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
                     InputAction::Forward => {
                         app.send_input(&key.raw);
                     }
+                    InputAction::Copy(text) => match &mut clipboard {
+                        Some(clip) => {
+                            if let Err(err) = clip.set_text(&text) {
+                                app.error_registry().record(
+                                    ErrorSeverity::Warning,
+                                    ErrorCategory::Process,
+                                    &err,
+                                );
+                            }
+                        }
+                        None => {
+                            app.error_registry().record(
+                                ErrorSeverity::Warning,
+                                ErrorCategory::Process,
+                                "Clipboard unavailable",
+                            );
+                        }
+                    },
                     InputAction::None => {}
                 }
             }
@@ -390,13 +420,18 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
                 if app.should_refresh_status(STATUS_REFRESH_INTERVAL) {
                     app.request_status_refresh();
                 }
-                if app.popup_kind() == Some(crate::ui::app::PopupKind::Status)
+                if (matches!(
+                    app.popup_kind(),
+                    Some(crate::ui::app::PopupKind::Status | crate::ui::app::PopupKind::Inspector)
+                ) || app.header_uses_metrics())
                     && app.should_refresh_metrics(METRICS_REFRESH_INTERVAL)
                 {
                     app.request_metrics_refresh(None);
                 }
-                if app.popup_kind() == Some(crate::ui::app::PopupKind::BackendSwitch)
-                    && app.should_refresh_backends(BACKENDS_REFRESH_INTERVAL)
+                if matches!(
+                    app.popup_kind(),
+                    Some(crate::ui::app::PopupKind::BackendSwitch | crate::ui::app::PopupKind::Status)
+                ) && app.should_refresh_backends(BACKENDS_REFRESH_INTERVAL)
                 {
                     app.request_backends_refresh();
                 }
@@ -426,6 +461,7 @@ pub fn run(backend_override: Option<String>, claude_args: Vec<String>) -> io::Re
             Ok(AppEvent::IpcMetrics(metrics)) => app.update_metrics(metrics),
             Ok(AppEvent::IpcBackends(backends)) => app.update_backends(backends),
             Ok(AppEvent::IpcError(message)) => app.set_ipc_error(message),
+            Ok(AppEvent::HarExported(result)) => app.on_har_exported(result),
             Ok(AppEvent::ConfigError(message)) => {
                 app.error_registry().record_with_details(
                     ErrorSeverity::Warning,
@@ -646,6 +682,14 @@ async fn run_ui_bridge(
                     let _ = event_tx.send(AppEvent::IpcError(err.to_string()));
                 }
             },
+            UiCommand::ExportHar => match ipc_client.export_har(None).await {
+                Ok(result) => {
+                    let _ = event_tx.send(AppEvent::HarExported(result));
+                }
+                Err(err) => {
+                    let _ = event_tx.send(AppEvent::IpcError(err.to_string()));
+                }
+            },
             UiCommand::ReloadConfig => match backend_state.update_config(config_store.get()) {
                 Ok(()) => {
                     if let Ok(status) = ipc_client.get_status().await {
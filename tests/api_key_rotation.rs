@@ -0,0 +1,302 @@
+//! Multiple API keys per backend: rotation, cooldown on rate limits and
+//! switching keys on 429.
+
+mod common;
+
+use anyclaude::backend::{KeyRotator, DEFAULT_KEY_COOLDOWN};
+use anyclaude::config::{ApiKeySource, Backend, KeyRotation, RetryConfig};
+use common::mock_backend::{MockBackend, MockResponse};
+use std::time::Duration;
+
+const KEY_A: &str = "sk-test-key-aaaaaaaaaaaa";
+const KEY_B: &str = "sk-test-key-bbbbbbbbbbbb";
+const KEY_C: &str = "sk-test-key-cccccccccccc";
+
+fn create_backend(base_url: &str, keys: &[&str], rotation: KeyRotation) -> Backend {
+    Backend {
+        name: "keyed".to_string(),
+        display_name: "Keyed".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "api_key".to_string(),
+        api_key: Some(keys[0].to_string()),
+        api_keys: keys[1..]
+            .iter()
+            .map(|key| ApiKeySource::Literal(key.to_string()))
+            .collect(),
+        key_rotation: rotation,
+        ..Default::default()
+    }
+}
+
+fn selected(rotator: &KeyRotator, backend: &Backend) -> usize {
+    rotator.select(backend).unwrap().index
+}
+
+#[test]
+fn test_single_key_is_not_rotated() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend("http://localhost", &[KEY_A], KeyRotation::RoundRobin);
+
+    assert!(rotator.select(&backend).is_none());
+    assert!(rotator.status(&backend).is_empty());
+}
+
+#[test]
+fn test_passthrough_backend_is_not_rotated() {
+    let rotator = KeyRotator::new();
+    let backend = Backend {
+        auth_type_str: "passthrough".to_string(),
+        ..create_backend("http://localhost", &[KEY_A, KEY_B], KeyRotation::RoundRobin)
+    };
+
+    assert!(rotator.select(&backend).is_none());
+}
+
+#[test]
+fn test_round_robin_cycles_keys() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend("http://localhost", &[KEY_A, KEY_B, KEY_C], KeyRotation::RoundRobin);
+
+    let picks: Vec<usize> = (0..6).map(|_| selected(&rotator, &backend)).collect();
+    assert_eq!(picks, vec![0, 1, 2, 0, 1, 2]);
+
+    let choice = rotator.select(&backend).unwrap();
+    assert_eq!(choice.key.expose(), KEY_A);
+}
+
+#[test]
+fn test_round_robin_skips_cooling_key() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend("http://localhost", &[KEY_A, KEY_B, KEY_C], KeyRotation::RoundRobin);
+
+    assert_eq!(selected(&rotator, &backend), 0);
+    rotator.throttled("keyed", 1, DEFAULT_KEY_COOLDOWN);
+
+    let picks: Vec<usize> = (0..4).map(|_| selected(&rotator, &backend)).collect();
+    assert!(!picks.contains(&1), "{:?}", picks);
+}
+
+#[test]
+fn test_all_cooling_uses_first_to_recover() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend("http://localhost", &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+
+    selected(&rotator, &backend);
+    rotator.throttled("keyed", 0, Duration::from_secs(30));
+    rotator.throttled("keyed", 1, Duration::from_secs(10));
+
+    assert_eq!(selected(&rotator, &backend), 1);
+    assert!(rotator.next_available(&backend, 1).is_none());
+}
+
+#[test]
+fn test_least_recently_throttled_prefers_unthrottled_keys() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend(
+        "http://localhost",
+        &[KEY_A, KEY_B, KEY_C],
+        KeyRotation::LeastRecentlyThrottled,
+    );
+
+    selected(&rotator, &backend);
+    rotator.throttled("keyed", 0, Duration::from_millis(1));
+    std::thread::sleep(Duration::from_millis(5));
+
+    // Key 0 has recovered but was throttled; 1 and 2 never were.
+    let picks: Vec<usize> = (0..4).map(|_| selected(&rotator, &backend)).collect();
+    assert_eq!(picks, vec![1, 2, 1, 2]);
+
+    rotator.throttled("keyed", 1, Duration::from_millis(1));
+    rotator.throttled("keyed", 2, Duration::from_millis(1));
+    std::thread::sleep(Duration::from_millis(5));
+    assert_eq!(selected(&rotator, &backend), 0);
+}
+
+#[test]
+fn test_unresolvable_key_is_skipped() {
+    let rotator = KeyRotator::new();
+    let mut backend = create_backend("http://localhost", &[KEY_A], KeyRotation::RoundRobin);
+    backend.api_keys = vec![
+        ApiKeySource::Env {
+            env: "ANYCLAUDE_TEST_ROTATION_KEY_UNSET".to_string(),
+        },
+        ApiKeySource::Literal(KEY_C.to_string()),
+    ];
+
+    let picks: Vec<usize> = (0..3).map(|_| selected(&rotator, &backend)).collect();
+    assert_eq!(picks, vec![0, 2, 2]);
+
+    let status = rotator.status(&backend);
+    assert_eq!(status[1].label, "#2 $ANYCLAUDE_TEST_ROTATION_KEY_UNSET");
+    assert!(status[1].error.as_deref().unwrap().contains("is not set"));
+}
+
+#[test]
+fn test_status_reports_usage_and_cooldown() {
+    let rotator = KeyRotator::new();
+    let backend = create_backend("http://localhost", &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+
+    for _ in 0..3 {
+        selected(&rotator, &backend);
+    }
+    rotator.throttled("keyed", 0, Duration::from_secs(30));
+
+    let status = rotator.status(&backend);
+    assert_eq!(status.len(), 2);
+    assert_eq!(status[0].label, "#1 …aaaa");
+    assert_eq!((status[0].requests, status[0].throttles), (2, 1));
+    assert!(status[0].cooling_secs.is_some_and(|secs| secs > 20));
+    assert_eq!((status[1].requests, status[1].throttles), (1, 0));
+    assert_eq!(status[1].cooling_secs, None);
+
+    // Labels never contain the key itself.
+    assert!(status.iter().all(|s| !s.label.contains(KEY_A) && !s.label.contains(KEY_B)));
+}
+
+#[test]
+fn test_api_keys_from_toml() {
+    let toml = r#"
+        name = "keyed"
+        display_name = "Keyed"
+        base_url = "https://api.example.com"
+        auth_type = "api_key"
+        api_key = "sk-primary"
+        key_rotation = "least_recently_throttled"
+        api_keys = [
+            "sk-second",
+            { env = "SECOND_KEY" },
+            { file = "~/.keys/third" },
+            { command = "pass show key", ttl_seconds = 60 },
+        ]
+    "#;
+    let backend: Backend = toml::from_str(toml).unwrap();
+
+    assert_eq!(backend.key_rotation, KeyRotation::LeastRecentlyThrottled);
+    assert_eq!(
+        backend.api_key_sources(),
+        vec![
+            ApiKeySource::Literal("sk-primary".to_string()),
+            ApiKeySource::Literal("sk-second".to_string()),
+            ApiKeySource::Env { env: "SECOND_KEY".to_string() },
+            ApiKeySource::File { file: "~/.keys/third".to_string() },
+            ApiKeySource::Command {
+                command: "pass show key".to_string(),
+                ttl_seconds: Some(60),
+            },
+        ]
+    );
+}
+
+async fn start_proxy(backend: Backend, retry: RetryConfig) -> String {
+    let mut config = common::proxy_config(vec![backend]);
+    config.defaults.max_retries = 0;
+    config.defaults.retry_backoff_base_ms = 10;
+    config.retry = retry;
+    common::start_proxy(config).await.url
+}
+
+async fn send(proxy_url: &str) -> u16 {
+    let body = r#"{"model":"claude-sonnet-4","max_tokens":16,"messages":[{"role":"user","content":"hi"}]}"#;
+    common::post_messages(proxy_url, body, &[("x-api-key", "client-key")])
+        .await
+        .status()
+        .as_u16()
+}
+
+async fn sent_keys(mock: &MockBackend) -> Vec<String> {
+    mock.captured_requests()
+        .await
+        .iter()
+        .map(|req| {
+            let keys: Vec<&String> = req
+                .headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("x-api-key"))
+                .map(|(_, value)| value)
+                .collect();
+            assert_eq!(keys.len(), 1, "one x-api-key header per request");
+            keys[0].clone()
+        })
+        .collect()
+}
+
+/// No status retries, so only key switching can re-send a request.
+fn no_status_retries() -> RetryConfig {
+    RetryConfig {
+        max_status_retries: 0,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_proxy_rotates_keys_round_robin() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend(&mock.base_url(), &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+    let proxy_url = start_proxy(backend, no_status_retries()).await;
+
+    for _ in 0..3 {
+        assert_eq!(send(&proxy_url).await, 200);
+    }
+    assert_eq!(sent_keys(&mock).await, vec![KEY_A, KEY_B, KEY_A]);
+}
+
+#[tokio::test]
+async fn test_proxy_switches_key_on_429() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::error(429, "rate limited")).await;
+    let backend = create_backend(&mock.base_url(), &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+    let proxy_url = start_proxy(backend, no_status_retries()).await;
+
+    assert_eq!(send(&proxy_url).await, 200);
+    // Key A is cooling down, so later requests stay on B.
+    for _ in 0..2 {
+        assert_eq!(send(&proxy_url).await, 200);
+    }
+    assert_eq!(sent_keys(&mock).await, vec![KEY_A, KEY_B, KEY_B, KEY_B]);
+}
+
+#[tokio::test]
+async fn test_proxy_returns_429_when_every_key_is_throttled() {
+    let mock = MockBackend::start().await;
+    for _ in 0..3 {
+        mock.enqueue_response(MockResponse::error(429, "rate limited")).await;
+    }
+    let backend = create_backend(&mock.base_url(), &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+    let proxy_url = start_proxy(backend, no_status_retries()).await;
+
+    assert_eq!(send(&proxy_url).await, 429);
+    assert_eq!(sent_keys(&mock).await, vec![KEY_A, KEY_B]);
+}
+
+#[tokio::test]
+async fn test_exhausted_rate_limit_header_cools_key() {
+    let mock = MockBackend::start().await;
+    let mut exhausted = MockResponse::json(r#"{"type":"message"}"#);
+    exhausted
+        .headers
+        .push(("anthropic-ratelimit-requests-remaining".to_string(), "0".to_string()));
+    mock.enqueue_response(exhausted).await;
+    let backend = create_backend(&mock.base_url(), &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+    let proxy_url = start_proxy(backend, no_status_retries()).await;
+
+    for _ in 0..3 {
+        assert_eq!(send(&proxy_url).await, 200);
+    }
+    assert_eq!(sent_keys(&mock).await, vec![KEY_A, KEY_B, KEY_B]);
+}
+
+#[tokio::test]
+async fn test_set_rule_for_auth_header_wins_over_rotation() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::error(429, "rate limited")).await;
+    let mut backend = create_backend(&mock.base_url(), &[KEY_A, KEY_B], KeyRotation::RoundRobin);
+    backend.headers.set.insert("X-Api-Key".to_string(), KEY_C.to_string());
+    let proxy_url = start_proxy(backend, no_status_retries()).await;
+
+    // The 429 is returned rather than retried with a key that is not sent.
+    assert_eq!(send(&proxy_url).await, 429);
+    for _ in 0..2 {
+        assert_eq!(send(&proxy_url).await, 200);
+    }
+    assert_eq!(sent_keys(&mock).await, vec![KEY_C, KEY_C, KEY_C]);
+}
@@ -0,0 +1,354 @@
+//! Backend group tests: member selection strategies, main-agent pinning,
+//! config validation, IPC listing and proxying through a group.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use serde_json::json;
+
+use anyclaude::backend::{AgentRegistry, BackendState, GroupBalancer};
+use anyclaude::config::{
+    Backend, BackendGroup, Config, DebugLoggingConfig, Defaults, GroupMember, GroupStrategy,
+};
+use anyclaude::ipc::IpcLayer;
+use anyclaude::metrics::{DebugLogger, ObservabilityHub, RequestRecord, RequestSpan};
+use anyclaude::proxy::pipeline::{self, PipelineContext};
+use anyclaude::proxy::shutdown::ShutdownManager;
+use anyclaude::proxy::thinking::TransformerRegistry;
+use common::mock_backend::MockBackend;
+
+fn create_backend(name: &str, base_url: &str) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_uppercase(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        ..Default::default()
+    }
+}
+
+fn create_group(strategy: GroupStrategy, members: &[(&str, u32)]) -> BackendGroup {
+    BackendGroup {
+        name: "pool".to_string(),
+        display_name: Some("Pool".to_string()),
+        strategy,
+        members: members
+            .iter()
+            .map(|(backend, weight)| GroupMember {
+                backend: backend.to_string(),
+                weight: *weight,
+            })
+            .collect(),
+    }
+}
+
+fn create_state(group: BackendGroup) -> BackendState {
+    let config = Config {
+        defaults: Defaults {
+            active: group.name.clone(),
+            ..Default::default()
+        },
+        backends: vec![
+            create_backend("alpha", "https://alpha.example.com"),
+            create_backend("beta", "https://beta.example.com"),
+        ],
+        backend_groups: vec![group],
+        ..Default::default()
+    };
+    BackendState::from_config(config).expect("backend state")
+}
+
+fn create_context(request_id: &str, observability: ObservabilityHub) -> PipelineContext {
+    let record = RequestRecord {
+        id: request_id.to_string(),
+        started_at: SystemTime::now(),
+        first_byte_at: None,
+        completed_at: None,
+        latency_ms: None,
+        ttfb_ms: None,
+        backend: String::new(),
+        session_id: None,
+        status: None,
+        timed_out: false,
+        request_bytes: 0,
+        response_bytes: 0,
+        request_analysis: None,
+        response_analysis: None,
+        usage: None,
+        routing_decision: None,
+        agent: Default::default(),
+        agent_id: None,
+        request_meta: None,
+        response_meta: None,
+        trace: Default::default(),
+    };
+    let debug_logger = Arc::new(DebugLogger::new(DebugLoggingConfig::default()));
+    PipelineContext::new(RequestSpan::new(record), observability, debug_logger)
+}
+
+/// Resolve a request; `teammate` routes it as a teammate override to `pool`.
+fn resolve(backend_state: &BackendState, ctx: &mut PipelineContext, teammate: bool) -> String {
+    pipeline::resolve_backend(
+        backend_state,
+        teammate.then(|| "pool".to_string()),
+        None,
+        Some(&json!({"model": "claude-sonnet-4-5"})),
+        "/v1/messages",
+        &AgentRegistry::new(),
+        ctx,
+    )
+    .expect("resolve")
+    .name
+}
+
+fn select(backend_state: &BackendState, pin: bool) -> String {
+    let group = backend_state.get_group("pool").unwrap();
+    backend_state
+        .select_group_member(&group, pin, |_| None)
+        .unwrap()
+        .name
+}
+
+#[test]
+fn test_weighted_round_robin() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 2), ("beta", 1)],
+    ));
+
+    let picks: Vec<String> = (0..6).map(|_| select(&backend_state, false)).collect();
+    assert_eq!(picks, ["alpha", "alpha", "beta", "alpha", "alpha", "beta"]);
+}
+
+#[test]
+fn test_weighted_random_respects_weights() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::WeightedRandom,
+        &[("alpha", 1), ("beta", 3)],
+    ));
+
+    let beta = (0..400)
+        .filter(|_| select(&backend_state, false) == "beta")
+        .count();
+    assert!((220..380).contains(&beta), "beta picked {} times", beta);
+}
+
+#[test]
+fn test_least_in_flight() {
+    let balancer = GroupBalancer::new();
+    let members = [("alpha", 1), ("beta", 1)];
+    let pick = || {
+        balancer
+            .select("pool", GroupStrategy::LeastInFlight, &members, false, |_| None)
+            .unwrap()
+    };
+
+    assert_eq!(pick(), "alpha");
+    balancer.begin("req-1", "alpha");
+    assert_eq!(pick(), "beta");
+    balancer.begin("req-2", "beta");
+    balancer.begin("req-3", "beta");
+    assert_eq!(balancer.in_flight("beta"), 2);
+    assert_eq!(pick(), "alpha");
+}
+
+#[test]
+fn test_lowest_latency_prefers_unmeasured_then_fastest() {
+    let balancer = GroupBalancer::new();
+    let members = [("alpha", 1), ("beta", 1)];
+    let pick = |latency: fn(&str) -> Option<u64>| {
+        balancer
+            .select("pool", GroupStrategy::LowestLatency, &members, false, latency)
+            .unwrap()
+    };
+
+    assert_eq!(pick(|name| (name == "alpha").then_some(100)), "beta");
+    assert_eq!(pick(|name| Some(if name == "alpha" { 300 } else { 200 })), "beta");
+    assert_eq!(pick(|name| Some(if name == "alpha" { 150 } else { 200 })), "alpha");
+}
+
+#[test]
+fn test_open_circuit_member_skipped() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 1), ("beta", 1)],
+    ));
+    for _ in 0..5 {
+        backend_state.circuit_breakers().record("alpha", true);
+    }
+
+    assert!((0..4).all(|_| select(&backend_state, false) == "beta"));
+}
+
+#[test]
+fn test_main_agent_pinned_teammates_balanced() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 1), ("beta", 1)],
+    ));
+    let observability = ObservabilityHub::new(10);
+
+    let main: Vec<String> = (0..4)
+        .map(|i| resolve(&backend_state, &mut create_context(&format!("m{i}"), observability.clone()), false))
+        .collect();
+    assert_eq!(main, ["alpha"; 4]);
+    assert_eq!(backend_state.group_balancer().pinned("pool").as_deref(), Some("alpha"));
+
+    let teammate: Vec<String> = (0..2)
+        .map(|i| resolve(&backend_state, &mut create_context(&format!("t{i}"), observability.clone()), true))
+        .collect();
+    assert_eq!(teammate, ["beta", "alpha"]);
+
+    let mut ctx = create_context("m-last", observability);
+    resolve(&backend_state, &mut ctx, false);
+    let decision = ctx.span.record_mut().routing_decision.take().unwrap();
+    assert_eq!(decision.backend, "alpha");
+    assert_eq!(decision.reason, "active backend via group pool");
+}
+
+#[test]
+fn test_pinned_member_replaced_when_circuit_opens() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 1), ("beta", 1)],
+    ));
+    assert_eq!(select(&backend_state, true), "alpha");
+
+    for _ in 0..5 {
+        backend_state.circuit_breakers().record("alpha", true);
+    }
+    assert_eq!(select(&backend_state, true), "beta");
+    assert_eq!(backend_state.group_balancer().pinned("pool").as_deref(), Some("beta"));
+}
+
+#[test]
+fn test_finished_request_released() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::LeastInFlight,
+        &[("alpha", 1), ("beta", 1)],
+    ));
+    let balancer = backend_state.group_balancer();
+    let observability = ObservabilityHub::new(10).with_plugins(vec![Arc::new(balancer.clone())]);
+
+    let mut ctx = create_context("req-1", observability.clone());
+    let backend = resolve(&backend_state, &mut ctx, true);
+    assert_eq!(balancer.in_flight(&backend), 1);
+
+    observability.finish_request(ctx.span.clone());
+    assert_eq!(balancer.in_flight(&backend), 0);
+}
+
+#[test]
+fn test_switch_to_group() {
+    let backend_state = create_state(create_group(GroupStrategy::RoundRobin, &[("alpha", 1)]));
+
+    backend_state.switch_backend("alpha").unwrap();
+    backend_state.switch_backend("pool").unwrap();
+    assert_eq!(backend_state.get_active_backend(), "pool");
+    assert!(backend_state.validate_backend("pool"));
+    assert!(backend_state.switch_backend("missing").is_err());
+}
+
+#[test]
+fn test_backend_groups_from_toml() {
+    let toml = r#"
+[defaults]
+active = "pool"
+timeout_seconds = 30
+
+[[backends]]
+name = "alpha"
+display_name = "Alpha"
+base_url = "https://alpha.example.com"
+auth_type = "bearer"
+api_key = "key"
+
+[[backends]]
+name = "beta"
+display_name = "Beta"
+base_url = "https://beta.example.com"
+auth_type = "bearer"
+api_key = "key"
+
+[[backend_groups]]
+name = "pool"
+strategy = "least_in_flight"
+members = [
+    { backend = "alpha", weight = 3 },
+    { backend = "beta" },
+]
+"#;
+    let config: Config = toml::from_str(toml).unwrap();
+    let group = &config.backend_groups[0];
+    assert_eq!(group.strategy, GroupStrategy::LeastInFlight);
+    assert_eq!(group.display_name(), "pool");
+    assert_eq!(group.members[0].weight, 3);
+    assert_eq!(group.members[1].weight, 1);
+    config.validate().unwrap();
+
+    let unknown_member: Config = toml::from_str(&toml.replace("backend = \"beta\"", "backend = \"gamma\"")).unwrap();
+    let err = unknown_member.validate().unwrap_err();
+    assert!(err.to_string().contains("member 'gamma' not found"), "{}", err);
+
+    let clash: Config = toml::from_str(&toml.replace("name = \"pool\"", "name = \"alpha\"").replace("active = \"pool\"", "active = \"alpha\"")).unwrap();
+    let err = clash.validate().unwrap_err();
+    assert!(err.to_string().contains("same name as a backend"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_ipc_lists_groups() {
+    let backend_state = create_state(create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 1), ("beta", 1)],
+    ));
+    let debug_logger = Arc::new(DebugLogger::new(DebugLoggingConfig::default()));
+    let (client, server) = IpcLayer::create();
+    let server_task = tokio::spawn(server.run(
+        backend_state,
+        ObservabilityHub::new(10),
+        debug_logger,
+        Arc::new(ShutdownManager::new()),
+        Instant::now(),
+        Arc::new(TransformerRegistry::new()),
+    ));
+
+    let backends = client.list_backends().await.expect("backends");
+    let pool = backends.iter().find(|b| b.id == "pool").unwrap();
+    assert_eq!(pool.display_name, "Pool");
+    assert!(pool.is_active);
+    assert_eq!(pool.members, ["alpha", "beta"]);
+    assert!(backends.iter().filter(|b| b.id != "pool").all(|b| b.members.is_empty()));
+
+    drop(client);
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn test_proxy_through_group() {
+    let alpha = MockBackend::start().await;
+    let beta = MockBackend::start().await;
+
+    let mut config = common::proxy_config(vec![
+        create_backend("alpha", &alpha.base_url()),
+        create_backend("beta", &beta.base_url()),
+    ]);
+    config.defaults.active = "pool".to_string();
+    config.defaults.max_retries = 0;
+    config.backend_groups = vec![create_group(
+        GroupStrategy::RoundRobin,
+        &[("alpha", 1), ("beta", 1)],
+    )];
+    let proxy = common::start_proxy(config).await;
+
+    for _ in 0..3 {
+        let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    // Main-agent requests stay on the first member; all were released
+    assert_eq!(alpha.captured_requests().await.len(), 3);
+    assert!(beta.captured_requests().await.is_empty());
+    assert_eq!(proxy.backend_state.group_balancer().in_flight("alpha"), 0);
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use anyclaude::backend::{BackendError, BackendState};
+use anyclaude::backend::{BackendError, BackendState, SWITCH_LOG_CAPACITY};
 use anyclaude::config::{Backend, Config, Defaults, ProxyConfig, TerminalConfig, DebugLoggingConfig};
 use std::collections::HashMap;
 
@@ -33,6 +33,7 @@ fn create_test_config() -> Config {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "backend2".to_string(),
@@ -46,9 +47,11 @@ fn create_test_config() -> Config {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
         ],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -121,6 +124,24 @@ fn test_switch_backend_same_noop() {
     assert_eq!(state.get_switch_log().len(), 1);
 }
 
+#[test]
+fn test_switch_log_is_capped() {
+    let config = create_test_config();
+    let state = BackendState::from_config(config).unwrap();
+
+    for _ in 0..SWITCH_LOG_CAPACITY * 2 {
+        state.record_failover("backend1", "backend2");
+    }
+    state.switch_backend("backend2").unwrap();
+
+    let log = state.get_switch_log();
+    assert_eq!(log.len(), SWITCH_LOG_CAPACITY);
+    assert!(log[0].failover, "oldest entries dropped first");
+    let last = log.last().unwrap();
+    assert!(!last.failover);
+    assert_eq!(last.new_backend, "backend2");
+}
+
 #[test]
 fn test_switch_log() {
     let config = create_test_config();
@@ -149,6 +170,17 @@ fn test_validate_backend() {
     assert!(!state.validate_backend("nonexistent"));
 }
 
+#[test]
+fn test_replay_treats_backends_as_configured() {
+    let config = create_test_config();
+    let state = BackendState::from_config(config).unwrap();
+    let backend = state.get_backend_config("backend1").unwrap();
+
+    assert!(!state.is_configured(&backend));
+    state.clone().set_replay(true);
+    assert!(state.is_configured(&backend));
+}
+
 #[test]
 fn test_list_backends() {
     let config = create_test_config();
@@ -192,6 +224,7 @@ fn test_update_config() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     });
 
     state.update_config(new_config).unwrap();
@@ -0,0 +1,383 @@
+//! `[budgets]`: spend tracking, warnings, rejection and fallback.
+
+mod common;
+
+use anyclaude::backend::{BudgetScope, Budgets};
+use anyclaude::config::{Backend, BackendPricing, BudgetLimit, BudgetsConfig, Config};
+use anyclaude::error::ErrorSeverity;
+use anyclaude::metrics::ledger::LedgerEntry;
+use anyclaude::metrics::TokenUsage;
+use common::mock_backend::{MockBackend, MockResponse};
+use common::TestProxy;
+use serde_json::Value;
+
+/// 1000 input + 500 output tokens at $1/$2 per million: $0.002.
+const USAGE_BODY: &str = r#"{"type":"message","usage":{"input_tokens":1000,"output_tokens":500}}"#;
+
+fn backend(name: &str, base_url: &str) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        pricing: Some(BackendPricing {
+            input_per_million: 1.0,
+            output_per_million: 2.0,
+            cache_read_per_million: None,
+            cache_write_per_million: None,
+        }),
+        ..Default::default()
+    }
+}
+
+async fn start_proxy(backends: Vec<Backend>, budgets: BudgetsConfig) -> TestProxy {
+    common::start_proxy(Config {
+        budgets,
+        ..common::proxy_config(backends)
+    })
+    .await
+}
+
+async fn send_message(proxy_url: &str, session: &str) -> (u16, Value) {
+    let body = r#"{"model":"claude-sonnet-4-5","max_tokens":16,"messages":[]}"#;
+    let response = common::post_messages(proxy_url, body, &[("x-claude-code-session-id", session)]).await;
+    let status = response.status().as_u16();
+    let body = response.bytes().await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn usage(input_tokens: u64, output_tokens: u64, cost_usd: f64) -> TokenUsage {
+    TokenUsage {
+        input_tokens,
+        output_tokens,
+        cost_usd: Some(cost_usd),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn integration_session_limit_rejects() {
+    let mock = MockBackend::start().await;
+    for _ in 0..2 {
+        mock.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    }
+    let budgets = BudgetsConfig {
+        session: Some(BudgetLimit {
+            usd: Some(0.002),
+            tokens: None,
+        }),
+        ..Default::default()
+    };
+    let proxy = start_proxy(vec![backend("mock", &mock.base_url())], budgets).await;
+
+    assert_eq!(send_message(&proxy.url, "session-1").await.0, 200);
+    let (status, body) = send_message(&proxy.url, "session-1").await;
+
+    assert_eq!(status, 402);
+    assert_eq!(body["type"], "error");
+    assert_eq!(body["error"]["type"], "billing_error");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("session session-1 budget of $0.00 reached"), "{}", message);
+    assert_eq!(mock.captured_requests().await.len(), 1);
+
+    // Other sessions are unaffected.
+    assert_eq!(send_message(&proxy.url, "session-2").await.0, 200);
+
+    let current = proxy.error_registry.current_error().unwrap();
+    assert_eq!(current.severity, ErrorSeverity::Error);
+    assert!(current.message.contains("rejecting requests"), "{}", current.message);
+}
+
+#[tokio::test]
+async fn integration_day_limit_switches_to_fallback() {
+    let primary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let cheap = MockBackend::start().await;
+    cheap.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let budgets = BudgetsConfig {
+        day: Some(BudgetLimit {
+            usd: None,
+            tokens: Some(1500),
+        }),
+        fallback_backend: Some("cheap".to_string()),
+        ..Default::default()
+    };
+    let proxy = start_proxy(
+        vec![backend("primary", &primary.base_url()), backend("cheap", &cheap.base_url())],
+        budgets,
+    )
+    .await;
+
+    assert_eq!(send_message(&proxy.url, "session-1").await.0, 200);
+    assert_eq!(send_message(&proxy.url, "session-1").await.0, 200);
+
+    assert_eq!(primary.captured_requests().await.len(), 1);
+    assert_eq!(cheap.captured_requests().await.len(), 1);
+    let current = proxy.error_registry.current_error().unwrap();
+    assert_eq!(current.severity, ErrorSeverity::Warning);
+    assert!(current.message.contains("switching to 'cheap'"), "{}", current.message);
+}
+
+#[tokio::test]
+async fn integration_fallback_over_its_own_limit_rejects() {
+    let primary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let cheap = MockBackend::start().await;
+    cheap.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let budgets = BudgetsConfig {
+        day: Some(BudgetLimit {
+            usd: Some(0.001),
+            tokens: None,
+        }),
+        backends: [(
+            "cheap".to_string(),
+            BudgetLimit {
+                usd: Some(0.001),
+                tokens: None,
+            },
+        )]
+        .into(),
+        fallback_backend: Some("cheap".to_string()),
+        ..Default::default()
+    };
+    let proxy = start_proxy(
+        vec![backend("primary", &primary.base_url()), backend("cheap", &cheap.base_url())],
+        budgets,
+    )
+    .await;
+
+    assert_eq!(send_message(&proxy.url, "s").await.0, 200);
+    assert_eq!(send_message(&proxy.url, "s").await.0, 200);
+    let (status, body) = send_message(&proxy.url, "s").await;
+
+    assert_eq!(status, 402);
+    assert!(body["error"]["message"].as_str().unwrap().contains("daily budget"));
+    assert_eq!(cheap.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn integration_failover_hop_over_its_limit_rejects() {
+    let primary = MockBackend::start().await;
+    for _ in 0..2 {
+        primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    }
+    let backup = MockBackend::start().await;
+    backup.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let budgets = BudgetsConfig {
+        backends: [(
+            "backup".to_string(),
+            BudgetLimit {
+                usd: None,
+                tokens: Some(1000),
+            },
+        )]
+        .into(),
+        ..Default::default()
+    };
+    let mut first = backend("primary", &primary.base_url());
+    first.failover = vec!["backup".to_string()];
+    let proxy = start_proxy(vec![first, backend("backup", &backup.base_url())], budgets).await;
+
+    // The first failover spends the backup's budget; the second hop is
+    // not sent.
+    assert_eq!(send_message(&proxy.url, "s").await.0, 200);
+    let (status, body) = send_message(&proxy.url, "s").await;
+
+    assert_eq!(status, 402);
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("backend 'backup' daily budget"), "{}", message);
+    assert_eq!(primary.captured_requests().await.len(), 2);
+    assert_eq!(backup.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn integration_soft_threshold_warns() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(USAGE_BODY)).await;
+    let budgets = BudgetsConfig {
+        backends: [(
+            "mock".to_string(),
+            BudgetLimit {
+                usd: Some(0.0025),
+                tokens: None,
+            },
+        )]
+        .into(),
+        ..Default::default()
+    };
+    let proxy = start_proxy(vec![backend("mock", &mock.base_url())], budgets).await;
+
+    assert_eq!(send_message(&proxy.url, "session-1").await.0, 200);
+
+    let warnings: Vec<_> = proxy
+        .error_registry
+        .all_errors()
+        .into_iter()
+        .filter(|e| e.message.starts_with("Budget:"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert_eq!(warnings[0].severity, ErrorSeverity::Warning);
+    assert!(warnings[0].message.contains("80% of $0.00"), "{}", warnings[0].message);
+}
+
+#[test]
+fn test_budgets_track_scopes() {
+    let budgets = Budgets::new(BudgetsConfig {
+        session: Some(BudgetLimit {
+            usd: None,
+            tokens: Some(100),
+        }),
+        day: Some(BudgetLimit {
+            usd: Some(1.0),
+            tokens: None,
+        }),
+        ..Default::default()
+    });
+
+    budgets.record(Some("a"), "x", &usage(60, 0, 0.25));
+    budgets.record(None, "y", &usage(10, 5, 0.5));
+
+    let spend = budgets.spend(&BudgetScope::Session("a".to_string()));
+    assert_eq!(spend.tokens, 60);
+    assert_eq!(budgets.spend(&BudgetScope::Day).tokens, 75);
+    assert!((budgets.spend(&BudgetScope::Day).usd - 0.75).abs() < 1e-9);
+    assert_eq!(budgets.spend(&BudgetScope::Backend("y".to_string())).tokens, 15);
+    assert!(budgets.exceeded(Some("a"), "x").is_none());
+
+    budgets.record(Some("a"), "x", &usage(20, 20, 0.0));
+    let exceeded = budgets.exceeded(Some("a"), "x").unwrap();
+    assert_eq!(exceeded.scope, BudgetScope::Session("a".to_string()));
+    assert!(budgets.exceeded(Some("b"), "x").is_none());
+
+    budgets.record(Some("b"), "x", &usage(0, 0, 0.25));
+    let exceeded = budgets.exceeded(Some("b"), "x").unwrap();
+    assert_eq!(exceeded.scope, BudgetScope::Day);
+    assert_eq!(exceeded.to_string(), "daily budget of $1.00 reached (spent $1.00, 115 tokens)");
+}
+
+#[test]
+fn test_budgets_ignore_usage_without_limits() {
+    let budgets = Budgets::new(BudgetsConfig::default());
+    budgets.record(Some("a"), "x", &usage(60, 0, 0.25));
+    assert_eq!(budgets.spend(&BudgetScope::Day).tokens, 0);
+    assert!(budgets.exceeded(Some("a"), "x").is_none());
+}
+
+#[test]
+fn test_budgets_seed_from_ledger() {
+    let budgets = Budgets::new(BudgetsConfig {
+        day: Some(BudgetLimit {
+            usd: Some(1.0),
+            tokens: None,
+        }),
+        ..Default::default()
+    });
+    let entry = |ts: &str, cost: f64| LedgerEntry {
+        ts: ts.to_string(),
+        request_id: "req".to_string(),
+        session_id: Some("s1".to_string()),
+        backend: "x".to_string(),
+        model: None,
+        upstream_model: None,
+        status: Some(200),
+        input_tokens: 10,
+        output_tokens: 5,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 5,
+        cost_usd: Some(cost),
+        latency_ms: None,
+    };
+
+    budgets.seed(&[entry("2020-01-01T00:00:00Z", 5.0), entry("2020-01-02T00:00:00Z", 0.5)]);
+
+    // Past days only count towards their sessions.
+    let spend = budgets.spend(&BudgetScope::Session("s1".to_string()));
+    assert_eq!(spend.tokens, 40);
+    assert!((spend.usd - 5.5).abs() < 1e-9);
+    assert_eq!(budgets.spend(&BudgetScope::Day).tokens, 0);
+    assert_eq!(budgets.spend(&BudgetScope::Backend("x".to_string())).tokens, 0);
+    assert!(budgets.exceeded(None, "x").is_none());
+}
+
+#[test]
+fn test_budgets_from_toml() {
+    let toml = r#"
+        warn_at = 0.9
+        fallback_backend = "cheap"
+
+        [session]
+        usd = 5.0
+
+        [day]
+        usd = 50.0
+        tokens = 20000000
+
+        [backends.anthropic]
+        usd = 30.0
+    "#;
+    let budgets: BudgetsConfig = toml::from_str(toml).unwrap();
+
+    assert!(budgets.is_enabled());
+    assert_eq!(budgets.warn_at, 0.9);
+    assert_eq!(budgets.session.unwrap().usd, Some(5.0));
+    assert_eq!(budgets.day.unwrap().tokens, Some(20_000_000));
+    assert_eq!(budgets.backends["anthropic"].usd, Some(30.0));
+    assert_eq!(budgets.fallback_backend.as_deref(), Some("cheap"));
+
+    let budgets = Config::default().budgets;
+    assert!(!budgets.is_enabled());
+    assert_eq!(budgets.warn_at, 0.8);
+}
+
+#[test]
+fn test_budgets_validation() {
+    let mut config = Config::default();
+    config.backends[0].name = "main".to_string();
+    config.defaults.active = "main".to_string();
+    config.budgets.fallback_backend = Some("main".to_string());
+    config.validate().unwrap();
+
+    config.budgets.fallback_backend = Some("missing".to_string());
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("fallback_backend 'missing'"), "{}", err);
+
+    config.budgets.fallback_backend = None;
+    config.budgets.backends.insert("missing".to_string(), BudgetLimit::default());
+    assert!(config.validate().unwrap_err().to_string().contains("Budget backend 'missing'"));
+
+    config.budgets.backends.clear();
+    config.budgets.warn_at = 1.5;
+    assert!(config.validate().unwrap_err().to_string().contains("warn_at"));
+}
+
+#[test]
+fn test_budgets_usd_limit_requires_pricing() {
+    let mut config = Config::default();
+    config.backends[0].name = "main".to_string();
+    config.defaults.active = "main".to_string();
+    let usd = BudgetLimit { usd: Some(5.0), tokens: None };
+    let tokens = BudgetLimit { usd: None, tokens: Some(1000) };
+
+    // Token limits need no pricing
+    config.budgets.backends.insert("main".to_string(), tokens);
+    config.budgets.day = Some(tokens);
+    config.validate().unwrap();
+
+    config.budgets.backends.insert("main".to_string(), usd);
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("'main' has a usd limit but the backend has no pricing"), "{}", err);
+
+    config.budgets.backends.clear();
+    config.budgets.session = Some(usd);
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("budgets.session.usd"), "{}", err);
+
+    config.backends[0].pricing = Some(BackendPricing {
+        input_per_million: 1.0,
+        output_per_million: 2.0,
+        cache_read_per_million: None,
+        cache_write_per_million: None,
+    });
+    config.budgets.backends.insert("main".to_string(), usd);
+    config.validate().unwrap();
+}
@@ -0,0 +1,251 @@
+//! Circuit breaker tests: state machine, IPC exposure, and routing skip.
+
+mod common;
+
+use anyclaude::backend::{BackendState, CircuitBreakers, CircuitState};
+use anyclaude::config::{Backend, CircuitBreakerConfig, Config, DebugLoggingConfig, Defaults};
+use anyclaude::ipc::IpcLayer;
+use anyclaude::metrics::{DebugLogger, ObservabilityHub};
+use anyclaude::proxy::shutdown::ShutdownManager;
+use anyclaude::proxy::thinking::TransformerRegistry;
+use common::mock_backend::{MockBackend, MockResponse};
+use std::sync::Arc;
+use std::time::Instant;
+
+fn breaker_config(consecutive_failures: u32, open_seconds: u64) -> CircuitBreakerConfig {
+    CircuitBreakerConfig {
+        consecutive_failures,
+        open_seconds,
+        ..Default::default()
+    }
+}
+
+fn create_backend(name: &str, base_url: &str, failover: &[&str]) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_uppercase(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        failover: failover.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_consecutive_failures_open_circuit() {
+    let breakers = CircuitBreakers::new(breaker_config(3, 30));
+
+    breakers.record("a", true);
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::Closed);
+
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::Open);
+    assert!(!breakers.allows("a"));
+
+    // Other backends are unaffected
+    assert_eq!(breakers.state("b"), CircuitState::Closed);
+}
+
+#[test]
+fn test_success_resets_consecutive_failures() {
+    let breakers = CircuitBreakers::new(breaker_config(3, 30));
+
+    breakers.record("a", true);
+    breakers.record("a", true);
+    breakers.record("a", false);
+    breakers.record("a", true);
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::Closed);
+}
+
+#[test]
+fn test_error_rate_over_window_opens_circuit() {
+    let breakers = CircuitBreakers::new(CircuitBreakerConfig {
+        consecutive_failures: 100,
+        error_rate: 0.5,
+        window_size: 10,
+        min_requests: 4,
+        ..Default::default()
+    });
+
+    // Alternating outcomes never hit the consecutive threshold
+    breakers.record("a", true);
+    breakers.record("a", false);
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::Closed, "below min_requests");
+
+    breakers.record("a", false);
+    assert_eq!(breakers.state("a"), CircuitState::Open);
+}
+
+#[test]
+fn test_half_open_probe_closes_or_reopens() {
+    let breakers = CircuitBreakers::new(breaker_config(1, 0));
+
+    breakers.record("a", true);
+    // open_seconds = 0: cool-down is over immediately
+    assert_eq!(breakers.state("a"), CircuitState::HalfOpen);
+    assert!(breakers.allows("a"));
+
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::HalfOpen, "re-opened, then cooled down");
+
+    breakers.record("a", false);
+    assert_eq!(breakers.state("a"), CircuitState::Closed);
+}
+
+#[test]
+fn test_half_open_admits_single_concurrent_probe() {
+    let breakers = CircuitBreakers::new(breaker_config(1, 1));
+    breakers.record("a", true);
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    assert_eq!(breakers.state("a"), CircuitState::HalfOpen);
+
+    let barrier = Arc::new(std::sync::Barrier::new(8));
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let breakers = breakers.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                breakers.allows("a")
+            })
+        })
+        .collect();
+    let admitted = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .filter(|allowed| *allowed)
+        .count();
+    assert_eq!(admitted, 1, "only one probe while half-open");
+    assert!(!breakers.allows("a"), "probe still in flight");
+
+    // The probe's outcome settles the circuit
+    breakers.record("a", false);
+    assert_eq!(breakers.state("a"), CircuitState::Closed);
+    assert!(breakers.allows("a"));
+}
+
+#[test]
+fn test_disabled_breaker_stays_closed() {
+    let breakers = CircuitBreakers::new(CircuitBreakerConfig {
+        enabled: false,
+        consecutive_failures: 1,
+        ..Default::default()
+    });
+
+    breakers.record("a", true);
+    breakers.record("a", true);
+    assert_eq!(breakers.state("a"), CircuitState::Closed);
+}
+
+#[test]
+fn test_circuit_breaker_config_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "claude"
+timeout_seconds = 30
+
+[circuit_breaker]
+consecutive_failures = 2
+open_seconds = 5
+
+[[backends]]
+name = "claude"
+display_name = "Claude"
+base_url = "https://api.anthropic.com"
+auth_type = "passthrough"
+"#,
+    )
+    .unwrap();
+
+    assert!(config.circuit_breaker.enabled);
+    assert_eq!(config.circuit_breaker.consecutive_failures, 2);
+    assert_eq!(config.circuit_breaker.open_seconds, 5);
+    assert_eq!(config.circuit_breaker.window_size, 20);
+}
+
+#[tokio::test]
+async fn test_list_backends_reports_circuit_state() {
+    let config = Config {
+        defaults: Defaults {
+            active: "alpha".to_string(),
+            ..Default::default()
+        },
+        backends: vec![
+            create_backend("alpha", "https://alpha.example.com", &[]),
+            create_backend("beta", "https://beta.example.com", &[]),
+        ],
+        circuit_breaker: breaker_config(1, 30),
+        ..Default::default()
+    };
+    let backend_state = BackendState::from_config(config).expect("backend state");
+    backend_state.circuit_breakers().record("beta", true);
+
+    let debug_logger = Arc::new(DebugLogger::new(DebugLoggingConfig::default()));
+    let (client, server) = IpcLayer::create();
+    let server_task = tokio::spawn(server.run(
+        backend_state,
+        ObservabilityHub::new(10),
+        debug_logger,
+        Arc::new(ShutdownManager::new()),
+        Instant::now(),
+        Arc::new(TransformerRegistry::new()),
+    ));
+
+    let backends = client.list_backends().await.expect("backends");
+    let alpha = backends.iter().find(|b| b.id == "alpha").unwrap();
+    let beta = backends.iter().find(|b| b.id == "beta").unwrap();
+    assert_eq!(alpha.circuit, CircuitState::Closed);
+    assert_eq!(beta.circuit, CircuitState::Open);
+
+    drop(client);
+    let _ = server_task.await;
+}
+
+#[tokio::test]
+async fn test_routing_skips_open_circuit_when_failover_exists() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+
+    let mut config = common::proxy_config(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ]);
+    config.defaults.max_retries = 0;
+    config.circuit_breaker = breaker_config(2, 60);
+    let proxy = common::start_proxy(config).await;
+
+    for _ in 0..3 {
+        let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    // Two failed attempts trip the circuit; the third request skips primary
+    assert_eq!(primary.captured_requests().await.len(), 2);
+    assert_eq!(secondary.captured_requests().await.len(), 3);
+    assert_eq!(
+        proxy.backend_state.circuit_breakers().state("primary"),
+        CircuitState::Open
+    );
+}
+
+#[tokio::test]
+async fn test_open_circuit_without_failover_still_routes() {
+    let primary = MockBackend::start().await;
+
+    let config = Config {
+        circuit_breaker: breaker_config(1, 60),
+        ..common::proxy_config(vec![create_backend("primary", &primary.base_url(), &[])])
+    };
+    let proxy = common::start_proxy(config).await;
+    proxy.backend_state.circuit_breakers().record("primary", true);
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(primary.captured_requests().await.len(), 1);
+}
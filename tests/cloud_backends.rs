@@ -0,0 +1,469 @@
+//! Tests for Anthropic models hosted on AWS Bedrock (`auth_type = "bedrock"`)
+//! and Google Vertex AI (`auth_type = "vertex"`).
+//!
+//! Unit tests for SigV4 signing, path/body shaping and event stream
+//! decoding, plus integration tests through the full proxy pipeline against
+//! a stub backend that re-validates the request signature.
+
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyclaude::config::{build_auth_header, AwsCredentials, Backend, CredentialStatus, SecureString};
+use anyclaude::proxy::bedrock;
+use anyclaude::proxy::sigv4::{sign, SigningParams};
+use anyclaude::proxy::vertex;
+use axum::body::Bytes;
+use base64::Engine;
+use reqwest::Url;
+use serde_json::{json, Value};
+
+const ACCESS_KEY_ID: &str = "AKIDEXAMPLE";
+const SECRET_ACCESS_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+
+fn credentials(session_token: Option<&str>) -> AwsCredentials {
+    AwsCredentials {
+        access_key_id: ACCESS_KEY_ID.to_string(),
+        secret_access_key: SecureString::new(SECRET_ACCESS_KEY.to_string()),
+        session_token: session_token.map(|t| SecureString::new(t.to_string())),
+    }
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: SigV4
+// ---------------------------------------------------------------------------
+
+/// The GET ListUsers example from the AWS Signature Version 4 documentation.
+#[test]
+fn sigv4_matches_aws_reference_example() {
+    let url = Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+    let credentials = credentials(None);
+    let signed = sign(
+        "GET",
+        &url,
+        &[(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+        )],
+        b"",
+        &SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "iam",
+            time: UNIX_EPOCH + Duration::from_secs(1_440_938_160), // 2015-08-30T12:36:00Z
+        },
+    );
+
+    assert_eq!(header(&signed, "x-amz-date"), Some("20150830T123600Z"));
+    assert_eq!(
+        header(&signed, "authorization"),
+        Some(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        )
+    );
+    assert!(header(&signed, "x-amz-security-token").is_none());
+}
+
+#[test]
+fn sigv4_signs_session_token_and_body() {
+    let url = Url::parse("https://bedrock-runtime.us-east-1.amazonaws.com/model/m%3A0/invoke").unwrap();
+    let credentials = credentials(Some("session-token"));
+    let params = SigningParams {
+        credentials: &credentials,
+        region: "us-east-1",
+        service: "bedrock",
+        time: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    };
+
+    let a = sign("POST", &url, &[], b"{\"a\":1}", &params);
+    let b = sign("POST", &url, &[], b"{\"a\":2}", &params);
+
+    assert_eq!(header(&a, "x-amz-security-token"), Some("session-token"));
+    let auth = header(&a, "authorization").unwrap();
+    assert!(auth.contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+    assert!(auth.contains("/20231114/us-east-1/bedrock/aws4_request"));
+    assert_ne!(auth, header(&b, "authorization").unwrap(), "body is covered");
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: credentials and auth headers
+// ---------------------------------------------------------------------------
+
+fn bedrock_backend(base_url: &str) -> Backend {
+    Backend {
+        name: "bedrock".to_string(),
+        display_name: "Bedrock".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "bedrock".to_string(),
+        aws_region: Some("us-east-1".to_string()),
+        aws_access_key_id: Some(ACCESS_KEY_ID.to_string()),
+        aws_secret_access_key: Some(SECRET_ACCESS_KEY.to_string()),
+        model_sonnet: Some("anthropic.claude-sonnet-4-5-20250929-v1:0".to_string()),
+        ..Default::default()
+    }
+}
+
+fn vertex_backend(base_url: &str, token_command: &str) -> Backend {
+    Backend {
+        name: "vertex".to_string(),
+        display_name: "Vertex".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "vertex".to_string(),
+        vertex_project: Some("my-project".to_string()),
+        vertex_region: Some("us-east5".to_string()),
+        token_command: Some(token_command.to_string()),
+        model_sonnet: Some("claude-sonnet-4-5@20250929".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn bedrock_credentials_and_region() {
+    let backend = bedrock_backend("https://bedrock-runtime.eu-west-1.amazonaws.com");
+    assert!(backend.is_configured());
+    assert!(build_auth_header(&backend).is_none(), "bedrock is signed, not a static header");
+    assert_eq!(backend.aws_region().as_deref(), Some("us-east-1"));
+
+    let from_host = Backend { aws_region: None, ..backend.clone() };
+    if std::env::var("AWS_REGION").is_err() && std::env::var("AWS_DEFAULT_REGION").is_err() {
+        assert_eq!(from_host.aws_region().as_deref(), Some("eu-west-1"));
+    }
+
+    if std::env::var("AWS_ACCESS_KEY_ID").is_err() {
+        let missing = Backend { aws_access_key_id: None, ..backend };
+        assert!(matches!(
+            missing.resolve_credential(),
+            CredentialStatus::Unconfigured { .. }
+        ));
+    }
+}
+
+#[tokio::test]
+async fn vertex_token_from_command() {
+    let backend = vertex_backend("https://us-east5-aiplatform.googleapis.com", "echo ' vertex-token '");
+    // Not run until a refresh.
+    assert!(matches!(backend.resolve_credential(), CredentialStatus::Pending));
+    assert!(backend.is_configured());
+    backend.refresh_credentials().await;
+    assert_eq!(
+        build_auth_header(&backend),
+        Some(("Authorization".to_string(), "Bearer vertex-token".to_string()))
+    );
+
+    let failing = vertex_backend("https://example.com", "echo nope >&2; exit 3");
+    failing.refresh_credentials().await;
+    match failing.resolve_credential() {
+        CredentialStatus::Unconfigured { reason } => assert!(reason.contains("nope"), "{}", reason),
+        other => panic!("expected unconfigured, got {:?}", other),
+    }
+
+    let no_project = Backend { vertex_project: None, ..backend };
+    assert!(!no_project.is_configured());
+}
+
+#[tokio::test]
+async fn vertex_token_failures_back_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let runs = dir.path().join("runs");
+    let command = format!("echo run >> {}; exit 1", runs.display());
+    let backend = vertex_backend("https://example.com", &command);
+
+    // Concurrent and repeated refreshes share the one failed run.
+    tokio::join!(backend.refresh_credentials(), backend.refresh_credentials());
+    backend.refresh_credentials().await;
+    assert_eq!(std::fs::read_to_string(&runs).unwrap().lines().count(), 1);
+    assert!(!backend.is_configured());
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: request shaping
+// ---------------------------------------------------------------------------
+
+#[test]
+fn bedrock_path_and_body() {
+    assert_eq!(
+        bedrock::request_path("anthropic.claude-sonnet-4-5-20250929-v1:0", false),
+        "/model/anthropic.claude-sonnet-4-5-20250929-v1%3A0/invoke"
+    );
+    assert_eq!(
+        bedrock::request_path("m", true),
+        "/model/m/invoke-with-response-stream"
+    );
+
+    let body = bedrock::to_invoke_body(&json!({
+        "model": "m", "stream": true, "max_tokens": 8, "messages": [],
+    }));
+    assert_eq!(body, json!({
+        "anthropic_version": "bedrock-2023-05-31", "max_tokens": 8, "messages": [],
+    }));
+}
+
+#[test]
+fn vertex_path_and_body() {
+    assert_eq!(
+        vertex::request_path("p", "us-east5", "claude-sonnet-4-5@20250929", true),
+        "/v1/projects/p/locations/us-east5/publishers/anthropic/models/claude-sonnet-4-5@20250929:streamRawPredict"
+    );
+    assert!(vertex::request_path("p", "global", "m", false).ends_with("/models/m:rawPredict"));
+
+    let body = vertex::to_raw_predict_body(&json!({"model": "m", "stream": true, "messages": []}));
+    assert_eq!(body, json!({
+        "anthropic_version": "vertex-2023-10-16", "stream": true, "messages": [],
+    }));
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: Bedrock event stream decoding
+// ---------------------------------------------------------------------------
+
+/// Encode one event stream frame with string headers. CRCs are not checked
+/// by the decoder, so they are left zero.
+fn frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut encoded_headers = Vec::new();
+    for (name, value) in headers {
+        encoded_headers.push(name.len() as u8);
+        encoded_headers.extend_from_slice(name.as_bytes());
+        encoded_headers.push(7);
+        encoded_headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        encoded_headers.extend_from_slice(value.as_bytes());
+    }
+    let total_len = 12 + encoded_headers.len() + payload.len() + 4;
+
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&(total_len as u32).to_be_bytes());
+    frame.extend_from_slice(&(encoded_headers.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&[0; 4]);
+    frame.extend_from_slice(&encoded_headers);
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(&[0; 4]);
+    frame
+}
+
+fn chunk_frame(event: Value) -> Vec<u8> {
+    let bytes = base64::engine::general_purpose::STANDARD.encode(event.to_string());
+    frame(
+        &[(":event-type", "chunk"), (":content-type", "application/json"), (":message-type", "event")],
+        json!({"bytes": bytes}).to_string().as_bytes(),
+    )
+}
+
+fn bedrock_stream() -> Vec<u8> {
+    [
+        json!({"type": "message_start", "message": {"id": "msg_1", "type": "message", "role": "assistant", "model": "claude-sonnet-4-5-20250929", "content": [], "usage": {"input_tokens": 5, "output_tokens": 0}}}),
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hi!"}}),
+        json!({"type": "content_block_stop", "index": 0}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 2}}),
+        json!({"type": "message_stop", "amazon-bedrock-invocationMetrics": {"inputTokenCount": 5}}),
+    ]
+    .into_iter()
+    .flat_map(chunk_frame)
+    .collect()
+}
+
+fn events(bytes: &[u8]) -> Vec<anyclaude::sse::SseEvent> {
+    anyclaude::sse::parse_sse_events(bytes)
+}
+
+#[test]
+fn event_stream_decodes_to_sse() {
+    let mut translator = bedrock::make_stream_translator();
+    let out = translator(Bytes::from(bedrock_stream()));
+    let events = events(&out);
+
+    let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(types, [
+        "message_start",
+        "content_block_start",
+        "content_block_delta",
+        "content_block_stop",
+        "message_delta",
+        "message_stop",
+    ]);
+    assert_eq!(events[2].data["delta"]["text"], "Hi!");
+}
+
+#[test]
+fn event_stream_buffers_split_frames() {
+    let stream = bedrock_stream();
+    let mut translator = bedrock::make_stream_translator();
+    let mut out = Vec::new();
+    for chunk in stream.chunks(7) {
+        out.extend_from_slice(&translator(Bytes::copy_from_slice(chunk)));
+    }
+
+    let mut whole = bedrock::make_stream_translator();
+    assert_eq!(out, whole(Bytes::from(stream)).to_vec());
+}
+
+#[test]
+fn event_stream_exception_becomes_error_event() {
+    let mut translator = bedrock::make_stream_translator();
+    let out = translator(Bytes::from(frame(
+        &[(":exception-type", "throttlingException"), (":message-type", "exception")],
+        br#"{"message":"Too many requests"}"#,
+    )));
+    let events = events(&out);
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event_type, "error");
+    assert_eq!(events[0].data["error"], json!({"type": "rate_limit_error", "message": "Too many requests"}));
+}
+
+// ---------------------------------------------------------------------------
+// Integration tests: full proxy pipeline against a signature-checking stub
+// ---------------------------------------------------------------------------
+
+use common::mock_backend::{CapturedRequest, MockBackend, MockResponse};
+
+/// Start a proxy for `backend` without retries.
+async fn start_proxy(backend: Backend) -> String {
+    let mut config = common::proxy_config(vec![backend]);
+    config.defaults.max_retries = 0;
+    common::start_proxy(config).await.url
+}
+
+/// Parse an `x-amz-date` (`YYYYMMDDTHHMMSSZ`) back into a `SystemTime`.
+fn parse_amz_date(amz_date: &str) -> SystemTime {
+    let num = |range: std::ops::Range<usize>| amz_date[range].parse::<i64>().unwrap();
+    let (year, month, day) = (num(0..4), num(4..6), num(6..8));
+    let (hour, minute, second) = (num(9..11), num(11..13), num(13..15));
+
+    // Days since 1970-01-01 (Howard Hinnant's days_from_civil).
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    UNIX_EPOCH + Duration::from_secs((days * 86_400 + hour * 3_600 + minute * 60 + second) as u64)
+}
+
+/// Recompute the signature of a request as received by the stub and
+/// compare it with the one the proxy sent.
+fn assert_valid_signature(request: &CapturedRequest) {
+    let headers = &request.headers;
+    let url = Url::parse(&format!("http://{}{}", header(headers, "host").unwrap(), request.path)).unwrap();
+    let credentials = credentials(None);
+    let expected = sign(
+        &request.method,
+        &url,
+        &[("content-type".to_string(), header(headers, "content-type").unwrap().to_string())],
+        &request.body,
+        &SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "bedrock",
+            time: parse_amz_date(header(headers, "x-amz-date").unwrap()),
+        },
+    );
+    assert_eq!(header(headers, "authorization"), header(&expected, "authorization"));
+}
+
+#[tokio::test]
+async fn integration_bedrock_streaming_is_signed_and_decoded() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse {
+        headers: vec![("content-type".to_string(), bedrock::EVENTSTREAM_CONTENT_TYPE.to_string())],
+        body: bedrock_stream(),
+        ..Default::default()
+    }).await;
+    let proxy_url = start_proxy(bedrock_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-sonnet-4-5","stream":true,"max_tokens":64,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    let events = events(&resp.bytes().await.unwrap());
+    assert_eq!(events.first().unwrap().event_type, "message_start");
+    assert_eq!(events.last().unwrap().event_type, "message_stop");
+
+    let requests = mock.captured_requests().await;
+    let request = &requests[0];
+    assert_eq!(
+        request.path,
+        "/model/anthropic.claude-sonnet-4-5-20250929-v1%3A0/invoke-with-response-stream"
+    );
+    assert!(header(&request.headers, "x-api-key").is_none(), "client auth is stripped");
+    assert_valid_signature(request);
+
+    let sent: Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(sent["anthropic_version"], "bedrock-2023-05-31");
+    assert!(sent.get("model").is_none());
+    assert!(sent.get("stream").is_none());
+}
+
+#[tokio::test]
+async fn integration_bedrock_error_is_translated() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse {
+        status: 400,
+        body: br#"{"message":"Malformed input request"}"#.to_vec(),
+        ..Default::default()
+    }).await;
+    let proxy_url = start_proxy(bedrock_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-sonnet-4-5","max_tokens":64,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], json!({"type": "invalid_request_error", "message": "Malformed input request"}));
+
+    let requests = mock.captured_requests().await;
+    assert!(requests[0].path.ends_with("/invoke"));
+    assert_valid_signature(&requests[0]);
+}
+
+#[tokio::test]
+async fn integration_vertex_raw_predict() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(
+        r#"{"id":"msg_1","type":"message","role":"assistant","model":"claude-sonnet-4-5-20250929","content":[{"type":"text","text":"Hi!"}],"stop_reason":"end_turn","usage":{"input_tokens":5,"output_tokens":2}}"#,
+    )).await;
+    let proxy_url = start_proxy(vertex_backend(&mock.base_url(), "echo vertex-integration-token")).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-sonnet-4-5","max_tokens":64,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["content"][0]["text"], "Hi!");
+
+    let requests = mock.captured_requests().await;
+    assert_eq!(
+        requests[0].path,
+        "/v1/projects/my-project/locations/us-east5/publishers/anthropic/models/claude-sonnet-4-5@20250929:rawPredict"
+    );
+    assert_eq!(header(&requests[0].headers, "authorization"), Some("Bearer vertex-integration-token"));
+    let sent: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["anthropic_version"], "vertex-2023-10-16");
+    assert!(sent.get("model").is_none());
+}
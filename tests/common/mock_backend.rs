@@ -7,11 +7,17 @@ use axum::extract::State;
 use axum::http::{Request, Response, StatusCode};
 use axum::routing::any;
 use axum::Router;
+use futures_core::Stream;
 use std::collections::VecDeque;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
+use tokio::time::Sleep;
 
 /// A captured request for assertions.
 #[derive(Debug, Clone)]
@@ -29,6 +35,18 @@ pub struct MockResponse {
     pub headers: Vec<(String, String)>,
     pub body: Vec<u8>,
     pub delay_ms: u64,
+    pub end: BodyEnd,
+}
+
+/// How a mock body ends once `body` has been sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyEnd {
+    #[default]
+    Complete,
+    /// Keep the connection open without sending anything more.
+    Stall,
+    /// Drop the connection, so the client sees a body error.
+    Abort,
 }
 
 impl Default for MockResponse {
@@ -38,6 +56,7 @@ impl Default for MockResponse {
             headers: vec![("content-type".to_string(), "application/json".to_string())],
             body: br#"{"ok": true}"#.to_vec(),
             delay_ms: 0,
+            end: BodyEnd::Complete,
         }
     }
 }
@@ -49,6 +68,7 @@ impl MockResponse {
             headers: vec![("content-type".to_string(), "application/json".to_string())],
             body: body.as_bytes().to_vec(),
             delay_ms: 0,
+            end: BodyEnd::Complete,
         }
     }
 
@@ -58,6 +78,7 @@ impl MockResponse {
             headers: vec![("content-type".to_string(), "application/json".to_string())],
             body: format!(r#"{{"error": "{}"}}"#, message).into_bytes(),
             delay_ms: 0,
+            end: BodyEnd::Complete,
         }
     }
 
@@ -81,6 +102,7 @@ impl MockResponse {
             headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
             body: body.into_bytes(),
             delay_ms: 0,
+            end: BodyEnd::Complete,
         }
     }
 
@@ -94,6 +116,18 @@ impl MockResponse {
         self.delay_ms = ms;
         self
     }
+
+    /// Stop sending after the body without closing the connection.
+    pub fn stalled(mut self) -> Self {
+        self.end = BodyEnd::Stall;
+        self
+    }
+
+    /// Drop the connection after the body.
+    pub fn aborted(mut self) -> Self {
+        self.end = BodyEnd::Abort;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -220,5 +254,39 @@ async fn handle_request(
         builder = builder.header(name, value);
     }
 
-    builder.body(Body::from(mock_resp.body)).unwrap()
+    match mock_resp.end {
+        BodyEnd::Complete => builder.body(Body::from(mock_resp.body)).unwrap(),
+        end => builder
+            .body(Body::from_stream(MockBody {
+                body: Some(mock_resp.body),
+                end,
+                abort_delay: Box::pin(tokio::time::sleep(Duration::from_millis(50))),
+            }))
+            .unwrap(),
+    }
+}
+
+/// Body that sends its bytes, then stalls or fails.
+struct MockBody {
+    body: Option<Vec<u8>>,
+    end: BodyEnd,
+    abort_delay: Pin<Box<Sleep>>,
+}
+
+impl Stream for MockBody {
+    type Item = Result<Vec<u8>, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(body) = self.body.take() {
+            return Poll::Ready(Some(Ok(body)));
+        }
+        match self.end {
+            BodyEnd::Abort => {
+                // Give the sent bytes time to reach the client first.
+                std::task::ready!(self.abort_delay.as_mut().poll(cx));
+                Poll::Ready(Some(Err(std::io::Error::other("mock connection aborted"))))
+            }
+            _ => Poll::Pending,
+        }
+    }
 }
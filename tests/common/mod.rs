@@ -4,7 +4,12 @@
 
 pub mod mock_backend;
 
-use anyclaude::config::{Config, ConfigStore};
+use anyclaude::backend::BackendState;
+use anyclaude::config::{Backend, Config, ConfigStore, Defaults, ProxyConfig};
+use anyclaude::error::ErrorRegistry;
+use anyclaude::metrics::{DebugLogger, ObservabilityHub};
+use anyclaude::proxy::recording::Recording;
+use anyclaude::proxy::ProxyServer;
 use anyclaude::pty::emulator::TerminalEmulator;
 use anyclaude::pty::PtyHandle;
 use anyclaude::ui::app::App;
@@ -76,6 +81,91 @@ pub async fn wait_for_server(addr: SocketAddr, timeout: Duration) -> bool {
     false
 }
 
+// -- Proxy helpers ------------------------------------------------------------
+
+/// Config serving `backends` on a free port, with the first one active.
+pub fn proxy_config(backends: Vec<Backend>) -> Config {
+    let bind_addr = format!("127.0.0.1:{}", free_port());
+    Config {
+        defaults: Defaults {
+            active: backends[0].name.clone(),
+            ..Default::default()
+        },
+        proxy: ProxyConfig {
+            bind_addr: bind_addr.clone(),
+            base_url: format!("http://{}", bind_addr),
+        },
+        backends,
+        ..Default::default()
+    }
+}
+
+/// Server setup beyond the config, for [`start_proxy_with`].
+#[derive(Default)]
+pub struct ProxyOptions {
+    pub session_token: Option<String>,
+    pub recording: Option<Recording>,
+}
+
+/// A running proxy and handles to its shared state.
+pub struct TestProxy {
+    pub addr: SocketAddr,
+    /// `http://` URL of the proxy.
+    pub url: String,
+    pub backend_state: BackendState,
+    pub observability: ObservabilityHub,
+    pub error_registry: ErrorRegistry,
+}
+
+/// Start a proxy for `config` and wait until it accepts connections.
+pub async fn start_proxy(config: Config) -> TestProxy {
+    start_proxy_with(config, ProxyOptions::default()).await
+}
+
+/// [`start_proxy`] with a session token or recording.
+pub async fn start_proxy_with(config: Config, options: ProxyOptions) -> TestProxy {
+    let config_store = ConfigStore::new(config, PathBuf::from("/tmp/test.toml"));
+    let debug_logger = Arc::new(DebugLogger::new(Default::default()));
+    let mut server = ProxyServer::new(config_store.clone(), debug_logger, options.session_token).unwrap();
+    if let Some(recording) = options.recording {
+        server = server.with_recording(recording);
+    }
+    let backend_state = server.backend_state();
+    let observability = server.observability();
+    let error_registry = server.error_registry();
+
+    let (addr, _base_url) = server.try_bind(&config_store).await.unwrap();
+    tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+    assert!(wait_for_server(addr, Duration::from_secs(5)).await, "proxy did not start");
+
+    TestProxy {
+        addr,
+        url: format!("http://{}", addr),
+        backend_state,
+        observability,
+        error_registry,
+    }
+}
+
+/// POST a JSON `body` to `path` on the proxy, with extra `headers`.
+pub async fn post_json(proxy_url: &str, path: &str, body: &str, headers: &[(&str, &str)]) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .post(format!("{}{}", proxy_url, path))
+        .header("content-type", "application/json")
+        .body(body.to_string());
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.send().await.unwrap()
+}
+
+/// POST a JSON `body` to the proxy's Messages endpoint.
+pub async fn post_messages(proxy_url: &str, body: &str, headers: &[(&str, &str)]) -> reqwest::Response {
+    post_json(proxy_url, "/v1/messages", body, headers).await
+}
+
 // -- App helpers --------------------------------------------------------------
 
 pub fn make_app() -> App {
@@ -40,6 +40,7 @@ fn test_config(backend: Backend, bind_addr: &str) -> Config {
         claude_settings: HashMap::new(),
         backends: vec![backend],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -56,6 +57,7 @@ fn create_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -15,6 +15,7 @@ fn make_backend(auth_type: &str, api_key: Option<&str>) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -10,6 +10,8 @@ fn test_auth_type_parsing() {
     assert_eq!(AuthType::parse("Bearer"), AuthType::Bearer);
     assert_eq!(AuthType::parse("passthrough"), AuthType::Passthrough);
     assert_eq!(AuthType::parse("PASSTHROUGH"), AuthType::Passthrough);
+    assert_eq!(AuthType::parse("bedrock"), AuthType::Bedrock);
+    assert_eq!(AuthType::parse("vertex"), AuthType::Vertex);
     // Unknown values default to Passthrough (safe for OAuth)
     assert_eq!(AuthType::parse("unknown"), AuthType::Passthrough);
     assert_eq!(AuthType::parse(""), AuthType::Passthrough);
@@ -41,6 +43,10 @@ fn test_uses_own_credentials() {
     // ApiKey and Bearer use backend's configured credentials
     assert!(AuthType::ApiKey.uses_own_credentials());
     assert!(AuthType::Bearer.uses_own_credentials());
+
+    // Cloud-hosted backends sign or authorize with their own credentials
+    assert!(AuthType::Bedrock.uses_own_credentials());
+    assert!(AuthType::Vertex.uses_own_credentials());
 }
 
 #[test]
@@ -57,6 +63,7 @@ fn test_credential_resolution_passthrough() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     assert!(matches!(
@@ -65,3 +72,140 @@ fn test_credential_resolution_passthrough() {
     ));
     assert!(backend.is_configured());
 }
+
+fn api_key_backend() -> Backend {
+    Backend {
+        name: "test".to_string(),
+        display_name: "Test".to_string(),
+        base_url: "https://example.com".to_string(),
+        auth_type_str: "api_key".to_string(),
+        ..Default::default()
+    }
+}
+
+fn unconfigured_reason(backend: &Backend) -> String {
+    match backend.resolve_credential() {
+        CredentialStatus::Unconfigured { reason } => reason,
+        other => panic!("expected Unconfigured, got {:?}", other),
+    }
+}
+
+fn configured_key(backend: &Backend) -> String {
+    match backend.resolve_credential() {
+        CredentialStatus::Configured(key) => key.expose().to_string(),
+        other => panic!("expected Configured, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_credential_missing_api_key() {
+    let reason = unconfigured_reason(&api_key_backend());
+    assert!(reason.contains("api_key is not set"), "{}", reason);
+}
+
+#[test]
+fn test_credential_from_env() {
+    std::env::set_var("ANYCLAUDE_TEST_KEY_SET", " sk-env \n");
+    std::env::set_var("ANYCLAUDE_TEST_KEY_EMPTY", "");
+    let backend = |var: &str| Backend {
+        api_key_env: Some(var.to_string()),
+        ..api_key_backend()
+    };
+
+    assert_eq!(configured_key(&backend("ANYCLAUDE_TEST_KEY_SET")), "sk-env");
+    assert_eq!(
+        unconfigured_reason(&backend("ANYCLAUDE_TEST_KEY_EMPTY")),
+        "environment variable ANYCLAUDE_TEST_KEY_EMPTY is empty"
+    );
+    assert_eq!(
+        unconfigured_reason(&backend("ANYCLAUDE_TEST_KEY_UNSET")),
+        "environment variable ANYCLAUDE_TEST_KEY_UNSET is not set"
+    );
+}
+
+#[test]
+fn test_inline_api_key_takes_precedence() {
+    let backend = Backend {
+        api_key: Some("sk-inline".to_string()),
+        api_key_env: Some("ANYCLAUDE_TEST_KEY_UNSET".to_string()),
+        ..api_key_backend()
+    };
+    assert_eq!(configured_key(&backend), "sk-inline");
+}
+
+#[test]
+fn test_credential_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let key_path = dir.path().join("key");
+    let empty_path = dir.path().join("empty");
+    std::fs::write(&key_path, "sk-file\n").unwrap();
+    std::fs::write(&empty_path, "  \n").unwrap();
+    let backend = |path: &std::path::Path| Backend {
+        api_key_file: Some(path.display().to_string()),
+        ..api_key_backend()
+    };
+
+    assert_eq!(configured_key(&backend(&key_path)), "sk-file");
+    assert!(unconfigured_reason(&backend(&empty_path)).ends_with("is empty"));
+    let missing = unconfigured_reason(&backend(&dir.path().join("missing")));
+    assert!(missing.contains("could not be read"), "{}", missing);
+}
+
+#[tokio::test]
+async fn test_credential_from_command() {
+    let backend = |command: &str| Backend {
+        api_key_command: Some(command.to_string()),
+        ..api_key_backend()
+    };
+    let refreshed = |backend: Backend| async move {
+        backend.refresh_credentials().await;
+        backend
+    };
+
+    // Commands only run on refresh; until then the backend counts as configured.
+    let pending = backend("echo sk-pending");
+    assert!(matches!(pending.resolve_credential(), CredentialStatus::Pending));
+    assert!(pending.is_configured());
+
+    assert_eq!(configured_key(&refreshed(backend("echo sk-command")).await), "sk-command");
+    let failed = unconfigured_reason(&refreshed(backend("echo locked >&2; exit 3")).await);
+    assert!(failed.starts_with("api_key_command failed"), "{}", failed);
+    assert!(failed.contains("locked"), "{}", failed);
+    assert_eq!(
+        unconfigured_reason(&refreshed(backend("true")).await),
+        "api_key_command printed nothing"
+    );
+}
+
+#[tokio::test]
+async fn test_credential_command_with_large_output() {
+    // More than a pipe buffer of stderr before the key must not stall the command.
+    let backend = Backend {
+        api_key_command: Some("head -c 262144 /dev/zero | tr '\\0' x >&2; echo sk-noisy".to_string()),
+        ..api_key_backend()
+    };
+    let started = std::time::Instant::now();
+    backend.refresh_credentials().await;
+    assert_eq!(configured_key(&backend), "sk-noisy");
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_credential_command_cached_for_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let counter = dir.path().join("runs");
+    let command = format!("echo run >> {0}; wc -l < {0}", counter.display());
+    let backend = |ttl: u64| Backend {
+        api_key_command: Some(command.clone()),
+        api_key_command_ttl_seconds: Some(ttl),
+        ..api_key_backend()
+    };
+
+    backend(300).refresh_credentials().await;
+    assert_eq!(configured_key(&backend(300)), "1");
+    backend(300).refresh_credentials().await;
+    assert_eq!(configured_key(&backend(300)), "1");
+    // A zero TTL runs the command again.
+    backend(0).refresh_credentials().await;
+    assert_eq!(configured_key(&backend(0)), "2");
+}
@@ -1,6 +1,6 @@
 use anyclaude::config::{
-    build_auth_header, AgentsConfig, AuthType, Backend, Config, ConfigError,
-    CredentialStatus, DebugLoggingConfig, Defaults, ProxyConfig, TerminalConfig,
+    build_auth_header, AgentsConfig, AuthType, Backend, BackendProtocol, Config, ConfigError,
+    CredentialStatus, DebugLoggingConfig, Defaults, ModelMapEntry, ProxyConfig, TerminalConfig,
 };
 use std::collections::HashMap;
 
@@ -56,6 +56,7 @@ fn test_validation_fails_empty_backends() {
         claude_settings: HashMap::new(),
         backends: vec![],
         agents: None,
+        ..Default::default()
     };
 
     let result = config.validate();
@@ -89,6 +90,7 @@ fn test_validation_fails_missing_active_backend() {
         claude_settings: HashMap::new(),
         backends: vec![Backend::default()],
         agents: None,
+        ..Default::default()
     };
 
     let result = config.validate();
@@ -170,6 +172,7 @@ fn test_backend_is_configured_with_api_key() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     assert!(backend.is_configured());
@@ -190,6 +193,7 @@ fn test_backend_not_configured_without_api_key() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     assert!(!backend.is_configured());
@@ -210,6 +214,7 @@ fn test_backend_passthrough_always_configured() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     assert!(backend.is_configured());
@@ -234,6 +239,7 @@ fn test_build_auth_header_api_key() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let header = build_auth_header(&backend);
@@ -259,6 +265,7 @@ fn test_build_auth_header_bearer() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let header = build_auth_header(&backend);
@@ -299,8 +306,10 @@ fn test_validation_fails_unconfigured_active_backend() {
             model_opus: None,
             model_sonnet: None,
             model_haiku: None,
+            ..Default::default()
         }],
         agents: None,
+        ..Default::default()
     };
 
     let result = config.validate();
@@ -329,6 +338,7 @@ fn test_validation_fails_invalid_teammate_backend() {
             teammate_backend: "nonexistent".to_string(),
             subagent_backend: None,
         }),
+        ..Default::default()
     };
 
     let result = config.validate();
@@ -388,11 +398,341 @@ fn test_validation_passes_valid_teammate_backend() {
             teammate_backend: "claude".to_string(),
             subagent_backend: None,
         }),
+        ..Default::default()
     };
 
     assert!(config.validate().is_ok());
 }
 
+/// Test validation fails when a failover chain references a nonexistent backend.
+#[test]
+fn test_validation_fails_unknown_failover_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        r#"
+[defaults]
+active = "claude"
+timeout_seconds = 30
+
+[[backends]]
+name = "claude"
+display_name = "Claude"
+base_url = "https://api.anthropic.com"
+auth_type = "passthrough"
+failover = ["nonexistent"]
+"#,
+    )
+    .unwrap();
+
+    let result = Config::load_from(&path);
+    assert!(result.is_err(), "should reject nonexistent failover target");
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("nonexistent"), "got: {err}");
+}
+
+/// Test failover settings parse from TOML and drive the status policy.
+#[test]
+fn test_failover_config_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "claude"
+timeout_seconds = 30
+
+[[backends]]
+name = "claude"
+display_name = "Claude"
+base_url = "https://api.anthropic.com"
+auth_type = "passthrough"
+failover = ["backup"]
+
+[[backends]]
+name = "backup"
+display_name = "Backup"
+base_url = "https://backup.example.com"
+auth_type = "passthrough"
+failover_on_status = [400]
+"#,
+    )
+    .unwrap();
+    assert!(config.validate().is_ok());
+
+    let primary = &config.backends[0];
+    assert_eq!(primary.failover, vec!["backup".to_string()]);
+    assert!(primary.should_failover_on_status(529));
+    assert!(primary.should_failover_on_status(503));
+    assert!(!primary.should_failover_on_status(400));
+
+    let backup = &config.backends[1];
+    assert!(backup.failover.is_empty());
+    assert!(backup.should_failover_on_status(400));
+    assert!(!backup.should_failover_on_status(529));
+}
+
+#[test]
+fn test_protocol_config_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "local"
+timeout_seconds = 30
+
+[[backends]]
+name = "local"
+display_name = "Local"
+base_url = "http://localhost:8000"
+auth_type = "bearer"
+protocol = "openai"
+
+[[backends]]
+name = "claude"
+display_name = "Claude"
+base_url = "https://api.anthropic.com"
+auth_type = "passthrough"
+
+[[backends]]
+name = "gemini"
+display_name = "Gemini"
+base_url = "https://generativelanguage.googleapis.com"
+auth_type = "api_key"
+protocol = "gemini"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.backends[0].protocol, BackendProtocol::OpenAi);
+    assert_eq!(config.backends[1].protocol, BackendProtocol::Anthropic);
+    assert_eq!(config.backends[2].protocol, BackendProtocol::Gemini);
+}
+
+#[test]
+fn test_cloud_auth_config_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "bedrock"
+timeout_seconds = 30
+
+[[backends]]
+name = "bedrock"
+display_name = "Bedrock"
+base_url = "https://bedrock-runtime.us-east-1.amazonaws.com"
+auth_type = "bedrock"
+aws_region = "us-east-1"
+aws_access_key_id = "AKID"
+aws_secret_access_key = "secret"
+
+[[backends]]
+name = "vertex"
+display_name = "Vertex"
+base_url = "https://us-east5-aiplatform.googleapis.com"
+auth_type = "vertex"
+vertex_project = "my-project"
+vertex_region = "us-east5"
+token_command = "cat /tmp/token"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.backends[0].auth_type(), AuthType::Bedrock);
+    assert_eq!(config.backends[0].aws_region.as_deref(), Some("us-east-1"));
+    assert_eq!(config.backends[0].aws_access_key_id.as_deref(), Some("AKID"));
+    assert_eq!(config.backends[1].auth_type(), AuthType::Vertex);
+    assert_eq!(config.backends[1].vertex_project.as_deref(), Some("my-project"));
+    assert_eq!(config.backends[1].token_command.as_deref(), Some("cat /tmp/token"));
+}
+
+#[test]
+fn test_api_key_sources_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "env"
+timeout_seconds = 30
+
+[[backends]]
+name = "env"
+display_name = "Env"
+base_url = "https://api.example.com"
+auth_type = "api_key"
+api_key_env = "EXAMPLE_API_KEY"
+
+[[backends]]
+name = "file"
+display_name = "File"
+base_url = "https://api.example.com"
+auth_type = "bearer"
+api_key_file = "~/.config/anyclaude/example.key"
+
+[[backends]]
+name = "command"
+display_name = "Command"
+base_url = "https://api.example.com"
+auth_type = "bearer"
+api_key_command = "pass show example"
+api_key_command_ttl_seconds = 60
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.backends[0].api_key_env.as_deref(), Some("EXAMPLE_API_KEY"));
+    assert_eq!(config.backends[1].api_key_file.as_deref(), Some("~/.config/anyclaude/example.key"));
+    assert_eq!(config.backends[2].api_key_command.as_deref(), Some("pass show example"));
+    assert_eq!(config.backends[2].api_key_command_ttl_seconds, Some(60));
+    assert_eq!(config.backends[0].api_key_command_ttl_seconds, None);
+}
+
+#[test]
+fn test_header_rules_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "gateway"
+timeout_seconds = 30
+
+[[backends]]
+name = "gateway"
+display_name = "Gateway"
+base_url = "https://gateway.example.com"
+auth_type = "bearer"
+api_key = "key"
+
+[backends.headers]
+set = { "HTTP-Referer" = "https://example.com", "X-Tenant" = "{session_id}" }
+append = { "X-Tags" = "anyclaude" }
+remove = ["x-stainless-*"]
+strip_beta = ["context-management-*"]
+add_beta = ["context-1m-2025-08-07"]
+"#,
+    )
+    .unwrap();
+
+    let rules = &config.backends[0].headers;
+    assert_eq!(rules.set["HTTP-Referer"], "https://example.com");
+    assert_eq!(rules.set["X-Tenant"], "{session_id}");
+    assert_eq!(rules.append["X-Tags"], "anyclaude");
+    assert_eq!(rules.remove, vec!["x-stainless-*"]);
+    assert_eq!(rules.strip_beta, vec!["context-management-*"]);
+    assert_eq!(rules.add_beta, vec!["context-1m-2025-08-07"]);
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_body_rules_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "strict"
+timeout_seconds = 30
+
+[[backends]]
+name = "strict"
+display_name = "Strict"
+base_url = "https://api.example.com"
+auth_type = "bearer"
+api_key = "key"
+
+[backends.body]
+max_tokens = 8192
+max_thinking_budget = 4096
+temperature = 0.7
+top_k = 40
+drop = ["metadata", "cache_control"]
+drop_tools = ["WebSearch"]
+"#,
+    )
+    .unwrap();
+
+    let rules = &config.backends[0].body;
+    assert_eq!(rules.max_tokens, Some(8192));
+    assert_eq!(rules.max_thinking_budget, Some(4096));
+    assert_eq!(rules.temperature, Some(0.7));
+    assert_eq!(rules.top_p, None);
+    assert_eq!(rules.top_k, Some(40));
+    assert_eq!(rules.drop, vec!["metadata", "cache_control"]);
+    assert_eq!(rules.drop_tools, vec!["WebSearch"]);
+}
+
+#[test]
+fn test_validation_fails_invalid_header_rule() {
+    let backend = |name: &str, value: &str| {
+        let mut backend = Backend {
+            name: "gateway".to_string(),
+            display_name: "Gateway".to_string(),
+            base_url: "https://gateway.example.com".to_string(),
+            auth_type_str: "passthrough".to_string(),
+            ..Default::default()
+        };
+        backend.headers.set.insert(name.to_string(), value.to_string());
+        Config {
+            defaults: Defaults {
+                active: "gateway".to_string(),
+                ..Default::default()
+            },
+            backends: vec![backend],
+            ..Default::default()
+        }
+    };
+
+    for (config, expected) in [
+        (backend("Bad Header", "value"), "not a valid header name"),
+        (backend("X-Ok", "line\r\nbreak"), "control characters"),
+    ] {
+        match config.validate().unwrap_err() {
+            ConfigError::ValidationError { message } => {
+                assert!(message.contains(expected), "{}", message);
+            }
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+}
+
+#[test]
+fn test_routing_rules_from_toml() {
+    let config: Config = toml::from_str(
+        r#"
+[defaults]
+active = "anthropic"
+timeout_seconds = 30
+
+[[backends]]
+name = "anthropic"
+display_name = "Anthropic"
+base_url = "https://api.anthropic.com"
+auth_type = "passthrough"
+
+[[backends]]
+name = "cheap"
+display_name = "Cheap"
+base_url = "https://cheap.example.com"
+auth_type = "bearer"
+api_key = "key"
+
+[[routing.rules]]
+name = "haiku-to-cheap"
+backend = "cheap"
+model = "haiku"
+input_tokens_max = 20000
+
+[[routing.rules]]
+name = "missing"
+backend = "nowhere"
+"#,
+    )
+    .unwrap();
+
+    let rule = &config.routing.rules[0];
+    assert_eq!(rule.name, "haiku-to-cheap");
+    assert_eq!(rule.model.as_deref(), Some("haiku"));
+    assert_eq!(rule.input_tokens_max, Some(20000));
+    assert!(rule.tools.is_empty());
+
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("Routing rule 'missing'"), "{}", err);
+}
+
 /// Test configured_backends only returns backends with valid credentials.
 #[test]
 fn test_configured_backends_filters_correctly() {
@@ -424,6 +764,7 @@ fn test_configured_backends_filters_correctly() {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "unconfigured".to_string(),
@@ -437,6 +778,7 @@ fn test_configured_backends_filters_correctly() {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "passthrough".to_string(),
@@ -450,9 +792,11 @@ fn test_configured_backends_filters_correctly() {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
         ],
         agents: None,
+        ..Default::default()
     };
 
     let configured = config.configured_backends();
@@ -481,43 +825,44 @@ fn glm_backend() -> Backend {
         model_opus: Some("glm-4.7".to_string()),
         model_sonnet: Some("glm-4.7".to_string()),
         model_haiku: Some("glm-4.5-air".to_string()),
+        ..Default::default()
     }
 }
 
 #[test]
 fn resolve_model_opus_family() {
     let b = glm_backend();
-    assert_eq!(b.resolve_model("claude-opus-4-6"), Some("glm-4.7"));
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), Some("glm-4.7"));
 }
 
 #[test]
 fn resolve_model_sonnet_family() {
     let b = glm_backend();
-    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929"), Some("glm-4.7"));
+    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929").as_deref(), Some("glm-4.7"));
 }
 
 #[test]
 fn resolve_model_haiku_family() {
     let b = glm_backend();
-    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001"), Some("glm-4.5-air"));
+    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001").as_deref(), Some("glm-4.5-air"));
 }
 
 #[test]
 fn resolve_model_bedrock_id() {
     let b = glm_backend();
-    assert_eq!(b.resolve_model("us.anthropic.claude-opus-4-5-v1:0"), Some("glm-4.7"));
+    assert_eq!(b.resolve_model("us.anthropic.claude-opus-4-5-v1:0").as_deref(), Some("glm-4.7"));
 }
 
 #[test]
 fn resolve_model_unknown_passthrough() {
     let b = glm_backend();
-    assert_eq!(b.resolve_model("gpt-4o"), None);
+    assert_eq!(b.resolve_model("gpt-4o").as_deref(), None);
 }
 
 #[test]
 fn resolve_model_no_map() {
     let b = Backend::default();
-    assert_eq!(b.resolve_model("claude-opus-4-6"), None);
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), None);
 }
 
 #[test]
@@ -526,9 +871,107 @@ fn resolve_model_partial_map() {
         model_opus: Some("mapped-opus".to_string()),
         ..Backend::default()
     };
-    assert_eq!(b.resolve_model("claude-opus-4-6"), Some("mapped-opus"));
-    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929"), None);
-    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001"), None);
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), Some("mapped-opus"));
+    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929").as_deref(), None);
+    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001").as_deref(), None);
+}
+
+fn mapped_backend() -> Backend {
+    Backend {
+        model_opus: Some("glm-4.7".to_string()),
+        model_map: vec![
+            ModelMapEntry {
+                from: "claude-opus-4-6".to_string(),
+                to: "glm-5".to_string(),
+                regex: false,
+            },
+            ModelMapEntry {
+                from: r"claude-(sonnet|haiku)-(\d+)-(\d+).*".to_string(),
+                to: "qwen-$1-$2.$3".to_string(),
+                regex: true,
+            },
+        ],
+        default_model: Some("glm-4.5-air".to_string()),
+        ..Backend::default()
+    }
+}
+
+#[test]
+fn resolve_model_exact_map_before_family() {
+    let b = mapped_backend();
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), Some("glm-5"));
+    // Not an exact match: falls back to the opus family field
+    assert_eq!(b.resolve_model("claude-opus-4-5").as_deref(), Some("glm-4.7"));
+}
+
+#[test]
+fn resolve_model_regex_map_with_captures() {
+    let b = mapped_backend();
+    assert_eq!(
+        b.resolve_model("claude-sonnet-4-5-20250929").as_deref(),
+        Some("qwen-sonnet-4.5")
+    );
+    assert_eq!(b.resolve_model("claude-haiku-4-5").as_deref(), Some("qwen-haiku-4.5"));
+}
+
+#[test]
+fn resolve_model_regex_must_match_whole_name() {
+    let b = Backend {
+        model_map: vec![ModelMapEntry {
+            from: "sonnet".to_string(),
+            to: "mapped".to_string(),
+            regex: true,
+        }],
+        ..Backend::default()
+    };
+    assert_eq!(b.resolve_model("claude-sonnet-4-5").as_deref(), None);
+    assert_eq!(b.resolve_model("sonnet").as_deref(), Some("mapped"));
+}
+
+#[test]
+fn resolve_model_default_for_unmatched() {
+    let b = mapped_backend();
+    assert_eq!(b.resolve_model("gpt-4o").as_deref(), Some("glm-4.5-air"));
+}
+
+#[test]
+fn resolve_model_map_toml_and_validation() {
+    let toml_content = r#"
+[defaults]
+active = "mapped"
+timeout_seconds = 30
+
+[[backends]]
+name = "mapped"
+display_name = "Mapped"
+base_url = "https://api.example.com"
+auth_type = "bearer"
+api_key = "test-key"
+default_model = "fallback"
+
+[[backends.model_map]]
+from = "claude-opus-4-6"
+to = "big"
+
+[[backends.model_map]]
+from = 'claude-(\w+)-4-5.*'
+to = "mid-$1"
+regex = true
+"#;
+    let mut config: Config = toml::from_str(toml_content).expect("Should parse");
+    assert!(config.validate().is_ok());
+    let b = &config.backends[0];
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), Some("big"));
+    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929").as_deref(), Some("mid-sonnet"));
+    assert_eq!(b.resolve_model("claude-3-haiku").as_deref(), Some("fallback"));
+
+    config.backends[0].model_map[1].from = "claude-(".to_string();
+    match config.validate().unwrap_err() {
+        ConfigError::ValidationError { message } => {
+            assert!(message.contains("not a valid regex"), "{}", message);
+        }
+        _ => panic!("Expected ValidationError"),
+    }
 }
 
 #[test]
@@ -549,8 +992,8 @@ model_haiku = "glm-4.5-air"
 "#;
     let config: Config = toml::from_str(toml_content).expect("Should parse");
     let b = &config.backends[0];
-    assert_eq!(b.resolve_model("claude-opus-4-6"), Some("glm-4.7"));
-    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929"), None);
-    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001"), Some("glm-4.5-air"));
+    assert_eq!(b.resolve_model("claude-opus-4-6").as_deref(), Some("glm-4.7"));
+    assert_eq!(b.resolve_model("claude-sonnet-4-5-20250929").as_deref(), None);
+    assert_eq!(b.resolve_model("claude-haiku-4-5-20251001").as_deref(), Some("glm-4.5-air"));
 }
 
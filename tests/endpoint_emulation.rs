@@ -0,0 +1,180 @@
+//! `[backends.emulate]`: count_tokens and model listing answered by the
+//! proxy instead of the backend.
+
+mod common;
+
+use anyclaude::config::{Backend, BackendProtocol, EmulateRules, ModelMapEntry};
+use common::mock_backend::{MockBackend, MockResponse};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+fn create_backend(base_url: &str, emulate: EmulateRules) -> Backend {
+    Backend {
+        name: "emulated".to_string(),
+        display_name: "Emulated".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "api_key".to_string(),
+        api_key: Some("sk-test".to_string()),
+        protocol: BackendProtocol::OpenAi,
+        model_opus: Some("provider-large".to_string()),
+        model_sonnet: Some("provider-medium".to_string()),
+        model_map: vec![
+            ModelMapEntry {
+                from: "claude-opus-4-6".to_string(),
+                to: "provider-large-2".to_string(),
+                regex: false,
+            },
+            ModelMapEntry {
+                from: r"claude-haiku-(\d+)".to_string(),
+                to: "provider-small-$1".to_string(),
+                regex: true,
+            },
+        ],
+        emulate,
+        ..Default::default()
+    }
+}
+
+fn emulate_all() -> EmulateRules {
+    EmulateRules {
+        count_tokens: true,
+        models: true,
+    }
+}
+
+async fn get(url: String) -> (u16, Value) {
+    let resp = Client::new().get(url).send().await.unwrap();
+    (resp.status().as_u16(), resp.json().await.unwrap())
+}
+
+async fn count_tokens(proxy_url: &str, body: Value) -> (u16, Value) {
+    let resp = common::post_json(proxy_url, "/v1/messages/count_tokens", &body.to_string(), &[]).await;
+    (resp.status().as_u16(), resp.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn integration_count_tokens_is_estimated_locally() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend(&mock.base_url(), emulate_all());
+    let proxy = common::start_proxy(common::proxy_config(vec![backend])).await;
+
+    let body = json!({
+        "model": "claude-sonnet-4-5",
+        "system": "You are terse.",
+        "messages": [{"role": "user", "content": "word ".repeat(100)}],
+    });
+    let (status, resp) = count_tokens(&proxy.url, body).await;
+
+    assert_eq!(status, 200);
+    let tokens = resp["input_tokens"].as_u64().unwrap();
+    assert!((120..=140).contains(&tokens), "{}", tokens);
+    assert!(mock.captured_requests().await.is_empty());
+
+    let record = proxy.observability.snapshot().recent.pop().unwrap();
+    assert_eq!(record.status, Some(200));
+    let decision = record.routing_decision.unwrap();
+    assert_eq!(decision.backend, "emulated");
+    assert!(decision.synthesized);
+}
+
+#[tokio::test]
+async fn integration_count_tokens_counts_tools() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend(&mock.base_url(), emulate_all());
+    let proxy = common::start_proxy(common::proxy_config(vec![backend])).await;
+    let messages = json!([{"role": "user", "content": "hi"}]);
+
+    let (_, plain) = count_tokens(&proxy.url, json!({"model": "m", "messages": messages})).await;
+    let tools = json!([{
+        "name": "Read",
+        "description": "Read a file from the local filesystem.",
+        "input_schema": {"type": "object", "properties": {"file_path": {"type": "string"}}},
+    }]);
+    let (_, with_tools) =
+        count_tokens(&proxy.url, json!({"model": "m", "messages": messages, "tools": tools})).await;
+
+    assert!(with_tools["input_tokens"].as_u64() > plain["input_tokens"].as_u64());
+}
+
+#[tokio::test]
+async fn integration_models_listed_from_mapping() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend(&mock.base_url(), emulate_all());
+    let proxy = common::start_proxy(common::proxy_config(vec![backend])).await;
+
+    let (status, resp) = get(format!("{}/v1/models", proxy.url)).await;
+
+    assert_eq!(status, 200);
+    let ids: Vec<&str> = resp["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| model["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["claude-opus-4-6", "provider-large-2", "provider-large", "provider-medium"]);
+    assert_eq!(resp["data"][0]["type"], "model");
+    assert_eq!(resp["data"][0]["display_name"], "claude-opus-4-6 → provider-large-2");
+    assert_eq!(resp["has_more"], false);
+    assert_eq!(resp["first_id"], "claude-opus-4-6");
+    assert_eq!(resp["last_id"], "provider-medium");
+    assert!(mock.captured_requests().await.is_empty());
+}
+
+#[tokio::test]
+async fn integration_model_by_id() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend(&mock.base_url(), emulate_all());
+    let proxy = common::start_proxy(common::proxy_config(vec![backend])).await;
+
+    let (status, resp) = get(format!("{}/v1/models/provider-medium", proxy.url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(resp["id"], "provider-medium");
+
+    let (status, resp) = get(format!("{}/v1/models/claude-unknown", proxy.url)).await;
+    assert_eq!(status, 404);
+    assert_eq!(resp["type"], "error");
+    assert!(mock.captured_requests().await.is_empty());
+}
+
+#[tokio::test]
+async fn integration_endpoints_forwarded_without_emulation() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(r#"{"object":"list","data":[]}"#)).await;
+    mock.enqueue_response(MockResponse::json(r#"{"input_tokens":7}"#)).await;
+    let emulate = EmulateRules {
+        count_tokens: false,
+        models: false,
+    };
+    let backend = create_backend(&mock.base_url(), emulate);
+    let proxy = common::start_proxy(common::proxy_config(vec![backend])).await;
+
+    let (_, resp) = get(format!("{}/v1/models", proxy.url)).await;
+    assert_eq!(resp["object"], "list");
+    let (_, resp) = count_tokens(&proxy.url, json!({"model": "m", "messages": []})).await;
+    assert_eq!(resp["input_tokens"], 7);
+
+    let paths: Vec<String> = mock
+        .captured_requests()
+        .await
+        .into_iter()
+        .map(|req| req.path)
+        .collect();
+    assert_eq!(paths, vec!["/v1/models", "/v1/messages/count_tokens"]);
+}
+
+#[test]
+fn test_emulate_from_toml() {
+    let toml = r#"
+        name = "emulated"
+        display_name = "Emulated"
+        base_url = "https://api.example.com"
+        auth_type = "api_key"
+
+        [emulate]
+        count_tokens = true
+    "#;
+    let backend: Backend = toml::from_str(toml).unwrap();
+
+    assert!(backend.emulate.count_tokens);
+    assert!(!backend.emulate.models);
+}
@@ -42,6 +42,15 @@ fn test_recovery_tracking() {
     assert!(registry.active_recoveries().is_empty());
 }
 
+#[test]
+fn test_end_recovery_removes_without_success() {
+    let registry = ErrorRegistry::new(10);
+
+    registry.start_recovery("upstream", 3);
+    registry.end_recovery("upstream");
+    assert!(registry.active_recoveries().is_empty());
+}
+
 #[test]
 fn test_feature_degradation() {
     let registry = ErrorRegistry::new(10);
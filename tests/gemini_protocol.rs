@@ -0,0 +1,422 @@
+//! Tests for `protocol = "gemini"` backends.
+//!
+//! Unit tests for request, response and SSE stream translation, plus
+//! fixture-driven integration tests through the full proxy pipeline
+//! against the mock backend. Fixtures live in `tests/fixtures/gemini/`.
+
+mod common;
+
+use anyclaude::proxy::gemini::{
+    make_stream_translator, request_path, to_generate_content, to_message,
+};
+use axum::body::Bytes;
+use serde_json::{json, Value};
+
+const TEXT_RESPONSE: &str = include_str!("fixtures/gemini/generate_content_text.json");
+const TOOL_RESPONSE: &str = include_str!("fixtures/gemini/generate_content_tool.json");
+const THINKING_STREAM: &str = include_str!("fixtures/gemini/stream_thinking_text.sse");
+const FUNCTION_CALL_STREAM: &str = include_str!("fixtures/gemini/stream_function_call.sse");
+const INVALID_KEY_ERROR: &str = include_str!("fixtures/gemini/error_invalid_key.json");
+
+fn events(bytes: &[u8]) -> Vec<Value> {
+    anyclaude::sse::parse_sse_events(bytes)
+        .into_iter()
+        .map(|e| e.data)
+        .collect()
+}
+
+fn event_types(events: &[Value]) -> Vec<&str> {
+    events.iter().map(|e| e["type"].as_str().unwrap()).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: request translation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn request_path_depends_on_streaming() {
+    assert_eq!(
+        request_path("gemini-2.5-pro", false),
+        "/v1beta/models/gemini-2.5-pro:generateContent"
+    );
+    assert_eq!(
+        request_path("models/gemini-2.5-pro", true),
+        "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+    );
+}
+
+#[test]
+fn request_translates_system_and_generation_config() {
+    let body = json!({
+        "model": "gemini-2.5-pro",
+        "system": [{"type": "text", "text": "Be brief."}],
+        "max_tokens": 2048,
+        "temperature": 0.5,
+        "top_k": 40,
+        "stop_sequences": ["END"],
+        "thinking": {"type": "enabled", "budget_tokens": 1024},
+        "stream": true,
+        "messages": [
+            {"role": "user", "content": "hi"},
+            {"role": "assistant", "content": "hello"},
+        ],
+    });
+
+    let out = to_generate_content(&body);
+
+    assert!(out.get("model").is_none(), "model travels in the URL");
+    assert!(out.get("stream").is_none(), "streaming travels in the URL");
+    assert_eq!(out["systemInstruction"], json!({"parts": [{"text": "Be brief."}]}));
+    assert_eq!(out["contents"], json!([
+        {"role": "user", "parts": [{"text": "hi"}]},
+        {"role": "model", "parts": [{"text": "hello"}]},
+    ]));
+    assert_eq!(out["generationConfig"], json!({
+        "maxOutputTokens": 2048,
+        "temperature": 0.5,
+        "topK": 40,
+        "stopSequences": ["END"],
+        "thinkingConfig": {"includeThoughts": true, "thinkingBudget": 1024},
+    }));
+}
+
+#[test]
+fn request_translates_tool_round_trip_and_signatures() {
+    let body = json!({
+        "model": "m",
+        "messages": [
+            {"role": "user", "content": "weather?"},
+            {"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "", "signature": "c2ln"},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}},
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "rain"},
+                {"type": "tool_result", "tool_use_id": "toolu_missing", "content": "x", "is_error": true},
+            ]},
+        ],
+    });
+
+    let out = to_generate_content(&body);
+    let contents = out["contents"].as_array().unwrap();
+
+    assert_eq!(contents[1], json!({"role": "model", "parts": [{
+        "functionCall": {"name": "get_weather", "args": {"city": "Oslo"}},
+        "thoughtSignature": "c2ln",
+    }]}));
+    assert_eq!(contents[2]["role"], "user");
+    assert_eq!(contents[2]["parts"][0], json!({
+        "functionResponse": {"name": "get_weather", "response": {"content": "rain"}},
+    }));
+    assert_eq!(contents[2]["parts"][1]["functionResponse"]["response"], json!({"error": "x"}));
+}
+
+#[test]
+fn request_translates_images_tools_and_tool_choice() {
+    let body = json!({
+        "model": "m",
+        "messages": [{"role": "user", "content": [
+            {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "AAAA"}},
+            {"type": "text", "text": "what is this?"},
+        ]}],
+        "tools": [
+            {"name": "read_file", "description": "Read", "input_schema": {"type": "object", "additionalProperties": false}},
+            {"type": "web_search_20250305", "name": "web_search"},
+        ],
+        "tool_choice": {"type": "tool", "name": "read_file"},
+    });
+
+    let out = to_generate_content(&body);
+
+    assert_eq!(out["contents"][0]["parts"][0], json!({
+        "inlineData": {"mimeType": "image/jpeg", "data": "AAAA"},
+    }));
+    assert_eq!(out["tools"], json!([{"functionDeclarations": [{
+        "name": "read_file",
+        "description": "Read",
+        "parametersJsonSchema": {"type": "object", "additionalProperties": false},
+    }]}]));
+    assert_eq!(out["toolConfig"], json!({
+        "functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["read_file"]},
+    }));
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: response translation (fixtures)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn response_translates_thought_and_text() {
+    let out = to_message(&serde_json::from_str(TEXT_RESPONSE).unwrap());
+
+    assert_eq!(out["type"], "message");
+    assert_eq!(out["id"], "msg_resp-text-1");
+    assert_eq!(out["model"], "gemini-2.5-pro");
+    assert_eq!(out["content"], json!([
+        {"type": "thinking", "thinking": "The user wants a greeting.", "signature": "c2lnLXRob3VnaHQ="},
+        {"type": "text", "text": "Hello there!"},
+    ]));
+    assert_eq!(out["stop_reason"], "end_turn");
+    assert_eq!(out["usage"], json!({
+        "input_tokens": 20,
+        "output_tokens": 34,
+        "cache_read_input_tokens": 100,
+    }));
+}
+
+#[test]
+fn response_translates_function_call() {
+    let out = to_message(&serde_json::from_str(TOOL_RESPONSE).unwrap());
+    let content = out["content"].as_array().unwrap();
+
+    assert_eq!(content[0], json!({"type": "text", "text": "Checking the weather."}));
+    assert_eq!(content[1], json!({"type": "thinking", "thinking": "", "signature": "c2lnLWNhbGw="}));
+    assert_eq!(content[2]["type"], "tool_use");
+    assert_eq!(content[2]["name"], "get_weather");
+    assert_eq!(content[2]["input"], json!({"city": "Oslo"}));
+    assert!(content[2]["id"].as_str().unwrap().starts_with("toolu_"));
+    assert_eq!(out["stop_reason"], "tool_use", "STOP with a function call is tool use");
+}
+
+#[test]
+fn response_maps_finish_reasons() {
+    let finish = |reason: &str| {
+        to_message(&json!({"candidates": [{"content": {"parts": [{"text": "x"}]}, "finishReason": reason}]}))
+            ["stop_reason"]
+            .clone()
+    };
+    assert_eq!(finish("STOP"), "end_turn");
+    assert_eq!(finish("MAX_TOKENS"), "max_tokens");
+    assert_eq!(finish("SAFETY"), "refusal");
+
+    let blocked = to_message(&json!({"promptFeedback": {"blockReason": "SAFETY"}}));
+    assert_eq!(blocked["stop_reason"], "refusal");
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: SSE stream translation (fixtures)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn stream_translates_thinking_then_text() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(THINKING_STREAM));
+    let events = events(&out);
+
+    assert_eq!(event_types(&events), [
+        "message_start",
+        "content_block_start",
+        "content_block_delta",
+        "content_block_delta",
+        "content_block_delta",
+        "content_block_stop",
+        "content_block_start",
+        "content_block_delta",
+        "content_block_delta",
+        "content_block_stop",
+        "message_delta",
+        "message_stop",
+    ]);
+    assert_eq!(events[0]["message"]["model"], "gemini-2.5-pro");
+    assert_eq!(events[1]["content_block"]["type"], "thinking");
+    assert_eq!(events[2]["delta"], json!({"type": "thinking_delta", "thinking": "Considering"}));
+    assert_eq!(events[4]["delta"], json!({"type": "signature_delta", "signature": "c2lnLXN0cmVhbQ=="}));
+    assert_eq!(events[6]["index"], 1);
+    assert_eq!(events[6]["content_block"]["type"], "text");
+    assert_eq!(events[8]["delta"]["text"], "lo!");
+    assert_eq!(events[10]["delta"]["stop_reason"], "end_turn");
+    assert_eq!(events[10]["usage"]["output_tokens"], 11);
+
+    // The thinking registry sees well-formed thinking events.
+    let stats = anyclaude::sse::analyze_thinking_stream(&anyclaude::sse::parse_sse_events(&out));
+    assert_eq!(stats.thinking_blocks, 1);
+    assert_eq!(stats.thinking_stops, 1);
+    assert!(stats.has_signatures);
+}
+
+#[test]
+fn stream_translates_function_calls() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(FUNCTION_CALL_STREAM));
+    let events = events(&out);
+
+    let tool_starts: Vec<&Value> = events
+        .iter()
+        .filter(|e| e["type"] == "content_block_start" && e["content_block"]["type"] == "tool_use")
+        .collect();
+    assert_eq!(tool_starts.len(), 2);
+    assert_eq!(tool_starts[0]["index"], 1);
+    assert_eq!(tool_starts[0]["content_block"]["name"], "read_file");
+    assert_eq!(tool_starts[1]["index"], 2);
+    assert_ne!(tool_starts[0]["content_block"]["id"], tool_starts[1]["content_block"]["id"]);
+
+    let args: Value = serde_json::from_str(
+        events
+            .iter()
+            .find(|e| e["index"] == 1 && e["type"] == "content_block_delta")
+            .unwrap()["delta"]["partial_json"]
+            .as_str()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(args, json!({"path": "src/main.rs"}));
+
+    let delta = events.iter().find(|e| e["type"] == "message_delta").unwrap();
+    assert_eq!(delta["delta"]["stop_reason"], "tool_use");
+    assert_eq!(events.last().unwrap()["type"], "message_stop");
+}
+
+#[test]
+fn stream_buffers_lines_split_across_chunks() {
+    let mut translator = make_stream_translator();
+    let mut out = Vec::new();
+    for chunk in THINKING_STREAM.as_bytes().chunks(17) {
+        out.extend_from_slice(&translator(Bytes::copy_from_slice(chunk)));
+    }
+
+    let mut whole = make_stream_translator();
+    assert_eq!(out, whole(Bytes::from(THINKING_STREAM)).to_vec());
+}
+
+#[test]
+fn stream_without_finish_reason_is_finished_on_flush() {
+    let mut translator = make_stream_translator();
+    let truncated: String = THINKING_STREAM.split_inclusive("\n\n").take(2).collect();
+    let mut out = translator(Bytes::from(truncated)).to_vec();
+    assert!(!event_types(&events(&out)).contains(&"message_stop"));
+
+    out.extend_from_slice(&translator(Bytes::new()));
+    let events = events(&out);
+    assert_eq!(&event_types(&events)[events.len() - 3..], ["content_block_stop", "message_delta", "message_stop"]);
+    let delta = &events[events.len() - 2];
+    assert_eq!(delta["delta"]["stop_reason"], "end_turn");
+    assert_eq!(delta["usage"]["input_tokens"], 20);
+}
+
+#[test]
+fn stream_flushes_unterminated_last_line() {
+    let mut translator = make_stream_translator();
+    let mut out = translator(Bytes::from(THINKING_STREAM.trim_end())).to_vec();
+    out.extend_from_slice(&translator(Bytes::new()));
+
+    let mut whole = make_stream_translator();
+    assert_eq!(out, whole(Bytes::from(THINKING_STREAM)).to_vec());
+    assert_eq!(event_types(&events(&out)).iter().filter(|t| **t == "message_stop").count(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Integration tests: full proxy pipeline against the mock backend
+// ---------------------------------------------------------------------------
+
+use anyclaude::config::{Backend, BackendProtocol};
+use common::mock_backend::{MockBackend, MockResponse};
+
+fn gemini_backend(base_url: &str) -> Backend {
+    Backend {
+        name: "gemini".to_string(),
+        display_name: "Gemini".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "api_key".to_string(),
+        api_key: Some("goog-key".to_string()),
+        model_opus: Some("gemini-2.5-pro".to_string()),
+        protocol: BackendProtocol::Gemini,
+        ..Default::default()
+    }
+}
+
+/// Start a proxy for `backend` without retries.
+async fn start_proxy(backend: Backend) -> String {
+    let mut config = common::proxy_config(vec![backend]);
+    config.defaults.max_retries = 0;
+    common::start_proxy(config).await.url
+}
+
+#[tokio::test]
+async fn integration_generate_content() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(TEXT_RESPONSE)).await;
+    let proxy_url = start_proxy(gemini_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-opus-4-6","max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["content"][1]["text"], "Hello there!");
+    assert_eq!(body["stop_reason"], "end_turn");
+
+    let requests = mock.captured_requests().await;
+    assert_eq!(requests[0].path, "/v1beta/models/gemini-2.5-pro:generateContent");
+    assert!(requests[0]
+        .headers
+        .iter()
+        .any(|(k, v)| k == "x-goog-api-key" && v == "goog-key"));
+    assert!(!requests[0].headers.iter().any(|(k, _)| k == "x-api-key"));
+    let sent: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["contents"], json!([{"role": "user", "parts": [{"text": "hi"}]}]));
+    assert_eq!(sent["generationConfig"]["maxOutputTokens"], 1024);
+}
+
+#[tokio::test]
+async fn integration_stream_generate_content() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse {
+        headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+        body: THINKING_STREAM.as_bytes().to_vec(),
+        ..Default::default()
+    }).await;
+    let proxy_url = start_proxy(gemini_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-opus-4-6","stream":true,"max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    let events = events(&resp.bytes().await.unwrap());
+    assert_eq!(events[0]["type"], "message_start");
+    assert_eq!(events[0]["message"]["model"], "claude-opus-4-6", "model is reverse-mapped");
+    let text: String = events
+        .iter()
+        .filter(|e| e["delta"]["type"] == "text_delta")
+        .map(|e| e["delta"]["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(text, "Hello!");
+    assert_eq!(events.last().unwrap()["type"], "message_stop");
+
+    let requests = mock.captured_requests().await;
+    assert_eq!(requests[0].path, "/v1beta/models/gemini-2.5-pro:streamGenerateContent");
+}
+
+#[tokio::test]
+async fn integration_error_is_translated() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse {
+        status: 400,
+        body: INVALID_KEY_ERROR.as_bytes().to_vec(),
+        ..Default::default()
+    }).await;
+    let proxy_url = start_proxy(gemini_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-opus-4-6","max_tokens":16,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["type"], "error");
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["message"], "API key not valid. Please pass a valid API key.");
+}
@@ -0,0 +1,147 @@
+use anyclaude::metrics::har::{default_har_path, to_har, write_har};
+use anyclaude::metrics::{
+    redact_sse_text, AgentKind, RequestMeta, RequestRecord, RequestTrace, ResponseMeta,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn record(id: &str, status: Option<u16>) -> RequestRecord {
+    RequestRecord {
+        id: id.to_string(),
+        started_at: UNIX_EPOCH + Duration::from_millis(1_760_000_000_250),
+        first_byte_at: None,
+        completed_at: None,
+        latency_ms: Some(900),
+        ttfb_ms: Some(300),
+        backend: "alpha".to_string(),
+        session_id: None,
+        status,
+        timed_out: false,
+        request_bytes: 42,
+        response_bytes: 128,
+        request_analysis: None,
+        response_analysis: None,
+        usage: None,
+        routing_decision: None,
+        agent: AgentKind::Main,
+        agent_id: None,
+        request_meta: None,
+        response_meta: None,
+        trace: RequestTrace::default(),
+    }
+}
+
+fn streamed_record() -> RequestRecord {
+    let mut record = record("r1", Some(200));
+    record.request_meta = Some(RequestMeta {
+        method: "POST".to_string(),
+        path: "/v1/messages".to_string(),
+        query: Some("beta=true".to_string()),
+        headers: Some(vec![
+            ("content-type".to_string(), "application/json".to_string()),
+            ("x-api-key".to_string(), "sk-****".to_string()),
+        ]),
+        body_preview: Some("{\"model\":\"claude-sonnet-4-5\"}".to_string()),
+        body_truncated: false,
+    });
+    record.response_meta = Some(ResponseMeta {
+        headers: Some(vec![(
+            "content-type".to_string(),
+            "text/event-stream".to_string(),
+        )]),
+        body_preview: Some("SSE: 2 events".to_string()),
+        sse_text: Some("event: ping\ndata: {\"type\":\"ping\"}\n\n".to_string()),
+    });
+    record
+}
+
+#[test]
+fn test_har_entry_with_bodies() {
+    let har = to_har(&[streamed_record()], true, |_| "https://api.example.com/".to_string());
+    assert_eq!(har["log"]["version"], "1.2");
+    assert_eq!(har["log"]["creator"]["name"], "anyclaude");
+
+    let entry = &har["log"]["entries"][0];
+    assert_eq!(entry["startedDateTime"], "2025-10-09T08:53:20.250Z");
+    assert_eq!(entry["time"], 900);
+    assert_eq!(entry["timings"]["wait"], 300);
+    assert_eq!(entry["timings"]["receive"], 600);
+    assert_eq!(entry["_backend"], "alpha");
+
+    let request = &entry["request"];
+    assert_eq!(request["method"], "POST");
+    assert_eq!(request["url"], "https://api.example.com/v1/messages?beta=true");
+    assert_eq!(request["queryString"][0]["name"], "beta");
+    assert_eq!(request["queryString"][0]["value"], "true");
+    assert_eq!(request["headers"][1]["value"], "sk-****");
+    assert_eq!(request["postData"]["text"], "{\"model\":\"claude-sonnet-4-5\"}");
+
+    let response = &entry["response"];
+    assert_eq!(response["status"], 200);
+    assert_eq!(response["statusText"], "OK");
+    // Streams keep their event text, not the log's summary.
+    assert_eq!(response["content"]["mimeType"], "text/event-stream");
+    assert_eq!(
+        response["content"]["text"],
+        "event: ping\ndata: {\"type\":\"ping\"}\n\n"
+    );
+}
+
+#[test]
+fn test_har_entry_without_bodies() {
+    let har = to_har(&[streamed_record()], false, |_| "https://api.example.com".to_string());
+    let entry = &har["log"]["entries"][0];
+    assert!(entry["request"].get("postData").is_none());
+    assert!(entry["response"]["content"].get("text").is_none());
+    assert_eq!(entry["response"]["content"]["size"], 128);
+}
+
+#[test]
+fn test_har_entry_without_response() {
+    let mut record = record("r2", None);
+    record.timed_out = true;
+    record.trace.method = "GET".to_string();
+    record.trace.path = "/v1/models".to_string();
+
+    let har = to_har(&[record], true, |_| "http://127.0.0.1:8080".to_string());
+    let entry = &har["log"]["entries"][0];
+    assert_eq!(entry["request"]["url"], "http://127.0.0.1:8080/v1/models");
+    assert_eq!(entry["request"]["headers"].as_array().unwrap().len(), 0);
+    assert_eq!(entry["response"]["status"], 0);
+    assert_eq!(entry["response"]["statusText"], "");
+    assert_eq!(entry["_timedOut"], true);
+}
+
+#[test]
+fn test_redact_sse_text() {
+    let raw = b"event: message_start\n\
+data: {\"type\":\"message_start\",\"api_key\":\"sk-secret-value\"}\n\n\
+data: Bearer abcdef123456\n\n\
+data: {\"type\":\"ping\"}\n\n";
+    let text = redact_sse_text(raw);
+    assert!(!text.contains("sk-secret-value"));
+    assert!(!text.contains("abcdef123456"));
+    // Events without secrets are kept byte for byte.
+    assert!(text.starts_with("event: message_start\n"));
+    assert!(text.ends_with("data: {\"type\":\"ping\"}\n\n"));
+}
+
+#[test]
+fn test_default_har_path() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_760_000_000);
+    assert_eq!(
+        default_har_path("/tmp/anyclaude/debug.log", now),
+        std::path::PathBuf::from("/tmp/anyclaude/anyclaude-20251009T085320Z.har")
+    );
+}
+
+#[test]
+fn test_write_har_creates_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("exports").join("session.har");
+    let har = to_har(&[record("r1", Some(200))], false, |_| String::new());
+
+    write_har(&path, &har).unwrap();
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written, har);
+}
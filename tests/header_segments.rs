@@ -0,0 +1,183 @@
+//! `[ui.header]`: header bar segments and narrow-terminal fallback.
+
+use anyclaude::config::{Config, HeaderConfig, HeaderSegment};
+use anyclaude::error::{ErrorCategory, ErrorRegistry, ErrorSeverity};
+use anyclaude::ipc::ProxyStatus;
+use anyclaude::metrics::{BackendMetrics, InFlightRequest, MetricsSnapshot, ObservabilityHub, ObservedStream};
+use anyclaude::ui::header::Header;
+use axum::body::{Body, Bytes};
+use axum::http::Request;
+use futures_core::Stream;
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+
+fn status() -> ProxyStatus {
+    ProxyStatus {
+        active_backend: "main".to_string(),
+        uptime_seconds: 3_725,
+        total_requests: 12,
+        healthy: true,
+    }
+}
+
+fn metrics(in_flight: Vec<InFlightRequest>) -> MetricsSnapshot {
+    let main = BackendMetrics {
+        total: 10,
+        success_2xx: 10,
+        input_tokens: 12_345,
+        output_tokens: 678,
+        cache_read_input_tokens: 90_000,
+        cost_usd: 0.4,
+        ..Default::default()
+    };
+    let cheap = BackendMetrics {
+        total: 8,
+        success_2xx: 7,
+        input_tokens: 1_000,
+        cost_usd: 0.02,
+        ..Default::default()
+    };
+    MetricsSnapshot {
+        generated_at: SystemTime::now(),
+        per_backend: HashMap::from([("main".to_string(), main), ("cheap".to_string(), cheap)]),
+        recent: Vec::new(),
+        in_flight,
+    }
+}
+
+fn streaming(id: &str, elapsed_secs: u64, output_tokens: u64) -> InFlightRequest {
+    InFlightRequest {
+        id: id.to_string(),
+        backend: "main".to_string(),
+        started_at: SystemTime::now() - Duration::from_secs(elapsed_secs),
+        streaming: true,
+        output_tokens,
+    }
+}
+
+fn render(header: &Header, errors: &ErrorRegistry, metrics: Option<&MetricsSnapshot>, width: usize) -> String {
+    header.line(Some(&status()), errors, metrics, width).to_string()
+}
+
+#[test]
+fn test_all_segments() {
+    let snapshot = metrics(vec![streaming("a", 12, 1_500)]);
+    let line = render(&Header::new(), &ErrorRegistry::default(), Some(&snapshot), 500);
+
+    assert_eq!(
+        line,
+        " 🟢 Backend: main │ ↓ main 12s 1.5k tok │ Cost: $0.42 │ \
+         Tokens: 13.3k in / 678 out / 90.0k cache │ Reqs: 12 │ Err: cheap 13%, main 0% │ Uptime: 1h02m"
+    );
+}
+
+#[test]
+fn test_segments_without_metrics() {
+    let line = render(&Header::new(), &ErrorRegistry::default(), None, 500);
+    assert_eq!(line, " 🟢 Backend: main │ Reqs: 12 │ Uptime: 1h02m");
+}
+
+#[test]
+fn test_in_flight_waiting_and_count() {
+    let waiting = InFlightRequest {
+        streaming: false,
+        ..streaming("a", 3, 0)
+    };
+    let snapshot = metrics(vec![waiting.clone()]);
+    let header = Header::with_segments(vec![HeaderSegment::InFlight]);
+    assert_eq!(render(&header, &ErrorRegistry::default(), Some(&snapshot), 500), " 🟢 … main 3s");
+
+    let snapshot = metrics(vec![waiting, streaming("b", 65, 20)]);
+    assert_eq!(
+        render(&header, &ErrorRegistry::default(), Some(&snapshot), 500),
+        " 🟢 ↓ main 1m05s 20 tok +1"
+    );
+
+    // Idle: the segment is left out.
+    let snapshot = metrics(Vec::new());
+    assert_eq!(render(&header, &ErrorRegistry::default(), Some(&snapshot), 500), " 🟢 ");
+}
+
+#[test]
+fn test_narrow_terminal_compacts_then_drops() {
+    let snapshot = metrics(vec![streaming("a", 12, 1_500)]);
+    let header = Header::new();
+    let errors = ErrorRegistry::default();
+
+    let compact = render(&header, &errors, Some(&snapshot), 80);
+    assert_eq!(compact, " 🟢 main │ ↓ 12s │ $0.42 │ 13.3k/678 │ 12 req │ Err: 13% │ up 1h02m");
+
+    let narrow = render(&header, &errors, Some(&snapshot), 30);
+    assert_eq!(narrow, " 🟢 main │ ↓ 12s │ $0.42");
+
+    assert_eq!(render(&header, &errors, Some(&snapshot), 5), " 🟢 ");
+}
+
+#[test]
+fn test_error_message_precedes_segments() {
+    let errors = ErrorRegistry::default();
+    errors.record(ErrorSeverity::Warning, ErrorCategory::Backend, "Budget: daily spend at 80%");
+    let header = Header::with_segments(vec![HeaderSegment::Backend]);
+
+    assert_eq!(render(&header, &errors, None, 500), " 🟡 Budget: daily spend at 80% │ Backend: main");
+}
+
+#[test]
+fn test_header_segments_from_toml() {
+    let header: HeaderConfig = toml::from_str(r#"segments = ["cost", "in_flight", "backend"]"#).unwrap();
+    assert_eq!(
+        header.segments,
+        vec![HeaderSegment::Cost, HeaderSegment::InFlight, HeaderSegment::Backend]
+    );
+
+    let segments = Config::default().ui.header.segments;
+    assert_eq!(segments.len(), 7);
+    assert_eq!(segments[0], HeaderSegment::Backend);
+}
+
+/// Upstream stream that yields the chunks pushed onto it.
+struct Chunks(VecDeque<Bytes>);
+
+impl Stream for Chunks {
+    type Item = Result<Bytes, reqwest::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.pop_front().map(Ok))
+    }
+}
+
+fn delta(text: &str) -> Bytes {
+    Bytes::from(format!(
+        "data: {{\"type\":\"content_block_delta\",\"index\":0,\"delta\":{{\"type\":\"text_delta\",\"text\":\"{}\"}}}}\n\n",
+        text
+    ))
+}
+
+async fn next_chunk(stream: &mut ObservedStream<Chunks>) {
+    std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await.unwrap().unwrap();
+}
+
+fn streamed_tokens(hub: &ObservabilityHub) -> u64 {
+    hub.snapshot().in_flight[0].output_tokens
+}
+
+#[tokio::test]
+async fn test_stream_progress_is_throttled() {
+    let hub = ObservabilityHub::new(10);
+    let request = Request::new(Body::empty());
+    let span = hub.start_request("req-1".to_string(), &request, "main").span;
+    let chunks = Chunks(std::iter::repeat_with(|| delta("abcdefgh")).take(3).collect());
+    let mut stream = ObservedStream::new(chunks, span, hub.clone(), Duration::from_secs(60), None);
+
+    next_chunk(&mut stream).await;
+    assert_eq!(streamed_tokens(&hub), 2);
+
+    next_chunk(&mut stream).await;
+    assert_eq!(streamed_tokens(&hub), 2);
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    next_chunk(&mut stream).await;
+    assert_eq!(streamed_tokens(&hub), 6);
+}
@@ -0,0 +1,242 @@
+//! Active health probe tests: probe kinds, health classification, and the
+//! per-backend `/health` endpoint.
+
+mod common;
+
+use anyclaude::backend::BackendState;
+use anyclaude::config::{Backend, Config, Defaults, HealthCheckConfig, HealthCheckMethod};
+use anyclaude::proxy::health::HealthHandler;
+use anyclaude::proxy::prober::{probe_backend, HealthProber};
+use common::mock_backend::{MockBackend, MockResponse};
+use http_body_util::BodyExt;
+use std::time::Duration;
+
+fn create_backend(name: &str, base_url: &str, auth_type: &str, api_key: Option<&str>) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_uppercase(),
+        base_url: base_url.to_string(),
+        auth_type_str: auth_type.to_string(),
+        api_key: api_key.map(String::from),
+        ..Default::default()
+    }
+}
+
+fn health_check(method: HealthCheckMethod) -> HealthCheckConfig {
+    HealthCheckConfig {
+        enabled: true,
+        interval_seconds: 1,
+        timeout_seconds: 2,
+        method,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_models_probe_uses_backend_auth() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend("alpha", &mock.base_url(), "api_key", Some("probe-key"));
+
+    let probe = probe_backend(
+        &reqwest::Client::new(),
+        &backend,
+        &health_check(HealthCheckMethod::Models),
+    )
+    .await;
+
+    assert!(probe.reachable);
+    assert!(probe.healthy);
+    assert_eq!(probe.status, Some(200));
+    assert!(probe.latency_ms.is_some());
+
+    let captured = mock.captured_requests().await;
+    assert_eq!(captured.len(), 1);
+    assert_eq!(captured[0].method, "GET");
+    assert_eq!(captured[0].path, "/v1/models");
+    assert!(captured[0]
+        .headers
+        .iter()
+        .any(|(k, v)| k == "x-api-key" && v == "probe-key"));
+}
+
+#[tokio::test]
+async fn test_message_probe_sends_one_token_request_with_mapped_model() {
+    let mock = MockBackend::start().await;
+    let mut backend = create_backend("alpha", &mock.base_url(), "bearer", Some("token"));
+    backend.model_haiku = Some("provider-small".to_string());
+
+    let probe = probe_backend(
+        &reqwest::Client::new(),
+        &backend,
+        &health_check(HealthCheckMethod::Message),
+    )
+    .await;
+    assert!(probe.healthy);
+
+    let captured = mock.captured_requests().await;
+    assert_eq!(captured[0].method, "POST");
+    assert_eq!(captured[0].path, "/v1/messages");
+    let body: serde_json::Value = serde_json::from_slice(&captured[0].body).unwrap();
+    assert_eq!(body["max_tokens"], 1);
+    assert_eq!(body["model"], "provider-small");
+    assert!(captured[0]
+        .headers
+        .iter()
+        .any(|(k, v)| k == "authorization" && v == "Bearer token"));
+}
+
+#[tokio::test]
+async fn test_overloaded_backend_is_reachable_but_unhealthy() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    let backend = create_backend("alpha", &mock.base_url(), "passthrough", None);
+
+    let probe = probe_backend(
+        &reqwest::Client::new(),
+        &backend,
+        &health_check(HealthCheckMethod::Models),
+    )
+    .await;
+
+    assert!(probe.reachable);
+    assert!(!probe.healthy);
+    assert_eq!(probe.status, Some(529));
+    assert!(probe.error.is_some());
+}
+
+#[tokio::test]
+async fn test_auth_rejection_depends_on_auth_type() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::error(401, "unauthorized")).await;
+    mock.enqueue_response(MockResponse::error(401, "unauthorized")).await;
+    let client = reqwest::Client::new();
+    let config = health_check(HealthCheckMethod::Models);
+
+    // Own credentials rejected: unhealthy
+    let keyed = create_backend("keyed", &mock.base_url(), "api_key", Some("bad"));
+    assert!(!probe_backend(&client, &keyed, &config).await.healthy);
+
+    // Passthrough probes carry no auth, so 401 is expected
+    let passthrough = create_backend("pass", &mock.base_url(), "passthrough", None);
+    assert!(probe_backend(&client, &passthrough, &config).await.healthy);
+}
+
+#[tokio::test]
+async fn test_unreachable_backend() {
+    let dead_url = format!("http://127.0.0.1:{}", common::free_port());
+    let backend = create_backend("dead", &dead_url, "passthrough", None);
+    let client = reqwest::Client::new();
+
+    let probe = probe_backend(&client, &backend, &health_check(HealthCheckMethod::Models)).await;
+    assert!(!probe.reachable);
+    assert!(!probe.healthy);
+    assert!(probe.error.is_some());
+
+    let probe = probe_backend(&client, &backend, &health_check(HealthCheckMethod::Connect)).await;
+    assert!(!probe.reachable);
+}
+
+#[tokio::test]
+async fn test_connect_probe_sends_no_request() {
+    let mock = MockBackend::start().await;
+    let backend = create_backend("alpha", &mock.base_url(), "passthrough", None);
+
+    let probe = probe_backend(
+        &reqwest::Client::new(),
+        &backend,
+        &health_check(HealthCheckMethod::Connect),
+    )
+    .await;
+
+    assert!(probe.reachable);
+    assert!(probe.healthy);
+    assert_eq!(probe.status, None);
+    assert!(mock.captured_requests().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_probe_all_skips_unconfigured_backends() {
+    let mock = MockBackend::start().await;
+    let config = Config {
+        defaults: Defaults {
+            active: "alpha".to_string(),
+            ..Default::default()
+        },
+        backends: vec![
+            create_backend("alpha", &mock.base_url(), "passthrough", None),
+            create_backend("missing", &mock.base_url(), "api_key", None),
+        ],
+        health_check: health_check(HealthCheckMethod::Models),
+        ..Default::default()
+    };
+    let backend_state = BackendState::from_config(config).unwrap();
+
+    HealthProber::new(backend_state.clone()).probe_all().await;
+
+    let results = backend_state.probe_results();
+    assert!(results.get("alpha").is_some_and(|p| p.healthy));
+    assert!(results.get("missing").is_none());
+    assert_eq!(mock.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_health_handler_reports_backends_before_probing() {
+    let config = Config {
+        defaults: Defaults {
+            active: "alpha".to_string(),
+            ..Default::default()
+        },
+        backends: vec![create_backend("alpha", "http://127.0.0.1:1", "passthrough", None)],
+        ..Default::default()
+    };
+    let backend_state = BackendState::from_config(config).unwrap();
+
+    let resp = HealthHandler::new()
+        .with_backend_state(backend_state)
+        .handle()
+        .await;
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["status"], "healthy");
+    assert!(json["backends"]["alpha"]["healthy"].is_null());
+    assert_eq!(json["backends"]["alpha"]["circuit"], "closed");
+}
+
+#[tokio::test]
+async fn test_health_endpoint_serves_probe_results() {
+    let mock = MockBackend::start().await;
+    let dead_url = format!("http://127.0.0.1:{}", common::free_port());
+
+    let config = Config {
+        health_check: health_check(HealthCheckMethod::Models),
+        ..common::proxy_config(vec![
+            create_backend("alive", &mock.base_url(), "passthrough", None),
+            create_backend("dead", &dead_url, "passthrough", None),
+        ])
+    };
+    let proxy = common::start_proxy(config).await;
+
+    // First probe round runs immediately on startup
+    let client = reqwest::Client::new();
+    let mut json = serde_json::Value::Null;
+    for _ in 0..40 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        json = client
+            .get(format!("{}/health", proxy.url))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if !json["backends"]["dead"]["reachable"].is_null() {
+            break;
+        }
+    }
+
+    assert_eq!(json["backends"]["alive"]["healthy"], true);
+    assert!(json["backends"]["alive"]["latency_ms"].is_u64());
+    assert_eq!(json["backends"]["dead"]["reachable"], false);
+    assert_eq!(json["backends"]["dead"]["healthy"], false);
+}
@@ -17,6 +17,7 @@ fn make_entries(count: usize) -> Vec<HistoryEntry> {
                 Some(format!("backend-{}", i - 1))
             },
             to_backend: format!("backend-{}", i),
+            failover: false,
         })
         .collect()
 }
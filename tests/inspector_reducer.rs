@@ -0,0 +1,276 @@
+use anyclaude::metrics::{
+    AgentKind, RequestMeta, RequestRecord, RequestTrace, ResponseMeta, RoutingDecision,
+};
+use anyclaude::ui::inspector::{
+    curl_command, detail_lines, pretty_body, DetailLine, InspectorDialogState, InspectorIntent,
+    InspectorReducer, StatusFilter, DETAIL_ROWS,
+};
+use anyclaude::ui::mvi::Reducer;
+use std::time::SystemTime;
+
+fn record(id: &str, backend: &str, status: Option<u16>, agent: AgentKind) -> RequestRecord {
+    RequestRecord {
+        id: id.to_string(),
+        started_at: SystemTime::now(),
+        first_byte_at: None,
+        completed_at: None,
+        latency_ms: Some(120),
+        ttfb_ms: Some(40),
+        backend: backend.to_string(),
+        session_id: None,
+        status,
+        timed_out: false,
+        request_bytes: 0,
+        response_bytes: 0,
+        request_analysis: None,
+        response_analysis: None,
+        usage: None,
+        routing_decision: None,
+        agent,
+        agent_id: None,
+        request_meta: None,
+        response_meta: None,
+        trace: RequestTrace::default(),
+    }
+}
+
+/// Records oldest first, as in `MetricsSnapshot.recent`.
+fn sample_records() -> Vec<RequestRecord> {
+    vec![
+        record("r1", "alpha", Some(200), AgentKind::Main),
+        record("r2", "beta", Some(529), AgentKind::Subagent),
+        record("r3", "alpha", None, AgentKind::Teammate),
+        record("r4", "beta", Some(200), AgentKind::Main),
+    ]
+}
+
+fn loaded() -> InspectorDialogState {
+    InspectorReducer::reduce(
+        InspectorDialogState::Hidden,
+        InspectorIntent::Load {
+            records: sample_records(),
+        },
+    )
+}
+
+fn ids(state: &InspectorDialogState) -> Vec<&str> {
+    state.filtered().iter().map(|r| r.id.as_str()).collect()
+}
+
+#[test]
+fn test_load_lists_newest_first() {
+    let state = loaded();
+    assert!(state.is_visible());
+    assert_eq!(ids(&state), vec!["r4", "r3", "r2", "r1"]);
+    assert_eq!(state.selected_record().unwrap().id, "r4");
+}
+
+#[test]
+fn test_move_clamps_to_list() {
+    let mut state = loaded();
+    for _ in 0..10 {
+        state = InspectorReducer::reduce(state, InspectorIntent::MoveDown);
+    }
+    assert_eq!(state.selected_record().unwrap().id, "r1");
+    state = InspectorReducer::reduce(state, InspectorIntent::PageUp);
+    assert_eq!(state.selected_record().unwrap().id, "r4");
+}
+
+#[test]
+fn test_filters_cycle() {
+    let mut state = InspectorReducer::reduce(loaded(), InspectorIntent::CycleBackendFilter);
+    assert_eq!(ids(&state), vec!["r3", "r1"]);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleBackendFilter);
+    assert_eq!(ids(&state), vec!["r4", "r2"]);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleBackendFilter);
+    assert_eq!(ids(&state).len(), 4);
+
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleStatusFilter);
+    assert_eq!(ids(&state), vec!["r4", "r1"]);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleStatusFilter);
+    assert_eq!(ids(&state), vec!["r3", "r2"]);
+    let InspectorDialogState::Visible { filter, .. } = &state else {
+        panic!("inspector closed");
+    };
+    assert_eq!(filter.status, StatusFilter::Error);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleStatusFilter);
+
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleAgentFilter);
+    assert_eq!(ids(&state), vec!["r4", "r1"]);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleAgentFilter);
+    assert_eq!(ids(&state), vec!["r2"]);
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleAgentFilter);
+    assert_eq!(ids(&state), vec!["r3"]);
+}
+
+#[test]
+fn test_refresh_keeps_selected_request() {
+    let mut state = InspectorReducer::reduce(loaded(), InspectorIntent::MoveDown);
+    assert_eq!(state.selected_record().unwrap().id, "r3");
+
+    let mut records = sample_records();
+    records.push(record("r5", "alpha", Some(200), AgentKind::Main));
+    state = InspectorReducer::reduce(state, InspectorIntent::Refresh { records });
+
+    assert_eq!(ids(&state)[0], "r5");
+    assert_eq!(state.selected_record().unwrap().id, "r3");
+}
+
+#[test]
+fn test_detail_pane_scrolls_and_closes() {
+    let mut state = InspectorReducer::reduce(loaded(), InspectorIntent::OpenDetail);
+    let InspectorDialogState::Visible { detail: Some(pane), .. } = &state else {
+        panic!("detail pane not open");
+    };
+    assert_eq!(pane.request_id, "r4");
+    let max_offset = pane.lines.len().saturating_sub(DETAIL_ROWS);
+
+    // Filters are ignored under the detail pane.
+    state = InspectorReducer::reduce(state, InspectorIntent::CycleStatusFilter);
+    for _ in 0..3 {
+        state = InspectorReducer::reduce(state, InspectorIntent::PageDown);
+    }
+    let InspectorDialogState::Visible {
+        detail: Some(pane),
+        filter,
+        ..
+    } = &state
+    else {
+        panic!("detail pane not open");
+    };
+    assert_eq!(pane.scroll_offset, max_offset);
+    assert_eq!(filter.status, StatusFilter::All);
+
+    state = InspectorReducer::reduce(state, InspectorIntent::CloseDetail);
+    assert!(matches!(state, InspectorDialogState::Visible { detail: None, .. }));
+    state = InspectorReducer::reduce(state, InspectorIntent::Close);
+    assert!(!state.is_visible());
+}
+
+#[test]
+fn test_detail_lines_show_routing_and_capture() {
+    let mut record = record("r1", "alpha", Some(200), AgentKind::Subagent);
+    record.agent_id = Some("agent-7".to_string());
+    record.routing_decision = Some(RoutingDecision {
+        backend: "alpha".to_string(),
+        reason: "ac marker session affinity".to_string(),
+        synthesized: false,
+    });
+    record.trace.model = Some("claude-sonnet-4-5".to_string());
+    record.trace.upstream_model = Some("provider-medium".to_string());
+    record.trace.thinking_blocks_filtered = 2;
+    record.request_meta = Some(RequestMeta {
+        method: "POST".to_string(),
+        path: "/v1/messages".to_string(),
+        query: None,
+        headers: Some(vec![("x-api-key".to_string(), "sk-****".to_string())]),
+        body_preview: Some(r#"{"model":"claude-sonnet-4-5"}"#.to_string()),
+        body_truncated: false,
+    });
+
+    let lines = detail_lines(&record);
+    let field = |label: &str| {
+        lines.iter().find_map(|line| match line {
+            DetailLine::Field(l, value) if l == label => Some(value.as_str()),
+            _ => None,
+        })
+    };
+    assert_eq!(field("Agent"), Some("subagent agent-7"));
+    assert_eq!(field("Reason"), Some("ac marker session affinity"));
+    assert_eq!(field("Model"), Some("claude-sonnet-4-5 → provider-medium"));
+    assert_eq!(field("Thinking"), Some("2 blocks filtered"));
+    assert_eq!(field("Method"), Some("POST /v1/messages"));
+
+    let texts: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| match line {
+            DetailLine::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(texts.contains(&"x-api-key: sk-****"));
+    assert!(texts.contains(&"  \"model\": \"claude-sonnet-4-5\""));
+    // Response headers and body were not captured.
+    assert_eq!(texts.iter().filter(|t| t.starts_with("Not captured")).count(), 2);
+}
+
+#[test]
+fn test_pretty_body() {
+    assert_eq!(pretty_body(r#"{"a":[1]}"#), vec!["{", "  \"a\": [", "    1", "  ]", "}"]);
+    // Truncated previews are shown as captured.
+    assert_eq!(pretty_body(r#"{"a":[1"#), vec![r#"{"a":[1"#]);
+}
+
+#[test]
+fn test_curl_command() {
+    let mut record = record("r1", "alpha", Some(200), AgentKind::Main);
+    record.request_meta = Some(RequestMeta {
+        method: "POST".to_string(),
+        path: "/v1/messages".to_string(),
+        query: Some("beta=true".to_string()),
+        headers: Some(vec![
+            ("host".to_string(), "127.0.0.1:8080".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+        ]),
+        body_preview: Some("{\n  \"text\": \"it's\"\n}".to_string()),
+        body_truncated: false,
+    });
+    record.response_meta = Some(ResponseMeta {
+        headers: None,
+        body_preview: None,
+        sse_text: None,
+    });
+
+    assert_eq!(
+        curl_command(&record, "http://127.0.0.1:8080/"),
+        "curl -X POST 'http://127.0.0.1:8080/v1/messages?beta=true' \\\n  \
+         -H 'content-type: application/json' \\\n  \
+         --data-raw '{\"text\":\"it'\\''s\"}'"
+    );
+
+    // A truncated body would not be the request that was sent.
+    let mut truncated = record.clone();
+    if let Some(meta) = truncated.request_meta.as_mut() {
+        meta.body_preview = Some(r#"{"text":"it'"#.to_string());
+        meta.body_truncated = true;
+    }
+    assert_eq!(
+        curl_command(&truncated, "http://127.0.0.1:8080"),
+        "# Request body omitted: its preview was truncated (set full_body = true)\n\
+         curl -X POST 'http://127.0.0.1:8080/v1/messages?beta=true' \\\n  \
+         -H 'content-type: application/json'"
+    );
+
+    // Without captured metadata only the request line is known.
+    let mut bare = record.clone();
+    bare.request_meta = None;
+    bare.trace.method = "GET".to_string();
+    bare.trace.path = "/v1/models".to_string();
+    assert_eq!(
+        curl_command(&bare, "http://127.0.0.1:8080"),
+        "curl -X GET 'http://127.0.0.1:8080/v1/models'"
+    );
+}
+
+#[test]
+fn test_notice_cleared_by_next_key() {
+    let mut state = InspectorReducer::reduce(
+        loaded(),
+        InspectorIntent::ShowNotice {
+            message: "Exported 4 requests".to_string(),
+        },
+    );
+    state = InspectorReducer::reduce(
+        state,
+        InspectorIntent::Refresh {
+            records: sample_records(),
+        },
+    );
+    assert!(matches!(
+        &state,
+        InspectorDialogState::Visible { notice: Some(message), .. } if message == "Exported 4 requests"
+    ));
+
+    state = InspectorReducer::reduce(state, InspectorIntent::MoveDown);
+    assert!(matches!(state, InspectorDialogState::Visible { notice: None, .. }));
+}
@@ -43,6 +43,7 @@ fn test_config() -> Config {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "beta".to_string(),
@@ -56,9 +57,11 @@ fn test_config() -> Config {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
         ],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -1,9 +1,164 @@
 mod common;
 
-use anyclaude::metrics::StreamError;
+use anyclaude::config::BackendPricing;
+use anyclaude::metrics::{ObservabilityHub, SseUsageParser, StreamError, TokenUsage};
+use axum::body::Body;
+use axum::http::Request;
 
 #[test]
 fn stream_error_display() {
     let err = StreamError::IdleTimeout { duration: 60 };
     assert_eq!(err.to_string(), "idle timeout after 60s of inactivity");
 }
+
+#[test]
+fn sse_usage_from_message_start_and_delta() {
+    let mut parser = SseUsageParser::default();
+    let stream = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,",
+        "\"cache_creation_input_tokens\":300,\"cache_read_input_tokens\":2000,\"output_tokens\":1}}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"message_delta\"}}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":57}}\n\n",
+    );
+    // Split mid-line so events span chunks.
+    for chunk in stream.as_bytes().chunks(17) {
+        parser.push(chunk);
+    }
+
+    assert_eq!(
+        parser.usage(),
+        Some(TokenUsage {
+            input_tokens: 12,
+            output_tokens: 57,
+            cache_creation_input_tokens: 300,
+            cache_read_input_tokens: 2000,
+            cost_usd: None,
+        })
+    );
+}
+
+#[test]
+fn sse_usage_delta_overrides_input_counts() {
+    let mut parser = SseUsageParser::default();
+    parser.push(b"data:{\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":0,\"output_tokens\":0}}}\n");
+    // Translated streams report usage only at the end; no trailing newline.
+    parser.push(b"data: {\"type\":\"message_delta\",\"usage\":{\"input_tokens\":40,\"output_tokens\":9}}");
+
+    let usage = parser.usage().unwrap();
+    assert_eq!(usage.input_tokens, 40);
+    assert_eq!(usage.output_tokens, 9);
+}
+
+#[test]
+fn sse_usage_absent() {
+    let mut parser = SseUsageParser::default();
+    parser.push(b"data: {\"type\":\"content_block_delta\"}\n\ndata: [DONE]\n");
+
+    assert_eq!(parser.usage(), None);
+}
+
+#[test]
+fn usage_from_response_body() {
+    let body = br#"{"type":"message","usage":{"input_tokens":5,"output_tokens":7,"cache_read_input_tokens":11}}"#;
+    let usage = TokenUsage::from_response(body).unwrap();
+
+    assert_eq!(usage.input_tokens, 5);
+    assert_eq!(usage.output_tokens, 7);
+    assert_eq!(usage.cache_read_input_tokens, 11);
+    assert_eq!(usage.total_input_tokens(), 16);
+    assert!(TokenUsage::from_response(br#"{"type":"message"}"#).is_none());
+    assert!(TokenUsage::from_response(b"not json").is_none());
+}
+
+#[test]
+fn usage_pricing_with_cache_rates() {
+    let mut usage = TokenUsage {
+        input_tokens: 1_000_000,
+        output_tokens: 1_000_000,
+        cache_creation_input_tokens: 1_000_000,
+        cache_read_input_tokens: 1_000_000,
+        cost_usd: None,
+    };
+    let mut pricing = BackendPricing {
+        input_per_million: 3.0,
+        output_per_million: 15.0,
+        cache_read_per_million: None,
+        cache_write_per_million: None,
+    };
+
+    // Default cache rates: reads 10%, writes 125% of input.
+    usage.apply_pricing(Some(&pricing));
+    assert!((usage.cost_usd.unwrap() - (3.0 + 15.0 + 0.3 + 3.75)).abs() < 1e-9);
+
+    pricing.cache_read_per_million = Some(0.5);
+    pricing.cache_write_per_million = Some(6.0);
+    usage.apply_pricing(Some(&pricing));
+    assert!((usage.cost_usd.unwrap() - (3.0 + 15.0 + 0.5 + 6.0)).abs() < 1e-9);
+
+    usage.apply_pricing(None);
+    assert_eq!(usage.cost_usd, None);
+}
+
+#[test]
+fn pricing_cache_rates_from_toml() {
+    let pricing: BackendPricing = toml::from_str(
+        "input_per_million = 3.0\noutput_per_million = 15.0\ncache_read_per_million = 0.3",
+    )
+    .unwrap();
+
+    assert_eq!(pricing.cache_read_per_million, Some(0.3));
+    assert_eq!(pricing.cache_write_per_million, None);
+}
+
+#[test]
+fn sse_output_tokens_estimated_until_reported() {
+    let mut parser = SseUsageParser::default();
+    parser.push(b"data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":3,\"output_tokens\":1}}}\n");
+    parser.push(b"data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"abcdefgh\"}}\n");
+    parser.push(b"data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"abcde\"}}\n");
+    assert_eq!(parser.output_tokens_so_far(), 4);
+
+    parser.push(b"data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":9}}\n");
+    assert_eq!(parser.output_tokens_so_far(), 9);
+}
+
+#[test]
+fn hub_tracks_requests_in_flight() {
+    let hub = ObservabilityHub::new(10);
+    let request = Request::builder().uri("/v1/messages").body(Body::empty()).unwrap();
+    let first = hub.start_request("req-1".to_string(), &request, "alpha").span;
+    let mut second = hub.start_request("req-2".to_string(), &request, "alpha").span;
+
+    second.set_backend("beta".to_string());
+    hub.stream_progress(second.record_mut(), 42);
+    let in_flight = hub.snapshot().in_flight;
+    assert_eq!(in_flight.len(), 2);
+    assert_eq!(in_flight[0].id, "req-1");
+    assert!(!in_flight[0].streaming);
+    assert_eq!(in_flight[1].backend, "beta");
+    assert!(in_flight[1].streaming);
+    assert_eq!(in_flight[1].output_tokens, 42);
+
+    hub.finish_request(first);
+    let in_flight = hub.snapshot().in_flight;
+    assert_eq!(in_flight.len(), 1);
+    assert_eq!(in_flight[0].id, "req-2");
+
+    hub.finish_request(second);
+    assert!(hub.snapshot().in_flight.is_empty());
+}
+
+#[test]
+fn hub_ignores_progress_of_finished_request() {
+    let hub = ObservabilityHub::new(10);
+    let request = Request::builder().uri("/v1/messages").body(Body::empty()).unwrap();
+    let mut span = hub.start_request("req-1".to_string(), &request, "alpha").span;
+    let record = span.record_mut().clone();
+
+    hub.finish_request(span);
+    hub.stream_progress(&record, 7);
+    assert!(hub.snapshot().in_flight.is_empty());
+}
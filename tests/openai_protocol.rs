@@ -0,0 +1,570 @@
+//! Tests for `protocol = "openai"` backends.
+//!
+//! Unit tests for request, response and SSE stream translation, plus
+//! integration tests through the full proxy pipeline.
+
+mod common;
+
+use anyclaude::proxy::openai::{make_stream_translator, to_chat_completions, to_message};
+use anyclaude::proxy::protocol::to_error;
+use axum::body::Bytes;
+use serde_json::json;
+
+// ---------------------------------------------------------------------------
+// Unit tests: request translation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn request_translates_system_and_scalars() {
+    let body = json!({
+        "model": "gpt-4o",
+        "system": [{"type": "text", "text": "Be brief."}, {"type": "text", "text": "Be kind."}],
+        "max_tokens": 512,
+        "temperature": 0.2,
+        "top_k": 5,
+        "stop_sequences": ["END"],
+        "thinking": {"type": "enabled", "budget_tokens": 100},
+        "messages": [{"role": "user", "content": "hi"}],
+    });
+
+    let out = to_chat_completions(&body);
+
+    assert_eq!(out["model"], "gpt-4o");
+    assert_eq!(out["max_tokens"], 512);
+    assert_eq!(out["temperature"], 0.2);
+    assert_eq!(out["stop"], json!(["END"]));
+    assert_eq!(out["messages"][0], json!({"role": "system", "content": "Be brief.\nBe kind."}));
+    assert_eq!(out["messages"][1], json!({"role": "user", "content": "hi"}));
+    assert!(out.get("top_k").is_none());
+    assert!(out.get("thinking").is_none());
+    assert!(out.get("stream").is_none());
+}
+
+#[test]
+fn request_streaming_asks_for_usage() {
+    let out = to_chat_completions(&json!({"model": "m", "stream": true, "messages": []}));
+    assert_eq!(out["stream"], true);
+    assert_eq!(out["stream_options"]["include_usage"], true);
+}
+
+#[test]
+fn request_translates_tool_use_and_tool_result() {
+    let body = json!({
+        "model": "m",
+        "messages": [
+            {"role": "user", "content": "weather?"},
+            {"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "hmm", "signature": "sig"},
+                {"type": "text", "text": "Checking."},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}},
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "rain"}]},
+                {"type": "text", "text": "thanks"},
+            ]},
+        ],
+    });
+
+    let out = to_chat_completions(&body);
+    let messages = out["messages"].as_array().unwrap();
+
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[1]["role"], "assistant");
+    assert_eq!(messages[1]["content"], "Checking.");
+    let call = &messages[1]["tool_calls"][0];
+    assert_eq!(call["id"], "toolu_1");
+    assert_eq!(call["type"], "function");
+    assert_eq!(call["function"]["name"], "get_weather");
+    let args: serde_json::Value =
+        serde_json::from_str(call["function"]["arguments"].as_str().unwrap()).unwrap();
+    assert_eq!(args, json!({"city": "Oslo"}));
+
+    // Tool results come first so they directly follow the tool call.
+    assert_eq!(messages[2], json!({"role": "tool", "tool_call_id": "toolu_1", "content": "rain"}));
+    assert_eq!(messages[3]["role"], "user");
+    assert_eq!(messages[3]["content"][0], json!({"type": "text", "text": "thanks"}));
+}
+
+#[test]
+fn request_translates_error_and_image_tool_results() {
+    let body = json!({
+        "model": "m",
+        "messages": [{"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "toolu_1", "is_error": true, "content": "not found"},
+            {"type": "tool_result", "tool_use_id": "toolu_2", "content": [
+                {"type": "text", "text": "screenshot"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+            ]},
+        ]}],
+    });
+
+    let out = to_chat_completions(&body);
+    let messages = out["messages"].as_array().unwrap();
+
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["content"], "Error: not found");
+    assert_eq!(messages[1]["content"], "screenshot");
+    // Tool messages carry only text; the image follows in the user turn.
+    assert_eq!(messages[2]["role"], "user");
+    assert_eq!(messages[2]["content"][0]["image_url"]["url"], "data:image/png;base64,AAAA");
+}
+
+#[test]
+fn request_translates_images() {
+    let body = json!({
+        "model": "m",
+        "messages": [{"role": "user", "content": [
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+            {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}},
+        ]}],
+    });
+
+    let out = to_chat_completions(&body);
+    let parts = &out["messages"][0]["content"];
+
+    assert_eq!(parts[0]["type"], "image_url");
+    assert_eq!(parts[0]["image_url"]["url"], "data:image/png;base64,AAAA");
+    assert_eq!(parts[1]["image_url"]["url"], "https://example.com/a.png");
+}
+
+#[test]
+fn request_translates_tools_and_tool_choice() {
+    let body = json!({
+        "model": "m",
+        "messages": [],
+        "tools": [
+            {"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}},
+            {"type": "web_search_20250305", "name": "web_search"},
+        ],
+        "tool_choice": {"type": "any", "disable_parallel_tool_use": true},
+    });
+
+    let out = to_chat_completions(&body);
+
+    assert_eq!(out["tools"].as_array().unwrap().len(), 1, "server tools are dropped");
+    assert_eq!(out["tools"][0], json!({
+        "type": "function",
+        "function": {"name": "get_weather", "description": "Weather", "parameters": {"type": "object"}},
+    }));
+    assert_eq!(out["tool_choice"], "required");
+    assert_eq!(out["parallel_tool_calls"], false);
+
+    let named = to_chat_completions(&json!({
+        "model": "m",
+        "messages": [],
+        "tools": [{"name": "t", "input_schema": {}}],
+        "tool_choice": {"type": "tool", "name": "t"},
+    }));
+    assert_eq!(named["tool_choice"], json!({"type": "function", "function": {"name": "t"}}));
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: response translation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn response_translates_text_tool_calls_and_usage() {
+    let body = json!({
+        "id": "chatcmpl-1",
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": "Let me check.",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Oslo\"}"},
+                }],
+            },
+            "finish_reason": "tool_calls",
+        }],
+        "usage": {"prompt_tokens": 100, "completion_tokens": 20, "prompt_tokens_details": {"cached_tokens": 40}},
+    });
+
+    let out = to_message(&body);
+
+    assert_eq!(out["type"], "message");
+    assert_eq!(out["role"], "assistant");
+    assert_eq!(out["id"], "chatcmpl-1");
+    assert_eq!(out["model"], "gpt-4o");
+    assert_eq!(out["content"][0], json!({"type": "text", "text": "Let me check."}));
+    assert_eq!(out["content"][1], json!({
+        "type": "tool_use", "id": "call_1", "name": "get_weather", "input": {"city": "Oslo"},
+    }));
+    assert_eq!(out["stop_reason"], "tool_use");
+    assert_eq!(out["usage"]["input_tokens"], 60);
+    assert_eq!(out["usage"]["cache_read_input_tokens"], 40);
+    assert_eq!(out["usage"]["output_tokens"], 20);
+}
+
+#[test]
+fn response_maps_finish_reasons() {
+    let finish = |reason: &str| {
+        to_message(&json!({"choices": [{"message": {"content": "x"}, "finish_reason": reason}]}))
+            ["stop_reason"]
+            .clone()
+    };
+    assert_eq!(finish("stop"), "end_turn");
+    assert_eq!(finish("length"), "max_tokens");
+    assert_eq!(finish("tool_calls"), "tool_use");
+}
+
+#[test]
+fn error_translates_to_anthropic_envelope() {
+    let out = to_error(429, br#"{"error":{"message":"slow down","type":"rate_limit"}}"#);
+    assert_eq!(out, json!({"type": "error", "error": {"type": "rate_limit_error", "message": "slow down"}}));
+
+    let out = to_error(500, b"boom");
+    assert_eq!(out["error"]["type"], "api_error");
+    assert_eq!(out["error"]["message"], "boom");
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests: SSE stream translation
+// ---------------------------------------------------------------------------
+
+fn events(bytes: &[u8]) -> Vec<serde_json::Value> {
+    anyclaude::sse::parse_sse_events(bytes)
+        .into_iter()
+        .map(|e| e.data)
+        .collect()
+}
+
+#[test]
+fn stream_translates_text_deltas() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(concat!(
+        "data: {\"id\":\"c1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2}}\n\n",
+        "data: [DONE]\n\n",
+    )));
+    let text = String::from_utf8(out.to_vec()).unwrap();
+    assert!(text.starts_with("event: message_start\n"));
+
+    let events = events(&out);
+    let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+    assert_eq!(types, [
+        "message_start",
+        "content_block_start",
+        "content_block_delta",
+        "content_block_delta",
+        "content_block_stop",
+        "message_delta",
+        "message_stop",
+    ]);
+    assert_eq!(events[0]["message"]["model"], "gpt-4o");
+    assert_eq!(events[1]["content_block"]["type"], "text");
+    assert_eq!(events[2]["delta"], json!({"type": "text_delta", "text": "Hel"}));
+    assert_eq!(events[5]["delta"]["stop_reason"], "end_turn");
+    assert_eq!(events[5]["usage"]["input_tokens"], 9);
+    assert_eq!(events[5]["usage"]["output_tokens"], 2);
+}
+
+#[test]
+fn stream_translates_tool_calls() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(concat!(
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Sure.\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"a\",\"arguments\":\"\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"x\\\":\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"1}\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":1,\"id\":\"call_2\",\"type\":\"function\",\"function\":{\"name\":\"b\",\"arguments\":\"{}\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        "data: [DONE]\n\n",
+    )));
+
+    let events = events(&out);
+    let starts: Vec<&serde_json::Value> = events
+        .iter()
+        .filter(|e| e["type"] == "content_block_start")
+        .collect();
+    assert_eq!(starts.len(), 3);
+    assert_eq!(starts[0]["index"], 0);
+    assert_eq!(starts[1]["index"], 1);
+    assert_eq!(starts[1]["content_block"], json!({"type": "tool_use", "id": "call_1", "name": "a", "input": {}}));
+    assert_eq!(starts[2]["index"], 2);
+    assert_eq!(starts[2]["content_block"]["id"], "call_2");
+
+    let partial: String = events
+        .iter()
+        .filter(|e| e["type"] == "content_block_delta" && e["index"] == 1)
+        .map(|e| e["delta"]["partial_json"].as_str().unwrap())
+        .collect();
+    assert_eq!(partial, "{\"x\":1}");
+
+    let stops = events.iter().filter(|e| e["type"] == "content_block_stop").count();
+    assert_eq!(stops, 3);
+    let delta = events.iter().find(|e| e["type"] == "message_delta").unwrap();
+    assert_eq!(delta["delta"]["stop_reason"], "tool_use");
+}
+
+#[test]
+fn stream_interleaved_parallel_tool_calls_keep_their_blocks() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(concat!(
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"a\",\"arguments\":\"{\\\"x\\\":\"}},{\"index\":1,\"id\":\"call_2\",\"function\":{\"name\":\"b\",\"arguments\":\"{\\\"y\\\":\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"1}\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":1,\"function\":{\"arguments\":\"2}\"}}]}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        "data: [DONE]\n\n",
+    )));
+
+    let events = events(&out);
+    let starts = events.iter().filter(|e| e["type"] == "content_block_start").count();
+    assert_eq!(starts, 2);
+    let partial = |index: u64| -> String {
+        events
+            .iter()
+            .filter(|e| e["type"] == "content_block_delta" && e["index"] == index)
+            .map(|e| e["delta"]["partial_json"].as_str().unwrap())
+            .collect()
+    };
+    assert_eq!(partial(0), "{\"x\":1}");
+    assert_eq!(partial(1), "{\"y\":2}");
+    // No block is stopped before its last delta.
+    let last_delta = events.iter().rposition(|e| e["type"] == "content_block_delta").unwrap();
+    let first_stop = events.iter().position(|e| e["type"] == "content_block_stop").unwrap();
+    assert!(first_stop > last_delta);
+}
+
+#[test]
+fn stream_ends_without_done() {
+    let finish = concat!(
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"hi\"}}]}\n\n",
+        "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"length\"}]}\n\n",
+    );
+    let types = |out: &[u8]| -> Vec<String> {
+        events(out).iter().map(|e| e["type"].as_str().unwrap().to_string()).collect()
+    };
+
+    // The usage chunk after finish_reason ends the message.
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(format!(
+        "{}data: {{\"id\":\"c1\",\"model\":\"m\",\"choices\":[],\"usage\":{{\"prompt_tokens\":4,\"completion_tokens\":1}}}}\n\n",
+        finish
+    )));
+    assert_eq!(types(&out).last().unwrap(), "message_stop");
+    let delta = events(&out).into_iter().find(|e| e["type"] == "message_delta").unwrap();
+    assert_eq!(delta["delta"]["stop_reason"], "max_tokens");
+    assert_eq!(delta["usage"]["output_tokens"], 1);
+    assert!(translator(Bytes::from("data: [DONE]\n\n")).is_empty());
+
+    // Without one, the end of the stream does.
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(finish));
+    assert!(!types(&out).contains(&"message_stop".to_string()));
+    assert_eq!(types(&translator(Bytes::new())), ["message_delta", "message_stop"]);
+}
+
+#[test]
+fn stream_done_without_message_emits_no_message_events() {
+    let mut translator = make_stream_translator();
+    assert!(translator(Bytes::from("data: [DONE]\n\n")).is_empty());
+    assert!(translator(Bytes::new()).is_empty());
+
+    // An error chunk is passed on, but no message is closed after it.
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from(
+        "data: {\"error\":{\"message\":\"overloaded\"}}\n\ndata: [DONE]\n\n",
+    ));
+    let events = events(&out);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["type"], "error");
+    assert_eq!(events[0]["error"]["message"], "overloaded");
+}
+
+#[test]
+fn stream_buffers_lines_split_across_chunks() {
+    let mut translator = make_stream_translator();
+    let line = "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"hi\"}}]}\n\n";
+    let (head, tail) = line.split_at(30);
+
+    let first = translator(Bytes::from(head.to_string()));
+    assert!(first.is_empty(), "partial line must not be emitted");
+
+    let second = translator(Bytes::from(tail.to_string()));
+    let events = events(&second);
+    assert_eq!(events[0]["type"], "message_start");
+    assert_eq!(events[2]["delta"]["text"], "hi");
+}
+
+#[test]
+fn stream_translates_error_event() {
+    let mut translator = make_stream_translator();
+    let out = translator(Bytes::from("data: {\"error\":{\"message\":\"overloaded\"}}\n\n"));
+    let text = String::from_utf8(out.to_vec()).unwrap();
+    assert!(text.starts_with("event: error\n"));
+    assert_eq!(events(&out)[0]["error"]["message"], "overloaded");
+}
+
+// ---------------------------------------------------------------------------
+// Integration tests: full proxy pipeline
+// ---------------------------------------------------------------------------
+
+use anyclaude::config::{Backend, BackendProtocol};
+use common::mock_backend::{MockBackend, MockResponse};
+use reqwest::Client;
+
+fn openai_backend(base_url: &str) -> Backend {
+    Backend {
+        name: "local".to_string(),
+        display_name: "Local".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "bearer".to_string(),
+        api_key: Some("sk-local".to_string()),
+        model_opus: Some("qwen3-coder".to_string()),
+        protocol: BackendProtocol::OpenAi,
+        ..Default::default()
+    }
+}
+
+/// Start a proxy for `backend` without retries.
+async fn start_proxy(backend: Backend) -> String {
+    let mut config = common::proxy_config(vec![backend]);
+    config.defaults.max_retries = 0;
+    common::start_proxy(config).await.url
+}
+
+#[tokio::test]
+async fn integration_json_request_is_translated_both_ways() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(
+        r#"{"id":"chatcmpl-1","object":"chat.completion","model":"qwen3-coder","choices":[{"index":0,"message":{"role":"assistant","content":"Hello"},"finish_reason":"stop"}],"usage":{"prompt_tokens":10,"completion_tokens":5}}"#,
+    )).await;
+    let proxy_url = start_proxy(openai_backend(&mock.base_url())).await;
+
+    let resp = common::post_json(
+        &proxy_url,
+        "/v1/messages?beta=true",
+        r#"{"model":"claude-opus-4-6","max_tokens":1024,"system":"Be brief.","messages":[{"role":"user","content":"hi"}]}"#,
+        &[("x-api-key", "client-key")],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["type"], "message");
+    assert_eq!(body["model"], "claude-opus-4-6", "reverse model mapping still applies");
+    assert_eq!(body["content"][0]["text"], "Hello");
+    assert_eq!(body["stop_reason"], "end_turn");
+    assert_eq!(body["usage"]["input_tokens"], 10);
+
+    let requests = mock.captured_requests().await;
+    assert_eq!(requests[0].path, "/v1/chat/completions");
+    assert!(requests[0]
+        .headers
+        .iter()
+        .any(|(k, v)| k == "authorization" && v == "Bearer sk-local"));
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["model"], "qwen3-coder");
+    assert_eq!(sent["messages"][0], json!({"role": "system", "content": "Be brief."}));
+    assert_eq!(sent["messages"][1], json!({"role": "user", "content": "hi"}));
+}
+
+#[tokio::test]
+async fn integration_sse_stream_is_translated() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[
+        r#"{"id":"c1","model":"qwen3-coder","choices":[{"index":0,"delta":{"role":"assistant","content":"Hel"}}]}"#,
+        r#"{"id":"c1","model":"qwen3-coder","choices":[{"index":0,"delta":{"content":"lo"},"finish_reason":"stop"}]}"#,
+        r#"{"id":"c1","model":"qwen3-coder","choices":[],"usage":{"prompt_tokens":7,"completion_tokens":2}}"#,
+        "[DONE]",
+    ])).await;
+    let proxy_url = start_proxy(openai_backend(&mock.base_url())).await;
+
+    let resp = common::post_messages(
+        &proxy_url,
+        r#"{"model":"claude-opus-4-6","stream":true,"max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-length").is_none());
+    let body = resp.bytes().await.unwrap();
+    let events = events(&body);
+
+    assert_eq!(events[0]["type"], "message_start");
+    assert_eq!(events[0]["message"]["model"], "claude-opus-4-6");
+    let text: String = events
+        .iter()
+        .filter(|e| e["type"] == "content_block_delta")
+        .map(|e| e["delta"]["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(text, "Hello");
+    let delta = events.iter().find(|e| e["type"] == "message_delta").unwrap();
+    assert_eq!(delta["usage"]["output_tokens"], 2);
+    assert_eq!(events.last().unwrap()["type"], "message_stop");
+
+    let sent: serde_json::Value =
+        serde_json::from_slice(&mock.captured_requests().await[0].body).unwrap();
+    assert_eq!(sent["stream"], true);
+    assert_eq!(sent["stream_options"]["include_usage"], true);
+}
+
+#[tokio::test]
+async fn integration_sse_stream_without_done_is_finished() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[
+        r#"{"id":"c1","model":"qwen3-coder","choices":[{"index":0,"delta":{"content":"Hi"},"finish_reason":"stop"}]}"#,
+    ])).await;
+    let proxy_url = start_proxy(openai_backend(&mock.base_url())).await;
+
+    let resp = common::post_messages(
+        &proxy_url,
+        r#"{"model":"claude-opus-4-6","stream":true,"max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[],
+    )
+    .await;
+
+    let events = events(&resp.bytes().await.unwrap());
+    let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+    assert_eq!(&types[types.len() - 3..], ["content_block_stop", "message_delta", "message_stop"]);
+}
+
+#[tokio::test]
+async fn integration_error_is_translated() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse {
+        status: 401,
+        body: br#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error"}}"#.to_vec(),
+        ..Default::default()
+    }).await;
+    let proxy_url = start_proxy(openai_backend(&mock.base_url())).await;
+
+    let resp = common::post_messages(
+        &proxy_url,
+        r#"{"model":"claude-opus-4-6","max_tokens":16,"messages":[{"role":"user","content":"hi"}]}"#,
+        &[],
+    )
+    .await;
+
+    assert_eq!(resp.status(), 401);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["type"], "error");
+    assert_eq!(body["error"]["type"], "authentication_error");
+    assert_eq!(body["error"]["message"], "Incorrect API key provided");
+}
+
+#[tokio::test]
+async fn integration_other_paths_pass_through() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(r#"{"object":"list","data":[]}"#)).await;
+    let proxy_url = start_proxy(openai_backend(&mock.base_url())).await;
+
+    let resp = Client::new()
+        .get(format!("{}/v1/models", proxy_url))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["object"], "list");
+    assert_eq!(mock.captured_requests().await[0].path, "/v1/models");
+}
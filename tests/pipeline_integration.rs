@@ -52,6 +52,7 @@ fn create_integration_config(mock_base_url: &str) -> Config {
                 model_opus: None,
                 model_sonnet: Some("mock-sonnet".to_string()),
                 model_haiku: Some("mock-haiku".to_string()),
+                ..Default::default()
             },
         ],
         ..Default::default()
@@ -67,15 +68,20 @@ fn create_pipeline_context() -> PipelineContext {
         latency_ms: None,
         ttfb_ms: None,
         backend: String::new(),
+        session_id: None,
         status: None,
         timed_out: false,
         request_bytes: 0,
         response_bytes: 0,
         request_analysis: None,
         response_analysis: None,
+        usage: None,
         routing_decision: None,
+        agent: Default::default(),
+        agent_id: None,
         request_meta: None,
         response_meta: None,
+        trace: Default::default(),
     };
     let span = RequestSpan::new(record);
     let debug_config = DebugLoggingConfig {
@@ -198,7 +204,10 @@ async fn test_main_pipeline_streaming_request() {
 #[tokio::test]
 async fn test_main_pipeline_error_response() {
     let mock = MockBackend::start().await;
-    mock.enqueue_response(MockResponse::error(429, "Rate limit exceeded")).await;
+    // Initial attempt plus the default two status retries
+    for _ in 0..3 {
+        mock.enqueue_response(MockResponse::error(429, "Rate limit exceeded")).await;
+    }
 
     let config = create_integration_config(&mock.base_url());
     let backend_state = BackendState::from_config(config).unwrap();
@@ -247,6 +256,7 @@ async fn test_teammate_pipeline_no_thinking_session() {
         query: None,
         headers: None,
         body_preview: None,
+        body_truncated: false,
     });
 
     let req = Request::builder()
@@ -292,6 +302,7 @@ async fn test_pipeline_with_backend_override() {
         model_opus: None,
         model_sonnet: Some("override-model".to_string()),
         model_haiku: None,
+        ..Default::default()
     });
 
     let backend_state = BackendState::from_config(config).unwrap();
@@ -32,6 +32,7 @@ fn create_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -60,6 +61,7 @@ fn config_with_teams(
         claude_settings: HashMap::new(),
         backends,
         agents,
+        ..Default::default()
     }
 }
 
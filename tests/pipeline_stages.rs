@@ -12,7 +12,7 @@ use axum::http::{header::{AUTHORIZATION, CONTENT_TYPE}, HeaderMap, Method, Reque
 use serde_json::json;
 
 use anyclaude::backend::{BackendState, AgentRegistry};
-use anyclaude::config::{Backend, Config, DebugLogDestination, DebugLogFormat, DebugLogLevel, DebugLoggingConfig, Defaults};
+use anyclaude::config::{Backend, BodyRules, Config, DebugLogDestination, DebugLogFormat, DebugLogLevel, DebugLoggingConfig, Defaults, RoutingConfig, RoutingRule};
 use anyclaude::metrics::{BackendOverride, DebugLogger, ObservabilityHub, RequestRecord, RequestSpan};
 use anyclaude::proxy::pipeline::{self, PipelineContext, PipelineConfig};
 use anyclaude::proxy::pool::PoolConfig;
@@ -48,6 +48,7 @@ fn create_test_config() -> Config {
                 model_opus: None,
                 model_sonnet: Some("test-sonnet".to_string()),
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "anthropic".to_string(),
@@ -61,6 +62,7 @@ fn create_test_config() -> Config {
                 model_opus: None,
                 model_sonnet: None,
                 model_haiku: None,
+                ..Default::default()
             },
             Backend {
                 name: "openrouter".to_string(),
@@ -74,6 +76,7 @@ fn create_test_config() -> Config {
                 model_opus: Some("openrouter-opus".to_string()),
                 model_sonnet: Some("openrouter-sonnet".to_string()),
                 model_haiku: Some("openrouter-haiku".to_string()),
+                ..Default::default()
             },
         ],
         ..Default::default()
@@ -89,15 +92,20 @@ fn create_test_context() -> PipelineContext {
         latency_ms: None,
         ttfb_ms: None,
         backend: String::new(),
+        session_id: None,
         status: None,
         timed_out: false,
         request_bytes: 0,
         response_bytes: 0,
         request_analysis: None,
         response_analysis: None,
+        usage: None,
         routing_decision: None,
+        agent: Default::default(),
+        agent_id: None,
         request_meta: None,
         response_meta: None,
+        trace: Default::default(),
     };
     let span = RequestSpan::new(record);
     let debug_config = DebugLoggingConfig {
@@ -161,6 +169,30 @@ async fn test_extract_request_json_body() {
     assert_eq!(parsed["model"], "claude-3-sonnet");
 }
 
+#[tokio::test]
+async fn test_extract_request_session_id() {
+    let from_header = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/messages")
+        .header(CONTENT_TYPE, "application/json")
+        .header("x-claude-code-session-id", "sess-header")
+        .body(Body::from(r#"{"metadata": {"user_id": "user_abc_account__session_sess-body"}}"#))
+        .unwrap();
+    let mut ctx = create_test_context();
+    pipeline::extract_request(from_header, &mut ctx).await.unwrap();
+    assert_eq!(ctx.session_id.as_deref(), Some("sess-header"));
+
+    let from_body = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/messages")
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"metadata": {"user_id": "user_abc_account__session_sess-body"}}"#))
+        .unwrap();
+    let mut ctx = create_test_context();
+    pipeline::extract_request(from_body, &mut ctx).await.unwrap();
+    assert_eq!(ctx.session_id.as_deref(), Some("sess-body"));
+}
+
 #[tokio::test]
 async fn test_extract_request_non_json_body() {
     let req = Request::builder()
@@ -245,6 +277,7 @@ fn test_resolve_backend_active_backend() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -266,6 +299,7 @@ fn test_resolve_backend_override() {
         Some("anthropic".to_string()),
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -292,6 +326,7 @@ fn test_resolve_backend_plugin_override() {
         None,
         Some(plugin_override),
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -315,6 +350,7 @@ fn test_resolve_backend_marker_model() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -338,6 +374,7 @@ fn test_resolve_backend_anyclaude_prefix() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -361,6 +398,7 @@ fn test_resolve_backend_direct_backend_name() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -382,6 +420,7 @@ fn test_resolve_backend_missing_backend() {
         Some("nonexistent".to_string()),
         None,
         None,
+        "/v1/messages",
         &registry,
         &mut ctx,
     );
@@ -408,6 +447,7 @@ fn test_resolve_backend_priority_plugin_over_teammate() {
         None,
         Some(plugin_override),
         None,
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -431,6 +471,7 @@ fn test_resolve_backend_priority_teammate_over_marker() {
         Some("anthropic".to_string()), // teammate route
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -463,6 +504,7 @@ fn test_ac_marker_routes_to_backend() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -489,6 +531,7 @@ fn test_no_ac_marker_uses_active_backend() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -517,6 +560,7 @@ fn test_ac_marker_wins_over_marker_model() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -543,6 +587,7 @@ fn test_ac_marker_skipped_when_registry_empty() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     );
@@ -552,6 +597,141 @@ fn test_ac_marker_skipped_when_registry_empty() {
     assert_eq!(backend.name, backend_state.get_active_backend());
 }
 
+fn create_routing_state(rules: Vec<RoutingRule>) -> BackendState {
+    let mut config = create_test_config();
+    config.routing = RoutingConfig { rules };
+    BackendState::from_config(config).unwrap()
+}
+
+/// Resolve `body` at `path` and return the backend name and routing reason.
+fn route(backend_state: &BackendState, body: serde_json::Value, path: &str) -> (String, String) {
+    let mut ctx = create_test_context();
+    let backend = pipeline::resolve_backend(
+        backend_state,
+        None,
+        None,
+        Some(&body),
+        path,
+        &AgentRegistry::new(),
+        &mut ctx,
+    ).unwrap();
+    let reason = ctx.span.record_mut().routing_decision.take().unwrap().reason;
+    (backend.name, reason)
+}
+
+#[test]
+fn test_routing_rule_by_model_family() {
+    let backend_state = create_routing_state(vec![RoutingRule {
+        name: "haiku-to-cheap".to_string(),
+        backend: "openrouter".to_string(),
+        model: Some("haiku".to_string()),
+        ..Default::default()
+    }]);
+
+    assert_eq!(
+        route(&backend_state, json!({"model": "claude-haiku-4-5-20251001"}), "/v1/messages"),
+        ("openrouter".to_string(), "haiku-to-cheap".to_string())
+    );
+    assert_eq!(
+        route(&backend_state, json!({"model": "claude-opus-4-6"}), "/v1/messages"),
+        ("test".to_string(), "active backend".to_string())
+    );
+}
+
+#[test]
+fn test_routing_rules_first_match_wins() {
+    let backend_state = create_routing_state(vec![
+        RoutingRule {
+            name: "count-tokens".to_string(),
+            backend: "anthropic".to_string(),
+            path: Some("/v1/messages/count_tokens".to_string()),
+            ..Default::default()
+        },
+        RoutingRule {
+            name: "web-search".to_string(),
+            backend: "anthropic".to_string(),
+            tools: vec!["web_search".to_string()],
+            ..Default::default()
+        },
+        RoutingRule {
+            name: "thinking".to_string(),
+            backend: "openrouter".to_string(),
+            thinking: Some(true),
+            ..Default::default()
+        },
+        RoutingRule {
+            name: "large".to_string(),
+            backend: "openrouter".to_string(),
+            max_tokens_min: Some(32_000),
+            ..Default::default()
+        },
+        RoutingRule {
+            name: "small-input".to_string(),
+            backend: "anthropic".to_string(),
+            input_tokens_max: Some(10),
+            ..Default::default()
+        },
+    ]);
+    let model = "claude-sonnet-4-5";
+
+    let (_, reason) = route(&backend_state, json!({"model": model}), "/v1/messages/count_tokens");
+    assert_eq!(reason, "count-tokens");
+
+    let body = json!({"model": model, "tools": [{"name": "Read"}, {"type": "web_search_20250305", "name": "web_search"}]});
+    assert_eq!(route(&backend_state, body, "/v1/messages").1, "web-search");
+
+    let body = json!({"model": model, "thinking": {"type": "adaptive"}});
+    assert_eq!(route(&backend_state, body, "/v1/messages").1, "thinking");
+
+    let body = json!({"model": model, "max_tokens": 64_000, "thinking": {"type": "disabled"}});
+    assert_eq!(route(&backend_state, body, "/v1/messages").1, "large");
+
+    let body = json!({"model": model, "max_tokens": 1024, "messages": [{"role": "user", "content": "hi"}]});
+    assert_eq!(route(&backend_state, body, "/v1/messages").1, "small-input");
+
+    let long = "word ".repeat(100);
+    let body = json!({"model": model, "max_tokens": 1024, "messages": [{"role": "user", "content": long}]});
+    assert_eq!(route(&backend_state, body, "/v1/messages").1, "active backend");
+}
+
+#[test]
+fn test_routing_rule_skips_unconfigured_backend() {
+    let mut config = create_test_config();
+    config.backends[1].api_key = None; // "anthropic" uses api_key auth
+    config.routing = RoutingConfig {
+        rules: vec![
+            RoutingRule {
+                name: "unconfigured".to_string(),
+                backend: "anthropic".to_string(),
+                ..Default::default()
+            },
+            RoutingRule {
+                name: "fallback".to_string(),
+                backend: "openrouter".to_string(),
+                ..Default::default()
+            },
+        ],
+    };
+    let backend_state = BackendState::from_config(config).unwrap();
+
+    assert_eq!(
+        route(&backend_state, json!({"model": "claude-opus-4-6"}), "/v1/messages"),
+        ("openrouter".to_string(), "fallback".to_string())
+    );
+}
+
+#[test]
+fn test_marker_model_takes_priority_over_routing_rules() {
+    let backend_state = create_routing_state(vec![RoutingRule {
+        name: "everything".to_string(),
+        backend: "openrouter".to_string(),
+        ..Default::default()
+    }]);
+
+    let (backend, _) = route(&backend_state, json!({"model": "anyclaude-anthropic"}), "/v1/messages");
+    assert_eq!(backend, "anthropic");
+}
+
 // =============================================================================
 // Stage 3: create_thinking tests
 // =============================================================================
@@ -592,6 +772,7 @@ fn test_transform_body_no_json() {
         model_opus: None,
         model_sonnet: Some("mapped-sonnet".to_string()),
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, is_streaming, mapping) = pipeline::transform_body(
@@ -599,6 +780,7 @@ fn test_transform_body_no_json() {
         None,
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -627,6 +809,7 @@ fn test_transform_body_model_rewrite() {
         model_opus: None,
         model_sonnet: Some("mapped-sonnet".to_string()),
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, mapping) = pipeline::transform_body(
@@ -634,6 +817,7 @@ fn test_transform_body_model_rewrite() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -664,6 +848,7 @@ fn test_transform_body_thinking_compat_adaptive_to_enabled() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, _) = pipeline::transform_body(
@@ -671,6 +856,7 @@ fn test_transform_body_thinking_compat_adaptive_to_enabled() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -700,6 +886,7 @@ fn test_transform_body_thinking_compat_no_adaptive() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, _) = pipeline::transform_body(
@@ -707,6 +894,7 @@ fn test_transform_body_thinking_compat_no_adaptive() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -736,6 +924,7 @@ fn test_transform_body_no_thinking_compat() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, _) = pipeline::transform_body(
@@ -743,6 +932,7 @@ fn test_transform_body_no_thinking_compat() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -765,6 +955,7 @@ fn test_transform_body_streaming_detection() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -795,6 +986,7 @@ fn test_transform_body_thinking_filtering() {
         Some(body_json.clone()),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -824,6 +1016,7 @@ fn test_transform_body_budget_calculation_from_max_tokens() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, _) = pipeline::transform_body(
@@ -831,6 +1024,7 @@ fn test_transform_body_budget_calculation_from_max_tokens() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -859,6 +1053,7 @@ fn test_transform_body_budget_default_when_no_max_tokens() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, _) = pipeline::transform_body(
@@ -866,6 +1061,7 @@ fn test_transform_body_budget_default_when_no_max_tokens() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
@@ -873,6 +1069,167 @@ fn test_transform_body_budget_default_when_no_max_tokens() {
     assert_eq!(result_json["thinking"]["budget_tokens"], 10000); // Default
 }
 
+fn transform_with_body_rules(body_json: serde_json::Value, rules: BodyRules) -> serde_json::Value {
+    let body_bytes = serde_json::to_vec(&body_json).unwrap();
+    let mut ctx = create_test_context();
+    let backend = Backend {
+        thinking_compat: Some(false),
+        body: rules,
+        ..Default::default()
+    };
+
+    let (result, _, _) = pipeline::transform_body(
+        body_bytes,
+        Some(body_json),
+        &backend,
+        None,
+        false,
+        &mut ctx,
+    ).unwrap();
+    serde_json::from_slice(&result).unwrap()
+}
+
+#[test]
+fn test_transform_body_rules_clamp_max_tokens_and_budget() {
+    let result = transform_with_body_rules(
+        json!({
+            "model": "claude-3-sonnet",
+            "max_tokens": 32000,
+            "thinking": {"type": "enabled", "budget_tokens": 16000}
+        }),
+        BodyRules {
+            max_tokens: Some(8192),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(result["max_tokens"], 8192);
+    // Budget must stay below the clamped max_tokens
+    assert_eq!(result["thinking"]["budget_tokens"], 8191);
+}
+
+#[test]
+fn test_transform_body_rules_cap_thinking_budget() {
+    let result = transform_with_body_rules(
+        json!({
+            "max_tokens": 32000,
+            "thinking": {"type": "enabled", "budget_tokens": 16000}
+        }),
+        BodyRules {
+            max_thinking_budget: Some(4096),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(result["max_tokens"], 32000);
+    assert_eq!(result["thinking"]["budget_tokens"], 4096);
+}
+
+#[test]
+fn test_transform_body_rules_leave_smaller_values_alone() {
+    let body = json!({
+        "max_tokens": 1000,
+        "thinking": {"type": "enabled", "budget_tokens": 500}
+    });
+    let result = transform_with_body_rules(
+        body.clone(),
+        BodyRules {
+            max_tokens: Some(8192),
+            max_thinking_budget: Some(4096),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(result, body);
+}
+
+#[test]
+fn test_transform_body_rules_force_and_drop_fields() {
+    let result = transform_with_body_rules(
+        json!({
+            "max_tokens": 100,
+            "temperature": 1.0,
+            "top_k": 5,
+            "metadata": {"user_id": "u"},
+            "context_management": {"edits": []}
+        }),
+        BodyRules {
+            temperature: Some(0.5),
+            top_p: Some(0.9),
+            drop: vec![
+                "metadata".to_string(),
+                "context_management".to_string(),
+                "top_k".to_string(),
+            ],
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(result["temperature"], 0.5);
+    assert_eq!(result["top_p"], 0.9);
+    assert!(result.get("top_k").is_none());
+    assert!(result.get("metadata").is_none());
+    assert!(result.get("context_management").is_none());
+    assert_eq!(result["max_tokens"], 100);
+}
+
+#[test]
+fn test_transform_body_rules_drop_cache_control() {
+    let result = transform_with_body_rules(
+        json!({
+            "cache_control": {"type": "ephemeral"},
+            "system": [{"type": "text", "text": "sys", "cache_control": {"type": "ephemeral"}}],
+            "tools": [{"name": "Read", "input_schema": {}, "cache_control": {"type": "ephemeral"}}],
+            "messages": [
+                {"role": "user", "content": "plain"},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": [
+                        {"type": "text", "text": "out", "cache_control": {"type": "ephemeral"}}
+                    ]},
+                    {"type": "text", "text": "hi", "cache_control": {"type": "ephemeral"}}
+                ]}
+            ]
+        }),
+        BodyRules {
+            drop: vec!["cache_control".to_string()],
+            ..Default::default()
+        },
+    );
+
+    assert!(!result.to_string().contains("cache_control"), "{}", result);
+    assert_eq!(result["messages"][0]["content"], "plain");
+    assert_eq!(result["messages"][1]["content"][0]["content"][0]["text"], "out");
+}
+
+#[test]
+fn test_transform_body_rules_drop_tools() {
+    let result = transform_with_body_rules(
+        json!({
+            "tools": [{"name": "Read"}, {"name": "WebSearch"}],
+            "tool_choice": {"type": "tool", "name": "WebSearch"}
+        }),
+        BodyRules {
+            drop_tools: vec!["WebSearch".to_string()],
+            ..Default::default()
+        },
+    );
+    assert_eq!(result["tools"], json!([{"name": "Read"}]));
+    assert!(result.get("tool_choice").is_none());
+
+    let result = transform_with_body_rules(
+        json!({
+            "tools": [{"name": "WebSearch"}],
+            "tool_choice": {"type": "auto"}
+        }),
+        BodyRules {
+            drop_tools: vec!["WebSearch".to_string()],
+            ..Default::default()
+        },
+    );
+    assert!(result.get("tools").is_none());
+    assert!(result.get("tool_choice").is_none());
+}
+
 // =============================================================================
 // Stage 5: build_headers tests
 // =============================================================================
@@ -917,6 +1274,7 @@ fn test_build_headers_strips_auth_for_own_credentials() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
@@ -950,6 +1308,7 @@ fn test_build_headers_passthrough_keeps_auth() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
@@ -979,6 +1338,7 @@ fn test_build_headers_patches_anthropic_beta() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
@@ -1016,6 +1376,7 @@ fn test_build_headers_no_anthropic_beta_patch_for_anthropic() {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     };
 
     let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
@@ -1027,6 +1388,107 @@ fn test_build_headers_no_anthropic_beta_patch_for_anthropic() {
     assert!(beta_header.unwrap().1.contains("adaptive-thinking"));
 }
 
+fn header_values<'a>(headers: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+        .collect()
+}
+
+#[test]
+fn test_build_headers_rules_set_append_remove() {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    headers.insert("x-stainless-os", "Linux".parse().unwrap());
+    headers.insert("x-stainless-lang", "js".parse().unwrap());
+    headers.insert("anthropic-dangerous-direct-browser-access", "true".parse().unwrap());
+    headers.insert("http-referer", "client".parse().unwrap());
+    headers.insert("x-tags", "client-tag".parse().unwrap());
+
+    let mut ctx = create_test_context();
+    ctx.session_id = Some("sess-1".to_string());
+    ctx.agent_id = Some("agent-7".to_string());
+    let mut backend = Backend {
+        auth_type_str: "bearer".to_string(),
+        api_key: Some("backend-key".to_string()),
+        ..Default::default()
+    };
+    backend.headers.remove = vec![
+        "X-Stainless-*".to_string(),
+        "anthropic-dangerous-direct-browser-access".to_string(),
+    ];
+    backend.headers.set.insert("HTTP-Referer".to_string(), "https://example.com".to_string());
+    backend.headers.set.insert(
+        "X-Trace".to_string(),
+        "{session_id}/{request_id}/{agent_id}".to_string(),
+    );
+    backend.headers.append.insert("x-tags".to_string(), "anyclaude".to_string());
+
+    let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
+
+    assert!(header_values(&result, "x-stainless-os").is_empty());
+    assert!(header_values(&result, "x-stainless-lang").is_empty());
+    assert!(header_values(&result, "anthropic-dangerous-direct-browser-access").is_empty());
+    assert_eq!(header_values(&result, "http-referer"), vec!["https://example.com"]);
+    assert_eq!(header_values(&result, "x-trace"), vec!["sess-1/test-request-id/agent-7"]);
+    assert_eq!(header_values(&result, "x-tags"), vec!["client-tag", "anyclaude"]);
+    assert_eq!(header_values(&result, "authorization"), vec!["Bearer backend-key"]);
+}
+
+#[test]
+fn test_build_headers_rules_unknown_template_values_are_empty() {
+    let mut ctx = create_test_context();
+    let mut backend = Backend::default();
+    backend.headers.set.insert("x-session".to_string(), "s={session_id}".to_string());
+
+    let result = pipeline::build_headers(&HeaderMap::new(), &backend, &mut ctx).unwrap();
+
+    assert_eq!(header_values(&result, "x-session"), vec!["s="]);
+}
+
+#[test]
+fn test_build_headers_rules_strip_and_add_beta() {
+    let mut headers = HeaderMap::new();
+    headers.append("anthropic-beta", "prompt-caching-2024-07-01,context-management-2025-06-27".parse().unwrap());
+    headers.append("anthropic-beta", "fine-grained-tool-streaming-2025-05-14".parse().unwrap());
+
+    let mut ctx = create_test_context();
+    let mut backend = Backend {
+        thinking_compat: Some(false),
+        ..Default::default()
+    };
+    backend.headers.strip_beta = vec!["context-management-*".to_string()];
+    backend.headers.add_beta = vec![
+        "prompt-caching-2024-07-01".to_string(),
+        "context-1m-2025-08-07".to_string(),
+    ];
+
+    let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
+
+    assert_eq!(
+        header_values(&result, "anthropic-beta"),
+        vec!["prompt-caching-2024-07-01,fine-grained-tool-streaming-2025-05-14,context-1m-2025-08-07"]
+    );
+}
+
+#[test]
+fn test_build_headers_rules_strip_every_beta_drops_header() {
+    let mut headers = HeaderMap::new();
+    headers.insert("anthropic-beta", "context-management-2025-06-27".parse().unwrap());
+
+    let mut ctx = create_test_context();
+    let mut backend = Backend {
+        thinking_compat: Some(false),
+        ..Default::default()
+    };
+    backend.headers.strip_beta = vec!["*".to_string()];
+
+    let result = pipeline::build_headers(&headers, &backend, &mut ctx).unwrap();
+
+    assert!(header_values(&result, "anthropic-beta").is_empty());
+}
+
 // =============================================================================
 // Corner Cases Documentation
 // =============================================================================
@@ -1060,6 +1522,8 @@ fn test_build_headers_no_anthropic_beta_patch_for_anthropic() {
 /// - **max_tokens = 1**: budget_tokens becomes 0 (1 - 1 = 0)
 /// - **JSON serialization failure**: Falls back to original body bytes
 /// - **No model field**: No model mapping created
+/// - **`[backends.body]` overrides**: Run after thinking compat, so a converted
+///   budget is capped too; `max_tokens` is only ever lowered
 /// - **Empty model string**: Treated as valid model name (empty string)
 ///
 /// ## Stage 5: build_headers
@@ -1068,6 +1532,8 @@ fn test_build_headers_no_anthropic_beta_patch_for_anthropic() {
 /// - **Invalid header values**: Skipped via to_str().ok() check
 /// - **Missing backend auth**: build_auth_header returns None, no panic
 /// - **Mixed auth headers**: Both Authorization and x-api-key stripped when appropriate
+/// - **Header rules**: `remove` only sees client headers; `set` runs after the
+///   auth header is added, so it can replace it
 ///
 /// ## Stage 6: forward_with_retry
 /// - **Streaming + retry**: Each retry starts fresh stream (previous streams dropped)
@@ -1079,6 +1545,7 @@ fn test_build_headers_no_anthropic_beta_patch_for_anthropic() {
 /// ## Stage 7: handle_response
 /// - **Model mapping with non-JSON response**: Passes through unchanged
 /// - **Streaming error after headers sent**: Client sees truncated stream
+///   (re-issued or ended with an SSE error event with `[retry] resume_streams`)
 /// - **Content-Length with model mapping**: Stripped to avoid mismatch
 /// - **Zero-byte response**: Empty body forwarded correctly
 /// - **Invalid UTF-8 in SSE**: Handled by bytes-based processing
@@ -1113,11 +1580,12 @@ fn test_corner_case_model_family_detection_case_sensitive() {
         model_opus: Some("openrouter-opus".to_string()),
         model_sonnet: Some("openrouter-sonnet".to_string()),
         model_haiku: Some("openrouter-haiku".to_string()),
+        ..Default::default()
     };
 
     for (model, expected) in test_cases {
         let result = backend.resolve_model(model);
-        assert_eq!(result, expected, "Model '{}' resolution mismatch", model);
+        assert_eq!(result.as_deref(), expected, "Model '{}' resolution mismatch", model);
     }
 }
 
@@ -1153,6 +1621,7 @@ fn test_corner_case_budget_tokens_with_small_max_tokens() {
             model_opus: None,
             model_sonnet: None,
             model_haiku: None,
+            ..Default::default()
         };
 
         let (result, _, _) = pipeline::transform_body(
@@ -1160,6 +1629,7 @@ fn test_corner_case_budget_tokens_with_small_max_tokens() {
             Some(body_json),
             &backend,
             None,
+            false,
             &mut ctx,
         ).unwrap();
 
@@ -1191,6 +1661,7 @@ fn test_corner_case_invalid_marker_model() {
         None,
         None,
         parsed_body.as_ref(),
+        "/v1/messages",
         &registry,
         &mut ctx,
     ).unwrap();
@@ -1220,6 +1691,7 @@ fn test_corner_case_empty_model_string() {
         model_opus: None,
         model_sonnet: Some("mapped-sonnet".to_string()),
         model_haiku: None,
+        ..Default::default()
     };
 
     let (result, _, mapping) = pipeline::transform_body(
@@ -1227,6 +1699,7 @@ fn test_corner_case_empty_model_string() {
         Some(body_json),
         &backend,
         None,
+        false,
         &mut ctx,
     ).unwrap();
 
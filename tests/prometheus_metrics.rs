@@ -0,0 +1,115 @@
+//! `GET /metrics` in the Prometheus text format.
+
+mod common;
+
+use anyclaude::config::Backend;
+use anyclaude::metrics::{LatencyHistogram, ObservabilityHub, LATENCY_BUCKETS_MS};
+use anyclaude::proxy::prometheus::MetricsHandler;
+use anyclaude::proxy::shutdown::ShutdownManager;
+use anyclaude::proxy::thinking::TransformerRegistry;
+use common::mock_backend::{MockBackend, MockResponse};
+use common::ProxyOptions;
+use reqwest::Client;
+use std::sync::Arc;
+
+async fn start_proxy(base_url: &str, session_token: Option<String>) -> String {
+    let backend = Backend {
+        name: "mock".to_string(),
+        display_name: "Mock".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        ..Default::default()
+    };
+    let options = ProxyOptions {
+        session_token,
+        ..Default::default()
+    };
+    common::start_proxy_with(common::proxy_config(vec![backend]), options).await.url
+}
+
+async fn scrape(proxy_url: &str) -> String {
+    let resp = Client::new()
+        .get(format!("{}/metrics", proxy_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let content_type = resp.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/plain; version=0.0.4"), "{}", content_type);
+    resp.text().await.unwrap()
+}
+
+fn has_line(text: &str, line: &str) -> bool {
+    text.lines().any(|l| l == line)
+}
+
+#[test]
+fn test_histogram_buckets() {
+    let mut histogram = LatencyHistogram::default();
+    histogram.observe(50);
+    histogram.observe(51);
+    histogram.observe(1_000_000);
+
+    assert_eq!(histogram.buckets[0], 1);
+    assert_eq!(histogram.buckets[1], 1);
+    assert_eq!(histogram.buckets[LATENCY_BUCKETS_MS.len()], 1);
+    assert_eq!(histogram.count, 3);
+    assert_eq!(histogram.sum_ms, 1_000_101);
+}
+
+#[test]
+fn test_render_without_requests() {
+    let handler = MetricsHandler::new(
+        ObservabilityHub::new(10),
+        Arc::new(TransformerRegistry::new()),
+        Arc::new(ShutdownManager::new()),
+    );
+    let text = handler.render();
+
+    assert!(has_line(&text, "# TYPE anyclaude_requests_total counter"));
+    assert!(has_line(&text, "# TYPE anyclaude_request_duration_seconds histogram"));
+    assert!(has_line(&text, "anyclaude_active_connections 0"));
+    assert!(has_line(&text, "anyclaude_thinking_cache_blocks{status=\"confirmed\"} 0"));
+    assert!(has_line(&text, "anyclaude_thinking_cache_session_blocks{session=\"old\"} 0"));
+    assert!(!text.contains("backend="));
+}
+
+#[tokio::test]
+async fn integration_metrics_after_requests() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(r#"{"type":"message"}"#)).await;
+    mock.enqueue_response(MockResponse::error(400, "bad request")).await;
+    let proxy_url = start_proxy(&mock.base_url(), None).await;
+
+    for _ in 0..2 {
+        common::post_messages(
+            &proxy_url,
+            r#"{"model":"claude-sonnet-4","max_tokens":16,"messages":[]}"#,
+            &[],
+        )
+        .await;
+    }
+    let text = scrape(&proxy_url).await;
+
+    assert!(has_line(&text, "anyclaude_requests_total{backend=\"mock\",status_class=\"2xx\"} 1"), "{}", text);
+    assert!(has_line(&text, "anyclaude_requests_total{backend=\"mock\",status_class=\"4xx\"} 1"));
+    assert!(has_line(&text, "anyclaude_request_timeouts_total{backend=\"mock\"} 0"));
+    assert!(has_line(&text, "anyclaude_request_duration_seconds_bucket{backend=\"mock\",le=\"+Inf\"} 2"));
+    assert!(has_line(&text, "anyclaude_request_duration_seconds_count{backend=\"mock\"} 2"));
+    assert!(has_line(&text, "anyclaude_time_to_first_byte_seconds_count{backend=\"mock\"} 2"));
+    assert!(text.contains("anyclaude_request_duration_seconds_bucket{backend=\"mock\",le=\"0.05\"}"));
+    assert!(text.contains("anyclaude_input_tokens_total{backend=\"mock\"}"));
+    assert!(text.contains("anyclaude_cost_usd_total{backend=\"mock\"}"));
+    // The scrape itself is on an open connection.
+    assert!(!has_line(&text, "anyclaude_active_connections 0"));
+}
+
+#[tokio::test]
+async fn integration_metrics_skip_session_token() {
+    let mock = MockBackend::start().await;
+    let proxy_url = start_proxy(&mock.base_url(), Some("secret".to_string())).await;
+
+    let text = scrape(&proxy_url).await;
+
+    assert!(text.contains("# TYPE anyclaude_requests_total counter"));
+}
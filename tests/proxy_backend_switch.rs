@@ -36,6 +36,7 @@ fn test_config_with_backends(backends: Vec<Backend>, bind_addr: &str) -> Config
         claude_settings: HashMap::new(),
         backends,
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -52,6 +53,7 @@ fn create_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,209 @@
+//! Failover chain tests: requests move to the next backend on connection
+//! errors and failover statuses.
+
+mod common;
+
+use anyclaude::config::Backend;
+use common::mock_backend::{MockBackend, MockResponse};
+use common::TestProxy;
+
+fn create_backend(name: &str, base_url: &str, failover: &[&str]) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_uppercase(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        failover: failover.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// Start a proxy for `backends` with short timeouts and no retries.
+async fn start_proxy(backends: Vec<Backend>) -> TestProxy {
+    let mut config = common::proxy_config(backends);
+    config.defaults.timeout_seconds = 2;
+    config.defaults.connect_timeout_seconds = 1;
+    config.defaults.max_retries = 0;
+    config.defaults.retry_backoff_base_ms = 10;
+    common::start_proxy(config).await
+}
+
+#[tokio::test]
+async fn test_overloaded_status_fails_over_to_next_backend() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    secondary.enqueue_response(MockResponse::json(r#"{"served_by": "secondary"}"#)).await;
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text().await.unwrap().contains("secondary"));
+    assert_eq!(primary.captured_requests().await.len(), 1);
+    assert_eq!(secondary.captured_requests().await.len(), 1);
+
+    // Hop is recorded in the switch history; active backend is unchanged
+    let log = proxy.backend_state.get_switch_log();
+    let hop = log.last().unwrap();
+    assert!(hop.failover);
+    assert_eq!(hop.old_backend.as_deref(), Some("primary"));
+    assert_eq!(hop.new_backend, "secondary");
+    assert_eq!(proxy.backend_state.get_active_backend(), "primary");
+}
+
+#[tokio::test]
+async fn test_failover_finishes_one_record_per_request() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    secondary.enqueue_response(MockResponse::json(r#"{"served_by": "secondary"}"#)).await;
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    resp.text().await.unwrap();
+
+    let snapshot = proxy.observability.snapshot();
+    assert_eq!(snapshot.recent.len(), 1);
+    assert_eq!(snapshot.recent[0].backend, "secondary");
+    assert_eq!(snapshot.recent[0].status, Some(200));
+    assert!(!snapshot.per_backend.contains_key("primary"));
+    let failovers = proxy.backend_state.get_switch_log().iter().filter(|hop| hop.failover).count();
+    assert_eq!(failovers, 1);
+}
+
+#[tokio::test]
+async fn test_connection_error_fails_over() {
+    let secondary = MockBackend::start().await;
+    let dead_url = format!("http://127.0.0.1:{}", common::free_port());
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &dead_url, &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(secondary.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_chain_is_walked_in_order() {
+    let first = MockBackend::start().await;
+    let second = MockBackend::start().await;
+    let third = MockBackend::start().await;
+    first.enqueue_response(MockResponse::error(503, "unavailable")).await;
+    second.enqueue_response(MockResponse::error(429, "rate limited")).await;
+
+    let proxy = start_proxy(vec![
+        create_backend("first", &first.base_url(), &["second", "third"]),
+        create_backend("second", &second.base_url(), &[]),
+        create_backend("third", &third.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(first.captured_requests().await.len(), 1);
+    assert_eq!(second.captured_requests().await.len(), 1);
+    assert_eq!(third.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_last_backend_error_is_returned() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    secondary.enqueue_response(MockResponse::error(503, "also down")).await;
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 503);
+    assert!(resp.text().await.unwrap().contains("also down"));
+}
+
+#[tokio::test]
+async fn test_client_error_does_not_fail_over() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(400, "bad request")).await;
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 400);
+    assert!(secondary.captured_requests().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_custom_failover_statuses() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(404, "no such model")).await;
+
+    let mut primary_backend = create_backend("primary", &primary.base_url(), &["secondary"]);
+    primary_backend.failover_on_status = Some(vec![404]);
+
+    let proxy = start_proxy(vec![
+        primary_backend,
+        create_backend("secondary", &secondary.base_url(), &[]),
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(secondary.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_failover_applies_target_model_mapping() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    secondary
+        .enqueue_response(MockResponse::json(r#"{"model": "glm-5", "content": []}"#))
+        .await;
+
+    let mut secondary_backend = create_backend("secondary", &secondary.base_url(), &[]);
+    secondary_backend.model_opus = Some("glm-5".to_string());
+
+    let proxy = start_proxy(vec![
+        create_backend("primary", &primary.base_url(), &["secondary"]),
+        secondary_backend,
+    ])
+    .await;
+
+    let resp = common::post_messages(&proxy.url, r#"{"model": "claude-opus-4-6", "messages": []}"#, &[]).await;
+    assert_eq!(resp.status(), 200);
+
+    // Primary saw the original model, secondary its own mapping
+    let primary_body = String::from_utf8(primary.captured_requests().await[0].body.clone()).unwrap();
+    assert!(primary_body.contains("claude-opus-4-6"));
+    let secondary_body =
+        String::from_utf8(secondary.captured_requests().await[0].body.clone()).unwrap();
+    assert!(secondary_body.contains("glm-5"));
+
+    // Response model is mapped back to what the client sent
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["model"], "claude-opus-4-6");
+}
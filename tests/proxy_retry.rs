@@ -3,16 +3,20 @@
 mod common;
 
 use anyclaude::config::{
-    Backend, Config, ConfigStore, DebugLoggingConfig, Defaults, ProxyConfig, TerminalConfig,
+    Backend, Config, ConfigStore, DebugLoggingConfig, Defaults, ProxyConfig, RetryConfig,
+    TerminalConfig,
 };
 use anyclaude::metrics::DebugLogger;
+use anyclaude::proxy::retry::{backoff, retry_after};
 use anyclaude::proxy::ProxyServer;
 use common::mock_backend::{MockBackend, MockResponse};
+use common::TestProxy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Client;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn test_config(backend: Backend, bind_addr: &str) -> Config {
     Config {
@@ -36,6 +40,7 @@ fn test_config(backend: Backend, bind_addr: &str) -> Config {
         claude_settings: HashMap::new(),
         backends: vec![backend],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -52,6 +57,7 @@ fn create_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -153,3 +159,159 @@ async fn test_slow_response_succeeds() {
     let body = resp.text().await.unwrap();
     assert!(body.contains("slow"));
 }
+
+fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            (
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn with_header(mut response: MockResponse, name: &str, value: &str) -> MockResponse {
+    response.headers.push((name.to_string(), value.to_string()));
+    response
+}
+
+/// Start a proxy in front of `mock` with `retry`.
+async fn start_proxy(mock: &MockBackend, retry: RetryConfig) -> TestProxy {
+    let bind_addr = format!("127.0.0.1:{}", common::free_port());
+    let mut config = test_config(create_backend("test", &mock.base_url()), &bind_addr);
+    config.retry = retry;
+    common::start_proxy(config).await
+}
+
+#[test]
+fn test_retry_after_seconds_and_ms() {
+    let now = SystemTime::now();
+    assert_eq!(retry_after(&headers(&[("retry-after", "3")]), now), Some(Duration::from_secs(3)));
+    assert_eq!(
+        retry_after(&headers(&[("retry-after-ms", "1500"), ("retry-after", "3")]), now),
+        Some(Duration::from_millis(1500))
+    );
+    assert_eq!(retry_after(&headers(&[]), now), None);
+    assert_eq!(retry_after(&headers(&[("retry-after", "2.5")]), now), Some(Duration::from_millis(2500)));
+}
+
+#[test]
+fn test_retry_after_out_of_range_values() {
+    let now = SystemTime::now();
+    for value in ["inf", "1e300", "18446744073709551615"] {
+        let wait = retry_after(&headers(&[("retry-after", value)]), now).unwrap();
+        assert!(wait > Duration::from_secs(u64::from(u32::MAX)), "{}: {:?}", value, wait);
+    }
+    assert_eq!(retry_after(&headers(&[("retry-after-ms", "-5")]), now), Some(Duration::ZERO));
+    assert_eq!(retry_after(&headers(&[("retry-after", "NaN")]), now), None);
+}
+
+#[test]
+fn test_retry_after_http_date() {
+    // Wed, 21 Oct 2015 07:28:00 GMT
+    let now = UNIX_EPOCH + Duration::from_secs(1_445_412_470);
+    assert_eq!(
+        retry_after(&headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]), now),
+        Some(Duration::from_secs(10))
+    );
+}
+
+#[test]
+fn test_retry_after_ratelimit_reset_of_exhausted_limits() {
+    // 2026-01-01T00:00:00Z
+    let now = UNIX_EPOCH + Duration::from_secs(1_767_225_600);
+    let exhausted = headers(&[
+        ("anthropic-ratelimit-requests-remaining", "0"),
+        ("anthropic-ratelimit-requests-reset", "2026-01-01T00:00:05Z"),
+        ("anthropic-ratelimit-tokens-remaining", "0"),
+        ("anthropic-ratelimit-tokens-reset", "2026-01-01T01:00:12.5+01:00"),
+        ("anthropic-ratelimit-input-tokens-remaining", "5000"),
+        ("anthropic-ratelimit-input-tokens-reset", "2026-01-01T00:01:00Z"),
+    ]);
+    assert_eq!(retry_after(&exhausted, now), Some(Duration::from_millis(12_500)));
+
+    let remaining = headers(&[
+        ("anthropic-ratelimit-requests-remaining", "10"),
+        ("anthropic-ratelimit-requests-reset", "2026-01-01T00:00:05Z"),
+    ]);
+    assert_eq!(retry_after(&remaining, now), None);
+}
+
+#[test]
+fn test_backoff_is_jittered_within_bounds() {
+    let base = Duration::from_millis(100);
+    for attempt in 0..4 {
+        let full = base * (1 << attempt);
+        let delays: Vec<Duration> = (0..50).map(|_| backoff(base, attempt)).collect();
+        assert!(delays.iter().all(|d| *d >= full / 2 && *d <= full), "{:?}", delays);
+        assert!(delays.iter().any(|d| *d != delays[0]), "no jitter: {:?}", delays);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_response_retried() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(with_header(MockResponse::error(429, "rate limited"), "retry-after-ms", "200")).await;
+    mock.enqueue_response(MockResponse::json(r#"{"ok": true}"#)).await;
+
+    let proxy = start_proxy(&mock, RetryConfig::default()).await;
+    let started = Instant::now();
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+
+    assert_eq!(resp.status(), 200);
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(mock.captured_requests().await.len(), 2);
+    assert!(proxy.error_registry.active_recoveries().is_empty());
+    assert!(proxy.error_registry.current_error().is_none());
+}
+
+#[tokio::test]
+async fn test_overloaded_retries_exhausted() {
+    let mock = MockBackend::start().await;
+    for _ in 0..3 {
+        mock.enqueue_response(MockResponse::error(529, "overloaded")).await;
+    }
+
+    let proxy = start_proxy(&mock, RetryConfig::default()).await;
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+
+    assert_eq!(resp.status(), 529);
+    assert_eq!(mock.captured_requests().await.len(), 3);
+    assert!(proxy.error_registry.active_recoveries().is_empty());
+    let warning = proxy.error_registry.current_error().expect("warning recorded");
+    assert!(warning.message.contains("still failing"), "{}", warning.message);
+}
+
+#[tokio::test]
+async fn test_retry_after_beyond_wait_budget_not_retried() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(with_header(MockResponse::error(429, "rate limited"), "retry-after", "60")).await;
+
+    let retry = RetryConfig {
+        max_wait_seconds: 5,
+        ..Default::default()
+    };
+    let proxy = start_proxy(&mock, retry).await;
+    let resp = common::post_messages(&proxy.url, "{}", &[]).await;
+
+    assert_eq!(resp.status(), 429);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "60");
+    assert_eq!(mock.captured_requests().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_status_retries_disabled() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::error(429, "rate limited")).await;
+
+    let retry = RetryConfig {
+        max_status_retries: 0,
+        ..Default::default()
+    };
+    let proxy = start_proxy(&mock, retry).await;
+
+    assert_eq!(common::post_messages(&proxy.url, "{}", &[]).await.status(), 429);
+    assert_eq!(mock.captured_requests().await.len(), 1);
+}
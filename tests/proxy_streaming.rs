@@ -3,11 +3,13 @@
 mod common;
 
 use anyclaude::config::{
-    Backend, Config, ConfigStore, DebugLoggingConfig, Defaults, ProxyConfig, TerminalConfig,
+    Backend, BackendPricing, Config, ConfigStore, DebugLoggingConfig, Defaults, ProxyConfig,
+    TerminalConfig,
 };
 use anyclaude::metrics::DebugLogger;
 use anyclaude::proxy::ProxyServer;
 use common::mock_backend::{MockBackend, MockResponse};
+use common::TestProxy;
 use reqwest::Client;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -36,6 +38,7 @@ fn test_config(backend: Backend, bind_addr: &str) -> Config {
         claude_settings: HashMap::new(),
         backends: vec![backend],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -52,6 +55,7 @@ fn create_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -139,3 +143,74 @@ async fn test_json_response_passthrough() {
     let body = resp.text().await.unwrap();
     assert!(body.contains("Hello"));
 }
+
+async fn start_proxy(backend: Backend) -> TestProxy {
+    let bind_addr = format!("127.0.0.1:{}", common::free_port());
+    common::start_proxy(test_config(backend, &bind_addr)).await
+}
+
+fn priced_backend(base_url: &str) -> Backend {
+    Backend {
+        pricing: Some(BackendPricing {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+            cache_read_per_million: None,
+            cache_write_per_million: None,
+        }),
+        ..create_backend("test", base_url)
+    }
+}
+
+#[tokio::test]
+async fn test_sse_usage_recorded() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[
+        r#"{"type":"message_start","message":{"usage":{"input_tokens":10,"cache_read_input_tokens":1000,"output_tokens":1}}}"#,
+        r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"hi"}}"#,
+        r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":20}}"#,
+        r#"{"type":"message_stop"}"#,
+    ])).await;
+    let proxy = start_proxy(priced_backend(&mock.base_url())).await;
+
+    let body = common::post_messages(&proxy.url, r#"{"stream": true}"#, &[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("message_stop"));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let usage = proxy.observability.snapshot().recent.pop().unwrap().usage.unwrap();
+    assert_eq!(usage.input_tokens, 10);
+    assert_eq!(usage.cache_read_input_tokens, 1000);
+    assert_eq!(usage.output_tokens, 20);
+    let expected = (10.0 * 3.0 + 1000.0 * 0.3 + 20.0 * 15.0) / 1_000_000.0;
+    assert!((usage.cost_usd.unwrap() - expected).abs() < 1e-12);
+
+    let metrics = &proxy.observability.snapshot().per_backend["test"];
+    assert_eq!(metrics.input_tokens, 10);
+    assert_eq!(metrics.cache_read_input_tokens, 1000);
+    assert_eq!(metrics.output_tokens, 20);
+}
+
+#[tokio::test]
+async fn test_json_usage_recorded() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::json(
+        r#"{"type":"message","usage":{"input_tokens":4,"output_tokens":6,"cache_creation_input_tokens":100}}"#,
+    )).await;
+    let proxy = start_proxy(create_backend("test", &mock.base_url())).await;
+
+    common::post_messages(&proxy.url, "{}", &[])
+        .await
+        .bytes()
+        .await
+        .unwrap();
+
+    let usage = proxy.observability.snapshot().recent.pop().unwrap().usage.unwrap();
+    assert_eq!(usage.input_tokens, 4);
+    assert_eq!(usage.output_tokens, 6);
+    assert_eq!(usage.cache_creation_input_tokens, 100);
+    // No pricing configured.
+    assert_eq!(usage.cost_usd, None);
+}
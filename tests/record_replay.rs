@@ -0,0 +1,291 @@
+//! `--record` / `--replay`: upstream exchanges saved to a directory and
+//! served from it without contacting any backend.
+
+mod common;
+
+use anyclaude::config::Backend;
+use anyclaude::proxy::recording::{
+    request_key, Chunk, Exchange, MatchMode, Recorder, Recording, Replayer,
+};
+use common::mock_backend::{MockBackend, MockResponse};
+use common::ProxyOptions;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn create_backend(base_url: &str, auth_type: &str) -> Backend {
+    Backend {
+        name: "recorded".to_string(),
+        display_name: "Recorded".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: auth_type.to_string(),
+        ..Default::default()
+    }
+}
+
+async fn start_proxy(backends: Vec<Backend>, recording: Recording) -> String {
+    let options = ProxyOptions {
+        recording: Some(recording),
+        ..Default::default()
+    };
+    common::start_proxy_with(common::proxy_config(backends), options).await.url
+}
+
+fn message(system: &str, text: &str) -> Value {
+    json!({
+        "model": "claude-sonnet-4-5",
+        "max_tokens": 64,
+        "stream": true,
+        "system": system,
+        "metadata": {"user_id": format!("user_{}", uuid::Uuid::new_v4())},
+        "messages": [{"role": "user", "content": text}],
+    })
+}
+
+async fn send(proxy_url: &str, body: &Value) -> (u16, String) {
+    let resp = common::post_messages(proxy_url, &body.to_string(), &[]).await;
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+/// Exchanges in `dir`, once the writer thread has stored `count`.
+async fn wait_for_exchanges(dir: &Path, count: usize) -> Vec<Exchange> {
+    for _ in 0..100 {
+        let exchanges: Vec<Exchange> = std::fs::read_dir(dir)
+            .unwrap()
+            .flat_map(|entry| {
+                let text = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+                text.lines()
+                    .map(|line| serde_json::from_str(line).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        if exchanges.len() >= count {
+            return exchanges;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("recording never reached {} exchanges", count);
+}
+
+const EVENTS: [&str; 3] = [
+    r#"{"type":"message_start","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":5,"output_tokens":0}}}"#,
+    r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"recorded"}}"#,
+    r#"{"type":"message_stop"}"#,
+];
+
+async fn record_session(dir: &Path) -> String {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&EVENTS)).await;
+    let recorder = Recorder::new(dir.to_path_buf()).unwrap();
+    let proxy_url = start_proxy(
+        vec![create_backend(&mock.base_url(), "passthrough")],
+        Recording::Record(Arc::new(recorder)),
+    )
+    .await;
+
+    let (status, body) = send(&proxy_url, &message("You are helpful.", "hello")).await;
+    assert_eq!(status, 200);
+    assert_eq!(mock.captured_requests().await.len(), 1);
+    body
+}
+
+#[tokio::test]
+async fn integration_record_then_replay_strict() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorded_body = record_session(dir.path()).await;
+
+    let exchanges = wait_for_exchanges(dir.path(), 1).await;
+    let exchange = &exchanges[0];
+    assert_eq!(exchange.request.method, "POST");
+    assert_eq!(exchange.request.path, "/v1/messages");
+    assert_eq!(exchange.response.status, 200);
+    assert!(exchange
+        .response
+        .headers
+        .iter()
+        .any(|(name, value)| name == "content-type" && value == "text/event-stream"));
+    let text: Vec<u8> = exchange.response.chunks.iter().flat_map(Chunk::bytes).collect();
+    assert!(String::from_utf8(text).unwrap().contains("\"text\":\"recorded\""));
+    assert_eq!(exchange.response.error, None);
+
+    // Nothing listens on the replay backend, and it has no API key.
+    let replayer = Replayer::load(dir.path().to_path_buf(), MatchMode::Strict).unwrap();
+    let proxy_url = start_proxy(
+        vec![create_backend("http://127.0.0.1:1", "api_key")],
+        Recording::Replay(Arc::new(replayer)),
+    )
+    .await;
+
+    // A new session id in `metadata` still matches.
+    let (status, body) = send(&proxy_url, &message("You are helpful.", "hello")).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, recorded_body);
+
+    // Any other change misses.
+    let (status, body) = send(&proxy_url, &message("You are terse.", "hello")).await;
+    assert_eq!(status, 404);
+    let error: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["error"]["type"], "replay_miss");
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("No recorded response for POST /v1/messages"));
+}
+
+#[tokio::test]
+async fn integration_replay_fuzzy_ignores_system_prompt() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorded_body = record_session(dir.path()).await;
+    wait_for_exchanges(dir.path(), 1).await;
+
+    let replayer = Replayer::load(dir.path().to_path_buf(), MatchMode::Fuzzy).unwrap();
+    let proxy_url = start_proxy(
+        vec![create_backend("http://127.0.0.1:1", "passthrough")],
+        Recording::Replay(Arc::new(replayer)),
+    )
+    .await;
+
+    let (status, body) = send(&proxy_url, &message("You are terse.", "hello")).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, recorded_body);
+
+    let (status, _) = send(&proxy_url, &message("You are terse.", "goodbye")).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn integration_replay_keeps_chunk_timing() {
+    let dir = tempfile::tempdir().unwrap();
+    let body = message("You are helpful.", "hello");
+    let exchange = json!({
+        "recorded_at_ms": 1,
+        "request": {"method": "POST", "path": "/v1/messages", "body": body},
+        "response": {
+            "status": 200,
+            "headers": [["content-type", "application/json"]],
+            "wait_ms": 0,
+            "chunks": [
+                {"at_ms": 0, "text": "{\"id\":"},
+                {"at_ms": 300, "base64": "Im1zZ18xIn0="},
+            ],
+        },
+    });
+    let key = request_key(MatchMode::Strict, "POST", "/v1/messages", &body);
+    std::fs::write(dir.path().join(format!("{}.jsonl", key)), format!("{}\n", exchange)).unwrap();
+
+    let replayer = Replayer::load(dir.path().to_path_buf(), MatchMode::Strict).unwrap();
+    assert_eq!(replayer.len(), 1);
+    let proxy_url = start_proxy(
+        vec![create_backend("http://127.0.0.1:1", "passthrough")],
+        Recording::Replay(Arc::new(replayer)),
+    )
+    .await;
+
+    let started = std::time::Instant::now();
+    let (status, text) = send(&proxy_url, &body).await;
+    assert_eq!(status, 200);
+    assert_eq!(text, r#"{"id":"msg_1"}"#);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn integration_replay_fails_over_without_credentials() {
+    let dir = tempfile::tempdir().unwrap();
+    let body = message("You are helpful.", "hello");
+    let exchange = |status: u16, text: &str| {
+        json!({
+            "recorded_at_ms": 1,
+            "request": {"method": "POST", "path": "/v1/messages", "body": body},
+            "response": {
+                "status": status,
+                "headers": [["content-type", "application/json"]],
+                "wait_ms": 0,
+                "chunks": [{"at_ms": 0, "text": text}],
+            },
+        })
+    };
+    let key = request_key(MatchMode::Strict, "POST", "/v1/messages", &body);
+    std::fs::write(
+        dir.path().join(format!("{}.jsonl", key)),
+        format!("{}\n{}\n", exchange(404, "{}"), exchange(200, r#"{"id":"msg_1"}"#)),
+    )
+    .unwrap();
+
+    // Neither backend has an API key; the failover target is still used.
+    let replayer = Replayer::load(dir.path().to_path_buf(), MatchMode::Strict).unwrap();
+    let mut primary = create_backend("http://127.0.0.1:1", "api_key");
+    primary.failover = vec!["backup".to_string()];
+    primary.failover_on_status = Some(vec![404]);
+    let backup = Backend {
+        name: "backup".to_string(),
+        ..create_backend("http://127.0.0.1:1", "api_key")
+    };
+    let proxy_url = start_proxy(vec![primary, backup], Recording::Replay(Arc::new(replayer))).await;
+
+    let (status, text) = send(&proxy_url, &body).await;
+    assert_eq!(status, 200);
+    assert_eq!(text, r#"{"id":"msg_1"}"#);
+}
+
+#[test]
+fn test_request_key_normalization() {
+    let a = message("You are helpful.", "hello");
+    let mut b = a.clone();
+    b["metadata"] = json!({"user_id": "someone_else"});
+    b["messages"][0]["content"] = json!([{"type": "text", "text": "hello", "cache_control": {"type": "ephemeral"}}]);
+    let mut c = a.clone();
+    c["messages"][0]["content"] = json!([{"type": "text", "text": "hello"}]);
+
+    let strict = |body: &Value| request_key(MatchMode::Strict, "POST", "/v1/messages", body);
+    let fuzzy = |body: &Value| request_key(MatchMode::Fuzzy, "POST", "/v1/messages", body);
+
+    // Key order and `metadata` never matter.
+    let reordered: Value = serde_json::from_str(&a.to_string()).unwrap();
+    assert_eq!(strict(&a), strict(&reordered));
+    assert_ne!(strict(&b), strict(&c));
+    // Fuzzy keys ignore `cache_control` and the system prompt.
+    assert_eq!(fuzzy(&b), fuzzy(&c));
+    let mut d = c.clone();
+    d["system"] = json!("Another prompt.");
+    assert_eq!(fuzzy(&c), fuzzy(&d));
+    assert_ne!(strict(&c), strict(&d));
+    // But not the model.
+    d["model"] = json!("claude-opus-4-6");
+    assert_ne!(fuzzy(&c), fuzzy(&d));
+}
+
+#[test]
+fn test_replayer_serves_in_recorded_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let body = json!({"model": "m", "messages": []});
+    let exchange = |at: u64, status: u16| {
+        json!({
+            "recorded_at_ms": at,
+            "request": {"method": "POST", "path": "/v1/messages", "body": body},
+            "response": {"status": status, "headers": [], "wait_ms": 0, "chunks": []},
+        })
+    };
+    // Written out of order, and with a line cut short.
+    let lines = format!("{}\n{}\n{{\"recorded_at_ms\":\n", exchange(2, 200), exchange(1, 529));
+    std::fs::write(dir.path().join("exchanges.jsonl"), lines).unwrap();
+
+    let replayer = Replayer::load(dir.path().to_path_buf(), MatchMode::Strict).unwrap();
+    assert_eq!(replayer.len(), 2);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let status = |replayer: &Replayer| {
+        runtime.block_on(async {
+            replayer
+                .respond(&reqwest::Method::POST, "/v1/messages", body.to_string().as_bytes())
+                .await
+                .unwrap()
+                .status()
+                .as_u16()
+        })
+    };
+    assert_eq!(status(&replayer), 529);
+    assert_eq!(status(&replayer), 200);
+    // The last one repeats.
+    assert_eq!(status(&replayer), 200);
+}
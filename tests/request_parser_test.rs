@@ -119,6 +119,30 @@ fn test_estimate_tokens() {
     assert!(analysis.estimated_input_tokens.unwrap() > 0);
 }
 
+#[test]
+fn test_estimate_tokens_counts_system_tools_and_images() {
+    let parser = RequestParser::new();
+    let text = "x".repeat(400);
+    let body = json!({
+        "system": [{"type": "text", "text": text}],
+        "messages": [
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Read", "input": {"path": text}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": [{"type": "text", "text": text}]},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}}
+            ]}
+        ]
+    });
+
+    let tokens = parser.estimate_tokens(&body).unwrap();
+
+    // Three 400-character texts plus one image.
+    assert!((1900..=1950).contains(&tokens), "{}", tokens);
+    assert_eq!(parser.estimate_tokens(&json!({"messages": []})), None);
+}
+
 #[test]
 fn test_invalid_json_returns_default() {
     let parser = RequestParser::new();
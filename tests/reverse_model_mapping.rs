@@ -296,7 +296,8 @@ fn response_handles_binary_body() {
 // ---------------------------------------------------------------------------
 
 use anyclaude::config::{
-    Backend, Config, ConfigStore, DebugLoggingConfig, Defaults, ProxyConfig, TerminalConfig,
+    Backend, Config, ConfigStore, DebugLoggingConfig, Defaults, ModelMapEntry, ProxyConfig,
+    TerminalConfig,
 };
 use anyclaude::metrics::DebugLogger;
 use anyclaude::proxy::ProxyServer;
@@ -329,6 +330,7 @@ fn test_config(backend: Backend, bind_addr: &str) -> Config {
         claude_settings: HashMap::new(),
         backends: vec![backend],
         agents: None,
+        ..Default::default()
     }
 }
 
@@ -351,6 +353,7 @@ fn create_backend_with_model_map(
         model_opus: model_opus.map(String::from),
         model_sonnet: model_sonnet.map(String::from),
         model_haiku: model_haiku.map(String::from),
+        ..Default::default()
     }
 }
 
@@ -367,6 +370,7 @@ fn create_passthrough_backend(name: &str, base_url: &str) -> Backend {
         model_opus: None,
         model_sonnet: None,
         model_haiku: None,
+        ..Default::default()
     }
 }
 
@@ -614,6 +618,74 @@ async fn integration_json_reverse_maps_haiku_model() {
     assert_eq!(json["model"], "claude-haiku-4-5-20251001", "haiku model should be reverse-mapped");
 }
 
+#[tokio::test]
+async fn integration_regex_model_map_round_trips() {
+    let mock = MockBackend::start().await;
+
+    mock.enqueue_response(MockResponse::json(
+        r#"{"id":"msg_01","type":"message","role":"assistant","model":"qwen-sonnet-4.5","content":[{"type":"text","text":"ok"}]}"#,
+    )).await;
+
+    let bind_addr = format!("127.0.0.1:{}", common::free_port());
+    let backend = Backend {
+        model_map: vec![ModelMapEntry {
+            from: r"claude-(\w+)-(\d+)-(\d+)-\d{8}".to_string(),
+            to: "qwen-$1-$2.$3".to_string(),
+            regex: true,
+        }],
+        ..create_passthrough_backend("test", &mock.base_url())
+    };
+    let config = test_config(backend, &bind_addr);
+    let (_addr, proxy_url, _handle) = start_proxy(config).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/v1/messages", proxy_url))
+        .header("content-type", "application/json")
+        .body(r#"{"model":"claude-sonnet-4-5-20250929","stream":false,"max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+    let sent: serde_json::Value = serde_json::from_slice(&mock.captured_requests().await[0].body).unwrap();
+    assert_eq!(sent["model"], "qwen-sonnet-4.5", "regex mapping should be applied upstream");
+    let json: serde_json::Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    assert_eq!(json["model"], "claude-sonnet-4-5-20250929", "regex-mapped model should be reverse-mapped");
+}
+
+#[tokio::test]
+async fn integration_default_model_round_trips() {
+    let mock = MockBackend::start().await;
+
+    mock.enqueue_response(MockResponse::sse(&[
+        r#"{"type":"message_start","message":{"id":"msg_01","model":"catch-all","role":"assistant","content":[]}}"#,
+        r#"{"type":"message_stop"}"#,
+    ])).await;
+
+    let bind_addr = format!("127.0.0.1:{}", common::free_port());
+    let backend = Backend {
+        default_model: Some("catch-all".to_string()),
+        ..create_passthrough_backend("test", &mock.base_url())
+    };
+    let config = test_config(backend, &bind_addr);
+    let (_addr, proxy_url, _handle) = start_proxy(config).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/v1/messages", proxy_url))
+        .header("content-type", "application/json")
+        .body(r#"{"model":"some-custom-model","stream":true,"max_tokens":1024,"messages":[{"role":"user","content":"hi"}]}"#)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+    let text = resp.text().await.unwrap();
+    assert!(text.contains("some-custom-model"), "default model should be reverse-mapped: {}", text);
+    assert!(!text.contains("catch-all"), "backend model should not leak: {}", text);
+}
+
 // M6: Concurrent request isolation test
 #[tokio::test]
 async fn integration_concurrent_requests_have_independent_rewriters() {
@@ -662,7 +734,7 @@ async fn integration_error_response_with_model_field_is_rewritten() {
         status: 400,
         headers: vec![("content-type".to_string(), "application/json".to_string())],
         body: r#"{"type":"error","model":"glm-5","error":{"type":"invalid_request_error","message":"bad request"}}"#.into(),
-        delay_ms: 0,
+        ..Default::default()
     }).await;
 
     let bind_addr = format!("127.0.0.1:{}", common::free_port());
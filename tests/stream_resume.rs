@@ -0,0 +1,257 @@
+//! Mid-stream recovery tests: streams that break before content are
+//! re-issued and spliced, later breaks end with an SSE `error` event.
+
+mod common;
+
+use anyclaude::config::{Backend, BackendPricing, RetryConfig};
+use common::mock_backend::{MockBackend, MockResponse};
+use common::TestProxy;
+use std::time::Duration;
+
+const MESSAGE_START: &str = r#"{"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-sonnet-4","content":[],"usage":{"input_tokens":5,"output_tokens":0}}}"#;
+const PING: &str = r#"{"type":"ping"}"#;
+const BLOCK_START: &str = r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#;
+const DELTA: &str = r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"hello"}}"#;
+const BLOCK_STOP: &str = r#"{"type":"content_block_stop","index":0}"#;
+const MESSAGE_STOP: &str = r#"{"type":"message_stop"}"#;
+
+const REQUEST: &str = r#"{"model":"claude-sonnet-4","stream":true,"max_tokens":16,"messages":[{"role":"user","content":"hi"}]}"#;
+
+fn full_stream() -> MockResponse {
+    MockResponse::sse(&[MESSAGE_START, PING, BLOCK_START, DELTA, BLOCK_STOP, MESSAGE_STOP])
+}
+
+fn create_backend(name: &str, base_url: &str, failover: &[&str]) -> Backend {
+    Backend {
+        name: name.to_string(),
+        display_name: name.to_uppercase(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        failover: failover.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// Start a proxy for `backends` with `retry` and a 1s idle timeout.
+async fn start_proxy(backends: Vec<Backend>, retry: RetryConfig) -> TestProxy {
+    let mut config = common::proxy_config(backends);
+    config.defaults.idle_timeout_seconds = 1;
+    config.defaults.max_retries = 0;
+    config.defaults.retry_backoff_base_ms = 10;
+    config.retry = retry;
+    common::start_proxy(config).await
+}
+
+fn resuming() -> RetryConfig {
+    RetryConfig {
+        resume_streams: true,
+        ..Default::default()
+    }
+}
+
+/// Send a streaming request and read the whole body, tolerating a broken
+/// stream.
+async fn stream_text(proxy: &TestProxy) -> String {
+    let mut resp = common::post_messages(&proxy.url, REQUEST, &[]).await;
+    assert_eq!(resp.status(), 200);
+
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = resp.chunk().await {
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8(body).unwrap()
+}
+
+fn count(haystack: &str, needle: &str) -> usize {
+    haystack.matches(needle).count()
+}
+
+#[tokio::test]
+async fn test_abort_before_content_is_reissued_and_spliced() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START, PING]).aborted()).await;
+    mock.enqueue_response(full_stream()).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 2);
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("hello"), "{}", text);
+    assert!(text.contains("event: message_stop"), "{}", text);
+    assert!(!text.contains("event: error"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_reissued_crlf_stream_is_spliced() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START, PING]).aborted()).await;
+    let mut crlf = full_stream();
+    crlf.body = String::from_utf8(crlf.body).unwrap().replace('\n', "\r\n").into_bytes();
+    mock.enqueue_response(crlf).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("hello"), "{}", text);
+    assert!(text.contains("event: message_stop\r\n"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_reissued_stream_tail_is_flushed() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START, PING]).aborted()).await;
+    // No message_start to skip, and no blank line after the last event.
+    let mut unterminated = MockResponse::sse(&[PING, BLOCK_START, DELTA, BLOCK_STOP, MESSAGE_STOP]);
+    unterminated.body.truncate(unterminated.body.len() - 2);
+    mock.enqueue_response(unterminated).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert!(text.contains("hello"), "{}", text);
+    assert!(text.ends_with(MESSAGE_STOP), "{}", text);
+}
+
+#[tokio::test]
+async fn test_idle_timeout_before_content_is_reissued() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START]).stalled()).await;
+    mock.enqueue_response(full_stream()).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 2);
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("event: message_stop"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_reissue_goes_to_failover_backend() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    primary.enqueue_response(MockResponse::sse(&[MESSAGE_START]).aborted()).await;
+    secondary.enqueue_response(full_stream()).await;
+
+    let proxy = start_proxy(
+        vec![
+            create_backend("primary", &primary.base_url(), &["secondary"]),
+            create_backend("secondary", &secondary.base_url(), &[]),
+        ],
+        resuming(),
+    )
+    .await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(primary.captured_requests().await.len(), 1);
+    assert_eq!(secondary.captured_requests().await.len(), 1);
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("hello"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_reissue_records_usage_of_new_stream() {
+    let primary = MockBackend::start().await;
+    let secondary = MockBackend::start().await;
+    let cached_start = r#"{"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-sonnet-4","content":[],"usage":{"input_tokens":5,"cache_read_input_tokens":1000,"output_tokens":1}}}"#;
+    primary.enqueue_response(MockResponse::sse(&[cached_start]).aborted()).await;
+    let uncached_start = r#"{"type":"message_start","message":{"id":"msg_2","type":"message","role":"assistant","model":"claude-sonnet-4","content":[],"usage":{"input_tokens":40,"output_tokens":1}}}"#;
+    let message_delta = r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":3}}"#;
+    secondary
+        .enqueue_response(MockResponse::sse(&[uncached_start, BLOCK_START, DELTA, BLOCK_STOP, message_delta, MESSAGE_STOP]))
+        .await;
+
+    let priced = Backend {
+        pricing: Some(BackendPricing {
+            input_per_million: 2.0,
+            output_per_million: 10.0,
+            cache_read_per_million: None,
+            cache_write_per_million: None,
+        }),
+        ..create_backend("secondary", &secondary.base_url(), &[])
+    };
+    let proxy = start_proxy(
+        vec![create_backend("primary", &primary.base_url(), &["secondary"]), priced],
+        resuming(),
+    )
+    .await;
+    let text = stream_text(&proxy).await;
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("msg_1"), "{}", text);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let usage = proxy.observability.snapshot().recent.pop().unwrap().usage.unwrap();
+    assert_eq!(usage.input_tokens, 40);
+    assert_eq!(usage.cache_read_input_tokens, 0);
+    assert_eq!(usage.output_tokens, 3);
+    let expected = (40.0 * 2.0 + 3.0 * 10.0) / 1_000_000.0;
+    assert!((usage.cost_usd.unwrap() - expected).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn test_abort_after_content_ends_with_error_event() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START, BLOCK_START, DELTA]).aborted()).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 1);
+    assert!(text.contains("hello"), "{}", text);
+    assert!(text.ends_with("\n\n"), "{}", text);
+    let error = text.rsplit("event: error\ndata: ").next().unwrap();
+    let error: serde_json::Value = serde_json::from_str(error.trim()).unwrap();
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["error"]["type"], "api_error");
+}
+
+#[tokio::test]
+async fn test_stall_after_content_ends_with_overloaded_error() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START, BLOCK_START]).stalled()).await;
+
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], resuming()).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 1);
+    assert!(text.contains(r#""type":"overloaded_error""#), "{}", text);
+}
+
+#[tokio::test]
+async fn test_reissues_exhausted_end_with_error_event() {
+    let mock = MockBackend::start().await;
+    for _ in 0..2 {
+        mock.enqueue_response(MockResponse::sse(&[MESSAGE_START]).aborted()).await;
+    }
+
+    let retry = RetryConfig {
+        max_stream_resumes: 1,
+        ..resuming()
+    };
+    let proxy = start_proxy(vec![create_backend("primary", &mock.base_url(), &[])], retry).await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 2);
+    assert_eq!(count(&text, "event: message_start"), 1, "{}", text);
+    assert!(text.contains("event: error"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_disabled_by_default() {
+    let mock = MockBackend::start().await;
+    mock.enqueue_response(MockResponse::sse(&[MESSAGE_START]).aborted()).await;
+    mock.enqueue_response(full_stream()).await;
+
+    let proxy = start_proxy(
+        vec![create_backend("primary", &mock.base_url(), &[])],
+        RetryConfig::default(),
+    )
+    .await;
+    let text = stream_text(&proxy).await;
+
+    assert_eq!(mock.captured_requests().await.len(), 1);
+    assert!(!text.contains("event: error"), "{}", text);
+    assert!(!text.contains("message_stop"), "{}", text);
+}
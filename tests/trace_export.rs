@@ -0,0 +1,293 @@
+//! `[tracing]`: OTLP span export and `traceparent` propagation.
+
+mod common;
+
+use anyclaude::config::{Backend, Config, OtlpFormat, TracingConfig};
+use anyclaude::metrics::trace::hex;
+use anyclaude::metrics::{AgentKind, HopTiming, RequestRecord, RequestTrace};
+use anyclaude::proxy::otlp::{spans_from_record, SpanKind, StatusCode};
+use common::mock_backend::{CapturedRequest, MockBackend, MockResponse};
+use serde_json::Value;
+use std::time::{Duration, UNIX_EPOCH};
+
+const CLIENT_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+async fn start_proxy(base_url: &str, tracing: TracingConfig) -> String {
+    let backend = Backend {
+        name: "mock".to_string(),
+        display_name: "Mock".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        model_sonnet: Some("provider-medium".to_string()),
+        ..Default::default()
+    };
+    common::start_proxy(Config {
+        tracing,
+        ..common::proxy_config(vec![backend])
+    })
+    .await
+    .url
+}
+
+fn tracing_to(collector: &MockBackend, format: OtlpFormat) -> TracingConfig {
+    TracingConfig {
+        enabled: true,
+        endpoint: format!("{}/v1/traces", collector.base_url()),
+        format,
+        headers: [("x-collector-token".to_string(), "t0k".to_string())].into(),
+        service_name: "anyclaude-test".to_string(),
+    }
+}
+
+async fn send_message(proxy_url: &str, traceparent: Option<&str>) {
+    let body = r#"{"model":"claude-sonnet-4-5","max_tokens":16,"messages":[{"role":"user","content":"hi"}]}"#;
+    let headers: Vec<_> = traceparent.map(|value| ("traceparent", value)).into_iter().collect();
+    common::post_messages(proxy_url, body, &headers).await.bytes().await.unwrap();
+}
+
+/// Wait for the first export to reach the collector.
+async fn next_export(collector: &MockBackend) -> CapturedRequest {
+    for _ in 0..100 {
+        if let Some(export) = collector.captured_requests().await.into_iter().next() {
+            return export;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("no spans exported");
+}
+
+fn header<'a>(request: &'a CapturedRequest, name: &str) -> Option<&'a str> {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn attribute<'a>(span: &'a Value, key: &str) -> &'a Value {
+    let attribute = span["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|attr| attr["key"] == key)
+        .unwrap_or_else(|| panic!("missing attribute {}", key));
+    &attribute["value"]
+}
+
+#[tokio::test]
+async fn integration_json_export() {
+    let backend = MockBackend::start().await;
+    backend
+        .enqueue_response(MockResponse::json(
+            r#"{"type":"message","usage":{"input_tokens":12,"output_tokens":3}}"#,
+        ))
+        .await;
+    let collector = MockBackend::start().await;
+    let proxy_url = start_proxy(&backend.base_url(), tracing_to(&collector, OtlpFormat::Json)).await;
+
+    send_message(&proxy_url, None).await;
+    let export = next_export(&collector).await;
+
+    assert_eq!(export.method, "POST");
+    assert_eq!(export.path, "/v1/traces");
+    assert_eq!(header(&export, "content-type"), Some("application/json"));
+    assert_eq!(header(&export, "x-collector-token"), Some("t0k"));
+
+    let payload: Value = serde_json::from_slice(&export.body).unwrap();
+    let resource_spans = &payload["resourceSpans"][0];
+    assert_eq!(attribute(&resource_spans["resource"], "service.name")["stringValue"], "anyclaude-test");
+    let scope_spans = &resource_spans["scopeSpans"][0];
+    assert_eq!(scope_spans["scope"]["name"], "anyclaude");
+
+    let spans = scope_spans["spans"].as_array().unwrap();
+    let root = &spans[0];
+    assert_eq!(root["name"], "POST /v1/messages");
+    assert_eq!(root["kind"], 2);
+    assert!(root.get("parentSpanId").is_none());
+    assert_eq!(attribute(root, "anyclaude.backend")["stringValue"], "mock");
+    assert_eq!(attribute(root, "http.response.status_code")["intValue"], "200");
+    assert_eq!(attribute(root, "gen_ai.request.model")["stringValue"], "claude-sonnet-4-5");
+    assert_eq!(attribute(root, "anyclaude.upstream.model")["stringValue"], "provider-medium");
+    assert_eq!(attribute(root, "gen_ai.usage.input_tokens")["intValue"], "12");
+    assert_eq!(attribute(root, "gen_ai.usage.output_tokens")["intValue"], "3");
+    assert_eq!(attribute(root, "anyclaude.retries")["intValue"], "0");
+    assert_eq!(attribute(root, "anyclaude.thinking.blocks_filtered")["intValue"], "0");
+    assert!(attribute(root, "anyclaude.routing.reason")["stringValue"].is_string());
+
+    let stages: Vec<&str> = spans[1..].iter().map(|span| span["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        stages,
+        vec![
+            "pipeline.extract",
+            "pipeline.routing",
+            "pipeline.thinking",
+            "pipeline.transform",
+            "pipeline.headers",
+            "pipeline.forward",
+            "pipeline.response",
+        ]
+    );
+    for stage in &spans[1..] {
+        assert_eq!(stage["traceId"], root["traceId"]);
+        assert_eq!(stage["parentSpanId"], root["spanId"]);
+        assert_eq!(stage["kind"], 1);
+    }
+
+    let upstream = backend.captured_requests().await.remove(0);
+    let traceparent = header(&upstream, "traceparent").unwrap();
+    assert_eq!(
+        traceparent,
+        format!("00-{}-{}-01", root["traceId"].as_str().unwrap(), root["spanId"].as_str().unwrap())
+    );
+}
+
+#[tokio::test]
+async fn integration_client_traceparent_continued() {
+    let backend = MockBackend::start().await;
+    let collector = MockBackend::start().await;
+    let proxy_url = start_proxy(&backend.base_url(), tracing_to(&collector, OtlpFormat::Json)).await;
+
+    send_message(&proxy_url, Some(CLIENT_TRACEPARENT)).await;
+    let export = next_export(&collector).await;
+
+    let payload: Value = serde_json::from_slice(&export.body).unwrap();
+    let root = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+    assert_eq!(root["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(root["parentSpanId"], "00f067aa0ba902b7");
+
+    let upstream = backend.captured_requests().await.remove(0);
+    let traceparent = header(&upstream, "traceparent").unwrap();
+    assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"), "{}", traceparent);
+    assert_ne!(traceparent, CLIENT_TRACEPARENT);
+}
+
+#[tokio::test]
+async fn integration_protobuf_export() {
+    let backend = MockBackend::start().await;
+    let collector = MockBackend::start().await;
+    let proxy_url = start_proxy(&backend.base_url(), tracing_to(&collector, OtlpFormat::Protobuf)).await;
+
+    send_message(&proxy_url, Some(CLIENT_TRACEPARENT)).await;
+    let export = next_export(&collector).await;
+
+    assert_eq!(header(&export, "content-type"), Some("application/x-protobuf"));
+    let contains = |needle: &[u8]| export.body.windows(needle.len()).any(|window| window == needle);
+    let trace_id = RequestTrace::from_traceparent(Some(CLIENT_TRACEPARENT)).trace_id;
+    // Span.trace_id: field 1, length 16.
+    assert!(contains(&[[0x0a, 16].as_slice(), &trace_id].concat()));
+    assert!(contains(b"anyclaude-test"));
+    assert!(contains(b"POST /v1/messages"));
+    assert!(contains(b"pipeline.forward"));
+    assert!(contains(b"anyclaude.upstream.model"));
+}
+
+#[tokio::test]
+async fn integration_disabled_adds_no_traceparent() {
+    let backend = MockBackend::start().await;
+    let proxy_url = start_proxy(&backend.base_url(), TracingConfig::default()).await;
+
+    send_message(&proxy_url, None).await;
+
+    let upstream = backend.captured_requests().await.remove(0);
+    assert_eq!(header(&upstream, "traceparent"), None);
+}
+
+#[test]
+fn test_traceparent_parsing() {
+    let trace = RequestTrace::from_traceparent(Some(CLIENT_TRACEPARENT));
+    assert_eq!(hex(&trace.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(trace.parent_span_id.map(|id| hex(&id)).as_deref(), Some("00f067aa0ba902b7"));
+
+    for invalid in [
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "garbage",
+    ] {
+        let trace = RequestTrace::from_traceparent(Some(invalid));
+        assert_ne!(hex(&trace.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736", "{}", invalid);
+        assert_eq!(trace.parent_span_id, None, "{}", invalid);
+    }
+}
+
+#[test]
+fn test_tracing_from_toml() {
+    let toml = r#"
+        enabled = true
+        format = "json"
+        endpoint = "http://collector:4318/v1/traces"
+
+        [headers]
+        authorization = "Bearer abc"
+    "#;
+    let tracing: TracingConfig = toml::from_str(toml).unwrap();
+
+    assert!(tracing.enabled);
+    assert_eq!(tracing.format, OtlpFormat::Json);
+    assert_eq!(tracing.endpoint, "http://collector:4318/v1/traces");
+    assert_eq!(tracing.headers["authorization"], "Bearer abc");
+    assert_eq!(tracing.service_name, "anyclaude");
+
+    let tracing = Config::default().tracing;
+    assert!(!tracing.enabled);
+    assert_eq!(tracing.format, OtlpFormat::Protobuf);
+    assert_eq!(tracing.endpoint, "http://127.0.0.1:4318/v1/traces");
+}
+
+#[test]
+fn test_failover_hops_are_child_spans() {
+    let started_at = UNIX_EPOCH + Duration::from_secs(1_760_000_000);
+    let mut trace = RequestTrace::from_traceparent(None);
+    trace.method = "POST".to_string();
+    trace.path = "/v1/messages".to_string();
+    trace.hops.push(HopTiming {
+        backend: "primary".to_string(),
+        started_at,
+        ended_at: started_at + Duration::from_millis(300),
+        status: Some(529),
+        timed_out: false,
+    });
+    let record = RequestRecord {
+        id: "r1".to_string(),
+        started_at,
+        first_byte_at: None,
+        completed_at: Some(started_at + Duration::from_millis(900)),
+        latency_ms: Some(900),
+        ttfb_ms: None,
+        backend: "secondary".to_string(),
+        session_id: None,
+        status: Some(200),
+        timed_out: false,
+        request_bytes: 0,
+        response_bytes: 0,
+        request_analysis: None,
+        response_analysis: None,
+        usage: None,
+        routing_decision: None,
+        agent: AgentKind::Main,
+        agent_id: None,
+        request_meta: None,
+        response_meta: None,
+        trace,
+    };
+
+    let spans = spans_from_record(&record);
+    let roots: Vec<_> = spans.iter().filter(|span| span.parent_span_id.is_none()).collect();
+    assert_eq!(roots.len(), 1);
+    let root = roots[0];
+
+    let hops: Vec<_> = spans.iter().filter(|span| span.kind == SpanKind::Client).collect();
+    assert_eq!(hops.len(), 2);
+    assert_eq!(hops[0].name, "upstream primary");
+    assert_eq!(hops[0].status, StatusCode::Error);
+    assert_eq!(hops[1].name, "upstream secondary");
+    assert_eq!(hops[1].status, StatusCode::Unset);
+    assert_eq!(hops[1].start_unix_nanos, hops[0].end_unix_nanos);
+    assert_eq!(hops[1].end_unix_nanos, root.end_unix_nanos);
+    for hop in &hops {
+        assert_eq!(hop.trace_id, root.trace_id);
+        assert_eq!(hop.parent_span_id, Some(root.span_id));
+        assert_ne!(hop.span_id, root.span_id);
+    }
+}
@@ -0,0 +1,280 @@
+//! `[usage_ledger]` and the `anyclaude --usage` report.
+
+mod common;
+
+use anyclaude::config::{Backend, BackendPricing, Config, UsageLedgerConfig};
+use anyclaude::metrics::ledger::{
+    parse_day, read_ledger, render_csv, render_table, summarize, GroupBy, LedgerEntry, UsageQuery,
+};
+use common::mock_backend::{MockBackend, MockResponse};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+async fn start_proxy(base_url: &str, usage_ledger: UsageLedgerConfig) -> String {
+    let backend = Backend {
+        name: "mock".to_string(),
+        display_name: "Mock".to_string(),
+        base_url: base_url.to_string(),
+        auth_type_str: "passthrough".to_string(),
+        model_sonnet: Some("provider-medium".to_string()),
+        pricing: Some(BackendPricing {
+            input_per_million: 1.0,
+            output_per_million: 2.0,
+            cache_read_per_million: None,
+            cache_write_per_million: None,
+        }),
+        ..Default::default()
+    };
+    common::start_proxy(Config {
+        usage_ledger,
+        ..common::proxy_config(vec![backend])
+    })
+    .await
+    .url
+}
+
+async fn send_message(proxy_url: &str) {
+    let body = r#"{"model":"claude-sonnet-4-5","max_tokens":16,"messages":[]}"#;
+    common::post_messages(proxy_url, body, &[("x-claude-code-session-id", "session-1")])
+        .await
+        .bytes()
+        .await
+        .unwrap();
+}
+
+/// Wait for the writer thread to append `count` entries.
+async fn wait_for_entries(path: &Path, count: usize) -> Vec<LedgerEntry> {
+    for _ in 0..100 {
+        let entries = read_ledger(path).unwrap();
+        if entries.len() >= count {
+            return entries;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("ledger has fewer than {} entries", count);
+}
+
+fn entry(ts: &str, backend: &str, model: &str, session: Option<&str>, status: u16, cost: f64) -> LedgerEntry {
+    LedgerEntry {
+        ts: ts.to_string(),
+        request_id: "req".to_string(),
+        session_id: session.map(str::to_string),
+        backend: backend.to_string(),
+        model: Some("claude-sonnet-4-5".to_string()),
+        upstream_model: Some(model.to_string()),
+        status: Some(status),
+        input_tokens: 100,
+        output_tokens: 10,
+        cache_creation_input_tokens: 0,
+        cache_read_input_tokens: 50,
+        cost_usd: Some(cost),
+        latency_ms: Some(5),
+    }
+}
+
+fn sample_entries() -> Vec<LedgerEntry> {
+    vec![
+        entry("2026-03-01T10:00:00Z", "alpha", "big", Some("s1"), 200, 0.5),
+        entry("2026-03-01T11:00:00Z", "beta", "small", Some("s1"), 529, 0.0),
+        entry("2026-03-02T09:00:00Z", "alpha", "small", None, 200, 0.25),
+    ]
+}
+
+#[tokio::test]
+async fn integration_requests_appended_to_ledger() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("usage.jsonl");
+    let mock = MockBackend::start().await;
+    for _ in 0..2 {
+        mock.enqueue_response(MockResponse::json(
+            r#"{"type":"message","usage":{"input_tokens":1000,"output_tokens":500,"cache_read_input_tokens":200}}"#,
+        ))
+        .await;
+    }
+    let ledger = UsageLedgerConfig {
+        enabled: true,
+        path: path.to_string_lossy().to_string(),
+    };
+    let proxy_url = start_proxy(&mock.base_url(), ledger).await;
+
+    send_message(&proxy_url).await;
+    send_message(&proxy_url).await;
+    let entries = wait_for_entries(&path, 2).await;
+
+    assert_eq!(entries.len(), 2);
+    let first = &entries[0];
+    assert_eq!(first.backend, "mock");
+    assert_eq!(first.session_id.as_deref(), Some("session-1"));
+    assert_eq!(first.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert_eq!(first.upstream_model.as_deref(), Some("provider-medium"));
+    assert_eq!(first.status, Some(200));
+    assert_eq!(first.input_tokens, 1000);
+    assert_eq!(first.output_tokens, 500);
+    assert_eq!(first.cache_read_input_tokens, 200);
+    let expected = (1000.0 + 500.0 * 2.0 + 200.0 * 0.1) / 1_000_000.0;
+    assert!((first.cost_usd.unwrap() - expected).abs() < 1e-12);
+    assert!(first.latency_ms.is_some());
+    assert_eq!(first.ts.len(), "2026-01-31T12:00:00Z".len());
+    assert!(first.ts.ends_with('Z'));
+    assert_ne!(entries[0].request_id, entries[1].request_id);
+}
+
+#[tokio::test]
+async fn integration_ledger_disabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.jsonl");
+    let mock = MockBackend::start().await;
+    let ledger = UsageLedgerConfig {
+        enabled: false,
+        path: path.to_string_lossy().to_string(),
+    };
+    let proxy_url = start_proxy(&mock.base_url(), ledger).await;
+
+    send_message(&proxy_url).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_read_ledger_skips_broken_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.jsonl");
+    let line = serde_json::to_string(&sample_entries()[0]).unwrap();
+    std::fs::write(&path, format!("{}\n{{\"ts\":\"2026-\n{}\n", line, line)).unwrap();
+
+    assert_eq!(read_ledger(&path).unwrap().len(), 2);
+    assert!(read_ledger(&dir.path().join("missing.jsonl")).unwrap().is_empty());
+}
+
+#[test]
+fn test_summarize_by_day_and_backend() {
+    let query = UsageQuery {
+        group_by: vec![GroupBy::Day, GroupBy::Backend],
+        ..Default::default()
+    };
+    let rows = summarize(&sample_entries(), &query);
+
+    let keys: Vec<Vec<&str>> = rows.iter().map(|r| r.key.iter().map(String::as_str).collect()).collect();
+    assert_eq!(
+        keys,
+        vec![
+            vec!["2026-03-01", "alpha"],
+            vec!["2026-03-01", "beta"],
+            vec!["2026-03-02", "alpha"],
+        ]
+    );
+    assert_eq!(rows[1].errors, 1);
+    assert_eq!(rows[0].cache_read_input_tokens, 50);
+}
+
+#[test]
+fn test_summarize_by_model_and_session_with_range() {
+    let query = UsageQuery {
+        group_by: vec![GroupBy::Model],
+        since: Some("2026-03-01".to_string()),
+        until: Some("2026-03-01".to_string()),
+    };
+    let rows = summarize(&sample_entries(), &query);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].key, vec!["big"]);
+    assert_eq!(rows[1].key, vec!["small"]);
+    assert_eq!(rows[1].requests, 1);
+
+    let query = UsageQuery {
+        group_by: vec![GroupBy::Session],
+        ..Default::default()
+    };
+    let rows = summarize(&sample_entries(), &query);
+    assert_eq!(rows[0].key, vec!["-"]);
+    assert_eq!(rows[1].key, vec!["s1"]);
+    assert_eq!(rows[1].requests, 2);
+    assert!((rows[1].cost_usd - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_render_csv_and_table() {
+    let group_by = [GroupBy::Backend];
+    let query = UsageQuery {
+        group_by: group_by.to_vec(),
+        ..Default::default()
+    };
+    let rows = summarize(&sample_entries(), &query);
+
+    let csv = render_csv(&group_by, &rows);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "backend,requests,errors,input_tokens,output_tokens,cache_read_input_tokens,cache_creation_input_tokens,cost_usd"
+    );
+    assert_eq!(lines[1], "alpha,2,0,200,20,100,0,0.7500");
+    assert_eq!(lines[2], "beta,1,1,100,10,50,0,0.0000");
+    assert_eq!(lines.len(), 3);
+
+    let table = render_table(&group_by, &rows);
+    let last = table.lines().last().unwrap();
+    assert!(last.starts_with("total"), "{}", table);
+    assert!(last.ends_with("0.7500"), "{}", table);
+    assert_eq!(table.lines().count(), 4);
+}
+
+#[test]
+fn test_parse_day() {
+    assert_eq!(parse_day("2026-03-01").unwrap(), "2026-03-01");
+    assert!(parse_day("2026-3-1").is_err());
+    assert!(parse_day("yesterday").is_err());
+}
+
+#[test]
+fn test_usage_flag_csv() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.jsonl");
+    let lines: Vec<String> = sample_entries()
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap())
+        .collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_anyclaude"))
+        .args(["--usage", "--by", "day", "--since", "2026-03-02", "--csv", "--file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert!(lines[1].starts_with("2026-03-02,1,0,"), "{}", stdout);
+}
+
+#[test]
+fn test_usage_flag_rejects_bad_day() {
+    let output = Command::new(env!("CARGO_BIN_EXE_anyclaude"))
+        .args(["--usage", "--since", "March"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected YYYY-MM-DD"));
+}
+
+#[test]
+fn test_usage_flag_conflicts_with_session_options() {
+    let output = Command::new(env!("CARGO_BIN_EXE_anyclaude"))
+        .args(["--backend", "mock", "--usage"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_usage_ledger_from_toml() {
+    let ledger: UsageLedgerConfig = toml::from_str("enabled = true").unwrap();
+    assert!(ledger.enabled);
+    assert_eq!(ledger.path, "~/.config/anyclaude/usage.jsonl");
+    assert!(!Config::default().usage_ledger.enabled);
+}